@@ -1,9 +1,14 @@
-/// Utility to recalculate all contribution amounts with 4-decimal precision
-/// This fixes rounding errors from the old 2-decimal calculation
+/// Utility to recalculate all contribution amounts from their weights
+/// Shares are allocated by largest remainder so they sum exactly to each payment,
+/// within each contribution's cap and floor
 ///
 /// Usage: cargo run --bin recalculate_contributions
+use bonscompte_backend::models::{CreateContribution, Money, SplitMode};
 use sqlx::SqlitePool;
 
+/// id, participant_id, amount, weight, cap, floor
+type ContributionRow = (i64, i64, Money, f64, Option<Money>, Option<Money>);
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Load environment variables
@@ -16,7 +21,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let pool = SqlitePool::connect(&format!("sqlite:{}?mode=rwc", database_url)).await?;
 
     // Get all payments
-    let payments: Vec<(i64, f64)> = sqlx::query_as("SELECT id, amount FROM payments")
+    let payments: Vec<(i64, Money)> = sqlx::query_as("SELECT id, amount FROM payments")
         .fetch_all(&pool)
         .await?;
 
@@ -27,8 +32,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     for (payment_id, payment_amount) in payments {
         // Get all contributions for this payment
-        let contributions: Vec<(i64, i64, f64, f64)> = sqlx::query_as(
-            "SELECT id, participant_id, amount, weight FROM contributions WHERE payment_id = ?",
+        let contributions: Vec<ContributionRow> = sqlx::query_as(
+            "SELECT id, participant_id, amount, weight, cap, floor
+             FROM contributions WHERE payment_id = ?",
         )
         .bind(payment_id)
         .fetch_all(&pool)
@@ -38,28 +44,41 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             continue;
         }
 
-        // Calculate total weight
-        let total_weight: f64 = contributions.iter().map(|(_, _, _, w)| w).sum();
-
-        if total_weight == 0.0 {
-            eprintln!(
-                "Warning: Payment {} has zero total weight, skipping",
-                payment_id
-            );
-            total_errors += 1;
-            continue;
-        }
+        // Split by weight (exact splits store their amounts as weights) by
+        // largest remainder, ties going to the lowest participant id
+        let split: Vec<CreateContribution> = contributions
+            .iter()
+            .map(
+                |(_, participant_id, _, weight, cap, floor)| CreateContribution {
+                    participant_id: *participant_id,
+                    weight: *weight,
+                    amount: None,
+                    cap: *cap,
+                    floor: *floor,
+                },
+            )
+            .collect();
+        let shares = match SplitMode::Weights.resolve(payment_amount, &split) {
+            Ok(shares) => shares,
+            Err(error) => {
+                eprintln!(
+                    "Warning: Payment {} cannot be split ({:?}), skipping",
+                    payment_id, error
+                );
+                total_errors += 1;
+                continue;
+            }
+        };
 
         // Track old and new amounts for debugging
-        let mut old_total = 0.0;
-        let mut new_total = 0.0;
+        let mut old_total = Money::ZERO;
+        let mut new_total = Money::ZERO;
 
         // Recalculate each contribution
-        for (contrib_id, _participant_id, old_amount, weight) in contributions {
+        for ((contrib_id, _participant_id, old_amount, ..), new_amount) in
+            contributions.into_iter().zip(shares)
+        {
             old_total += old_amount;
-
-            // New calculation with 4 decimals
-            let new_amount = (payment_amount * weight / total_weight * 10000.0).round() / 10000.0;
             new_total += new_amount;
 
             // Update the contribution
@@ -72,12 +91,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             total_updated += 1;
         }
 
-        // Check if there's still a rounding error
-        let diff = (new_total - payment_amount).abs();
-        if diff > 0.001 {
+        // Report payments whose stored split did not add up
+        if old_total != payment_amount {
             println!(
-                "Payment {}: amount={:.2}, old_total={:.4}, new_total={:.4}, diff={:.4}",
-                payment_id, payment_amount, old_total, new_total, diff
+                "Payment {}: amount={}, old_total={}, new_total={}",
+                payment_id, payment_amount, old_total, new_total
             );
         }
     }
@@ -1,9 +1,11 @@
 use sqlx::{
     sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions, SqliteSynchronous},
-    ConnectOptions, SqlitePool,
+    Acquire, ConnectOptions, Sqlite, SqlitePool,
 };
 use std::{path::Path, str::FromStr, time::Duration};
 
+use crate::models::{LegacyRecurrence, RecurrenceSet};
+
 pub async fn init_pool(database_url: &str) -> Result<SqlitePool, sqlx::Error> {
     // Extract path from sqlite: URL
     let db_path = database_url.strip_prefix("sqlite:").unwrap_or(database_url);
@@ -837,6 +839,810 @@ pub async fn run_migrations(pool: &SqlitePool) -> Result<(), sqlx::Error> {
         }
     }
 
+    // =====================
+    // Migration 025: Integer money amounts
+    // =====================
+    // payments.amount and contributions.amount were REAL (floating point), which
+    // made sums drift by fractions of a cent. Store them as INTEGER thousandths
+    // instead, which keeps the third decimal of sub-cent amounts. Contribution
+    // shares are re-rounded to the unit of their payment (cents, unless the
+    // payment has a third decimal) so that, whenever the old values were only
+    // off by rounding noise, they add up exactly to the payment amount.
+    let amount_type: Option<String> =
+        sqlx::query_scalar("SELECT type FROM pragma_table_info('payments') WHERE name = 'amount'")
+            .fetch_optional(pool)
+            .await?;
+
+    if amount_type.as_deref() == Some("REAL") {
+        tracing::info!("Converting payment and contribution amounts to integer thousandths");
+
+        let mut tx = pool.begin().await?;
+
+        for table in ["payments", "contributions"] {
+            sqlx::query(sqlx::AssertSqlSafe(format!(
+                "ALTER TABLE {} ADD COLUMN amount_minor INTEGER NOT NULL DEFAULT 0",
+                table
+            )))
+            .execute(&mut *tx)
+            .await?;
+
+            sqlx::query(sqlx::AssertSqlSafe(format!(
+                "UPDATE {} SET amount_minor = CAST(ROUND(amount * 1000) AS INTEGER)",
+                table
+            )))
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        let off_grid: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM payments WHERE ABS(amount * 1000 - amount_minor) > 0.000001",
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        if off_grid > 0 {
+            tracing::warn!(
+                "{} payment amounts had more than three decimals and were rounded",
+                off_grid
+            );
+        }
+
+        let rows: Vec<(i64, i64, f64, i64)> = sqlx::query_as(
+            "SELECT c.id, c.payment_id, c.amount, p.amount_minor
+             FROM contributions c
+             JOIN payments p ON p.id = c.payment_id
+             ORDER BY c.payment_id, c.id",
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        for group in rows.chunk_by(|a, b| a.1 == b.1) {
+            let amounts: Vec<f64> = group.iter().map(|(_, _, amount, _)| *amount).collect();
+            let target = group[0].3;
+            // Thousandths per unit of the payment
+            let unit = if target % 10 == 0 { 10 } else { 1 };
+            let split = legacy_minor_split(&amounts, target / unit, (1000 / unit) as f64);
+            for ((contribution_id, _, _, _), value) in group.iter().zip(split) {
+                sqlx::query("UPDATE contributions SET amount_minor = ? WHERE id = ?")
+                    .bind(value * unit)
+                    .bind(contribution_id)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+        }
+
+        for table in ["payments", "contributions"] {
+            sqlx::query(sqlx::AssertSqlSafe(format!(
+                "ALTER TABLE {} DROP COLUMN amount",
+                table
+            )))
+            .execute(&mut *tx)
+            .await?;
+
+            sqlx::query(sqlx::AssertSqlSafe(format!(
+                "ALTER TABLE {} RENAME COLUMN amount_minor TO amount",
+                table
+            )))
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        tracing::info!("Money amounts converted to integer thousandths");
+    }
+
+    // =====================
+    // Migration 026: Multi-currency support
+    // =====================
+    // Each project has a base currency (ISO 4217 code) in which balances are
+    // reported. Payments may be entered in another currency; NULL means the
+    // project's base currency. Amounts stay in minor units of their own currency.
+    sqlx::query("ALTER TABLE projects ADD COLUMN base_currency TEXT NOT NULL DEFAULT 'EUR'")
+        .execute(pool)
+        .await
+        .ok();
+
+    sqlx::query("ALTER TABLE payments ADD COLUMN currency TEXT")
+        .execute(pool)
+        .await
+        .ok();
+
+    // Exchange rates: one unit of base_currency = rate units of quote_currency
+    // on rate_date. Entered manually or imported from an ECB-style CSV file.
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS exchange_rates (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            project_id INTEGER NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+            base_currency TEXT NOT NULL,
+            quote_currency TEXT NOT NULL,
+            rate_date TEXT NOT NULL,
+            rate REAL NOT NULL CHECK(rate > 0),
+            source TEXT NOT NULL DEFAULT 'manual',
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            UNIQUE(project_id, base_currency, quote_currency, rate_date)
+        )",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_exchange_rates_project ON exchange_rates(project_id)",
+    )
+    .execute(pool)
+    .await?;
+
+    // =====================
+    // Migration 027: Ledger stamps for cached debt computation
+    // =====================
+    // Debt summaries are computed from an in-memory ledger of expanded occurrences.
+    // ledger_stamp is replaced with a fresh random value by triggers on every
+    // change that can affect balances, so a cached ledger is reused only while
+    // its stamp is still current.
+    sqlx::query("ALTER TABLE projects ADD COLUMN ledger_stamp TEXT")
+        .execute(pool)
+        .await
+        .ok();
+
+    sqlx::query(
+        "UPDATE projects SET ledger_stamp = lower(hex(randomblob(16))) WHERE ledger_stamp IS NULL",
+    )
+    .execute(pool)
+    .await?;
+
+    // Project rows stamp themselves; every other table goes through
+    // create_ledger_stamp_triggers, which later migrations use for their tables
+    for (name, event) in [
+        ("projects_insert", "INSERT ON projects"),
+        ("projects_currency", "UPDATE OF base_currency ON projects"),
+    ] {
+        let sql = format!(
+            "CREATE TRIGGER IF NOT EXISTS ledger_stamp_{name}
+            AFTER {event}
+            BEGIN
+                UPDATE projects SET ledger_stamp = lower(hex(randomblob(16)))
+                WHERE id = NEW.id;
+            END"
+        );
+        sqlx::query(sqlx::AssertSqlSafe(sql)).execute(pool).await?;
+    }
+    for table in ["payments", "participants", "exchange_rates"] {
+        create_ledger_stamp_triggers(pool, table, "{row}.project_id").await?;
+    }
+    create_ledger_stamp_triggers(
+        pool,
+        "contributions",
+        "(SELECT project_id FROM payments WHERE id = {row}.payment_id)",
+    )
+    .await?;
+
+    // =====================
+    // Migration 028: RRULE recurrence
+    // =====================
+    // Recurring payments store an RFC 5545 RRULE, expanded by a single engine.
+    // The older recurrence columns stay for existing clients; rows that only
+    // have those are converted here.
+    sqlx::query("ALTER TABLE payments ADD COLUMN recurrence_rule TEXT")
+        .execute(pool)
+        .await
+        .ok();
+
+    // (id, payment_date, type, interval, times_per, weekdays, monthdays, months)
+    type LegacyRow = (
+        i64,
+        String,
+        Option<String>,
+        Option<i32>,
+        Option<i32>,
+        Option<String>,
+        Option<String>,
+        Option<String>,
+    );
+    let legacy_rows: Vec<LegacyRow> = sqlx::query_as(
+        "SELECT id, payment_date, recurrence_type, recurrence_interval, recurrence_times_per,
+                recurrence_weekdays, recurrence_monthdays, recurrence_months
+         FROM payments WHERE is_recurring = 1 AND recurrence_rule IS NULL",
+    )
+    .fetch_all(pool)
+    .await?;
+    if !legacy_rows.is_empty() {
+        let mut tx = pool.begin().await?;
+        for (id, payment_date, recurrence_type, interval, times_per, weekdays, monthdays, months) in
+            &legacy_rows
+        {
+            let Some(start) = payment_date
+                .get(..10)
+                .and_then(|d| chrono::NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+            else {
+                continue;
+            };
+            let recurrence = RecurrenceSet::from_legacy(
+                start,
+                &LegacyRecurrence {
+                    recurrence_type: recurrence_type.as_deref(),
+                    interval: *interval,
+                    times_per: *times_per,
+                    weekdays: weekdays.as_deref(),
+                    monthdays: monthdays.as_deref(),
+                    months: months.as_deref(),
+                },
+            );
+            sqlx::query("UPDATE payments SET recurrence_rule = ? WHERE id = ?")
+                .bind(recurrence.to_string())
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        tracing::info!(
+            "Converted {} recurring payments to RRULE",
+            legacy_rows.len()
+        );
+    }
+
+    // =====================
+    // Migration 029: Occurrence exceptions
+    // =====================
+    // One row per changed occurrence of a recurring payment, keyed by the date
+    // the series puts it on: skipped, moved, or with its own amount or weights.
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS occurrence_exceptions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            payment_id INTEGER NOT NULL REFERENCES payments(id) ON DELETE CASCADE,
+            original_date TEXT NOT NULL,
+            is_skipped BOOLEAN NOT NULL DEFAULT 0,
+            moved_to TEXT,
+            amount INTEGER,
+            weights TEXT,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            UNIQUE(payment_id, original_date)
+        )",
+    )
+    .execute(pool)
+    .await?;
+
+    create_ledger_stamp_triggers(
+        pool,
+        "occurrence_exceptions",
+        "(SELECT project_id FROM payments WHERE id = {row}.payment_id)",
+    )
+    .await?;
+
+    // =====================
+    // Migration 030: Series splits
+    // =====================
+    // "Edit this and following" ends a series and continues it in a new
+    // payment; the continuation points back at the payment it was split from.
+    sqlx::query(
+        "ALTER TABLE payments ADD COLUMN split_from_id INTEGER REFERENCES payments(id) ON DELETE SET NULL",
+    )
+    .execute(pool)
+    .await
+    .ok();
+
+    // =====================
+    // Migration 031: Amount schedules
+    // =====================
+    // JSON steps or yearly indexation for the amount of recurring payments
+    sqlx::query("ALTER TABLE payments ADD COLUMN amount_schedule TEXT")
+        .execute(pool)
+        .await
+        .ok();
+
+    // =====================
+    // Migration 032: Business days
+    // =====================
+    // Recurring payments can move occurrences off weekends and project
+    // holidays to the next or previous business day.
+    sqlx::query("ALTER TABLE payments ADD COLUMN business_day_shift TEXT")
+        .execute(pool)
+        .await
+        .ok();
+
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS project_holidays (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            project_id INTEGER NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+            holiday_date TEXT NOT NULL,
+            name TEXT,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            UNIQUE(project_id, holiday_date)
+        )",
+    )
+    .execute(pool)
+    .await?;
+
+    create_ledger_stamp_triggers(pool, "project_holidays", "{row}.project_id").await?;
+
+    // =====================
+    // Migration 033: Participant presence
+    // =====================
+    // Periods a participant is present in the project (e.g. lives in the
+    // house). Recurring occurrences are split only among contributors present
+    // on their date, or pro-rated over partial months.
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS participant_presence (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            project_id INTEGER NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+            participant_id INTEGER NOT NULL REFERENCES participants(id) ON DELETE CASCADE,
+            from_date TEXT,
+            to_date TEXT,
+            created_at TEXT NOT NULL DEFAULT (datetime('now'))
+        )",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_participant_presence_project
+         ON participant_presence(project_id)",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query("ALTER TABLE payments ADD COLUMN prorate_presence BOOLEAN NOT NULL DEFAULT 0")
+        .execute(pool)
+        .await
+        .ok();
+
+    create_ledger_stamp_triggers(pool, "participant_presence", "{row}.project_id").await?;
+
+    // =====================
+    // Migration 034: Weight profiles
+    // =====================
+    // Named weights payments can reference instead of their own. A change
+    // adds a version taking effect from a date, so earlier occurrences keep
+    // the weights they were billed with.
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS weight_profiles (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            project_id INTEGER NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+            name TEXT NOT NULL,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            UNIQUE(project_id, name)
+        )",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS weight_profile_versions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            profile_id INTEGER NOT NULL REFERENCES weight_profiles(id) ON DELETE CASCADE,
+            effective_from TEXT,
+            weights TEXT NOT NULL,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            UNIQUE(profile_id, effective_from)
+        )",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "ALTER TABLE payments ADD COLUMN weight_profile_id INTEGER REFERENCES weight_profiles(id)",
+    )
+    .execute(pool)
+    .await
+    .ok();
+
+    create_ledger_stamp_triggers(
+        pool,
+        "weight_profile_versions",
+        "(SELECT project_id FROM weight_profiles WHERE id = {row}.profile_id)",
+    )
+    .await?;
+
+    // =====================
+    // Migration 035: Split modes
+    // =====================
+    // Payments can be split by exact amounts, percentages or shares as well
+    // as weights, and weighted contributions can be capped or floored. The
+    // resolved amounts stay in contributions.amount.
+    sqlx::query("ALTER TABLE payments ADD COLUMN split_mode TEXT")
+        .execute(pool)
+        .await
+        .ok();
+    sqlx::query("ALTER TABLE contributions ADD COLUMN cap INTEGER")
+        .execute(pool)
+        .await
+        .ok();
+    sqlx::query("ALTER TABLE contributions ADD COLUMN floor INTEGER")
+        .execute(pool)
+        .await
+        .ok();
+
+    // =====================
+    // Migration 036: Itemized receipts
+    // =====================
+    // Receipt lines of a payment: items with their own weights, and tax, tip
+    // and discount lines spread over the item subtotals. The contributions
+    // derived from them stay the source of truth for balances.
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS payment_items (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            payment_id INTEGER NOT NULL REFERENCES payments(id) ON DELETE CASCADE,
+            position INTEGER NOT NULL,
+            kind TEXT NOT NULL DEFAULT 'item',
+            description TEXT NOT NULL DEFAULT '',
+            amount INTEGER NOT NULL,
+            weights TEXT
+        )",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_payment_items_payment ON payment_items(payment_id)",
+    )
+    .execute(pool)
+    .await?;
+
+    // =====================
+    // Migration 037: Multiple payers
+    // =====================
+    // A payment paid by several participants lists the part each paid;
+    // payments.payer_id keeps the first of them for older clients.
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS payment_payers (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            payment_id INTEGER NOT NULL REFERENCES payments(id) ON DELETE CASCADE,
+            participant_id INTEGER NOT NULL REFERENCES participants(id) ON DELETE CASCADE,
+            amount INTEGER NOT NULL,
+            UNIQUE(payment_id, participant_id)
+        )",
+    )
+    .execute(pool)
+    .await?;
+
+    create_ledger_stamp_triggers(
+        pool,
+        "payment_payers",
+        "(SELECT project_id FROM payments WHERE id = {row}.payment_id)",
+    )
+    .await?;
+
+    // =====================
+    // Migration 038: Refunds
+    // =====================
+    // Money given back on an expense, dated on its own and split like the
+    // payment unless weights say otherwise
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS refunds (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            payment_id INTEGER NOT NULL REFERENCES payments(id) ON DELETE CASCADE,
+            amount INTEGER NOT NULL,
+            refund_date TEXT NOT NULL,
+            description TEXT NOT NULL DEFAULT '',
+            weights TEXT,
+            created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_refunds_payment ON refunds(payment_id)")
+        .execute(pool)
+        .await?;
+
+    create_ledger_stamp_triggers(
+        pool,
+        "refunds",
+        "(SELECT project_id FROM payments WHERE id = {row}.payment_id)",
+    )
+    .await?;
+
+    // =====================
+    // Migration 039: Unitized pools
+    // =====================
+    // A unitized pool prices ownership in units; valuations record what the
+    // pool is worth (or gained) on a date and so move the unit value.
+    sqlx::query("ALTER TABLE participants ADD COLUMN unitized BOOLEAN NOT NULL DEFAULT 0")
+        .execute(pool)
+        .await
+        .ok();
+
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS pool_valuations (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            project_id INTEGER NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+            pool_id INTEGER NOT NULL REFERENCES participants(id) ON DELETE CASCADE,
+            valuation_date TEXT NOT NULL,
+            kind TEXT NOT NULL DEFAULT 'value',
+            amount INTEGER NOT NULL,
+            description TEXT NOT NULL DEFAULT '',
+            created_at TEXT NOT NULL DEFAULT (datetime('now'))
+        )",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_pool_valuations_project ON pool_valuations(project_id)",
+    )
+    .execute(pool)
+    .await?;
+
+    create_ledger_stamp_triggers(pool, "pool_valuations", "{row}.project_id").await?;
+
+    // =====================
+    // Migration 040: Pool rules
+    // =====================
+    // Recurring fees and interest of a pool, charged or credited on the dates
+    // of an RRULE and shared by the pool's owners.
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS pool_rules (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            project_id INTEGER NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+            pool_id INTEGER NOT NULL REFERENCES participants(id) ON DELETE CASCADE,
+            kind TEXT NOT NULL,
+            amount INTEGER,
+            rate REAL,
+            recurrence_rule TEXT NOT NULL,
+            start_date TEXT NOT NULL,
+            end_date TEXT,
+            description TEXT NOT NULL DEFAULT '',
+            created_at TEXT NOT NULL DEFAULT (datetime('now'))
+        )",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_pool_rules_project ON pool_rules(project_id)")
+        .execute(pool)
+        .await?;
+
+    create_ledger_stamp_triggers(pool, "pool_rules", "{row}.project_id").await?;
+
+    // =====================
+    // Migration 041: Pool statements
+    // =====================
+    // Bank statement balances of pool accounts, reconciled against the
+    // balance on record. They are not part of the ledger.
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS pool_statements (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            project_id INTEGER NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+            pool_id INTEGER NOT NULL REFERENCES participants(id) ON DELETE CASCADE,
+            statement_date TEXT NOT NULL,
+            balance INTEGER NOT NULL,
+            description TEXT NOT NULL DEFAULT '',
+            created_at TEXT NOT NULL DEFAULT (datetime('now'))
+        )",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_pool_statements_pool ON pool_statements(pool_id, statement_date)",
+    )
+    .execute(pool)
+    .await?;
+
+    // =====================
+    // Migration 042: Contribution rules
+    // =====================
+    // Deposits expected from each participant on a schedule, replacing the
+    // "rule" payments that encoded them with affects_balance = 0 and
+    // affects_receiver_expectation = 1. Existing rule payments are converted
+    // once, in the transaction that creates the tables, so an interrupted
+    // conversion leaves no tables behind and runs again on the next startup.
+    let mut tx = pool.begin().await?;
+    let has_contribution_rules: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'contribution_rules'",
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS contribution_rules (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            project_id INTEGER NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+            pool_id INTEGER NOT NULL REFERENCES participants(id) ON DELETE CASCADE,
+            recurrence_rule TEXT,
+            start_date TEXT NOT NULL,
+            end_date TEXT,
+            description TEXT NOT NULL DEFAULT '',
+            created_at TEXT NOT NULL DEFAULT (datetime('now'))
+        )",
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS contribution_rule_amounts (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            rule_id INTEGER NOT NULL REFERENCES contribution_rules(id) ON DELETE CASCADE,
+            participant_id INTEGER NOT NULL REFERENCES participants(id) ON DELETE CASCADE,
+            amount INTEGER NOT NULL,
+            UNIQUE(rule_id, participant_id)
+        )",
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_contribution_rules_project ON contribution_rules(project_id)",
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    create_ledger_stamp_triggers(&mut *tx, "contribution_rules", "{row}.project_id").await?;
+
+    create_ledger_stamp_triggers(
+        &mut *tx,
+        "contribution_rule_amounts",
+        "(SELECT project_id FROM contribution_rules WHERE id = {row}.rule_id)",
+    )
+    .await?;
+
+    if has_contribution_rules == 0 {
+        // Rule payments a contribution rule can express: a final deposit to a
+        // pool in the base currency, with fixed shares and without exceptions,
+        // amount schedule, business day shift, items, refunds or several
+        // payers. A participant paying is expected to deposit the amount;
+        // without a payer, each contributor is expected to deposit their share.
+        // Others stay payments.
+        // (id, project_id, pool_id, payer_id, amount, recurrence_rule, start, end, description)
+        type RuleRow = (
+            i64,
+            i64,
+            i64,
+            Option<i64>,
+            i64,
+            Option<String>,
+            String,
+            Option<String>,
+            String,
+        );
+        let rule_payments: Vec<RuleRow> = sqlx::query_as(
+            "SELECT p.id, p.project_id, p.receiver_account_id, p.payer_id, p.amount,
+                    CASE WHEN p.is_recurring = 1 THEN p.recurrence_rule END,
+                    substr(p.payment_date, 1, 10), substr(p.recurrence_end_date, 1, 10),
+                    p.description
+             FROM payments p
+             JOIN projects pr ON pr.id = p.project_id
+             JOIN participants receiver ON receiver.id = p.receiver_account_id
+             LEFT JOIN participants payer ON payer.id = p.payer_id
+             WHERE p.affects_balance = 0 AND p.affects_receiver_expectation = 1
+               AND receiver.account_type = 'pool'
+               AND p.is_final = 1
+               AND (p.currency IS NULL OR p.currency = pr.base_currency)
+               AND (p.is_recurring = 0 OR p.recurrence_rule IS NOT NULL)
+               AND p.amount_schedule IS NULL AND p.business_day_shift IS NULL
+               AND p.prorate_presence = 0 AND p.weight_profile_id IS NULL
+               AND NOT EXISTS (SELECT 1 FROM occurrence_exceptions e WHERE e.payment_id = p.id)
+               AND NOT EXISTS (SELECT 1 FROM payment_payers pp WHERE pp.payment_id = p.id)
+               AND NOT EXISTS (SELECT 1 FROM payment_items i WHERE i.payment_id = p.id)
+               AND NOT EXISTS (SELECT 1 FROM refunds r WHERE r.payment_id = p.id)
+               AND (payer.account_type != 'pool' OR (
+                   p.payer_id IS NULL
+                   AND EXISTS (SELECT 1 FROM contributions c
+                               WHERE c.payment_id = p.id AND c.amount > 0)
+                   AND NOT EXISTS (SELECT 1 FROM contributions c
+                                   JOIN participants cp ON cp.id = c.participant_id
+                                   WHERE c.payment_id = p.id
+                                   AND (cp.account_type = 'pool' OR c.amount < 0))))",
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        for (id, project_id, pool_id, payer_id, amount, recurrence_rule, start, end, description) in
+            &rule_payments
+        {
+            let amounts: Vec<(i64, i64)> = match payer_id {
+                Some(payer_id) => vec![(*payer_id, *amount)],
+                None => {
+                    sqlx::query_as(
+                        "SELECT participant_id, amount FROM contributions
+                             WHERE payment_id = ? AND amount > 0",
+                    )
+                    .bind(id)
+                    .fetch_all(&mut *tx)
+                    .await?
+                }
+            };
+            let result = sqlx::query(
+                "INSERT INTO contribution_rules
+                     (project_id, pool_id, recurrence_rule, start_date, end_date, description)
+                     VALUES (?, ?, ?, ?, ?, ?)",
+            )
+            .bind(project_id)
+            .bind(pool_id)
+            .bind(recurrence_rule)
+            .bind(start)
+            .bind(end)
+            .bind(description)
+            .execute(&mut *tx)
+            .await?;
+            let rule_id = result.last_insert_rowid();
+            for (participant_id, amount) in amounts {
+                sqlx::query(
+                    "INSERT INTO contribution_rule_amounts (rule_id, participant_id, amount)
+                         VALUES (?, ?, ?)",
+                )
+                .bind(rule_id)
+                .bind(participant_id)
+                .bind(amount)
+                .execute(&mut *tx)
+                .await?;
+            }
+            sqlx::query("DELETE FROM contributions WHERE payment_id = ?")
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query("DELETE FROM payments WHERE id = ?")
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        if !rule_payments.is_empty() {
+            tracing::info!(
+                "Converted {} rule payments to contribution rules",
+                rule_payments.len()
+            );
+        }
+    }
+    tx.commit().await?;
+
     tracing::info!("Database migrations completed");
     Ok(())
 }
+
+/// Create the `ledger_stamp_{table}_{insert,update,delete}` triggers of
+/// Migration 027, renewing the stamp of the projects a changed row belongs to.
+///
+/// `project_expr` yields the project id of a row written as `{row}`, e.g.
+/// `{row}.project_id`, or a parenthesized subquery for tables that reach their
+/// project through a parent. Updates stamp both the old and the new project.
+async fn create_ledger_stamp_triggers<'c>(
+    conn: impl Acquire<'c, Database = Sqlite>,
+    table: &str,
+    project_expr: &str,
+) -> Result<(), sqlx::Error> {
+    let mut conn = conn.acquire().await?;
+    let old = project_expr.replace("{row}", "OLD");
+    let new = project_expr.replace("{row}", "NEW");
+    for (name, event, projects) in [
+        ("insert", "INSERT", new.clone()),
+        ("update", "UPDATE", format!("{old}, {new}")),
+        ("delete", "DELETE", old.clone()),
+    ] {
+        let sql = format!(
+            "CREATE TRIGGER IF NOT EXISTS ledger_stamp_{table}_{name}
+            AFTER {event} ON {table}
+            BEGIN
+                UPDATE projects SET ledger_stamp = lower(hex(randomblob(16)))
+                WHERE id IN ({projects});
+            END"
+        );
+        sqlx::query(sqlx::AssertSqlSafe(sql))
+            .execute(&mut *conn)
+            .await?;
+    }
+    Ok(())
+}
+
+/// Convert legacy floating-point contribution shares to minor units, with
+/// `per_unit` minor units to the unit of currency.
+///
+/// If the shares only miss `target` by rounding noise, the leftover units go
+/// to the shares with the largest fractional parts (earliest first on ties),
+/// so the result sums exactly to the payment. Otherwise each share is rounded
+/// on its own and the existing discrepancy is kept.
+fn legacy_minor_split(amounts: &[f64], target: i64, per_unit: f64) -> Vec<i64> {
+    let scaled: Vec<f64> = amounts.iter().map(|a| a * per_unit).collect();
+    let mut units: Vec<i64> = scaled.iter().map(|v| v.floor() as i64).collect();
+    let residual = target - units.iter().sum::<i64>();
+
+    if residual < 0 || residual > units.len() as i64 {
+        return scaled.iter().map(|v| v.round() as i64).collect();
+    }
+
+    let mut order: Vec<usize> = (0..units.len()).collect();
+    order.sort_by(|&a, &b| {
+        let frac_a = scaled[a] - scaled[a].floor();
+        let frac_b = scaled[b] - scaled[b].floor();
+        frac_b.total_cmp(&frac_a).then(a.cmp(&b))
+    });
+    for &idx in order.iter().take(residual as usize) {
+        units[idx] += 1;
+    }
+    units
+}
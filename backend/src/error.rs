@@ -25,6 +25,7 @@ pub enum ErrorCode {
     // Validation errors
     InvalidInput,
     AmountMustBePositive,
+    InvalidAmountPrecision,
     ContributionRequired,
     TotalWeightMustBePositive,
     InvalidPayer,
@@ -45,6 +46,12 @@ pub enum ErrorCode {
     ProjectNameTooLong,
     ProjectDescriptionTooLong,
     ParticipantNameTooLong,
+    InvalidCurrency,
+    InvalidExchangeRate,
+    InvalidRateFile,
+    InvalidSettlementOptions,
+    InvalidSeriesRange,
+    InvalidRecurrenceRule,
 
     // Not found errors
     NotFound,
@@ -56,6 +63,9 @@ pub enum ErrorCode {
     InviteNotFound,
     RecoveryNotFound,
     ApprovalNotFound,
+    ExchangeRateNotFound,
+    OccurrenceNotFound,
+    OccurrenceExceptionNotFound,
 
     // Permission/access errors
     Forbidden,
@@ -88,6 +98,43 @@ pub enum ErrorCode {
     CannotTrustSelf,
     AlreadyTrustedUser,
     CannotDeleteAccountNoAdmin,
+    ExchangeRateMissing,
+    BaseCurrencyInUse,
+    BalancesChanged,
+    NothingToSettle,
+    SettlementNotSuggested,
+    PaymentNotRecurring,
+    CannotSplitFirstOccurrence,
+    InvalidAmountSchedule,
+    InvalidBusinessDayShift,
+    HolidayNotFound,
+    PresenceNotFound,
+    InvalidPresencePeriod,
+    WeightProfileNotFound,
+    WeightProfileInUse,
+    WeightProfileNameExists,
+    InvalidSplitMode,
+    SplitAmountsMismatch,
+    PercentagesMustTotal100,
+    SharesMustBeWhole,
+    InvalidSplitLimits,
+    InvalidPaymentItem,
+    InvalidItemAdjustment,
+    ItemsTotalMismatch,
+    InvalidPayers,
+    PayerAmountsMismatch,
+    RefundNotFound,
+    PaymentNotRefundable,
+    RefundExceedsPayment,
+    PoolValuationNotFound,
+    InvalidPoolValuation,
+    PoolRuleNotFound,
+    InvalidPoolRule,
+    PoolStatementNotFound,
+    NothingToAdjust,
+    ContributionRuleNotFound,
+    InvalidContributionRule,
+    UseContributionRule,
 
     // Image validation errors
     InvalidBase64Image,
@@ -118,6 +165,7 @@ impl ErrorCode {
             // Validation
             Self::InvalidInput => "INVALID_INPUT",
             Self::AmountMustBePositive => "AMOUNT_MUST_BE_POSITIVE",
+            Self::InvalidAmountPrecision => "INVALID_AMOUNT_PRECISION",
             Self::ContributionRequired => "CONTRIBUTION_REQUIRED",
             Self::TotalWeightMustBePositive => "TOTAL_WEIGHT_MUST_BE_POSITIVE",
             Self::InvalidPayer => "INVALID_PAYER",
@@ -138,6 +186,12 @@ impl ErrorCode {
             Self::ProjectNameTooLong => "PROJECT_NAME_TOO_LONG",
             Self::ProjectDescriptionTooLong => "PROJECT_DESCRIPTION_TOO_LONG",
             Self::ParticipantNameTooLong => "PARTICIPANT_NAME_TOO_LONG",
+            Self::InvalidCurrency => "INVALID_CURRENCY",
+            Self::InvalidExchangeRate => "INVALID_EXCHANGE_RATE",
+            Self::InvalidRateFile => "INVALID_RATE_FILE",
+            Self::InvalidSettlementOptions => "INVALID_SETTLEMENT_OPTIONS",
+            Self::InvalidSeriesRange => "INVALID_SERIES_RANGE",
+            Self::InvalidRecurrenceRule => "INVALID_RECURRENCE_RULE",
 
             // Not found
             Self::NotFound => "NOT_FOUND",
@@ -149,6 +203,9 @@ impl ErrorCode {
             Self::InviteNotFound => "INVITE_NOT_FOUND",
             Self::RecoveryNotFound => "RECOVERY_NOT_FOUND",
             Self::ApprovalNotFound => "APPROVAL_NOT_FOUND",
+            Self::ExchangeRateNotFound => "EXCHANGE_RATE_NOT_FOUND",
+            Self::OccurrenceNotFound => "OCCURRENCE_NOT_FOUND",
+            Self::OccurrenceExceptionNotFound => "OCCURRENCE_EXCEPTION_NOT_FOUND",
 
             // Permission
             Self::Forbidden => "FORBIDDEN",
@@ -181,6 +238,43 @@ impl ErrorCode {
             Self::CannotTrustSelf => "CANNOT_TRUST_SELF",
             Self::AlreadyTrustedUser => "ALREADY_TRUSTED_USER",
             Self::CannotDeleteAccountNoAdmin => "CANNOT_DELETE_ACCOUNT_NO_ADMIN",
+            Self::ExchangeRateMissing => "EXCHANGE_RATE_MISSING",
+            Self::BaseCurrencyInUse => "BASE_CURRENCY_IN_USE",
+            Self::BalancesChanged => "BALANCES_CHANGED",
+            Self::NothingToSettle => "NOTHING_TO_SETTLE",
+            Self::SettlementNotSuggested => "SETTLEMENT_NOT_SUGGESTED",
+            Self::PaymentNotRecurring => "PAYMENT_NOT_RECURRING",
+            Self::CannotSplitFirstOccurrence => "CANNOT_SPLIT_FIRST_OCCURRENCE",
+            Self::InvalidAmountSchedule => "INVALID_AMOUNT_SCHEDULE",
+            Self::InvalidBusinessDayShift => "INVALID_BUSINESS_DAY_SHIFT",
+            Self::HolidayNotFound => "HOLIDAY_NOT_FOUND",
+            Self::PresenceNotFound => "PRESENCE_NOT_FOUND",
+            Self::InvalidPresencePeriod => "INVALID_PRESENCE_PERIOD",
+            Self::WeightProfileNotFound => "WEIGHT_PROFILE_NOT_FOUND",
+            Self::WeightProfileInUse => "WEIGHT_PROFILE_IN_USE",
+            Self::WeightProfileNameExists => "WEIGHT_PROFILE_NAME_EXISTS",
+            Self::InvalidSplitMode => "INVALID_SPLIT_MODE",
+            Self::SplitAmountsMismatch => "SPLIT_AMOUNTS_MISMATCH",
+            Self::PercentagesMustTotal100 => "PERCENTAGES_MUST_TOTAL_100",
+            Self::SharesMustBeWhole => "SHARES_MUST_BE_WHOLE",
+            Self::InvalidSplitLimits => "INVALID_SPLIT_LIMITS",
+            Self::InvalidPaymentItem => "INVALID_PAYMENT_ITEM",
+            Self::InvalidItemAdjustment => "INVALID_ITEM_ADJUSTMENT",
+            Self::ItemsTotalMismatch => "ITEMS_TOTAL_MISMATCH",
+            Self::InvalidPayers => "INVALID_PAYERS",
+            Self::PayerAmountsMismatch => "PAYER_AMOUNTS_MISMATCH",
+            Self::RefundNotFound => "REFUND_NOT_FOUND",
+            Self::PaymentNotRefundable => "PAYMENT_NOT_REFUNDABLE",
+            Self::RefundExceedsPayment => "REFUND_EXCEEDS_PAYMENT",
+            Self::PoolValuationNotFound => "POOL_VALUATION_NOT_FOUND",
+            Self::InvalidPoolValuation => "INVALID_POOL_VALUATION",
+            Self::PoolRuleNotFound => "POOL_RULE_NOT_FOUND",
+            Self::InvalidPoolRule => "INVALID_POOL_RULE",
+            Self::PoolStatementNotFound => "POOL_STATEMENT_NOT_FOUND",
+            Self::NothingToAdjust => "NOTHING_TO_ADJUST",
+            Self::ContributionRuleNotFound => "CONTRIBUTION_RULE_NOT_FOUND",
+            Self::InvalidContributionRule => "INVALID_CONTRIBUTION_RULE",
+            Self::UseContributionRule => "USE_CONTRIBUTION_RULE",
 
             // Image
             Self::InvalidBase64Image => "INVALID_BASE64_IMAGE",
@@ -292,6 +386,11 @@ impl AppError {
         Self::Coded(code, code.as_str().to_string())
     }
 
+    /// Create a coded conflict error
+    pub fn conflict(code: ErrorCode) -> Self {
+        Self::Coded(code, code.as_str().to_string())
+    }
+
     /// Create a coded validation error
     pub fn validation(code: ErrorCode) -> Self {
         Self::Coded(code, code.as_str().to_string())
@@ -414,7 +513,9 @@ impl IntoResponse for AppError {
                     | ErrorCode::ParticipantAlreadyClaimed
                     | ErrorCode::ParticipantAlreadyLinked
                     | ErrorCode::AlreadyHasParticipant
-                    | ErrorCode::MemberAlreadyActive => StatusCode::CONFLICT,
+                    | ErrorCode::MemberAlreadyActive
+                    | ErrorCode::BalancesChanged
+                    | ErrorCode::BaseCurrencyInUse => StatusCode::CONFLICT,
 
                     // Not found errors -> 404
                     ErrorCode::NotFound
@@ -425,7 +526,18 @@ impl IntoResponse for AppError {
                     | ErrorCode::MemberNotFound
                     | ErrorCode::InviteNotFound
                     | ErrorCode::RecoveryNotFound
-                    | ErrorCode::ApprovalNotFound => StatusCode::NOT_FOUND,
+                    | ErrorCode::ApprovalNotFound
+                    | ErrorCode::ExchangeRateNotFound
+                    | ErrorCode::OccurrenceNotFound
+                    | ErrorCode::OccurrenceExceptionNotFound
+                    | ErrorCode::HolidayNotFound
+                    | ErrorCode::PresenceNotFound
+                    | ErrorCode::WeightProfileNotFound
+                    | ErrorCode::RefundNotFound
+                    | ErrorCode::PoolValuationNotFound
+                    | ErrorCode::PoolRuleNotFound
+                    | ErrorCode::PoolStatementNotFound
+                    | ErrorCode::ContributionRuleNotFound => StatusCode::NOT_FOUND,
 
                     // Permission errors -> 403
                     ErrorCode::Forbidden
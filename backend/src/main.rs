@@ -143,7 +143,16 @@ async fn main() {
         .nest("/members", routes::members::router())
         .nest("/payments", routes::payments::router())
         .nest("/debts", routes::debts::router())
-        .nest("/history", routes::history::router());
+        .nest("/exchange-rates", routes::exchange_rates::router())
+        .nest("/holidays", routes::holidays::router())
+        .nest("/presence", routes::presence::router())
+        .nest("/pool-valuations", routes::pool_valuations::router())
+        .nest("/pool-rules", routes::pool_rules::router())
+        .nest("/pool-statements", routes::pool_statements::router())
+        .nest("/contribution-rules", routes::contribution_rules::router())
+        .nest("/weight-profiles", routes::weight_profiles::router())
+        .nest("/history", routes::history::router())
+        .nest("/warnings", routes::warnings::router());
 
     // Build router - all routes at root level (use reverse proxy for /api prefix if needed)
     let app = Router::new()
@@ -0,0 +1,271 @@
+//! Scheduled amount changes for recurring payments
+//!
+//! A schedule is stored as JSON on the payment, either explicit steps
+//!
+//! `{"type": "steps", "steps": [{"effective_from": "2025-07-01", "amount": 120.0}]}`
+//!
+//! or a yearly indexation
+//!
+//! `{"type": "yearly", "percent": 2.5, "round_to": 1.0, "first_increase": "2026-07-01"}`
+//!
+//! Each occurrence is billed the amount in effect on its date; before the
+//! first change it is the payment's own amount.
+
+use chrono::{Months, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+use super::{Money, MAX_AMOUNT};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AmountSchedule {
+    /// Explicit amounts, each from its date on
+    Steps { steps: Vec<AmountStep> },
+    /// Increase by `percent` every year, compounded, rounding each new amount
+    /// to a multiple of `round_to` (default: the minor unit) and stopping at
+    /// `MAX_AMOUNT`. Increases fall on
+    /// `first_increase` and its anniversaries, by default on the anniversaries
+    /// of the payment date.
+    Yearly {
+        percent: f64,
+        #[serde(default)]
+        round_to: Option<Money>,
+        #[serde(default)]
+        first_increase: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AmountStep {
+    pub effective_from: String,
+    pub amount: Money,
+}
+
+fn parse_date(date: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()
+}
+
+impl AmountSchedule {
+    /// Parse a stored or submitted schedule; None if it is not valid JSON or
+    /// does not describe a usable schedule
+    pub fn parse(json: &str) -> Option<Self> {
+        serde_json::from_str::<Self>(json)
+            .ok()
+            .filter(Self::is_valid)
+    }
+
+    fn is_valid(&self) -> bool {
+        match self {
+            Self::Steps { steps } => {
+                !steps.is_empty()
+                    && steps.iter().all(|step| {
+                        parse_date(&step.effective_from).is_some() && step.amount.is_positive()
+                    })
+            }
+            Self::Yearly {
+                percent,
+                round_to,
+                first_increase,
+            } => {
+                percent.is_finite()
+                    && *percent > -100.0
+                    && round_to.is_none_or(|unit| unit.is_positive())
+                    && first_increase
+                        .as_deref()
+                        .is_none_or(|d| parse_date(d).is_some())
+            }
+        }
+    }
+
+    /// Amount billed on `date` by a payment of `base` starting on `start`
+    pub fn amount_on(&self, base: Money, start: NaiveDate, date: NaiveDate) -> Money {
+        match self {
+            Self::Steps { steps } => steps
+                .iter()
+                .filter_map(|step| Some((parse_date(&step.effective_from)?, step.amount)))
+                .filter(|(from, _)| *from <= date)
+                .max_by_key(|(from, _)| *from)
+                .map_or(base, |(_, amount)| amount),
+            Self::Yearly {
+                percent, round_to, ..
+            } => {
+                let unit = round_to.unwrap_or(Money::from_minor(1, base.scale()));
+                let increases = self
+                    .increase_dates(start)
+                    .take_while(|increase| *increase <= date)
+                    .count();
+                let limit = Money::from_major(MAX_AMOUNT);
+                (0..increases).fold(base, |amount, _| {
+                    amount
+                        .mul_ratio(100.0 + percent, 100.0)
+                        .min(limit)
+                        .round_to(unit)
+                })
+            }
+        }
+    }
+
+    /// Schedule of a series continued from `from` with the amount in effect
+    /// on that date as its own: changes already applied are dropped and
+    /// yearly increases keep falling on the same days. None once no change
+    /// is left.
+    pub fn continued_from(&self, start: NaiveDate, from: NaiveDate) -> Option<Self> {
+        match self {
+            Self::Steps { steps } => {
+                let steps: Vec<AmountStep> = steps
+                    .iter()
+                    .filter(|step| parse_date(&step.effective_from).is_some_and(|d| d > from))
+                    .cloned()
+                    .collect();
+                (!steps.is_empty()).then_some(Self::Steps { steps })
+            }
+            Self::Yearly {
+                percent, round_to, ..
+            } => Some(Self::Yearly {
+                percent: *percent,
+                round_to: *round_to,
+                first_increase: self
+                    .increase_dates(start)
+                    .find(|increase| *increase > from)
+                    .map(|d| d.format("%Y-%m-%d").to_string()),
+            }),
+        }
+    }
+
+    /// Dates of the yearly increases, in order
+    fn increase_dates(&self, start: NaiveDate) -> impl Iterator<Item = NaiveDate> {
+        let first = match self {
+            Self::Yearly {
+                first_increase: Some(first),
+                ..
+            } => parse_date(first),
+            Self::Yearly { .. } => start.checked_add_months(Months::new(12)),
+            Self::Steps { .. } => None,
+        };
+        (0u32..)
+            .map_while(move |year| first?.checked_add_months(Months::new(year.checked_mul(12)?)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn money(value: f64) -> Money {
+        Money::from_f64(value, 2)
+    }
+
+    #[test]
+    fn test_steps_apply_from_their_date() {
+        let schedule = AmountSchedule::parse(
+            r#"{"type": "steps", "steps": [
+                {"effective_from": "2025-10-01", "amount": 80.0},
+                {"effective_from": "2025-04-01", "amount": 60.0}
+            ]}"#,
+        )
+        .unwrap();
+        let start = date("2025-01-01");
+        let amount = |d| schedule.amount_on(money(100.0), start, date(d));
+
+        assert_eq!(amount("2025-03-31"), money(100.0));
+        assert_eq!(amount("2025-04-01"), money(60.0));
+        assert_eq!(amount("2025-09-30"), money(60.0));
+        assert_eq!(amount("2026-01-01"), money(80.0));
+    }
+
+    #[test]
+    fn test_yearly_increase_compounds_and_rounds() {
+        let schedule =
+            AmountSchedule::parse(r#"{"type": "yearly", "percent": 3, "round_to": 1}"#).unwrap();
+        let start = date("2024-07-01");
+        let amount = |d| schedule.amount_on(money(1000.0), start, date(d));
+
+        assert_eq!(amount("2025-06-30"), money(1000.0));
+        assert_eq!(amount("2025-07-01"), money(1030.0));
+        // 1030 * 1.03 = 1060.90, rounded to 1061
+        assert_eq!(amount("2026-07-01"), money(1061.0));
+        // 1061 * 1.03 = 1092.83, rounded to 1093
+        assert_eq!(amount("2027-07-01"), money(1093.0));
+    }
+
+    #[test]
+    fn test_yearly_increase_on_given_day() {
+        let schedule = AmountSchedule::parse(
+            r#"{"type": "yearly", "percent": 10, "first_increase": "2025-01-01"}"#,
+        )
+        .unwrap();
+        let start = date("2024-07-01");
+        let amount = |d| schedule.amount_on(money(100.0), start, date(d));
+
+        assert_eq!(amount("2024-12-31"), money(100.0));
+        assert_eq!(amount("2025-01-01"), money(110.0));
+        assert_eq!(amount("2026-01-01"), money(121.0));
+    }
+
+    #[test]
+    fn test_yearly_increase_stops_at_the_limit() {
+        let schedule = AmountSchedule::parse(r#"{"type": "yearly", "percent": 100}"#).unwrap();
+        let start = date("2000-01-01");
+        let amount = schedule.amount_on(money(1000.0), start, date("2200-01-01"));
+        assert_eq!(amount, Money::from_major(MAX_AMOUNT));
+    }
+
+    #[test]
+    fn test_invalid_schedules_rejected() {
+        for json in [
+            r#"{"type": "steps", "steps": []}"#,
+            r#"{"type": "steps", "steps": [{"effective_from": "soon", "amount": 10}]}"#,
+            r#"{"type": "steps", "steps": [{"effective_from": "2025-01-01", "amount": 0}]}"#,
+            r#"{"type": "yearly", "percent": -100}"#,
+            r#"{"type": "yearly", "percent": 2, "round_to": 0}"#,
+            r#"{"type": "monthly", "percent": 2}"#,
+            "not json",
+        ] {
+            assert_eq!(AmountSchedule::parse(json), None, "{}", json);
+        }
+    }
+
+    #[test]
+    fn test_continued_schedule_keeps_increase_days() {
+        let start = date("2024-07-01");
+        let yearly = AmountSchedule::parse(r#"{"type": "yearly", "percent": 3}"#).unwrap();
+        let continued = yearly.continued_from(start, date("2025-09-01")).unwrap();
+        let from = date("2025-09-01");
+
+        assert_eq!(
+            continued.amount_on(money(500.0), from, date("2026-06-30")),
+            money(500.0)
+        );
+        assert_eq!(
+            continued.amount_on(money(500.0), from, date("2026-07-01")),
+            money(515.0)
+        );
+
+        let steps = AmountSchedule::Steps {
+            steps: vec![
+                AmountStep {
+                    effective_from: "2025-01-01".to_string(),
+                    amount: money(60.0),
+                },
+                AmountStep {
+                    effective_from: "2026-01-01".to_string(),
+                    amount: money(80.0),
+                },
+            ],
+        };
+        assert_eq!(
+            steps.continued_from(start, date("2025-09-01")),
+            Some(AmountSchedule::Steps {
+                steps: vec![AmountStep {
+                    effective_from: "2026-01-01".to_string(),
+                    amount: money(80.0),
+                }],
+            })
+        );
+        assert_eq!(steps.continued_from(start, date("2026-01-01")), None);
+    }
+}
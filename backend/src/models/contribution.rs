@@ -1,13 +1,17 @@
 use serde::Serialize;
 use sqlx::FromRow;
 
+use super::Money;
+
 #[derive(Debug, Clone, FromRow, Serialize)]
 pub struct Contribution {
     pub id: i64,
     pub participant_id: i64,
     pub payment_id: i64,
-    pub amount: f64,
+    pub amount: Money,
     pub weight: f64,
+    pub cap: Option<Money>,
+    pub floor: Option<Money>,
 }
 
 #[derive(Debug, Clone, FromRow, Serialize)]
@@ -16,6 +20,8 @@ pub struct ContributionWithParticipant {
     pub participant_id: i64,
     pub participant_name: String,
     pub payment_id: i64,
-    pub amount: f64,
+    pub amount: Money,
     pub weight: f64,
+    pub cap: Option<Money>,
+    pub floor: Option<Money>,
 }
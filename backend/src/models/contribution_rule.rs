@@ -0,0 +1,61 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+use super::{bounded::ShortString, Money, RecurrenceSet};
+
+/// Deposits participants are expected to make to a pool, on a schedule. A
+/// rule moves no money: it raises the pool's expected minimum, and each
+/// participant's, by their amount on every date.
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct ContributionRule {
+    pub id: i64,
+    pub project_id: i64,
+    pub pool_id: i64,
+    // RRULE of the due dates (NULL = once, on the start date)
+    pub recurrence_rule: Option<String>,
+    pub start_date: String,
+    pub end_date: Option<String>,
+    pub description: String,
+    pub created_at: String,
+}
+
+impl ContributionRule {
+    pub fn recurrence(&self) -> Option<RecurrenceSet> {
+        RecurrenceSet::parse(self.recurrence_rule.as_deref()?)
+    }
+}
+
+/// What one participant is expected to deposit on each date of a rule
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct ContributionRuleAmount {
+    pub id: i64,
+    pub rule_id: i64,
+    pub participant_id: i64,
+    pub participant_name: String,
+    // In the base currency
+    pub amount: Money,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ContributionRuleWithAmounts {
+    #[serde(flatten)]
+    pub rule: ContributionRule,
+    pub amounts: Vec<ContributionRuleAmount>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CreateContributionRuleAmount {
+    pub participant_id: i64,
+    pub amount: Money,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateContributionRule {
+    pub pool_id: i64,
+    // Omit for a single deposit on the start date
+    pub recurrence_rule: Option<String>,
+    pub start_date: ShortString,
+    pub end_date: Option<ShortString>,
+    pub description: Option<ShortString>,
+    pub amounts: Vec<CreateContributionRuleAmount>,
+}
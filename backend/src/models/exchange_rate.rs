@@ -0,0 +1,45 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+use super::bounded::ShortString;
+
+/// Stored exchange rate: one unit of `base_currency` = `rate` units of `quote_currency`
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct ExchangeRate {
+    pub id: i64,
+    pub project_id: i64,
+    pub base_currency: String,
+    pub quote_currency: String,
+    pub rate_date: String,
+    pub rate: f64,
+    pub source: String, // 'manual' or 'import'
+    pub created_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateExchangeRate {
+    /// Defaults to the project's base currency
+    pub base_currency: Option<ShortString>,
+    pub quote_currency: ShortString,
+    pub rate_date: ShortString,
+    pub rate: f64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ImportExchangeRates {
+    /// CSV content in ECB format (first column Date, one column per currency)
+    pub csv: String,
+    /// Currency the file's rates are quoted against (ECB files use EUR)
+    pub base_currency: Option<ShortString>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportExchangeRatesResult {
+    pub imported: usize,
+    pub skipped: usize,
+}
+
+/// Check for a three-letter uppercase ISO 4217 style code (e.g. "EUR", "CAD")
+pub fn is_valid_currency_code(code: &str) -> bool {
+    code.len() == 3 && code.bytes().all(|b| b.is_ascii_uppercase())
+}
@@ -11,6 +11,16 @@ pub enum EntityType {
     ProjectMember,
     Project,
     ParticipantInvite,
+    ExchangeRate,
+    OccurrenceException,
+    Holiday,
+    Presence,
+    WeightProfile,
+    Refund,
+    PoolValuation,
+    PoolRule,
+    PoolStatement,
+    ContributionRule,
 }
 
 impl EntityType {
@@ -22,6 +32,16 @@ impl EntityType {
             EntityType::ProjectMember => "project_member",
             EntityType::Project => "project",
             EntityType::ParticipantInvite => "participant_invite",
+            EntityType::ExchangeRate => "exchange_rate",
+            EntityType::OccurrenceException => "occurrence_exception",
+            EntityType::Holiday => "holiday",
+            EntityType::Presence => "presence",
+            EntityType::WeightProfile => "weight_profile",
+            EntityType::Refund => "refund",
+            EntityType::PoolValuation => "pool_valuation",
+            EntityType::PoolRule => "pool_rule",
+            EntityType::PoolStatement => "pool_statement",
+            EntityType::ContributionRule => "contribution_rule",
         }
     }
 }
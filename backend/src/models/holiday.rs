@@ -0,0 +1,20 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+use super::bounded::ShortString;
+
+/// A project holiday: not a business day when shifting recurring payments
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct Holiday {
+    pub id: i64,
+    pub project_id: i64,
+    pub holiday_date: String,
+    pub name: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateHoliday {
+    pub holiday_date: ShortString,
+    pub name: Option<ShortString>,
+}
@@ -1,23 +1,59 @@
+pub mod amount_schedule;
 pub mod approval;
 pub mod bounded;
 pub mod contribution;
+pub mod contribution_rule;
+pub mod exchange_rate;
 pub mod history;
+pub mod holiday;
 pub mod member;
+pub mod money;
+pub mod occurrence_exception;
 pub mod participant;
+pub mod payer;
 pub mod payment;
+pub mod payment_item;
+pub mod pool_rule;
+pub mod pool_statement;
+pub mod pool_valuation;
+pub mod presence;
 pub mod project;
 pub mod recovery_intent;
+pub mod recurrence;
+pub mod refund;
+pub mod settlement;
+pub mod split;
 pub mod trusted_user;
 pub mod user;
+pub mod warning;
+pub mod weight_profile;
 
+pub use amount_schedule::*;
 pub use approval::*;
 pub use bounded::*;
 pub use contribution::*;
+pub use contribution_rule::*;
+pub use exchange_rate::*;
 pub use history::*;
+pub use holiday::*;
 pub use member::*;
+pub use money::*;
+pub use occurrence_exception::*;
 pub use participant::*;
+pub use payer::*;
 pub use payment::*;
+pub use payment_item::*;
+pub use pool_rule::*;
+pub use pool_statement::*;
+pub use pool_valuation::*;
+pub use presence::*;
 pub use project::*;
 pub use recovery_intent::*;
+pub use recurrence::*;
+pub use refund::*;
+pub use settlement::*;
+pub use split::*;
 pub use trusted_user::*;
 pub use user::*;
+pub use warning::*;
+pub use weight_profile::*;
@@ -0,0 +1,745 @@
+//! Fixed-point money type
+//!
+//! Amounts are kept as integer minor units (e.g. cents) together with the number
+//! of decimal places of their currency. Sums are exact, so balances no longer
+//! drift and splits can be checked against the payment amount to the unit.
+//!
+//! The database stores every amount in thousandths, the finest minor unit of
+//! any currency; amounts read back use cents unless they need the third
+//! decimal.
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use sqlx::{
+    encode::IsNull, error::BoxDynError, sqlite::SqliteTypeInfo, Database, Decode, Encode, Sqlite,
+    Type,
+};
+use std::cmp::Ordering;
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Neg, Sub, SubAssign};
+
+/// Decimal places used when no currency is known (cents)
+pub const DEFAULT_SCALE: u32 = 2;
+
+/// Largest supported number of decimal places
+pub const MAX_SCALE: u32 = 6;
+
+/// Decimal places amounts are stored and accepted with: thousandths, as for
+/// the Kuwaiti dinar
+pub const STORAGE_SCALE: u32 = 3;
+
+/// Largest amount accepted, in major units. Keeps every sum the ledger builds
+/// from accepted amounts far within `i64` minor units.
+pub const MAX_AMOUNT: i64 = 1_000_000_000;
+
+/// Decimal places of a currency's minor unit (ISO 4217 exponent): none for
+/// the yen or the won, three for the dinars of Bahrain, Iraq, Jordan, Kuwait,
+/// Libya and Tunisia and for the Omani rial, two otherwise
+pub fn currency_scale(code: &str) -> u32 {
+    match code {
+        "BIF" | "CLP" | "DJF" | "GNF" | "ISK" | "JPY" | "KMF" | "KRW" | "PYG" | "RWF" | "UGX"
+        | "UYI" | "VND" | "VUV" | "XAF" | "XOF" | "XPF" => 0,
+        "BHD" | "IQD" | "JOD" | "KWD" | "LYD" | "OMR" | "TND" => 3,
+        _ => DEFAULT_SCALE,
+    }
+}
+
+/// Tolerance used when reading an `f64` that should already be on the minor-unit grid
+const F64_GRID_TOLERANCE: f64 = 1e-6;
+
+/// An exact amount of money: `minor` units at `scale` decimal places.
+///
+/// Values with different scales can be combined; the result uses the finer
+/// scale so no precision is lost. Serialized to JSON as a plain decimal number
+/// for API compatibility.
+#[derive(Debug, Clone, Copy)]
+pub struct Money {
+    minor: i64,
+    scale: u32,
+}
+
+fn pow10(exp: u32) -> i64 {
+    10i64.pow(exp)
+}
+
+impl Money {
+    /// Zero at the default scale
+    pub const ZERO: Money = Money {
+        minor: 0,
+        scale: DEFAULT_SCALE,
+    };
+
+    /// Build an amount from minor units at the given scale
+    pub fn from_minor(minor: i64, scale: u32) -> Self {
+        Self {
+            minor,
+            scale: scale.min(MAX_SCALE),
+        }
+    }
+
+    /// Build an amount from whole major units (e.g. dollars) at the default scale
+    pub fn from_major(major: i64) -> Self {
+        Self::from_minor(major * pow10(DEFAULT_SCALE), DEFAULT_SCALE)
+    }
+
+    /// Convert an `f64` to money, rounding half away from zero to `scale` decimals.
+    /// Only meant for legacy data (REAL columns, old history payloads).
+    pub fn from_f64(value: f64, scale: u32) -> Self {
+        let scale = scale.min(MAX_SCALE);
+        Self::from_minor((value * pow10(scale) as f64).round() as i64, scale)
+    }
+
+    /// Convert an `f64` to money, returning None if it is not finite or has
+    /// more decimal places than `scale` allows.
+    pub fn try_from_f64(value: f64, scale: u32) -> Option<Self> {
+        if !value.is_finite() {
+            return None;
+        }
+        let scale = scale.min(MAX_SCALE);
+        let scaled = value * pow10(scale) as f64;
+        let rounded = scaled.round();
+        if (scaled - rounded).abs() > F64_GRID_TOLERANCE || rounded.abs() >= i64::MAX as f64 {
+            return None;
+        }
+        Some(Self::from_minor(rounded as i64, scale))
+    }
+
+    /// Parse a decimal string such as "12.34" or "-0.5".
+    /// Returns None if the string is malformed or has more than `scale` decimals.
+    pub fn parse(input: &str, scale: u32) -> Option<Self> {
+        let scale = scale.min(MAX_SCALE);
+        let input = input.trim();
+        let (negative, digits) = match input.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, input.strip_prefix('+').unwrap_or(input)),
+        };
+        let (whole, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+        if whole.is_empty() && fraction.is_empty() {
+            return None;
+        }
+        if !whole.chars().all(|c| c.is_ascii_digit())
+            || !fraction.chars().all(|c| c.is_ascii_digit())
+        {
+            return None;
+        }
+        // Trailing zeros beyond the scale are harmless ("1.500" at scale 2)
+        let fraction = fraction.trim_end_matches('0');
+        if fraction.len() > scale as usize {
+            return None;
+        }
+
+        let whole: i64 = if whole.is_empty() {
+            0
+        } else {
+            whole.parse().ok()?
+        };
+        let fraction_minor: i64 = if fraction.is_empty() {
+            0
+        } else {
+            fraction.parse::<i64>().ok()? * pow10(scale - fraction.len() as u32)
+        };
+        let minor = whole
+            .checked_mul(pow10(scale))?
+            .checked_add(fraction_minor)?;
+        Some(Self::from_minor(
+            if negative { -minor } else { minor },
+            scale,
+        ))
+    }
+
+    /// Amount in minor units at this value's own scale
+    pub fn minor(&self) -> i64 {
+        self.minor
+    }
+
+    /// Number of decimal places
+    pub fn scale(&self) -> u32 {
+        self.scale
+    }
+
+    /// Approximate value as `f64`, for display and ratio calculations only
+    pub fn to_f64(&self) -> f64 {
+        self.minor as f64 / pow10(self.scale) as f64
+    }
+
+    /// Express the amount at another scale, rounding half away from zero when
+    /// the target scale is coarser.
+    pub fn rescale(&self, scale: u32) -> Self {
+        let scale = scale.min(MAX_SCALE);
+        match scale.cmp(&self.scale) {
+            Ordering::Equal => *self,
+            Ordering::Greater => Self::from_minor(self.minor * pow10(scale - self.scale), scale),
+            Ordering::Less => {
+                let divisor = pow10(self.scale - scale);
+                let half = divisor / 2;
+                let minor = if self.minor >= 0 {
+                    (self.minor + half) / divisor
+                } else {
+                    (self.minor - half) / divisor
+                };
+                Self::from_minor(minor, scale)
+            }
+        }
+    }
+
+    /// Multiply by `factor` and round to `scale` decimals, e.g. to convert to
+    /// a currency with its own minor unit
+    pub fn mul_to_scale(&self, factor: f64, scale: u32) -> Self {
+        let scale = scale.min(MAX_SCALE);
+        let minor = (self.minor as f64 * factor * pow10(scale) as f64 / pow10(self.scale) as f64)
+            .round() as i64;
+        Self::from_minor(minor, scale)
+    }
+
+    /// Whether the amount is exact at `scale` decimals
+    pub fn fits_scale(&self, scale: u32) -> bool {
+        self.rescale(scale) == *self
+    }
+
+    /// Whether the amount is within `MAX_AMOUNT` either way
+    pub fn is_within_limit(&self) -> bool {
+        self.abs() <= Self::from_major(MAX_AMOUNT)
+    }
+
+    /// The same amount at the coarsest scale, down to `min_scale`, that still
+    /// represents it exactly
+    fn trimmed(self, min_scale: u32) -> Self {
+        let mut money = self;
+        while money.scale > min_scale && money.minor % 10 == 0 {
+            money = Self::from_minor(money.minor / 10, money.scale - 1);
+        }
+        money
+    }
+
+    /// Multiply by `numerator / denominator` and round to this value's scale.
+    /// Used for weighted shares; the caller must ensure the denominator is non-zero.
+    pub fn mul_ratio(&self, numerator: f64, denominator: f64) -> Self {
+        let minor = (self.minor as f64 * numerator / denominator).round() as i64;
+        Self::from_minor(minor, self.scale)
+    }
+
+    /// Split this amount by weight using the largest-remainder method.
+    ///
+    /// Each entry is `(key, weight)`. Every share is first rounded down to a whole
+    /// minor unit; the units left over go one at a time to the shares with the
+    /// largest fractional remainder, ties broken by the smallest key (e.g. the
+    /// participant id). The result is in input order and always sums exactly to
+    /// `self` as long as at least one weight is positive. Entries with a
+    /// non-positive weight get zero; if no weight is positive, every share is zero.
+    pub fn allocate(&self, weights: &[(i64, f64)]) -> Vec<Money> {
+        let total_weight: f64 = weights.iter().map(|(_, w)| w.max(0.0)).sum();
+        if total_weight <= 0.0 {
+            return vec![Self::from_minor(0, self.scale); weights.len()];
+        }
+
+        // Work on the magnitude so negative amounts split symmetrically
+        let sign = if self.minor < 0 { -1 } else { 1 };
+        let total = self.minor.abs();
+
+        let mut units = Vec::with_capacity(weights.len());
+        let mut remainders = Vec::with_capacity(weights.len());
+        for (_, weight) in weights {
+            let exact = total as f64 * weight.max(0.0) / total_weight;
+            let floor = exact.floor();
+            units.push(floor as i64);
+            remainders.push(exact - floor);
+        }
+
+        // Only positively weighted entries may receive or give up leftover units
+        let mut order: Vec<usize> = (0..weights.len()).filter(|&i| weights[i].1 > 0.0).collect();
+        order.sort_by(|&a, &b| {
+            remainders[b]
+                .total_cmp(&remainders[a])
+                .then(weights[a].0.cmp(&weights[b].0))
+                .then(a.cmp(&b))
+        });
+
+        let mut leftover = total - units.iter().sum::<i64>();
+        // Floating-point error can make the floors overshoot; take back from the
+        // smallest remainders first in that case
+        while leftover < 0 {
+            for &i in order.iter().rev() {
+                if leftover == 0 {
+                    break;
+                }
+                if units[i] > 0 {
+                    units[i] -= 1;
+                    leftover += 1;
+                }
+            }
+        }
+        while leftover > 0 {
+            for &i in &order {
+                if leftover == 0 {
+                    break;
+                }
+                units[i] += 1;
+                leftover -= 1;
+            }
+        }
+
+        units
+            .into_iter()
+            .map(|u| Self::from_minor(sign * u, self.scale))
+            .collect()
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.minor == 0
+    }
+
+    pub fn is_positive(&self) -> bool {
+        self.minor > 0
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.minor < 0
+    }
+
+    pub fn abs(&self) -> Self {
+        Self::from_minor(self.minor.abs(), self.scale)
+    }
+
+    /// Round to the nearest multiple of `unit` (e.g. 0.05 or 1.00), halves away
+    /// from zero. A non-positive unit leaves the amount unchanged.
+    pub fn round_to(&self, unit: Money) -> Self {
+        if !unit.is_positive() {
+            return *self;
+        }
+        let (value, step, scale) = self.aligned(unit);
+        let steps = (value.abs() * 2 + step) / (step * 2);
+        Self::from_minor(value.signum() * steps * step, scale)
+    }
+
+    /// Bring two values to a common (the finer) scale
+    fn aligned(self, other: Self) -> (i64, i64, u32) {
+        let scale = self.scale.max(other.scale);
+        (self.rescale(scale).minor, other.rescale(scale).minor, scale)
+    }
+}
+
+impl Default for Money {
+    fn default() -> Self {
+        Self::ZERO
+    }
+}
+
+impl PartialEq for Money {
+    fn eq(&self, other: &Self) -> bool {
+        let (a, b, _) = self.aligned(*other);
+        a == b
+    }
+}
+
+impl Eq for Money {}
+
+impl PartialOrd for Money {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Money {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let (a, b, _) = self.aligned(*other);
+        a.cmp(&b)
+    }
+}
+
+impl Add for Money {
+    type Output = Money;
+
+    fn add(self, other: Money) -> Money {
+        let (a, b, scale) = self.aligned(other);
+        Money::from_minor(a + b, scale)
+    }
+}
+
+impl Sub for Money {
+    type Output = Money;
+
+    fn sub(self, other: Money) -> Money {
+        let (a, b, scale) = self.aligned(other);
+        Money::from_minor(a - b, scale)
+    }
+}
+
+impl Neg for Money {
+    type Output = Money;
+
+    fn neg(self) -> Money {
+        Money::from_minor(-self.minor, self.scale)
+    }
+}
+
+impl AddAssign for Money {
+    fn add_assign(&mut self, other: Money) {
+        *self = *self + other;
+    }
+}
+
+impl SubAssign for Money {
+    fn sub_assign(&mut self, other: Money) {
+        *self = *self - other;
+    }
+}
+
+impl Sum for Money {
+    fn sum<I: Iterator<Item = Money>>(iter: I) -> Money {
+        iter.fold(Money::ZERO, |acc, m| acc + m)
+    }
+}
+
+impl<'a> Sum<&'a Money> for Money {
+    fn sum<I: Iterator<Item = &'a Money>>(iter: I) -> Money {
+        iter.fold(Money::ZERO, |acc, m| acc + *m)
+    }
+}
+
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.minor < 0 { "-" } else { "" };
+        let abs = self.minor.unsigned_abs();
+        if self.scale == 0 {
+            return write!(f, "{}{}", sign, abs);
+        }
+        let divisor = pow10(self.scale) as u64;
+        write!(
+            f,
+            "{}{}.{:0width$}",
+            sign,
+            abs / divisor,
+            abs % divisor,
+            width = self.scale as usize
+        )
+    }
+}
+
+impl Serialize for Money {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(self.to_f64())
+    }
+}
+
+impl<'de> Deserialize<'de> for Money {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct MoneyVisitor;
+
+        impl de::Visitor<'_> for MoneyVisitor {
+            type Value = Money;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(
+                    f,
+                    "a decimal amount with at most {} decimal places",
+                    STORAGE_SCALE
+                )
+            }
+
+            fn visit_i64<E: de::Error>(self, v: i64) -> Result<Money, E> {
+                within_limit(
+                    v.checked_mul(pow10(DEFAULT_SCALE))
+                        .map(|minor| Money::from_minor(minor, DEFAULT_SCALE)),
+                )
+            }
+
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<Money, E> {
+                i64::try_from(v)
+                    .map_err(|_| E::custom("amount out of range"))
+                    .and_then(|v| self.visit_i64(v))
+            }
+
+            fn visit_f64<E: de::Error>(self, v: f64) -> Result<Money, E> {
+                let money = Money::try_from_f64(v, STORAGE_SCALE)
+                    .ok_or_else(|| E::custom("amount has too many decimal places"))?;
+                within_limit(Some(money.trimmed(DEFAULT_SCALE)))
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Money, E> {
+                let money =
+                    Money::parse(v, STORAGE_SCALE).ok_or_else(|| E::custom("invalid amount"))?;
+                within_limit(Some(money.trimmed(DEFAULT_SCALE)))
+            }
+        }
+
+        fn within_limit<E: de::Error>(money: Option<Money>) -> Result<Money, E> {
+            money
+                .filter(Money::is_within_limit)
+                .ok_or_else(|| E::custom("amount out of range"))
+        }
+
+        deserializer.deserialize_any(MoneyVisitor)
+    }
+}
+
+// SQLite storage: INTEGER thousandths
+
+impl Type<Sqlite> for Money {
+    fn type_info() -> SqliteTypeInfo {
+        <i64 as Type<Sqlite>>::type_info()
+    }
+
+    fn compatible(ty: &SqliteTypeInfo) -> bool {
+        <i64 as Type<Sqlite>>::compatible(ty)
+    }
+}
+
+impl Encode<'_, Sqlite> for Money {
+    fn encode_by_ref(
+        &self,
+        buf: &mut <Sqlite as Database>::ArgumentBuffer,
+    ) -> Result<IsNull, BoxDynError> {
+        <i64 as Encode<Sqlite>>::encode(self.rescale(STORAGE_SCALE).minor, buf)
+    }
+}
+
+impl<'r> Decode<'r, Sqlite> for Money {
+    fn decode(value: <Sqlite as Database>::ValueRef<'r>) -> Result<Self, BoxDynError> {
+        let minor = <i64 as Decode<Sqlite>>::decode(value)?;
+        Ok(Money::from_minor(minor, STORAGE_SCALE).trimmed(DEFAULT_SCALE))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sum_is_exact() {
+        // 0.1 + 0.2 drifts with f64; minor units do not
+        let total: Money = [Money::from_minor(10, 2), Money::from_minor(20, 2)]
+            .iter()
+            .sum();
+        assert_eq!(total, Money::from_minor(30, 2));
+        assert_eq!(total.to_string(), "0.30");
+    }
+
+    #[test]
+    fn test_mixed_scales_use_finer_scale() {
+        let yen = Money::from_minor(100, 0);
+        let dinar = Money::from_minor(1, 3);
+        let total = yen + dinar;
+        assert_eq!(total.scale(), 3);
+        assert_eq!(total.minor(), 100_001);
+        assert_eq!(Money::from_minor(5, 0), Money::from_minor(500, 2));
+    }
+
+    #[test]
+    fn test_rescale_rounds_half_away_from_zero() {
+        assert_eq!(Money::from_minor(125, 3).rescale(2).minor(), 13);
+        assert_eq!(Money::from_minor(-125, 3).rescale(2).minor(), -13);
+        assert_eq!(Money::from_minor(124, 3).rescale(2).minor(), 12);
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Money::parse("12.34", 2), Some(Money::from_minor(1234, 2)));
+        assert_eq!(Money::parse("-0.5", 2), Some(Money::from_minor(-50, 2)));
+        assert_eq!(Money::parse("7", 2), Some(Money::from_minor(700, 2)));
+        assert_eq!(Money::parse("1.500", 2), Some(Money::from_minor(150, 2)));
+        assert_eq!(Money::parse("1.234", 2), None);
+        assert_eq!(Money::parse("abc", 2), None);
+        assert_eq!(Money::parse("", 2), None);
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let m: Money = serde_json::from_str("33.33").unwrap();
+        assert_eq!(m, Money::from_minor(3333, 2));
+        assert_eq!(serde_json::to_string(&m).unwrap(), "33.33");
+
+        let whole: Money = serde_json::from_str("100").unwrap();
+        assert_eq!(whole, Money::from_major(100));
+
+        let text: Money = serde_json::from_str("\"19.99\"").unwrap();
+        assert_eq!(text, Money::from_minor(1999, 2));
+    }
+
+    #[test]
+    fn test_json_keeps_cents_unless_thousandths_are_needed() {
+        let m: Money = serde_json::from_str("33.3").unwrap();
+        assert_eq!(m.scale(), 2);
+        let dinar: Money = serde_json::from_str("10.005").unwrap();
+        assert_eq!((dinar.minor(), dinar.scale()), (10005, 3));
+        let dinar: Money = serde_json::from_str("\"1.250\"").unwrap();
+        assert_eq!((dinar.minor(), dinar.scale()), (125, 2));
+    }
+
+    #[test]
+    fn test_json_rejects_sub_minor_precision() {
+        let result: Result<Money, _> = serde_json::from_str("10.0005");
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_json_rejects_amounts_over_the_limit() {
+        let limit: Money = serde_json::from_str("1000000000").unwrap();
+        assert!(limit.is_within_limit());
+        for json in [
+            "1000000000.01",
+            "-1000000001",
+            "9223372036854775807",
+            "1e300",
+        ] {
+            let result: Result<Money, _> = serde_json::from_str(json);
+            assert!(result.is_err(), "{}", json);
+        }
+    }
+
+    #[test]
+    fn test_currency_scale() {
+        assert_eq!(currency_scale("EUR"), 2);
+        assert_eq!(currency_scale("JPY"), 0);
+        assert_eq!(currency_scale("KWD"), 3);
+        assert!(Money::from_major(1000).fits_scale(currency_scale("JPY")));
+        assert!(!Money::from_minor(100050, 2).fits_scale(currency_scale("JPY")));
+        assert!(Money::from_minor(1234, 3).fits_scale(currency_scale("BHD")));
+    }
+
+    #[test]
+    fn test_mul_to_scale() {
+        // 1000 yen at 0.0061 euro per yen
+        let yen = Money::from_minor(1000, 0);
+        assert_eq!(yen.mul_to_scale(0.0061, 2), Money::from_minor(610, 2));
+        // 10 euros at 163.456 yen per euro
+        let euros = Money::from_major(10);
+        let converted = euros.mul_to_scale(163.456, 0);
+        assert_eq!((converted.minor(), converted.scale()), (1635, 0));
+    }
+
+    #[test]
+    fn test_mul_ratio() {
+        let amount = Money::from_major(100);
+        assert_eq!(amount.mul_ratio(1.0, 3.0), Money::from_minor(3333, 2));
+        assert_eq!(amount.mul_ratio(2.0, 3.0), Money::from_minor(6667, 2));
+    }
+
+    fn keyed(weights: &[f64]) -> Vec<(i64, f64)> {
+        weights
+            .iter()
+            .enumerate()
+            .map(|(i, w)| (i as i64 + 1, *w))
+            .collect()
+    }
+
+    #[test]
+    fn test_allocate_three_ways_sums_to_total() {
+        let shares = Money::from_major(100).allocate(&keyed(&[1.0, 1.0, 1.0]));
+        assert_eq!(
+            shares,
+            vec![
+                Money::from_minor(3334, 2),
+                Money::from_minor(3333, 2),
+                Money::from_minor(3333, 2),
+            ]
+        );
+        assert_eq!(shares.iter().sum::<Money>(), Money::from_major(100));
+    }
+
+    #[test]
+    fn test_allocate_tie_break_uses_smallest_key() {
+        // Same weights, keys out of order: the leftover cent goes to key 2
+        let shares = Money::from_minor(100, 2).allocate(&[(7, 1.0), (2, 1.0), (5, 1.0)]);
+        assert_eq!(
+            shares,
+            vec![
+                Money::from_minor(33, 2),
+                Money::from_minor(34, 2),
+                Money::from_minor(33, 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_allocate_largest_remainder_wins() {
+        // 10.00 split 1:2 -> 3.333.. / 6.666..; the larger remainder gets the cent
+        let shares = Money::from_major(10).allocate(&keyed(&[1.0, 2.0]));
+        assert_eq!(
+            shares,
+            vec![Money::from_minor(333, 2), Money::from_minor(667, 2)]
+        );
+    }
+
+    #[test]
+    fn test_allocate_zero_weight_gets_nothing() {
+        let shares = Money::from_minor(1, 2).allocate(&keyed(&[0.0, 1.0, 1.0]));
+        assert_eq!(shares[0], Money::ZERO);
+        assert_eq!(shares.iter().sum::<Money>(), Money::from_minor(1, 2));
+    }
+
+    #[test]
+    fn test_allocate_negative_amount() {
+        let shares = Money::from_minor(-100, 2).allocate(&keyed(&[1.0, 1.0, 1.0]));
+        assert_eq!(shares[0], Money::from_minor(-34, 2));
+        assert_eq!(shares.iter().sum::<Money>(), Money::from_minor(-100, 2));
+    }
+
+    #[test]
+    fn test_allocate_no_positive_weight() {
+        let shares = Money::from_major(5).allocate(&keyed(&[0.0, 0.0]));
+        assert_eq!(shares, vec![Money::ZERO, Money::ZERO]);
+    }
+
+    #[test]
+    fn test_allocate_always_sums_to_total() {
+        let weight_sets: [&[f64]; 5] = [
+            &[1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0],
+            &[0.5, 1.5, 2.25, 0.1],
+            &[3.0, 7.0, 11.0, 13.0],
+            &[0.333, 0.333, 0.334],
+            &[1e-3, 1e3, 1.0],
+        ];
+        for minor in [1, 2, 99, 100, 101, 9_999, 123_457, 10_000_001] {
+            for weights in weight_sets {
+                let total = Money::from_minor(minor, 2);
+                let shares = total.allocate(&keyed(weights));
+                assert_eq!(shares.len(), weights.len());
+                assert_eq!(
+                    shares.iter().sum::<Money>(),
+                    total,
+                    "{} split by {:?}",
+                    total,
+                    weights
+                );
+                // No share is more than one unit away from its exact value
+                let total_weight: f64 = weights.iter().sum();
+                for (share, weight) in shares.iter().zip(weights.iter()) {
+                    let exact = minor as f64 * weight / total_weight;
+                    assert!((share.minor() as f64 - exact).abs() < 1.0);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_round_to_unit() {
+        let unit = Money::from_major(1);
+        assert_eq!(
+            Money::from_minor(1249, 2).round_to(unit),
+            Money::from_major(12)
+        );
+        assert_eq!(
+            Money::from_minor(1250, 2).round_to(unit),
+            Money::from_major(13)
+        );
+        assert_eq!(
+            Money::from_minor(-1250, 2).round_to(unit),
+            Money::from_major(-13)
+        );
+
+        let nickel = Money::from_minor(5, 2);
+        assert_eq!(
+            Money::from_minor(1232, 2).round_to(nickel),
+            Money::from_minor(1230, 2)
+        );
+        assert_eq!(
+            Money::from_minor(1233, 2).round_to(nickel),
+            Money::from_minor(1235, 2)
+        );
+
+        assert_eq!(
+            Money::from_minor(1233, 2).round_to(Money::ZERO),
+            Money::from_minor(1233, 2)
+        );
+    }
+}
@@ -0,0 +1,44 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+use super::{CreateContribution, Money};
+
+/// Change to a single occurrence of a recurring payment, keyed by the date the
+/// series would have put it on
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct OccurrenceException {
+    pub id: i64,
+    pub payment_id: i64,
+    pub original_date: String,
+    // The occurrence does not happen at all
+    pub is_skipped: bool,
+    // New date of the occurrence (NULL = unchanged)
+    pub moved_to: Option<String>,
+    // Amount in the payment's currency (NULL = the payment's amount)
+    pub amount: Option<Money>,
+    // JSON: [{"participant_id": 1, "weight": 2.0}] (NULL = the payment's split)
+    pub weights: Option<String>,
+    pub created_at: String,
+}
+
+impl OccurrenceException {
+    /// Overriding weights as (participant_id, weight) pairs
+    pub fn weights(&self) -> Option<Vec<(i64, f64)>> {
+        let contributions: Vec<CreateContribution> =
+            serde_json::from_str(self.weights.as_deref()?).ok()?;
+        Some(
+            contributions
+                .into_iter()
+                .map(|c| (c.participant_id, c.weight))
+                .collect(),
+        )
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetOccurrenceException {
+    pub skip: Option<bool>,
+    pub moved_to: Option<String>,
+    pub amount: Option<Money>,
+    pub contributions: Option<Vec<CreateContribution>>,
+}
@@ -14,6 +14,7 @@ pub struct Participant {
     pub account_type: String,                    // "user" or "pool"
     pub warning_horizon_account: Option<String>, // Pool warning horizon for account total (NULL = disabled)
     pub warning_horizon_users: Option<String>, // Pool warning horizon for user ownership (NULL = disabled)
+    pub unitized: bool, // Pool ownership tracked in units priced by the pool's valuations
 }
 
 #[derive(Debug, Deserialize)]
@@ -39,3 +40,10 @@ pub struct UpdatePoolWarningSettings {
     /// null or empty = disable, value = set
     pub warning_horizon_users: Option<WarningHorizon>,
 }
+
+#[derive(Debug, Deserialize)]
+pub struct UpdatePoolMode {
+    /// Deposits buy units at the current unit value, and valuations move
+    /// that value; ownership is then units × unit value
+    pub unitized: bool,
+}
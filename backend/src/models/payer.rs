@@ -0,0 +1,21 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+use super::Money;
+
+/// One of several payers of a payment and the part of it they paid. A
+/// payment with a single payer has no rows, only `payments.payer_id`.
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct PaymentPayerWithParticipant {
+    pub id: i64,
+    pub payment_id: i64,
+    pub participant_id: i64,
+    pub participant_name: String,
+    pub amount: Money,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CreatePaymentPayer {
+    pub participant_id: i64,
+    pub amount: Money,
+}
@@ -1,15 +1,23 @@
+use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 
+use super::{
+    AmountSchedule, BusinessDayShift, CreatePaymentItem, CreatePaymentPayer, LegacyRecurrence,
+    Money, NetCost, PaymentItem, PaymentPayerWithParticipant, RecurrenceSet, Refund, SplitMode,
+};
+
 #[derive(Debug, Clone, FromRow, Serialize)]
 pub struct Payment {
     pub id: i64,
     pub project_id: Option<i64>,
     pub payer_id: Option<i64>,
-    pub amount: f64,
+    pub amount: Money,
     pub description: String,
     pub payment_date: String,
     pub created_at: String,
+    // ISO 4217 currency code; NULL = the project's base currency
+    pub currency: Option<String>,
     // Receipt image (Base64 encoded)
     pub receipt_image: Option<String>,
     // Recurrence fields
@@ -22,6 +30,11 @@ pub struct Payment {
     pub recurrence_weekdays: Option<String>, // JSON: [[1,3],[0,5]] for week patterns
     pub recurrence_monthdays: Option<String>, // JSON: [1, 15] for monthly day selection
     pub recurrence_months: Option<String>,   // JSON: [1, 6, 12] for yearly month selection
+    // RFC 5545 recurrence, see `recurrence`: a bare RRULE (`FREQ=MONTHLY;BYDAY=-1FR`),
+    // or `RRULE:` lines (each optionally after its own `DTSTART:YYYYMMDD`) followed
+    // by `RDATE;VALUE=DATE:` / `EXDATE;VALUE=DATE:` lines of comma-separated dates.
+    // The payment date is DTSTART. The fields above are kept for older clients
+    pub recurrence_rule: Option<String>,
     // Internal transfer support
     // NULL = external expense (money leaves system)
     // NOT NULL = internal transfer (money moves between accounts, e.g., user → pool)
@@ -38,15 +51,38 @@ pub struct Payment {
     // affects_receiver_expectation: When receiver is a pool and true, increases receiver's expected minimum
     // (Used for "Earmarked" deposits to pools, and "Rules" that set expected minimums)
     pub affects_receiver_expectation: bool,
+    // Series this payment continues after an "edit this and following" split
+    pub split_from_id: Option<i64>,
+    // JSON, see `AmountSchedule`: how the amount of a recurring payment changes
+    pub amount_schedule: Option<String>,
+    // 'next' or 'previous': move occurrences off weekends and project holidays
+    pub business_day_shift: Option<String>,
+    // Charge contributors present only part of an occurrence's month for that part
+    pub prorate_presence: bool,
+    // Weight profile splitting the payment instead of its own weights
+    pub weight_profile_id: Option<i64>,
+    // See `SplitMode`; NULL = weights
+    pub split_mode: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct CreatePayment {
     pub payer_id: Option<i64>,
-    pub amount: f64,
+    // Several payers and the part each paid, summing to `amount`; replaces
+    // `payer_id` (external expenses only)
+    #[serde(default)]
+    pub payers: Vec<CreatePaymentPayer>,
+    pub amount: Money,
     pub description: String,
     pub payment_date: Option<String>,
+    // ISO 4217 currency code (omit for the project's base currency)
+    pub currency: Option<String>,
+    // Ignored when a weight profile or items are given
+    #[serde(default)]
     pub contributions: Vec<CreateContribution>,
+    // Receipt lines the contributions are derived from (see `payment_item`)
+    #[serde(default)]
+    pub items: Vec<CreatePaymentItem>,
     // Receipt image (Base64 encoded)
     pub receipt_image: Option<String>,
     // Recurrence fields
@@ -59,6 +95,18 @@ pub struct CreatePayment {
     pub recurrence_weekdays: Option<String>,  // JSON array
     pub recurrence_monthdays: Option<String>, // JSON array
     pub recurrence_months: Option<String>,    // JSON array
+    // RFC 5545 RRULE; takes precedence over the fields above
+    pub recurrence_rule: Option<String>,
+    // JSON, see `AmountSchedule` (recurring payments only)
+    pub amount_schedule: Option<String>,
+    // 'next' or 'previous' business day (recurring payments only)
+    pub business_day_shift: Option<String>,
+    // Pro-rate contributors present part of a month (default: false)
+    pub prorate_presence: Option<bool>,
+    // Split by a weight profile of the project instead of `contributions`
+    pub weight_profile_id: Option<i64>,
+    // 'weights' (default), 'exact', 'percentages' or 'shares'
+    pub split_mode: Option<String>,
     // Internal transfer: recipient account (NULL = external expense)
     pub receiver_account_id: Option<i64>,
     // Payment finalization status: true (default) = final, false = draft
@@ -72,10 +120,121 @@ pub struct CreatePayment {
     pub affects_receiver_expectation: Option<bool>,
 }
 
-#[derive(Debug, Deserialize)]
+impl Payment {
+    /// Recurrence rules of a recurring payment, converted from the legacy
+    /// columns when no RRULE is stored
+    pub fn recurrence(&self) -> Option<RecurrenceSet> {
+        if !self.is_recurring {
+            return None;
+        }
+        if let Some(set) = self
+            .recurrence_rule
+            .as_deref()
+            .and_then(RecurrenceSet::parse)
+        {
+            return Some(set);
+        }
+        let start = self
+            .payment_date
+            .get(..10)
+            .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())?;
+        Some(RecurrenceSet::from_legacy(
+            start,
+            &LegacyRecurrence {
+                recurrence_type: self.recurrence_type.as_deref(),
+                interval: self.recurrence_interval,
+                times_per: self.recurrence_times_per,
+                weekdays: self.recurrence_weekdays.as_deref(),
+                monthdays: self.recurrence_monthdays.as_deref(),
+                months: self.recurrence_months.as_deref(),
+            },
+        ))
+    }
+
+    /// Business day a recurring payment's occurrences move to when they fall
+    /// on a weekend or holiday
+    pub fn business_day_shift(&self) -> Option<BusinessDayShift> {
+        self.business_day_shift
+            .as_deref()
+            .filter(|_| self.is_recurring)
+            .and_then(BusinessDayShift::parse)
+    }
+
+    /// How the payment's amount is split among its contributions
+    pub fn split_mode(&self) -> SplitMode {
+        self.split_mode
+            .as_deref()
+            .and_then(SplitMode::parse)
+            .unwrap_or_default()
+    }
+
+    /// Amount schedule of a recurring payment
+    pub fn schedule(&self) -> Option<AmountSchedule> {
+        self.amount_schedule
+            .as_deref()
+            .filter(|_| self.is_recurring)
+            .and_then(AmountSchedule::parse)
+    }
+
+    /// Amount billed by the occurrence on `date`, following the amount
+    /// schedule if there is one
+    pub fn amount_on(&self, date: NaiveDate) -> Money {
+        let start = self
+            .payment_date
+            .get(..10)
+            .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok());
+        match (self.schedule(), start) {
+            (Some(schedule), Some(start)) => schedule.amount_on(self.amount, start, date),
+            _ => self.amount,
+        }
+    }
+}
+
+impl CreatePayment {
+    /// Legacy recurrence fields, for clients that send no RRULE
+    pub fn legacy_recurrence(&self) -> LegacyRecurrence<'_> {
+        LegacyRecurrence {
+            recurrence_type: self.recurrence_type.as_deref(),
+            interval: self.recurrence_interval,
+            times_per: self.recurrence_times_per,
+            weekdays: self.recurrence_weekdays.as_deref(),
+            monthdays: self.recurrence_monthdays.as_deref(),
+            months: self.recurrence_months.as_deref(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct CreateContribution {
     pub participant_id: i64,
+    // Weight, percentage or number of shares depending on the split mode
+    #[serde(default)]
     pub weight: f64,
+    // Exact amount (split mode 'exact')
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub amount: Option<Money>,
+    // Most and least this contribution takes (split mode 'weights')
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cap: Option<Money>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub floor: Option<Money>,
+}
+
+/// Split a recurring payment at one of its occurrences: the series ends the
+/// day before and a continuation takes over with the given changes
+#[derive(Debug, Deserialize)]
+pub struct SplitPayment {
+    pub from_date: String,
+    pub amount: Option<Money>,
+    pub payer_id: Option<i64>,
+    pub contributions: Option<Vec<CreateContribution>>,
+}
+
+/// Both halves of a split series
+#[derive(Debug, Serialize)]
+pub struct SplitPaymentResult {
+    pub ended: PaymentWithContributions,
+    pub continuation: PaymentWithContributions,
 }
 
 #[derive(Debug, Serialize)]
@@ -83,5 +242,12 @@ pub struct PaymentWithContributions {
     #[serde(flatten)]
     pub payment: Payment,
     pub payer_name: Option<String>,
+    // Payers of a payment paid by several (empty with a single payer)
+    pub payers: Vec<PaymentPayerWithParticipant>,
     pub contributions: Vec<super::ContributionWithParticipant>,
+    // Receipt lines of an itemized payment (empty otherwise)
+    pub items: Vec<PaymentItem>,
+    pub refunds: Vec<Refund>,
+    // The amount and contributions less the refunds (None without refunds)
+    pub net_cost: Option<NetCost>,
 }
@@ -0,0 +1,252 @@
+//! Itemized receipts
+//!
+//! A payment can list its lines instead of splitting its amount directly:
+//! items are split among their own participants by weight, then tax, tip and
+//! discount lines are spread over the participants in proportion to their
+//! item subtotals. The per-participant totals become the payment's exact
+//! contributions, so balances are computed from contributions as before.
+
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+use super::{CreateContribution, Money, SplitMode};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ItemKind {
+    #[default]
+    Item,
+    Tax,
+    Tip,
+    Discount,
+}
+
+impl ItemKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Item => "item",
+            Self::Tax => "tax",
+            Self::Tip => "tip",
+            Self::Discount => "discount",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "item" => Some(Self::Item),
+            "tax" => Some(Self::Tax),
+            "tip" => Some(Self::Tip),
+            "discount" => Some(Self::Discount),
+            _ => None,
+        }
+    }
+}
+
+/// A line of an itemized payment, in receipt order
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct PaymentItem {
+    pub id: i64,
+    pub payment_id: i64,
+    pub position: i64,
+    // 'item', 'tax', 'tip' or 'discount'
+    pub kind: String,
+    pub description: String,
+    // Positive; discounts are subtracted
+    pub amount: Money,
+    // JSON: [{"participant_id": 1, "weight": 2.0}], items only
+    pub weights: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CreatePaymentItem {
+    // 'item' (default), 'tax', 'tip' or 'discount'
+    pub kind: Option<String>,
+    #[serde(default)]
+    pub description: String,
+    pub amount: Money,
+    // Who shares an item and by which weights (ignored for other kinds)
+    #[serde(default)]
+    pub contributions: Vec<CreateContribution>,
+}
+
+impl CreatePaymentItem {
+    pub fn kind(&self) -> Option<ItemKind> {
+        self.kind
+            .as_deref()
+            .map_or(Some(ItemKind::Item), ItemKind::parse)
+    }
+}
+
+/// Why a payment's lines cannot be turned into contributions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItemsError {
+    /// Unknown kind, amount not positive, or an item nobody shares
+    InvalidItem,
+    /// Tax, tip or discount with no item subtotal to spread over, or a
+    /// discount larger than the items
+    InvalidAdjustment,
+    /// The lines do not add up to the payment's amount
+    TotalMismatch,
+}
+
+/// Contributions of an itemized payment of `amount`: each participant's
+/// share of the items plus their part of the tax, tip and discounts, as
+/// exact amounts, in order of first appearance
+pub fn itemized_contributions(
+    amount: Money,
+    items: &[CreatePaymentItem],
+) -> Result<Vec<CreateContribution>, ItemsError> {
+    let mut totals: Vec<(i64, Money)> = Vec::new();
+    let add = |totals: &mut Vec<(i64, Money)>, participant_id: i64, share: Money| match totals
+        .iter_mut()
+        .find(|(id, _)| *id == participant_id)
+    {
+        Some((_, total)) => *total += share,
+        None => totals.push((participant_id, share)),
+    };
+
+    let mut adjustments = Vec::new();
+    for item in items {
+        let kind = item.kind().ok_or(ItemsError::InvalidItem)?;
+        if !item.amount.is_positive() {
+            return Err(ItemsError::InvalidItem);
+        }
+        match kind {
+            ItemKind::Item => {
+                let shares = SplitMode::Weights
+                    .resolve(item.amount, &item.contributions)
+                    .map_err(|_| ItemsError::InvalidItem)?;
+                for (contrib, share) in item.contributions.iter().zip(shares) {
+                    add(&mut totals, contrib.participant_id, share);
+                }
+            }
+            ItemKind::Discount => adjustments.push(-item.amount),
+            ItemKind::Tax | ItemKind::Tip => adjustments.push(item.amount),
+        }
+    }
+
+    // Adjustments follow the item subtotals, not what earlier adjustments
+    // already moved
+    let subtotals: Vec<(i64, f64)> = totals.iter().map(|(id, s)| (*id, s.to_f64())).collect();
+    if !adjustments.is_empty() && subtotals.iter().all(|(_, s)| *s <= 0.0) {
+        return Err(ItemsError::InvalidAdjustment);
+    }
+    for adjustment in adjustments {
+        for ((participant_id, _), share) in subtotals.iter().zip(adjustment.allocate(&subtotals)) {
+            add(&mut totals, *participant_id, share);
+        }
+    }
+
+    if totals.iter().any(|(_, total)| total.is_negative()) {
+        return Err(ItemsError::InvalidAdjustment);
+    }
+    if totals.iter().map(|(_, total)| *total).sum::<Money>() != amount {
+        return Err(ItemsError::TotalMismatch);
+    }
+
+    Ok(totals
+        .into_iter()
+        .map(|(participant_id, total)| CreateContribution {
+            participant_id,
+            weight: total.to_f64(),
+            amount: Some(total),
+            cap: None,
+            floor: None,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn money(value: f64) -> Money {
+        Money::from_f64(value, 2)
+    }
+
+    fn line(kind: &str, amount: f64, weights: &[(i64, f64)]) -> CreatePaymentItem {
+        CreatePaymentItem {
+            kind: Some(kind.to_string()),
+            description: String::new(),
+            amount: money(amount),
+            contributions: weights
+                .iter()
+                .map(|&(participant_id, weight)| CreateContribution {
+                    participant_id,
+                    weight,
+                    amount: None,
+                    cap: None,
+                    floor: None,
+                })
+                .collect(),
+        }
+    }
+
+    fn amounts(contributions: &[CreateContribution]) -> Vec<(i64, Money)> {
+        contributions
+            .iter()
+            .map(|c| (c.participant_id, c.amount.unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn test_tax_and_tip_follow_item_subtotals() {
+        // Alice's 30 and Bob's 10 main courses, a 20 shared bottle of wine,
+        // 6 of tax and 12 of tip: subtotals 40 and 20 take 2/3 and 1/3
+        let items = [
+            line("item", 30.0, &[(1, 1.0)]),
+            line("item", 10.0, &[(2, 1.0)]),
+            line("item", 20.0, &[(1, 1.0), (2, 1.0)]),
+            line("tax", 6.0, &[]),
+            line("tip", 12.0, &[]),
+        ];
+        let contributions = itemized_contributions(money(78.0), &items).unwrap();
+        assert_eq!(
+            amounts(&contributions),
+            [(1, money(52.0)), (2, money(26.0))]
+        );
+        assert_eq!(contributions[0].weight, 52.0);
+    }
+
+    #[test]
+    fn test_discount_is_subtracted_proportionally() {
+        let items = [
+            line("item", 10.0, &[(1, 1.0)]),
+            line("item", 20.0, &[(2, 1.0), (3, 1.0)]),
+            line("discount", 3.0, &[]),
+        ];
+        let contributions = itemized_contributions(money(27.0), &items).unwrap();
+        assert_eq!(
+            amounts(&contributions),
+            [(1, money(9.0)), (2, money(9.0)), (3, money(9.0))]
+        );
+    }
+
+    #[test]
+    fn test_invalid_lines() {
+        let items = [line("item", 10.0, &[(1, 1.0)])];
+        assert_eq!(
+            itemized_contributions(money(12.0), &items).err(),
+            Some(ItemsError::TotalMismatch)
+        );
+        assert_eq!(
+            itemized_contributions(money(10.0), &[line("item", 10.0, &[])]).err(),
+            Some(ItemsError::InvalidItem)
+        );
+        assert_eq!(
+            itemized_contributions(money(10.0), &[line("fee", 10.0, &[(1, 1.0)])]).err(),
+            Some(ItemsError::InvalidItem)
+        );
+        assert_eq!(
+            itemized_contributions(money(2.0), &[line("tip", 2.0, &[])]).err(),
+            Some(ItemsError::InvalidAdjustment)
+        );
+        assert_eq!(
+            itemized_contributions(
+                money(-5.0),
+                &[line("item", 10.0, &[(1, 1.0)]), line("discount", 15.0, &[])]
+            )
+            .err(),
+            Some(ItemsError::InvalidAdjustment)
+        );
+    }
+}
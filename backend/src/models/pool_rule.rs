@@ -0,0 +1,77 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+use super::{bounded::ShortString, Money, RecurrenceSet};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PoolRuleKind {
+    /// A fixed amount charged to the pool on every date of the schedule
+    Fee,
+    /// Interest at an annual rate on the pool's daily balance, credited on
+    /// every date of the schedule
+    Interest,
+}
+
+impl PoolRuleKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Fee => "fee",
+            Self::Interest => "interest",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "fee" => Some(Self::Fee),
+            "interest" => Some(Self::Interest),
+            _ => None,
+        }
+    }
+}
+
+/// A recurring fee or interest of a pool. Each date of its schedule adds an
+/// occurrence shared by the pool's owners in proportion to what they own:
+/// on that date for a fee, day by day since the last credit for interest.
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct PoolRule {
+    pub id: i64,
+    pub project_id: i64,
+    pub pool_id: i64,
+    // 'fee' or 'interest'
+    pub kind: String,
+    // Fee charged on each date, in the base currency
+    pub amount: Option<Money>,
+    // Annual interest rate, in percent
+    pub rate: Option<f64>,
+    // RRULE of the dates fees are charged or interest is credited
+    pub recurrence_rule: String,
+    pub start_date: String,
+    pub end_date: Option<String>,
+    pub description: String,
+    pub created_at: String,
+}
+
+impl PoolRule {
+    pub fn kind(&self) -> Option<PoolRuleKind> {
+        PoolRuleKind::parse(&self.kind)
+    }
+
+    pub fn recurrence(&self) -> Option<RecurrenceSet> {
+        RecurrenceSet::parse(&self.recurrence_rule)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreatePoolRule {
+    pub pool_id: i64,
+    // 'fee' or 'interest'
+    pub kind: ShortString,
+    // Required for a fee
+    pub amount: Option<Money>,
+    // Required for interest
+    pub rate: Option<f64>,
+    pub recurrence_rule: String,
+    pub start_date: ShortString,
+    pub end_date: Option<ShortString>,
+    pub description: Option<ShortString>,
+}
@@ -0,0 +1,27 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+use super::{bounded::ShortString, Money};
+
+/// Balance of a pool's bank account on a statement, to check the balance the
+/// pool has on record against. Statements do not change the ledger; an
+/// adjustment payment does.
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct PoolStatement {
+    pub id: i64,
+    pub project_id: i64,
+    pub pool_id: i64,
+    pub statement_date: String,
+    // At the end of the statement date, in the base currency
+    pub balance: Money,
+    pub description: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreatePoolStatement {
+    pub pool_id: i64,
+    pub statement_date: ShortString,
+    pub balance: Money,
+    pub description: Option<ShortString>,
+}
@@ -0,0 +1,61 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+use super::{bounded::ShortString, Money};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValuationKind {
+    /// What the whole pool is worth on the date
+    Value,
+    /// Gained (or, negative, lost) on the date
+    Interest,
+}
+
+impl ValuationKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Value => "value",
+            Self::Interest => "interest",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "value" => Some(Self::Value),
+            "interest" => Some(Self::Interest),
+            _ => None,
+        }
+    }
+}
+
+/// A change in what a unitized pool is worth, outside deposits and
+/// withdrawals. It moves the unit value, so every owner gains or loses in
+/// proportion to their units. Applied at the end of its date.
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct PoolValuation {
+    pub id: i64,
+    pub project_id: i64,
+    pub pool_id: i64,
+    pub valuation_date: String,
+    // 'value' or 'interest'
+    pub kind: String,
+    pub amount: Money,
+    pub description: String,
+    pub created_at: String,
+}
+
+impl PoolValuation {
+    pub fn kind(&self) -> Option<ValuationKind> {
+        ValuationKind::parse(&self.kind)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreatePoolValuation {
+    pub pool_id: i64,
+    pub valuation_date: ShortString,
+    // 'value' (default) or 'interest'
+    pub kind: Option<ShortString>,
+    pub amount: Money,
+    pub description: Option<ShortString>,
+}
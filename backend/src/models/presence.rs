@@ -0,0 +1,24 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+use super::bounded::ShortString;
+
+/// A period a participant is present in the project (e.g. lives in the
+/// house), open-ended on a side without a date. Recurring occurrences are
+/// split only among contributors present on their date.
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct PresencePeriod {
+    pub id: i64,
+    pub project_id: i64,
+    pub participant_id: i64,
+    pub from_date: Option<String>,
+    pub to_date: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreatePresencePeriod {
+    pub participant_id: i64,
+    pub from_date: Option<ShortString>,
+    pub to_date: Option<ShortString>,
+}
@@ -2,6 +2,7 @@ use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 
 use super::bounded::{ProjectDescription, ProjectName, ShortString};
+use super::{Money, PoolWarning};
 
 #[derive(Debug, Clone, FromRow, Serialize)]
 pub struct Project {
@@ -17,6 +18,8 @@ pub struct Project {
     pub pool_warning_horizon: String, // Deprecated: now per-pool in participants table
     #[sqlx(default)]
     pub pending_member_access: String, // 'none', 'read_only', 'auto_approve'
+    #[sqlx(default)]
+    pub base_currency: String, // ISO 4217 code balances are reported in
 }
 
 #[derive(Debug, Deserialize)]
@@ -25,6 +28,8 @@ pub struct CreateProject {
     pub name: ProjectName,
     /// Project description bounded to 500 chars at deserialization
     pub description: Option<ProjectDescription>,
+    /// ISO 4217 code, defaults to EUR
+    pub base_currency: Option<ShortString>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -48,6 +53,8 @@ pub struct UpdateProjectSettings {
     pub require_approval: Option<bool>,
     /// Bounded to 50 chars at deserialization
     pub pending_member_access: Option<ShortString>,
+    /// ISO 4217 code balances are reported in
+    pub base_currency: Option<ShortString>,
 }
 
 #[derive(Debug, Clone, FromRow, Serialize)]
@@ -64,6 +71,8 @@ pub struct ProjectWithRole {
     pub pool_warning_horizon: String, // Deprecated: now per-pool in participants table
     #[sqlx(default)]
     pub pending_member_access: String, // 'none', 'read_only', 'auto_approve'
+    #[sqlx(default)]
+    pub base_currency: String,
     pub role: String,
 }
 
@@ -71,7 +80,7 @@ pub struct ProjectWithRole {
 #[derive(Debug, Clone, Serialize)]
 pub struct PoolSummary {
     pub pool_name: String,
-    pub ownership: f64,
+    pub ownership: Money,
 }
 
 /// Extended project info for the project list, including owner and user's debt summary
@@ -87,13 +96,16 @@ pub struct ProjectListItem {
     pub require_approval: bool,
     pub pool_warning_horizon: String, // Deprecated: now per-pool in participants table
     pub pending_member_access: String, // 'none', 'read_only', 'auto_approve'
+    pub base_currency: String,
     pub role: String,
     /// Display name or username of the project owner
     pub owner_name: String,
     /// Current user's net balance (positive = they are owed, negative = they owe)
-    pub user_balance: Option<f64>,
+    pub user_balance: Option<Money>,
     /// Current user's pool ownership summaries
     pub user_pools: Vec<PoolSummary>,
     /// Current user's membership status in this project
     pub member_status: String,
+    /// Pool warnings due within their configured horizons
+    pub warnings: Vec<PoolWarning>,
 }
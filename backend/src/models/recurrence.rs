@@ -0,0 +1,789 @@
+//! Recurrence rules for recurring payments
+//!
+//! Recurrence is stored as RFC 5545 RRULE text, e.g.
+//! `FREQ=MONTHLY;INTERVAL=2;BYDAY=-1FR`. Supported parts are FREQ, INTERVAL,
+//! BYDAY (with ordinals for monthly and yearly rules, e.g. `2TU` or `-1FR`),
+//! BYMONTHDAY (negative values count from the end of the month), BYMONTH,
+//! BYSETPOS, COUNT, UNTIL and WKST. The payment date plays the role of
+//! DTSTART. Occurrences are expanded by `debt_calculator`.
+//!
+//! Occurrences falling on a weekend or project holiday can be shifted to the
+//! next or previous business day (`BusinessDayShift`), so "last business day of
+//! the month" is `FREQ=MONTHLY;BYMONTHDAY=-1` shifted to the previous one.
+//!
+//! A set may hold several rules, one per line, whose occurrences are merged.
+//! A `DTSTART:YYYYMMDD` line gives the following rule its own first date; this
+//! is how weekly cycles whose weeks differ (Monday one week, Friday the next)
+//! are written. `RDATE;VALUE=DATE:` and `EXDATE;VALUE=DATE:` lines add and
+//! remove single dates (comma-separated `YYYYMMDD`), as in RFC 5545.
+
+use chrono::{Datelike, Months, NaiveDate, Weekday};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+impl Frequency {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Daily => "DAILY",
+            Self::Weekly => "WEEKLY",
+            Self::Monthly => "MONTHLY",
+            Self::Yearly => "YEARLY",
+        }
+    }
+
+    /// Legacy `recurrence_type` value of this frequency
+    pub fn recurrence_type(&self) -> &'static str {
+        match self {
+            Self::Daily => "daily",
+            Self::Weekly => "weekly",
+            Self::Monthly => "monthly",
+            Self::Yearly => "yearly",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "DAILY" => Some(Self::Daily),
+            "WEEKLY" => Some(Self::Weekly),
+            "MONTHLY" => Some(Self::Monthly),
+            "YEARLY" => Some(Self::Yearly),
+            _ => None,
+        }
+    }
+}
+
+/// A BYDAY entry: a weekday, optionally the nth (or nth from last when
+/// negative) of its month or year
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ByDay {
+    pub ordinal: Option<i32>,
+    pub weekday: Weekday,
+}
+
+impl ByDay {
+    fn parse(value: &str) -> Option<Self> {
+        let split = value.len().checked_sub(2)?;
+        let (ordinal, weekday) = value.split_at_checked(split)?;
+        let weekday = parse_weekday(weekday)?;
+        let ordinal = if ordinal.is_empty() {
+            None
+        } else {
+            let n: i32 = ordinal.parse().ok()?;
+            if n == 0 || n.abs() > 53 {
+                return None;
+            }
+            Some(n)
+        };
+        Some(Self { ordinal, weekday })
+    }
+}
+
+impl fmt::Display for ByDay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(n) = self.ordinal {
+            write!(f, "{}", n)?;
+        }
+        f.write_str(weekday_code(self.weekday))
+    }
+}
+
+/// One RRULE
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecurrenceRule {
+    /// First date of this rule when it differs from the payment date
+    pub start: Option<NaiveDate>,
+    pub freq: Frequency,
+    pub interval: u32,
+    pub by_day: Vec<ByDay>,
+    pub by_month_day: Vec<i32>,
+    pub by_month: Vec<u32>,
+    /// Positions (from the end when negative) kept among each period's dates
+    pub by_set_pos: Vec<i32>,
+    pub count: Option<u32>,
+    pub until: Option<NaiveDate>,
+    pub week_start: Weekday,
+}
+
+impl RecurrenceRule {
+    /// Every `interval` periods, on the payment date's day
+    pub fn every(freq: Frequency, interval: u32) -> Self {
+        Self {
+            start: None,
+            freq,
+            interval: interval.max(1),
+            by_day: Vec::new(),
+            by_month_day: Vec::new(),
+            by_month: Vec::new(),
+            by_set_pos: Vec::new(),
+            count: None,
+            until: None,
+            week_start: Weekday::Mon,
+        }
+    }
+
+    /// Parse the parts of an RRULE (without the `RRULE:` prefix)
+    pub fn parse(input: &str) -> Option<Self> {
+        let mut freq = None;
+        let mut rule = Self::every(Frequency::Daily, 1);
+
+        for part in input.trim().split(';').filter(|p| !p.is_empty()) {
+            let (key, value) = part.split_once('=')?;
+            let value = value.trim().to_ascii_uppercase();
+            match key.trim().to_ascii_uppercase().as_str() {
+                "FREQ" => freq = Some(Frequency::parse(&value)?),
+                "INTERVAL" => {
+                    rule.interval = value.parse().ok().filter(|n| (1..=1000).contains(n))?;
+                }
+                "BYDAY" => rule.by_day = parse_list(&value, ByDay::parse)?,
+                "BYMONTHDAY" => {
+                    rule.by_month_day = parse_list(&value, |v| {
+                        v.parse::<i32>().ok().filter(|d| *d != 0 && d.abs() <= 31)
+                    })?;
+                }
+                "BYMONTH" => {
+                    rule.by_month = parse_list(&value, |v| {
+                        v.parse::<u32>().ok().filter(|m| (1..=12).contains(m))
+                    })?;
+                }
+                "BYSETPOS" => {
+                    rule.by_set_pos = parse_list(&value, |v| {
+                        v.parse::<i32>().ok().filter(|p| *p != 0 && p.abs() <= 366)
+                    })?;
+                }
+                "COUNT" => rule.count = Some(value.parse().ok().filter(|n| *n > 0)?),
+                "UNTIL" => rule.until = Some(parse_ical_date(&value)?),
+                "WKST" => rule.week_start = parse_weekday(&value)?,
+                _ => return None,
+            }
+        }
+
+        rule.freq = freq?;
+        // COUNT and UNTIL are exclusive; ordinals only make sense within a month
+        // or year, and day numbers not within a week
+        if rule.count.is_some() && rule.until.is_some() {
+            return None;
+        }
+        let has_ordinal = rule.by_day.iter().any(|d| d.ordinal.is_some());
+        if has_ordinal && !matches!(rule.freq, Frequency::Monthly | Frequency::Yearly) {
+            return None;
+        }
+        // Within a month there are at most five of each weekday
+        let within_month = rule.freq == Frequency::Monthly || !rule.by_month.is_empty();
+        if within_month
+            && rule
+                .by_day
+                .iter()
+                .any(|d| d.ordinal.is_some_and(|n| n.abs() > 5))
+        {
+            return None;
+        }
+        // BYSETPOS picks among the dates other BY* parts produce
+        if !rule.by_set_pos.is_empty() && rule.by_day.is_empty() && rule.by_month_day.is_empty() {
+            return None;
+        }
+        if rule.freq == Frequency::Weekly && !rule.by_month_day.is_empty() {
+            return None;
+        }
+        Some(rule)
+    }
+}
+
+impl fmt::Display for RecurrenceRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "FREQ={}", self.freq.as_str())?;
+        if self.interval != 1 {
+            write!(f, ";INTERVAL={}", self.interval)?;
+        }
+        if !self.by_month.is_empty() {
+            write!(f, ";BYMONTH={}", join(&self.by_month))?;
+        }
+        if !self.by_month_day.is_empty() {
+            write!(f, ";BYMONTHDAY={}", join(&self.by_month_day))?;
+        }
+        if !self.by_day.is_empty() {
+            write!(f, ";BYDAY={}", join(&self.by_day))?;
+        }
+        if !self.by_set_pos.is_empty() {
+            write!(f, ";BYSETPOS={}", join(&self.by_set_pos))?;
+        }
+        if let Some(count) = self.count {
+            write!(f, ";COUNT={}", count)?;
+        }
+        if let Some(until) = self.until {
+            write!(f, ";UNTIL={}", until.format("%Y%m%d"))?;
+        }
+        if self.week_start != Weekday::Mon {
+            write!(f, ";WKST={}", weekday_code(self.week_start))?;
+        }
+        Ok(())
+    }
+}
+
+/// Where an occurrence falling on a weekend or holiday moves to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BusinessDayShift {
+    Next,
+    Previous,
+}
+
+impl BusinessDayShift {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Next => "next",
+            Self::Previous => "previous",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "next" => Some(Self::Next),
+            "previous" => Some(Self::Previous),
+            _ => None,
+        }
+    }
+}
+
+/// The rules of a recurring payment, one per line when there are several
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecurrenceSet {
+    pub rules: Vec<RecurrenceRule>,
+    /// Extra dates, sorted
+    pub rdates: Vec<NaiveDate>,
+    /// Dates removed from the rules and extra dates, sorted
+    pub exdates: Vec<NaiveDate>,
+}
+
+impl RecurrenceSet {
+    /// Parse one RRULE, or several lines of `RRULE:` rules each optionally
+    /// preceded by a `DTSTART:` line, and `RDATE`/`EXDATE` lines
+    pub fn parse(input: &str) -> Option<Self> {
+        let mut rules = Vec::new();
+        let mut rdates = Vec::new();
+        let mut exdates = Vec::new();
+        let mut start = None;
+
+        for line in input.lines().map(str::trim).filter(|l| !l.is_empty()) {
+            let upper = line.to_ascii_uppercase();
+            if let Some(date) = upper.strip_prefix("DTSTART:") {
+                if start.is_some() {
+                    return None;
+                }
+                start = Some(parse_ical_date(date)?);
+                continue;
+            }
+            if let Some((name, dates)) = upper.split_once(':') {
+                let list = match name.split(';').next() {
+                    Some("RDATE") => Some(&mut rdates),
+                    Some("EXDATE") => Some(&mut exdates),
+                    _ => None,
+                };
+                if let Some(list) = list {
+                    if start.is_some() {
+                        return None;
+                    }
+                    list.extend(parse_list(dates, parse_ical_date)?);
+                    continue;
+                }
+            }
+            let rule = line
+                .get(..6)
+                .filter(|prefix| prefix.eq_ignore_ascii_case("RRULE:"))
+                .map_or(line, |_| &line[6..]);
+            rules.push(RecurrenceRule {
+                start: start.take(),
+                ..RecurrenceRule::parse(rule)?
+            });
+        }
+
+        if rules.is_empty() || start.is_some() {
+            return None;
+        }
+        Some(Self {
+            rules,
+            rdates: sorted(rdates),
+            exdates: sorted(exdates),
+        })
+    }
+
+    /// A set of plain rules
+    pub fn of(rules: Vec<RecurrenceRule>) -> Self {
+        Self {
+            rules,
+            rdates: Vec::new(),
+            exdates: Vec::new(),
+        }
+    }
+
+    /// Convert the recurrence columns used before RRULE support.
+    ///
+    /// Weekly patterns list the weekdays (0 = Sunday) of each week in a cycle of
+    /// `interval` weeks; month days and months only apply with an interval of 1.
+    pub fn from_legacy(start: NaiveDate, legacy: &LegacyRecurrence<'_>) -> Self {
+        let recurrence_type = legacy.recurrence_type.unwrap_or("monthly");
+        let freq =
+            Frequency::parse(&recurrence_type.to_ascii_uppercase()).unwrap_or(Frequency::Monthly);
+        let interval = legacy.interval.unwrap_or(1).max(1) as u32;
+
+        match freq {
+            Frequency::Weekly => {
+                if let Some(rules) = legacy
+                    .weekdays
+                    .and_then(|json| serde_json::from_str::<Vec<Vec<u32>>>(json).ok())
+                    .and_then(|weeks| weekly_cycle_rules(start, interval, &weeks))
+                {
+                    return Self::of(rules);
+                }
+            }
+            Frequency::Monthly if interval == 1 => {
+                if let Some(days) = legacy.monthdays.and_then(parse_json_days) {
+                    let mut rule = RecurrenceRule::every(Frequency::Monthly, 1);
+                    rule.by_month_day = days;
+                    return Self::of(vec![rule]);
+                }
+            }
+            Frequency::Yearly if interval == 1 => {
+                let months: Option<Vec<u32>> = legacy
+                    .months
+                    .and_then(|json| serde_json::from_str::<Vec<u32>>(json).ok())
+                    .map(|months| {
+                        months
+                            .into_iter()
+                            .filter(|m| (1..=12).contains(m))
+                            .collect()
+                    })
+                    .filter(|months: &Vec<u32>| !months.is_empty());
+                if let Some(months) = months {
+                    let mut rule = RecurrenceRule::every(Frequency::Yearly, 1);
+                    rule.by_month = sorted(months);
+                    rule.by_month_day = legacy
+                        .monthdays
+                        .and_then(parse_json_days)
+                        .unwrap_or_else(|| vec![start.day() as i32]);
+                    return Self::of(vec![rule]);
+                }
+            }
+            _ => {}
+        }
+
+        // Deprecated "X times per period": spread evenly as every N days
+        let days_per_period = match freq {
+            Frequency::Daily => 1,
+            Frequency::Weekly => 7,
+            Frequency::Monthly => 30,
+            Frequency::Yearly => 365,
+        };
+        let times_per = legacy.times_per.unwrap_or(0).max(0) as u32;
+        if let Some(days) = (days_per_period * interval).checked_div(times_per) {
+            // X times per day is treated as every day
+            let days = if freq == Frequency::Daily { 1 } else { days };
+            return Self::of(vec![RecurrenceRule::every(Frequency::Daily, days.max(1))]);
+        }
+
+        if matches!(freq, Frequency::Monthly | Frequency::Yearly) {
+            return clamped_month_set(start, freq, interval);
+        }
+
+        Self::of(vec![RecurrenceRule::every(freq, interval)])
+    }
+}
+
+impl fmt::Display for RecurrenceSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // A single rule starting with the payment is written as a bare RRULE
+        if let [rule] = self.rules.as_slice() {
+            if rule.start.is_none() && self.rdates.is_empty() && self.exdates.is_empty() {
+                return write!(f, "{}", rule);
+            }
+        }
+        for (i, rule) in self.rules.iter().enumerate() {
+            if i > 0 {
+                f.write_str("\n")?;
+            }
+            if let Some(start) = rule.start {
+                writeln!(f, "DTSTART:{}", start.format("%Y%m%d"))?;
+            }
+            write!(f, "RRULE:{}", rule)?;
+        }
+        for (name, dates) in [("RDATE", &self.rdates), ("EXDATE", &self.exdates)] {
+            if !dates.is_empty() {
+                let dates: Vec<_> = dates.iter().map(|d| d.format("%Y%m%d")).collect();
+                write!(f, "\n{};VALUE=DATE:{}", name, join(&dates))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Recurrence columns used before RRULE support
+#[derive(Debug, Default)]
+pub struct LegacyRecurrence<'a> {
+    pub recurrence_type: Option<&'a str>,
+    pub interval: Option<i32>,
+    pub times_per: Option<i32>,
+    pub weekdays: Option<&'a str>,
+    pub monthdays: Option<&'a str>,
+    pub months: Option<&'a str>,
+}
+
+/// One weekly rule per distinct week of the cycle, each starting on its week
+fn weekly_cycle_rules(
+    start: NaiveDate,
+    interval: u32,
+    weeks: &[Vec<u32>],
+) -> Option<Vec<RecurrenceRule>> {
+    if weeks.is_empty() {
+        return None;
+    }
+    let week_days = |k: u32| -> Vec<ByDay> {
+        let mut days: Vec<u32> = weeks[(k as usize) % weeks.len()]
+            .iter()
+            .copied()
+            .filter(|d| *d < 7)
+            .collect();
+        days.sort_unstable();
+        days.dedup();
+        days.into_iter()
+            .map(|d| ByDay {
+                ordinal: None,
+                // Legacy weekday numbers start at Sunday, chrono's at Monday
+                weekday: Weekday::try_from(((d + 6) % 7) as u8).unwrap_or(Weekday::Sun),
+            })
+            .collect()
+    };
+
+    let cycle: Vec<Vec<ByDay>> = (0..interval).map(week_days).collect();
+    if cycle.iter().all(|days| *days == cycle[0]) {
+        if cycle[0].is_empty() {
+            return None;
+        }
+        let mut rule = RecurrenceRule::every(Frequency::Weekly, 1);
+        rule.by_day = cycle[0].clone();
+        rule.week_start = Weekday::Sun;
+        return Some(vec![rule]);
+    }
+
+    let first_sunday =
+        start - chrono::Duration::days(start.weekday().num_days_from_sunday() as i64);
+    let rules: Vec<RecurrenceRule> = cycle
+        .into_iter()
+        .enumerate()
+        .filter(|(_, days)| !days.is_empty())
+        .map(|(k, by_day)| RecurrenceRule {
+            start: (k > 0).then(|| first_sunday + chrono::Duration::weeks(k as i64)),
+            by_day,
+            week_start: Weekday::Sun,
+            ..RecurrenceRule::every(Frequency::Weekly, interval)
+        })
+        .collect();
+    (!rules.is_empty()).then_some(rules)
+}
+
+/// Monthly or yearly recurrence keeping the dates legacy recurrence produced.
+///
+/// Legacy recurrence added the months to the previous occurrence, so once a
+/// short month clamped the day (Jan 31 to Feb 28) later occurrences kept the
+/// clamped day. The rule repeats on the day the dates settle on; the earlier
+/// dates are listed as RDATEs (the payment date included), and the rule's own
+/// dates before then as EXDATEs.
+fn clamped_month_set(start: NaiveDate, freq: Frequency, interval: u32) -> RecurrenceSet {
+    let step = match freq {
+        Frequency::Yearly => interval * 12,
+        _ => interval,
+    };
+    // The months of the year, leap years included, repeat every 400 years
+    let max_steps = (12 * 400 / step).max(1);
+
+    // The first date on the day the dates settle on
+    let mut settled = start;
+    while let Some((_, date)) = (1..=max_steps)
+        .map_while(|n| Some((n, settled.checked_add_months(Months::new(step * n))?)))
+        .find(|(_, date)| date.day() < settled.day())
+    {
+        settled = date;
+    }
+    let mut rule = RecurrenceRule::every(freq, interval);
+    if settled == start {
+        return RecurrenceSet::of(vec![rule]);
+    }
+
+    let day = settled.day();
+    rule.by_month_day = vec![day as i32];
+    if freq == Frequency::Yearly {
+        rule.by_month = vec![settled.month()];
+    }
+    let mut set = RecurrenceSet::of(vec![rule]);
+    let mut date = start;
+    while date < settled {
+        set.rdates.push(date);
+        // The rule's date that month, earlier than the legacy one
+        if let Some(on_rule) = date.with_day(day).filter(|d| *d > start) {
+            set.exdates.push(on_rule);
+        }
+        match date.checked_add_months(Months::new(step)) {
+            Some(next) => date = next,
+            None => break,
+        }
+    }
+    set
+}
+
+fn parse_json_days(json: &str) -> Option<Vec<i32>> {
+    let days: Vec<i32> = serde_json::from_str::<Vec<u32>>(json)
+        .ok()?
+        .into_iter()
+        .filter(|d| (1..=31).contains(d))
+        .map(|d| d as i32)
+        .collect();
+    (!days.is_empty()).then(|| sorted(days))
+}
+
+fn sorted<T: Ord>(mut values: Vec<T>) -> Vec<T> {
+    values.sort_unstable();
+    values.dedup();
+    values
+}
+
+fn parse_list<T>(value: &str, parse: impl Fn(&str) -> Option<T>) -> Option<Vec<T>> {
+    value.split(',').map(|v| parse(v.trim())).collect()
+}
+
+fn join<T: fmt::Display>(values: &[T]) -> String {
+    values
+        .iter()
+        .map(|v| v.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Date of an iCalendar DATE or DATE-TIME value (`20250131` or `20250131T235959Z`)
+fn parse_ical_date(value: &str) -> Option<NaiveDate> {
+    let date = value.split('T').next()?;
+    NaiveDate::parse_from_str(date, "%Y%m%d").ok()
+}
+
+fn parse_weekday(code: &str) -> Option<Weekday> {
+    match code {
+        "MO" => Some(Weekday::Mon),
+        "TU" => Some(Weekday::Tue),
+        "WE" => Some(Weekday::Wed),
+        "TH" => Some(Weekday::Thu),
+        "FR" => Some(Weekday::Fri),
+        "SA" => Some(Weekday::Sat),
+        "SU" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+fn weekday_code(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Mon => "MO",
+        Weekday::Tue => "TU",
+        Weekday::Wed => "WE",
+        Weekday::Thu => "TH",
+        Weekday::Fri => "FR",
+        Weekday::Sat => "SA",
+        Weekday::Sun => "SU",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_parse_round_trip() {
+        for rule in [
+            "FREQ=MONTHLY",
+            "FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,FR",
+            "FREQ=MONTHLY;BYDAY=-1FR",
+            "FREQ=MONTHLY;BYMONTHDAY=1,-1",
+            "FREQ=YEARLY;BYMONTH=1,7;BYDAY=2TU;COUNT=10",
+            "FREQ=DAILY;INTERVAL=10;UNTIL=20251231",
+            "FREQ=WEEKLY;BYDAY=SU,WE;WKST=SU",
+            "FREQ=MONTHLY;BYDAY=MO,TU,WE,TH,FR;BYSETPOS=-1",
+        ] {
+            let parsed = RecurrenceSet::parse(rule).unwrap();
+            assert_eq!(parsed.to_string(), rule);
+        }
+
+        let parsed = RecurrenceSet::parse("rrule:freq=monthly;until=20250131T235959Z").unwrap();
+        assert_eq!(parsed.to_string(), "FREQ=MONTHLY;UNTIL=20250131");
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_rules() {
+        for rule in [
+            "",
+            "INTERVAL=2",
+            "FREQ=HOURLY",
+            "FREQ=MONTHLY;INTERVAL=0",
+            "FREQ=MONTHLY;BYMONTHDAY=0",
+            "FREQ=MONTHLY;BYMONTHDAY=32",
+            "FREQ=YEARLY;BYMONTH=13",
+            "FREQ=WEEKLY;BYDAY=2MO",
+            "FREQ=WEEKLY;BYMONTHDAY=1",
+            "FREQ=MONTHLY;BYDAY=XX",
+            "FREQ=MONTHLY;COUNT=3;UNTIL=20250101",
+            "FREQ=MONTHLY;BYSETPOS=1",
+            "FREQ=MONTHLY;BYDAY=6MO",
+            "FREQ=YEARLY;BYMONTH=3;BYDAY=-6FR",
+            "FREQ=MONTHLY;BYDAY=MO;BYSETPOS=0",
+            "DTSTART:20250101",
+            "RDATE;VALUE=DATE:20250101",
+            "FREQ=MONTHLY\nEXDATE;VALUE=DATE:20250132",
+        ] {
+            assert!(RecurrenceSet::parse(rule).is_none(), "{}", rule);
+        }
+    }
+
+    #[test]
+    fn test_multi_rule_set_round_trip() {
+        let text = "RRULE:FREQ=WEEKLY;INTERVAL=2;BYDAY=MO;WKST=SU\n\
+                    DTSTART:20250112\n\
+                    RRULE:FREQ=WEEKLY;INTERVAL=2;BYDAY=FR;WKST=SU";
+        let parsed = RecurrenceSet::parse(text).unwrap();
+        assert_eq!(parsed.rules.len(), 2);
+        assert_eq!(parsed.rules[1].start, Some(date("2025-01-12")));
+        assert_eq!(parsed.to_string(), text);
+
+        let parsed = RecurrenceSet::parse(
+            "FREQ=MONTHLY\nEXDATE:20250310,20250210\nrdate;value=date:20250215",
+        )
+        .unwrap();
+        assert_eq!(parsed.rdates, vec![date("2025-02-15")]);
+        assert_eq!(parsed.exdates, vec![date("2025-02-10"), date("2025-03-10")]);
+        assert_eq!(
+            parsed.to_string(),
+            "RRULE:FREQ=MONTHLY\n\
+             RDATE;VALUE=DATE:20250215\n\
+             EXDATE;VALUE=DATE:20250210,20250310"
+        );
+    }
+
+    #[test]
+    fn test_from_legacy() {
+        let start = date("2025-01-08"); // a Wednesday
+        let convert =
+            |legacy: LegacyRecurrence| RecurrenceSet::from_legacy(start, &legacy).to_string();
+
+        assert_eq!(
+            convert(LegacyRecurrence {
+                recurrence_type: Some("monthly"),
+                interval: Some(3),
+                ..Default::default()
+            }),
+            "FREQ=MONTHLY;INTERVAL=3"
+        );
+        assert_eq!(
+            convert(LegacyRecurrence {
+                recurrence_type: Some("monthly"),
+                interval: Some(1),
+                monthdays: Some("[15, 1]"),
+                ..Default::default()
+            }),
+            "FREQ=MONTHLY;BYMONTHDAY=1,15"
+        );
+        assert_eq!(
+            convert(LegacyRecurrence {
+                recurrence_type: Some("yearly"),
+                interval: Some(1),
+                months: Some("[12, 6]"),
+                ..Default::default()
+            }),
+            "FREQ=YEARLY;BYMONTH=6,12;BYMONTHDAY=8"
+        );
+        assert_eq!(
+            convert(LegacyRecurrence {
+                recurrence_type: Some("monthly"),
+                interval: Some(1),
+                times_per: Some(3),
+                ..Default::default()
+            }),
+            "FREQ=DAILY;INTERVAL=10"
+        );
+        // Months added to the previous occurrence keep a clamped day
+        let month_end = |start: &str| {
+            RecurrenceSet::from_legacy(
+                date(start),
+                &LegacyRecurrence {
+                    recurrence_type: Some("monthly"),
+                    interval: Some(1),
+                    ..Default::default()
+                },
+            )
+        };
+        assert_eq!(
+            month_end("2025-01-31").to_string(),
+            "RRULE:FREQ=MONTHLY;BYMONTHDAY=28\n\
+             RDATE;VALUE=DATE:20250131"
+        );
+        // Feb 29 sets the day until the next February
+        let leap = month_end("2024-01-31");
+        assert_eq!(leap.rules[0].by_month_day, vec![28]);
+        assert_eq!(leap.rdates.len(), 13);
+        assert_eq!(leap.rdates[1], date("2024-02-29"));
+        assert_eq!(leap.rdates[12], date("2025-01-29"));
+        assert_eq!(leap.exdates.len(), 12);
+        assert_eq!(leap.exdates[0], date("2024-02-28"));
+        assert_eq!(leap.exdates[11], date("2025-01-28"));
+        assert_eq!(RecurrenceSet::parse(&leap.to_string()), Some(leap));
+        assert_eq!(
+            RecurrenceSet::from_legacy(
+                date("2024-02-29"),
+                &LegacyRecurrence {
+                    recurrence_type: Some("yearly"),
+                    interval: Some(1),
+                    ..Default::default()
+                }
+            )
+            .to_string(),
+            "RRULE:FREQ=YEARLY;BYMONTH=2;BYMONTHDAY=28\n\
+             RDATE;VALUE=DATE:20240229"
+        );
+        assert_eq!(
+            RecurrenceSet::from_legacy(
+                date("2025-03-31"),
+                &LegacyRecurrence {
+                    recurrence_type: Some("monthly"),
+                    interval: Some(12),
+                    ..Default::default()
+                }
+            )
+            .to_string(),
+            "FREQ=MONTHLY;INTERVAL=12"
+        );
+        // Same weekdays every week of the cycle
+        assert_eq!(
+            convert(LegacyRecurrence {
+                recurrence_type: Some("weekly"),
+                interval: Some(2),
+                weekdays: Some("[[1,3]]"),
+                ..Default::default()
+            }),
+            "FREQ=WEEKLY;BYDAY=MO,WE;WKST=SU"
+        );
+        // Monday in the first week, Friday in the second
+        assert_eq!(
+            convert(LegacyRecurrence {
+                recurrence_type: Some("weekly"),
+                interval: Some(2),
+                weekdays: Some("[[1],[5]]"),
+                ..Default::default()
+            }),
+            "RRULE:FREQ=WEEKLY;INTERVAL=2;BYDAY=MO;WKST=SU\n\
+             DTSTART:20250112\n\
+             RRULE:FREQ=WEEKLY;INTERVAL=2;BYDAY=FR;WKST=SU"
+        );
+    }
+}
@@ -0,0 +1,158 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+use super::{CreateContribution, Money};
+
+/// Money given back on an expense, returned to its payers and taken off its
+/// contributors' shares: by default in proportion to their contributions
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct Refund {
+    pub id: i64,
+    pub payment_id: i64,
+    // Positive, in the payment's currency
+    pub amount: Money,
+    pub refund_date: String,
+    pub description: String,
+    // JSON: [{"participant_id": 1, "weight": 2.0}] (NULL = the payment's split)
+    pub weights: Option<String>,
+    pub created_at: String,
+}
+
+impl Refund {
+    /// Overriding weights as (participant_id, weight) pairs
+    pub fn weights(&self) -> Option<Vec<(i64, f64)>> {
+        let contributions: Vec<CreateContribution> =
+            serde_json::from_str(self.weights.as_deref()?).ok()?;
+        Some(
+            contributions
+                .into_iter()
+                .map(|c| (c.participant_id, c.weight))
+                .collect(),
+        )
+    }
+
+    /// Part of the refund going back to each contributor of a payment split
+    /// as `contributions`
+    pub fn split(&self, contributions: &[(i64, Money)]) -> Vec<(i64, Money)> {
+        let weights = self.weights().unwrap_or_else(|| {
+            contributions
+                .iter()
+                .map(|(id, amount)| (*id, amount.to_f64()))
+                .collect()
+        });
+        let shares = self.amount.allocate(&weights);
+        weights.iter().map(|(id, _)| *id).zip(shares).collect()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateRefund {
+    pub amount: Money,
+    // Default: today
+    pub refund_date: Option<String>,
+    pub description: Option<String>,
+    // Who the refund goes back to (default: the payment's contributors, in
+    // proportion to their contributions)
+    pub contributions: Option<Vec<CreateContribution>>,
+}
+
+/// What a payment comes to once its refunds are deducted
+#[derive(Debug, Clone, Serialize)]
+pub struct NetCost {
+    pub amount: Money,
+    pub contributions: Vec<NetContribution>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NetContribution {
+    pub participant_id: i64,
+    pub amount: Money,
+}
+
+impl NetCost {
+    /// Net cost of a payment of `amount` split as `contributions`, or None
+    /// without refunds
+    pub fn of(amount: Money, contributions: &[(i64, Money)], refunds: &[Refund]) -> Option<Self> {
+        if refunds.is_empty() {
+            return None;
+        }
+        let mut net: Vec<NetContribution> = contributions
+            .iter()
+            .map(|(participant_id, amount)| NetContribution {
+                participant_id: *participant_id,
+                amount: *amount,
+            })
+            .collect();
+        for refund in refunds {
+            for (participant_id, share) in refund.split(contributions) {
+                match net.iter_mut().find(|c| c.participant_id == participant_id) {
+                    Some(contribution) => contribution.amount -= share,
+                    None => net.push(NetContribution {
+                        participant_id,
+                        amount: -share,
+                    }),
+                }
+            }
+        }
+        Some(Self {
+            amount: amount - refunds.iter().map(|r| r.amount).sum::<Money>(),
+            contributions: net,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn money(value: f64) -> Money {
+        Money::from_f64(value, 2)
+    }
+
+    fn refund(amount: f64, weights: Option<&str>) -> Refund {
+        Refund {
+            id: 1,
+            payment_id: 1,
+            amount: money(amount),
+            refund_date: "2025-03-10".to_string(),
+            description: String::new(),
+            weights: weights.map(str::to_string),
+            created_at: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_refund_follows_contributions_unless_overridden() {
+        let contributions = [(1, money(60.0)), (2, money(30.0))];
+        assert_eq!(
+            refund(9.0, None).split(&contributions),
+            [(1, money(6.0)), (2, money(3.0))]
+        );
+        assert_eq!(
+            refund(9.0, Some(r#"[{"participant_id": 2, "weight": 1.0}]"#)).split(&contributions),
+            [(2, money(9.0))]
+        );
+    }
+
+    #[test]
+    fn test_net_cost_deducts_refunds() {
+        let contributions = [(1, money(60.0)), (2, money(30.0))];
+        assert!(NetCost::of(money(90.0), &contributions, &[]).is_none());
+
+        let refunds = [
+            refund(9.0, None),
+            refund(5.0, Some(r#"[{"participant_id": 3, "weight": 1.0}]"#)),
+        ];
+        let net = NetCost::of(money(90.0), &contributions, &refunds).unwrap();
+        assert_eq!(net.amount, money(76.0));
+        let amounts: Vec<(i64, Money)> = net
+            .contributions
+            .iter()
+            .map(|c| (c.participant_id, c.amount))
+            .collect();
+        assert_eq!(
+            amounts,
+            [(1, money(54.0)), (2, money(27.0)), (3, money(-5.0))]
+        );
+    }
+}
@@ -0,0 +1,38 @@
+use serde::Deserialize;
+
+use super::Money;
+
+/// Which suggested list the selected debts come from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SettlementSource {
+    #[default]
+    Settlements,
+    DirectSettlements,
+    SettlementPlan,
+}
+
+/// A suggested debt to record as a transfer
+#[derive(Debug, Clone, Deserialize)]
+pub struct SelectedDebt {
+    pub from_participant_id: i64,
+    pub to_participant_id: i64,
+    pub amount: Money,
+}
+
+/// Net balance the client saw when it computed the suggestions
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExpectedBalance {
+    pub participant_id: i64,
+    pub net_balance: Money,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SettleDebts {
+    #[serde(default)]
+    pub source: SettlementSource,
+    /// Debts to record; all suggested debts when omitted
+    pub debts: Option<Vec<SelectedDebt>>,
+    /// Balances from the `DebtSummary` the selection was made from
+    pub expected_balances: Vec<ExpectedBalance>,
+}
@@ -0,0 +1,301 @@
+//! How a payment's amount is split among its contributions
+//!
+//! - `weights` (default): in proportion to the weights, each share kept
+//!   between the contribution's `floor` and `cap` with the excess
+//!   redistributed among the others
+//! - `exact`: the `amount` of each contribution, summing to the payment's
+//! - `percentages`: weights summing to 100
+//! - `shares`: weights that are whole numbers of shares
+//!
+//! Whatever the mode, the resolved amounts are what `contributions` stores.
+
+use super::{CreateContribution, Money};
+
+/// Tolerance on percentages summing to 100 and on whole shares
+const EPSILON: f64 = 1e-9;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SplitMode {
+    #[default]
+    Weights,
+    Exact,
+    Percentages,
+    Shares,
+}
+
+/// Why contributions cannot be split in a mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SplitError {
+    /// No contribution takes part in the split
+    NoWeight,
+    /// Exact amounts are missing, negative or do not sum to the payment
+    AmountsMismatch,
+    PercentagesNotHundred,
+    SharesNotWhole,
+    /// A floor above its cap, limits in a mode without them, or limits no
+    /// split of the payment can satisfy
+    InvalidLimits,
+}
+
+impl SplitMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Weights => "weights",
+            Self::Exact => "exact",
+            Self::Percentages => "percentages",
+            Self::Shares => "shares",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "weights" => Some(Self::Weights),
+            "exact" => Some(Self::Exact),
+            "percentages" => Some(Self::Percentages),
+            "shares" => Some(Self::Shares),
+            _ => None,
+        }
+    }
+
+    /// Amount of each contribution, in order
+    pub fn resolve(
+        &self,
+        amount: Money,
+        contributions: &[CreateContribution],
+    ) -> Result<Vec<Money>, SplitError> {
+        let has_limits = contributions
+            .iter()
+            .any(|c| c.cap.is_some() || c.floor.is_some());
+        if has_limits && *self != Self::Weights {
+            return Err(SplitError::InvalidLimits);
+        }
+
+        match self {
+            Self::Exact => {
+                let amounts: Vec<Money> = contributions
+                    .iter()
+                    .map(|c| c.amount.filter(|a| !a.is_negative()))
+                    .collect::<Option<_>>()
+                    .ok_or(SplitError::AmountsMismatch)?;
+                if amounts.iter().copied().sum::<Money>() != amount {
+                    return Err(SplitError::AmountsMismatch);
+                }
+                return Ok(amounts);
+            }
+            Self::Percentages => {
+                let total: f64 = contributions.iter().map(|c| c.weight).sum();
+                if (total - 100.0).abs() > EPSILON {
+                    return Err(SplitError::PercentagesNotHundred);
+                }
+            }
+            Self::Shares => {
+                if contributions
+                    .iter()
+                    .any(|c| (c.weight - c.weight.round()).abs() > EPSILON)
+                {
+                    return Err(SplitError::SharesNotWhole);
+                }
+            }
+            Self::Weights => {}
+        }
+
+        if contributions.iter().any(|c| c.weight < 0.0)
+            || contributions.iter().map(|c| c.weight).sum::<f64>() <= 0.0
+        {
+            return Err(SplitError::NoWeight);
+        }
+        let targets = if has_limits {
+            limited_targets(amount, contributions)?
+        } else {
+            contributions.iter().map(|c| c.weight).collect()
+        };
+        let weights: Vec<(i64, f64)> = contributions
+            .iter()
+            .zip(targets)
+            .map(|(c, target)| (c.participant_id, target))
+            .collect();
+        Ok(amount.allocate(&weights))
+    }
+
+    /// Weight stored with a contribution, used to re-split the payment when
+    /// an occurrence bills another amount: exact amounts split in proportion
+    pub fn stored_weight(&self, contribution: &CreateContribution) -> f64 {
+        match (self, contribution.amount) {
+            (Self::Exact, Some(amount)) => amount.to_f64(),
+            _ => contribution.weight,
+        }
+    }
+}
+
+/// Proportional shares of `amount` clamped between floors and caps: a share
+/// past its limit is fixed there and the rest is split again among the
+/// others, until no share crosses a limit
+fn limited_targets(
+    amount: Money,
+    contributions: &[CreateContribution],
+) -> Result<Vec<f64>, SplitError> {
+    let total = amount.to_f64();
+    let floor = |c: &CreateContribution| c.floor.map_or(0.0, |f| f.to_f64());
+    let cap = |c: &CreateContribution| c.cap.map_or(f64::INFINITY, |c| c.to_f64());
+
+    if contributions.iter().any(|c| floor(c) > cap(c)) {
+        return Err(SplitError::InvalidLimits);
+    }
+    let floors: f64 = contributions.iter().map(floor).sum();
+    let caps: f64 = contributions.iter().map(cap).sum();
+    if floors > total + EPSILON || caps < total - EPSILON {
+        return Err(SplitError::InvalidLimits);
+    }
+
+    let mut fixed: Vec<Option<f64>> = vec![None; contributions.len()];
+    loop {
+        let remaining = total - fixed.iter().flatten().sum::<f64>();
+        let free_weight: f64 = contributions
+            .iter()
+            .zip(&fixed)
+            .filter(|(_, f)| f.is_none())
+            .map(|(c, _)| c.weight)
+            .sum();
+        let share = |c: &CreateContribution| {
+            if free_weight > 0.0 {
+                remaining * c.weight / free_weight
+            } else {
+                0.0
+            }
+        };
+
+        // Fix the side that crosses its limits the most, then split again
+        let (mut over, mut under) = (0.0, 0.0);
+        for (c, _) in contributions
+            .iter()
+            .zip(&fixed)
+            .filter(|(_, f)| f.is_none())
+        {
+            over += (share(c) - cap(c)).max(0.0);
+            under += (floor(c) - share(c)).max(0.0);
+        }
+        if over <= EPSILON && under <= EPSILON {
+            if free_weight <= 0.0 && remaining.abs() > EPSILON {
+                return Err(SplitError::InvalidLimits);
+            }
+            return Ok(contributions
+                .iter()
+                .zip(fixed)
+                .map(|(c, f)| f.unwrap_or_else(|| share(c)))
+                .collect());
+        }
+        for (c, f) in contributions.iter().zip(fixed.iter_mut()) {
+            if f.is_some() {
+                continue;
+            }
+            if over >= under && share(c) > cap(c) {
+                *f = Some(cap(c));
+            } else if over < under && share(c) < floor(c) {
+                *f = Some(floor(c));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contribution(participant_id: i64, weight: f64) -> CreateContribution {
+        CreateContribution {
+            participant_id,
+            weight,
+            amount: None,
+            cap: None,
+            floor: None,
+        }
+    }
+
+    fn money(value: f64) -> Money {
+        Money::from_f64(value, 2)
+    }
+
+    #[test]
+    fn test_exact_amounts_must_sum_to_total() {
+        let mut alice = contribution(1, 0.0);
+        let mut bob = contribution(2, 0.0);
+        alice.amount = Some(money(12.5));
+        bob.amount = Some(money(87.5));
+        let split = [alice, bob];
+
+        assert_eq!(
+            SplitMode::Exact.resolve(money(100.0), &split),
+            Ok(vec![money(12.5), money(87.5)])
+        );
+        assert_eq!(
+            SplitMode::Exact.resolve(money(99.0), &split),
+            Err(SplitError::AmountsMismatch)
+        );
+        assert_eq!(SplitMode::Exact.stored_weight(&split[1]), 87.5);
+    }
+
+    #[test]
+    fn test_percentages_and_shares() {
+        let percentages = [contribution(1, 25.0), contribution(2, 75.0)];
+        assert_eq!(
+            SplitMode::Percentages.resolve(money(40.0), &percentages),
+            Ok(vec![money(10.0), money(30.0)])
+        );
+        assert_eq!(
+            SplitMode::Percentages.resolve(money(40.0), &percentages[..1]),
+            Err(SplitError::PercentagesNotHundred)
+        );
+
+        let shares = [contribution(1, 1.0), contribution(2, 2.0)];
+        assert_eq!(
+            SplitMode::Shares.resolve(money(30.0), &shares),
+            Ok(vec![money(10.0), money(20.0)])
+        );
+        assert_eq!(
+            SplitMode::Shares.resolve(money(30.0), &[contribution(1, 1.5)]),
+            Err(SplitError::SharesNotWhole)
+        );
+    }
+
+    #[test]
+    fn test_caps_and_floors_redistribute() {
+        // Equal thirds of 90, Carol capped at 20 and Alice at 33: Carol's
+        // excess goes to Alice and Bob, then Alice's to Bob
+        let mut split = [
+            contribution(1, 1.0),
+            contribution(2, 1.0),
+            contribution(3, 1.0),
+        ];
+        split[0].cap = Some(money(33.0));
+        split[2].cap = Some(money(20.0));
+        assert_eq!(
+            SplitMode::Weights.resolve(money(90.0), &split),
+            Ok(vec![money(33.0), money(37.0), money(20.0)])
+        );
+
+        // A floor takes from the others in proportion to their weights
+        let mut split = [
+            contribution(1, 1.0),
+            contribution(2, 3.0),
+            contribution(3, 0.0),
+        ];
+        split[2].floor = Some(money(20.0));
+        assert_eq!(
+            SplitMode::Weights.resolve(money(100.0), &split),
+            Ok(vec![money(20.0), money(60.0), money(20.0)])
+        );
+
+        // Limits no split can meet
+        let mut split = [contribution(1, 1.0), contribution(2, 1.0)];
+        split[0].cap = Some(money(10.0));
+        split[1].cap = Some(money(10.0));
+        assert_eq!(
+            SplitMode::Weights.resolve(money(30.0), &split),
+            Err(SplitError::InvalidLimits)
+        );
+        assert_eq!(
+            SplitMode::Shares.resolve(money(20.0), &split),
+            Err(SplitError::InvalidLimits)
+        );
+    }
+}
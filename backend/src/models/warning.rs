@@ -0,0 +1,32 @@
+use serde::Serialize;
+
+use super::Money;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PoolWarningKind {
+    /// The pool balance drops below its expected minimum
+    PoolBelowExpected,
+    /// A participant's share drops below what they are expected to keep in the pool
+    UserBelowExpected,
+    /// The pool's expected minimum itself goes negative (misconfigured rules)
+    NegativeExpectedMinimum,
+}
+
+/// A pool problem expected before the end of its configured warning horizon
+#[derive(Debug, Clone, Serialize)]
+pub struct PoolWarning {
+    pub kind: PoolWarningKind,
+    pub pool_id: i64,
+    pub pool_name: String,
+    /// Affected participant (user warnings only)
+    pub participant_id: Option<i64>,
+    pub participant_name: Option<String>,
+    /// First date the problem occurs (today if it already does)
+    pub date: String,
+    /// Largest gap to the expected minimum (or below zero) within the horizon
+    pub shortfall: Money,
+    /// Horizon setting that produced the warning, and the date it resolves to
+    pub horizon: String,
+    pub horizon_date: String,
+}
@@ -0,0 +1,54 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+use super::{bounded::ShortString, CreateContribution};
+
+/// Named weights payments can share instead of their own (e.g. "rent by
+/// room size"); changes take effect from a date as new versions
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct WeightProfile {
+    pub id: i64,
+    pub project_id: i64,
+    pub name: String,
+    pub created_at: String,
+}
+
+/// Weights of a profile from `effective_from` on (NULL = the first version)
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct WeightProfileVersion {
+    pub id: i64,
+    pub profile_id: i64,
+    pub effective_from: Option<String>,
+    // JSON: [{"participant_id": 1, "weight": 2.0}]
+    pub weights: String,
+    pub created_at: String,
+}
+
+impl WeightProfileVersion {
+    /// Weights as submitted, for building contributions
+    pub fn contributions(&self) -> Vec<CreateContribution> {
+        serde_json::from_str(&self.weights).unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WeightProfileWithVersions {
+    #[serde(flatten)]
+    pub profile: WeightProfile,
+    pub versions: Vec<WeightProfileVersion>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateWeightProfile {
+    pub name: ShortString,
+    pub contributions: Vec<CreateContribution>,
+}
+
+/// Rename a profile and/or change its weights for occurrences dated on or
+/// after `effective_from`
+#[derive(Debug, Deserialize)]
+pub struct UpdateWeightProfile {
+    pub name: Option<ShortString>,
+    pub contributions: Option<Vec<CreateContribution>>,
+    pub effective_from: Option<String>,
+}
@@ -0,0 +1,306 @@
+use axum::{
+    extract::{Path, State},
+    routing::{get, put},
+    Json, Router,
+};
+use chrono::NaiveDate;
+use serde::Deserialize;
+use sqlx::SqlitePool;
+use std::collections::HashSet;
+
+use crate::{
+    auth::ProjectMember,
+    error::{AppError, AppResult, ErrorCode},
+    models::{
+        ContributionRule, ContributionRuleAmount, ContributionRuleWithAmounts,
+        CreateContributionRule, EntityType, RecurrenceSet,
+    },
+    services::HistoryService,
+    AppState,
+};
+
+#[derive(Deserialize)]
+struct RulePath {
+    rule_id: i64,
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_rules).post(create_rule))
+        .route("/{rule_id}", put(update_rule).delete(delete_rule))
+}
+
+/// Expected amounts of a rule, with participant names
+async fn rule_amounts(pool: &SqlitePool, rule_id: i64) -> AppResult<Vec<ContributionRuleAmount>> {
+    Ok(sqlx::query_as(
+        "SELECT a.id, a.rule_id, a.participant_id, p.name as participant_name, a.amount
+         FROM contribution_rule_amounts a
+         JOIN participants p ON a.participant_id = p.id
+         WHERE a.rule_id = ?
+         ORDER BY a.id",
+    )
+    .bind(rule_id)
+    .fetch_all(pool)
+    .await?)
+}
+
+async fn find_rule(
+    pool: &SqlitePool,
+    project_id: i64,
+    rule_id: i64,
+) -> AppResult<ContributionRuleWithAmounts> {
+    let rule: ContributionRule =
+        sqlx::query_as("SELECT * FROM contribution_rules WHERE id = ? AND project_id = ?")
+            .bind(rule_id)
+            .bind(project_id)
+            .fetch_optional(pool)
+            .await?
+            .ok_or_else(|| AppError::not_found(ErrorCode::ContributionRuleNotFound))?;
+    let amounts = rule_amounts(pool, rule.id).await?;
+    Ok(ContributionRuleWithAmounts { rule, amounts })
+}
+
+/// Check a submitted rule: a pool of the project, a valid schedule and dates,
+/// and a positive amount for each of some distinct non-pool participants.
+/// Returns the schedule as stored, normalized.
+async fn validate_rule(
+    pool: &SqlitePool,
+    project_id: i64,
+    input: &CreateContributionRule,
+) -> AppResult<Option<String>> {
+    let pool_exists: Option<i64> = sqlx::query_scalar(
+        "SELECT id FROM participants WHERE id = ? AND project_id = ? AND account_type = 'pool'",
+    )
+    .bind(input.pool_id)
+    .bind(project_id)
+    .fetch_optional(pool)
+    .await?;
+    if pool_exists.is_none() {
+        return Err(AppError::bad_request(ErrorCode::InvalidParticipant));
+    }
+
+    let recurrence_rule = match &input.recurrence_rule {
+        Some(rule) => Some(
+            RecurrenceSet::parse(rule)
+                .ok_or_else(|| AppError::bad_request(ErrorCode::InvalidRecurrenceRule))?
+                .to_string(),
+        ),
+        None => None,
+    };
+
+    let start = NaiveDate::parse_from_str(input.start_date.as_str(), "%Y-%m-%d")
+        .map_err(|_| AppError::bad_request(ErrorCode::InvalidDateFormat))?;
+    if let Some(end_date) = &input.end_date {
+        let end = NaiveDate::parse_from_str(end_date.as_str(), "%Y-%m-%d")
+            .map_err(|_| AppError::bad_request(ErrorCode::InvalidDateFormat))?;
+        if end < start {
+            return Err(AppError::bad_request(ErrorCode::InvalidContributionRule));
+        }
+    }
+
+    if input.amounts.is_empty() {
+        return Err(AppError::bad_request(ErrorCode::InvalidContributionRule));
+    }
+    let mut seen = HashSet::new();
+    for amount in &input.amounts {
+        if !amount.amount.is_positive() || !seen.insert(amount.participant_id) {
+            return Err(AppError::bad_request(ErrorCode::InvalidContributionRule));
+        }
+        let participant_exists: Option<i64> = sqlx::query_scalar(
+            "SELECT id FROM participants WHERE id = ? AND project_id = ? AND account_type != 'pool'",
+        )
+        .bind(amount.participant_id)
+        .bind(project_id)
+        .fetch_optional(pool)
+        .await?;
+        if participant_exists.is_none() {
+            return Err(AppError::bad_request(ErrorCode::InvalidParticipant));
+        }
+    }
+
+    Ok(recurrence_rule)
+}
+
+/// Replace the expected amounts of a rule
+async fn save_amounts(
+    pool: &SqlitePool,
+    rule_id: i64,
+    input: &CreateContributionRule,
+) -> AppResult<()> {
+    let mut tx = pool.begin().await?;
+    sqlx::query("DELETE FROM contribution_rule_amounts WHERE rule_id = ?")
+        .bind(rule_id)
+        .execute(&mut *tx)
+        .await?;
+    for amount in &input.amounts {
+        sqlx::query(
+            "INSERT INTO contribution_rule_amounts (rule_id, participant_id, amount)
+             VALUES (?, ?, ?)",
+        )
+        .bind(rule_id)
+        .bind(amount.participant_id)
+        .bind(amount.amount)
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+/// GET /projects/{id}/contribution-rules
+async fn list_rules(
+    member: ProjectMember,
+    State(pool): State<SqlitePool>,
+) -> AppResult<Json<Vec<ContributionRuleWithAmounts>>> {
+    let rules: Vec<ContributionRule> = sqlx::query_as(
+        "SELECT * FROM contribution_rules WHERE project_id = ?
+         ORDER BY pool_id, start_date, id",
+    )
+    .bind(member.project_id)
+    .fetch_all(&pool)
+    .await?;
+
+    let mut result = Vec::with_capacity(rules.len());
+    for rule in rules {
+        let amounts = rule_amounts(&pool, rule.id).await?;
+        result.push(ContributionRuleWithAmounts { rule, amounts });
+    }
+
+    Ok(Json(result))
+}
+
+/// POST /projects/{id}/contribution-rules
+/// Expect deposits to a pool from some participants, on a schedule
+async fn create_rule(
+    member: ProjectMember,
+    State(pool): State<SqlitePool>,
+    Json(input): Json<CreateContributionRule>,
+) -> AppResult<Json<ContributionRuleWithAmounts>> {
+    if !member.can_edit() {
+        return Err(AppError::forbidden(ErrorCode::EditorRequired));
+    }
+
+    let recurrence_rule = validate_rule(&pool, member.project_id, &input).await?;
+
+    let result = sqlx::query(
+        "INSERT INTO contribution_rules
+         (project_id, pool_id, recurrence_rule, start_date, end_date, description)
+         VALUES (?, ?, ?, ?, ?, ?)",
+    )
+    .bind(member.project_id)
+    .bind(input.pool_id)
+    .bind(&recurrence_rule)
+    .bind(input.start_date.as_str())
+    .bind(input.end_date.as_ref().map(|d| d.as_str()))
+    .bind(input.description.as_ref().map_or("", |d| d.as_str()))
+    .execute(&pool)
+    .await?;
+    let rule_id = result.last_insert_rowid();
+    save_amounts(&pool, rule_id, &input).await?;
+
+    let rule = find_rule(&pool, member.project_id, rule_id).await?;
+
+    // Log the creation to history
+    let correlation_id = HistoryService::new_correlation_id();
+    let _ = HistoryService::log_create(
+        &pool,
+        &correlation_id,
+        member.user_id,
+        member.project_id,
+        EntityType::ContributionRule,
+        rule_id,
+        &rule,
+    )
+    .await;
+
+    Ok(Json(rule))
+}
+
+/// PUT /projects/{id}/contribution-rules/{rule_id}
+async fn update_rule(
+    Path(path): Path<RulePath>,
+    member: ProjectMember,
+    State(pool): State<SqlitePool>,
+    Json(input): Json<CreateContributionRule>,
+) -> AppResult<Json<ContributionRuleWithAmounts>> {
+    if !member.can_edit() {
+        return Err(AppError::forbidden(ErrorCode::EditorRequired));
+    }
+
+    let before = find_rule(&pool, member.project_id, path.rule_id).await?;
+    let recurrence_rule = validate_rule(&pool, member.project_id, &input).await?;
+
+    sqlx::query(
+        "UPDATE contribution_rules
+         SET pool_id = ?, recurrence_rule = ?, start_date = ?, end_date = ?, description = ?
+         WHERE id = ?",
+    )
+    .bind(input.pool_id)
+    .bind(&recurrence_rule)
+    .bind(input.start_date.as_str())
+    .bind(input.end_date.as_ref().map(|d| d.as_str()))
+    .bind(input.description.as_ref().map_or("", |d| d.as_str()))
+    .bind(path.rule_id)
+    .execute(&pool)
+    .await?;
+    save_amounts(&pool, path.rule_id, &input).await?;
+
+    let rule = find_rule(&pool, member.project_id, path.rule_id).await?;
+
+    // Log the update to history
+    let correlation_id = HistoryService::new_correlation_id();
+    let _ = HistoryService::log_update(
+        &pool,
+        crate::services::history::LogUpdateParams {
+            correlation_id: &correlation_id,
+            actor_user_id: member.user_id,
+            project_id: member.project_id,
+            entity_type: EntityType::ContributionRule,
+            entity_id: path.rule_id,
+            before: &before,
+            after: &rule,
+        },
+    )
+    .await;
+
+    Ok(Json(rule))
+}
+
+/// DELETE /projects/{id}/contribution-rules/{rule_id}
+async fn delete_rule(
+    Path(path): Path<RulePath>,
+    member: ProjectMember,
+    State(pool): State<SqlitePool>,
+) -> AppResult<Json<serde_json::Value>> {
+    if !member.can_edit() {
+        return Err(AppError::forbidden(ErrorCode::EditorRequired));
+    }
+
+    let existing = find_rule(&pool, member.project_id, path.rule_id).await?;
+
+    let mut tx = pool.begin().await?;
+    sqlx::query("DELETE FROM contribution_rule_amounts WHERE rule_id = ?")
+        .bind(path.rule_id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("DELETE FROM contribution_rules WHERE id = ?")
+        .bind(path.rule_id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    // Log the deletion to history
+    let correlation_id = HistoryService::new_correlation_id();
+    let _ = HistoryService::log_delete(
+        &pool,
+        &correlation_id,
+        member.user_id,
+        member.project_id,
+        EntityType::ContributionRule,
+        path.rule_id,
+        &existing,
+    )
+    .await;
+
+    Ok(Json(serde_json::json!({ "deleted": true })))
+}
@@ -1,21 +1,107 @@
 use axum::{
     extract::{Query, State},
-    routing::get,
+    routing::{get, post},
     Json, Router,
 };
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use sqlx::SqlitePool;
+use std::collections::{HashMap, HashSet};
 
-use crate::{auth::ProjectMember, error::AppResult, services::DebtSummary, AppState};
+use crate::{
+    auth::ProjectMember,
+    error::{AppError, AppResult, ErrorCode},
+    models::{
+        ContributionWithParticipant, EntityType, Money, Payment, PaymentWithContributions,
+        SettleDebts, SettlementSource,
+    },
+    services::{
+        ledger_cache::ledger_stamp, BalanceSeries, Debt, DebtSummary, HistoryService, PoolForecast,
+        SeriesStep, SettlementOptions, SettlementStrategy,
+    },
+    AppState,
+};
 
 #[derive(Deserialize)]
 struct DebtsQuery {
     date: Option<String>,
     include_drafts: Option<bool>,
+    strategy: Option<SettlementStrategy>,
+    /// Pairs that must never settle directly, as `from-to` participant ids
+    /// separated by commas (e.g. `3-5,4-5`)
+    exclude: Option<String>,
+    min_transfer: Option<Money>,
+    rounding: Option<Money>,
+}
+
+impl DebtsQuery {
+    fn target_date(&self) -> String {
+        self.date
+            .clone()
+            .unwrap_or_else(|| chrono::Utc::now().format("%Y-%m-%d").to_string())
+    }
+
+    fn settlement_options(&self) -> AppResult<SettlementOptions> {
+        let invalid = || AppError::bad_request(ErrorCode::InvalidSettlementOptions);
+
+        let mut excluded_pairs = HashSet::new();
+        for pair in self.exclude.iter().flat_map(|s| s.split(',')) {
+            let pair = pair.trim();
+            if pair.is_empty() {
+                continue;
+            }
+            let (from, to) = pair.split_once('-').ok_or_else(invalid)?;
+            let from: i64 = from.trim().parse().map_err(|_| invalid())?;
+            let to: i64 = to.trim().parse().map_err(|_| invalid())?;
+            excluded_pairs.insert((from, to));
+        }
+
+        let min_transfer = self.min_transfer.unwrap_or_default();
+        let rounding_unit = self.rounding.unwrap_or_default();
+        if min_transfer.is_negative() || rounding_unit.is_negative() {
+            return Err(invalid());
+        }
+
+        Ok(SettlementOptions {
+            strategy: self.strategy.unwrap_or_default(),
+            excluded_pairs,
+            min_transfer,
+            rounding_unit,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct SeriesQuery {
+    from: String,
+    /// Defaults to today
+    to: Option<String>,
+    /// Defaults to month
+    step: Option<SeriesStep>,
+    include_drafts: Option<bool>,
+}
+
+#[derive(Deserialize)]
+struct ForecastQuery {
+    /// Defaults to today
+    from: Option<String>,
+    to: String,
+    include_drafts: Option<bool>,
+}
+
+#[derive(Serialize)]
+struct SettleDebtsResult {
+    correlation_id: String,
+    payments: Vec<PaymentWithContributions>,
+    /// Debts recomputed after recording the transfers
+    summary: DebtSummary,
 }
 
 pub fn router() -> Router<AppState> {
-    Router::new().route("/", get(get_debts))
+    Router::new()
+        .route("/", get(get_debts))
+        .route("/series", get(get_balance_series))
+        .route("/forecast", get(get_pool_forecast))
+        .route("/settle", post(settle_debts))
 }
 
 async fn get_debts(
@@ -24,18 +110,270 @@ async fn get_debts(
     Query(query): Query<DebtsQuery>,
 ) -> AppResult<Json<DebtSummary>> {
     let include_drafts = query.include_drafts.unwrap_or(false);
+    let options = query.settlement_options()?;
+    let target_date = query.target_date();
 
-    let summary = match query.date {
-        Some(target_date) => {
-            crate::services::calculate_debts_at_date(
-                &pool,
-                member.project_id,
-                &target_date,
-                include_drafts,
-            )
-            .await?
+    let summary = crate::services::calculate_debts_with_options(
+        &pool,
+        member.project_id,
+        &target_date,
+        include_drafts,
+        &options,
+    )
+    .await?;
+    Ok(Json(summary))
+}
+
+/// GET /projects/{id}/debts/series?from=&to=&step=day|week|month
+/// Balances of every participant at each step, computed in one pass
+async fn get_balance_series(
+    member: ProjectMember,
+    State(pool): State<SqlitePool>,
+    Query(query): Query<SeriesQuery>,
+) -> AppResult<Json<BalanceSeries>> {
+    let parse = |date: &str| {
+        chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|_| AppError::bad_request(ErrorCode::InvalidDateFormat))
+    };
+    let from = parse(&query.from)?;
+    let to = match &query.to {
+        Some(to) => parse(to)?,
+        None => chrono::Utc::now().date_naive(),
+    };
+
+    let series = crate::services::calculate_balance_series(
+        &pool,
+        member.project_id,
+        from,
+        to,
+        query.step.unwrap_or(SeriesStep::Month),
+        query.include_drafts.unwrap_or(false),
+    )
+    .await?;
+    Ok(Json(series))
+}
+
+/// GET /projects/{id}/debts/forecast?from=&to=
+/// Balance and expected-minimum curves of every pool and user share up to `to`
+async fn get_pool_forecast(
+    member: ProjectMember,
+    State(pool): State<SqlitePool>,
+    Query(query): Query<ForecastQuery>,
+) -> AppResult<Json<Vec<PoolForecast>>> {
+    let parse = |date: &str| {
+        chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|_| AppError::bad_request(ErrorCode::InvalidDateFormat))
+    };
+    let from = match &query.from {
+        Some(from) => parse(from)?,
+        None => chrono::Utc::now().date_naive(),
+    };
+    let to = parse(&query.to)?;
+
+    let forecasts = crate::services::forecast_pools(
+        &pool,
+        member.project_id,
+        from,
+        to,
+        query.include_drafts.unwrap_or(false),
+    )
+    .await?;
+    Ok(Json(forecasts))
+}
+
+/// POST /projects/{id}/debts/settle
+/// Record suggested debts as user-to-user transfer payments, all at once.
+/// Takes the same query parameters as GET so the suggestions are recomputed the
+/// same way, and refuses to proceed if the balances differ from what the client saw.
+/// `rounding` and `min_transfer` are rejected: they leave part of each balance
+/// unsettled, so the recorded transfers would not zero the balances.
+async fn settle_debts(
+    member: ProjectMember,
+    State(pool): State<SqlitePool>,
+    Query(query): Query<DebtsQuery>,
+    Json(input): Json<SettleDebts>,
+) -> AppResult<Json<SettleDebtsResult>> {
+    if !member.can_edit() {
+        return Err(AppError::forbidden(ErrorCode::EditorRequired));
+    }
+
+    let options = query.settlement_options()?;
+    if !options.min_transfer.is_zero() || !options.rounding_unit.is_zero() {
+        return Err(AppError::bad_request(ErrorCode::InvalidSettlementOptions));
+    }
+    let target_date = query.target_date();
+    if chrono::NaiveDate::parse_from_str(&target_date, "%Y-%m-%d").is_err() {
+        return Err(AppError::bad_request(ErrorCode::InvalidDateFormat));
+    }
+
+    // The stamp the summary is computed from, checked again once the write
+    // lock is held
+    let stamp = ledger_stamp(&pool, member.project_id).await?;
+    let summary = crate::services::calculate_debts_with_options(
+        &pool,
+        member.project_id,
+        &target_date,
+        false,
+        &options,
+    )
+    .await?;
+
+    // Balances must be exactly what the client based its selection on
+    let current: HashMap<i64, Money> = summary
+        .balances
+        .iter()
+        .filter(|b| !b.net_balance.is_zero())
+        .map(|b| (b.participant_id, b.net_balance))
+        .collect();
+    let expected: HashMap<i64, Money> = input
+        .expected_balances
+        .iter()
+        .filter(|b| !b.net_balance.is_zero())
+        .map(|b| (b.participant_id, b.net_balance))
+        .collect();
+    if current != expected {
+        return Err(AppError::conflict(ErrorCode::BalancesChanged));
+    }
+
+    let suggested: &[Debt] = match input.source {
+        SettlementSource::Settlements => &summary.settlements,
+        SettlementSource::DirectSettlements => &summary.direct_settlements,
+        SettlementSource::SettlementPlan => &summary.settlement_plan.transfers,
+    };
+
+    let selected: Vec<&Debt> = match &input.debts {
+        None => suggested.iter().collect(),
+        Some(debts) => {
+            // Each selected debt must match a distinct suggestion
+            let mut remaining: Vec<&Debt> = suggested.iter().collect();
+            let mut selected = Vec::with_capacity(debts.len());
+            for debt in debts {
+                let idx = remaining
+                    .iter()
+                    .position(|s| {
+                        s.from_participant_id == debt.from_participant_id
+                            && s.to_participant_id == debt.to_participant_id
+                            && s.amount == debt.amount
+                    })
+                    .ok_or_else(|| AppError::bad_request(ErrorCode::SettlementNotSuggested))?;
+                selected.push(remaining.remove(idx));
+            }
+            selected
         }
-        None => crate::services::calculate_debts(&pool, member.project_id, include_drafts).await?,
     };
-    Ok(Json(summary))
+    if selected.is_empty() {
+        return Err(AppError::bad_request(ErrorCode::NothingToSettle));
+    }
+
+    // Take the write lock before checking that nothing changed since the
+    // summary, so no other change can land between the check and the transfers
+    let mut tx = pool.begin_with("BEGIN IMMEDIATE").await?;
+    let current_stamp: Option<String> =
+        sqlx::query_scalar("SELECT ledger_stamp FROM projects WHERE id = ?")
+            .bind(member.project_id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .flatten();
+    if current_stamp != stamp {
+        return Err(AppError::conflict(ErrorCode::BalancesChanged));
+    }
+
+    let mut payment_ids = Vec::with_capacity(selected.len());
+
+    for debt in &selected {
+        let description = format!(
+            "Settlement: {} → {}",
+            debt.from_participant_name, debt.to_participant_name
+        );
+
+        let result = sqlx::query(
+            "INSERT INTO payments (project_id, payer_id, amount, description, payment_date, receiver_account_id)
+             VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(member.project_id)
+        .bind(debt.from_participant_id)
+        .bind(debt.amount)
+        .bind(&description)
+        .bind(&target_date)
+        .bind(debt.to_participant_id)
+        .execute(&mut *tx)
+        .await?;
+        let payment_id = result.last_insert_rowid();
+
+        // Transfers carry a single contribution for the receiver
+        sqlx::query(
+            "INSERT INTO contributions (participant_id, payment_id, amount, weight) VALUES (?, ?, ?, 1.0)",
+        )
+        .bind(debt.to_participant_id)
+        .bind(payment_id)
+        .bind(debt.amount)
+        .execute(&mut *tx)
+        .await?;
+
+        payment_ids.push(payment_id);
+    }
+
+    // Log every transfer under one correlation id so they read as a single action
+    let correlation_id = HistoryService::new_correlation_id();
+    let mut payments = Vec::with_capacity(payment_ids.len());
+    for (payment_id, debt) in payment_ids.into_iter().zip(&selected) {
+        let payment: Payment = sqlx::query_as("SELECT * FROM payments WHERE id = ?")
+            .bind(payment_id)
+            .fetch_one(&mut *tx)
+            .await?;
+        let contribution_id: i64 =
+            sqlx::query_scalar("SELECT id FROM contributions WHERE payment_id = ?")
+                .bind(payment_id)
+                .fetch_one(&mut *tx)
+                .await?;
+
+        let result = PaymentWithContributions {
+            payment,
+            payer_name: Some(debt.from_participant_name.clone()),
+            payers: Vec::new(),
+            contributions: vec![ContributionWithParticipant {
+                id: contribution_id,
+                participant_id: debt.to_participant_id,
+                participant_name: debt.to_participant_name.clone(),
+                payment_id,
+                amount: debt.amount,
+                weight: 1.0,
+                cap: None,
+                floor: None,
+            }],
+            items: Vec::new(),
+            refunds: Vec::new(),
+            net_cost: None,
+        };
+
+        HistoryService::log_create(
+            &mut *tx,
+            &correlation_id,
+            member.user_id,
+            member.project_id,
+            EntityType::Payment,
+            payment_id,
+            &result,
+        )
+        .await?;
+
+        payments.push(result);
+    }
+
+    tx.commit().await?;
+
+    let summary = crate::services::calculate_debts_with_options(
+        &pool,
+        member.project_id,
+        &target_date,
+        false,
+        &options,
+    )
+    .await?;
+
+    Ok(Json(SettleDebtsResult {
+        correlation_id,
+        payments,
+        summary,
+    }))
 }
@@ -0,0 +1,249 @@
+use axum::{
+    extract::{Path, State},
+    routing::{delete, get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use sqlx::SqlitePool;
+
+use crate::{
+    auth::ProjectMember,
+    error::{AppError, AppResult, ErrorCode},
+    models::{
+        is_valid_currency_code, CreateExchangeRate, EntityType, ExchangeRate, ImportExchangeRates,
+        ImportExchangeRatesResult,
+    },
+    services::{history::LogEventParams, parse_ecb_csv, HistoryService},
+    AppState,
+};
+
+/// ECB reference rates are quoted against the euro
+const ECB_BASE_CURRENCY: &str = "EUR";
+
+#[derive(Deserialize)]
+struct ExchangeRatePath {
+    rate_id: i64,
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_exchange_rates).post(create_exchange_rate))
+        .route("/import", post(import_exchange_rates))
+        .route("/{rate_id}", delete(delete_exchange_rate))
+}
+
+async fn list_exchange_rates(
+    member: ProjectMember,
+    State(pool): State<SqlitePool>,
+) -> AppResult<Json<Vec<ExchangeRate>>> {
+    let rates: Vec<ExchangeRate> = sqlx::query_as(
+        "SELECT * FROM exchange_rates WHERE project_id = ?
+         ORDER BY rate_date DESC, base_currency, quote_currency",
+    )
+    .bind(member.project_id)
+    .fetch_all(&pool)
+    .await?;
+
+    Ok(Json(rates))
+}
+
+/// POST /projects/{id}/exchange-rates
+/// Add a rate manually; replaces any existing rate for the same pair and date
+async fn create_exchange_rate(
+    member: ProjectMember,
+    State(pool): State<SqlitePool>,
+    Json(input): Json<CreateExchangeRate>,
+) -> AppResult<Json<ExchangeRate>> {
+    if !member.can_edit() {
+        return Err(AppError::forbidden(ErrorCode::EditorRequired));
+    }
+
+    let base_currency = match input.base_currency {
+        Some(code) => code.into_inner(),
+        None => project_base_currency(&pool, member.project_id).await?,
+    };
+    let quote_currency = input.quote_currency.as_str();
+
+    if !is_valid_currency_code(&base_currency)
+        || !is_valid_currency_code(quote_currency)
+        || base_currency == quote_currency
+    {
+        return Err(AppError::bad_request(ErrorCode::InvalidCurrency));
+    }
+    if chrono::NaiveDate::parse_from_str(input.rate_date.as_str(), "%Y-%m-%d").is_err() {
+        return Err(AppError::bad_request(ErrorCode::InvalidDateFormat));
+    }
+    if !(input.rate.is_finite() && input.rate > 0.0) {
+        return Err(AppError::bad_request(ErrorCode::InvalidExchangeRate));
+    }
+
+    sqlx::query(
+        "INSERT INTO exchange_rates (project_id, base_currency, quote_currency, rate_date, rate, source)
+         VALUES (?, ?, ?, ?, ?, 'manual')
+         ON CONFLICT(project_id, base_currency, quote_currency, rate_date)
+         DO UPDATE SET rate = excluded.rate, source = excluded.source",
+    )
+    .bind(member.project_id)
+    .bind(&base_currency)
+    .bind(quote_currency)
+    .bind(input.rate_date.as_str())
+    .bind(input.rate)
+    .execute(&pool)
+    .await?;
+
+    let rate: ExchangeRate = sqlx::query_as(
+        "SELECT * FROM exchange_rates
+         WHERE project_id = ? AND base_currency = ? AND quote_currency = ? AND rate_date = ?",
+    )
+    .bind(member.project_id)
+    .bind(&base_currency)
+    .bind(quote_currency)
+    .bind(input.rate_date.as_str())
+    .fetch_one(&pool)
+    .await?;
+
+    // Log the creation to history
+    let correlation_id = HistoryService::new_correlation_id();
+    let _ = HistoryService::log_create(
+        &pool,
+        &correlation_id,
+        member.user_id,
+        member.project_id,
+        EntityType::ExchangeRate,
+        rate.id,
+        &rate,
+    )
+    .await;
+
+    Ok(Json(rate))
+}
+
+/// POST /projects/{id}/exchange-rates/import
+/// Import an ECB-style CSV file (Date column followed by one column per currency)
+async fn import_exchange_rates(
+    member: ProjectMember,
+    State(pool): State<SqlitePool>,
+    Json(input): Json<ImportExchangeRates>,
+) -> AppResult<Json<ImportExchangeRatesResult>> {
+    if !member.can_edit() {
+        return Err(AppError::forbidden(ErrorCode::EditorRequired));
+    }
+
+    let base_currency = input
+        .base_currency
+        .as_ref()
+        .map(|c| c.as_str())
+        .unwrap_or(ECB_BASE_CURRENCY);
+    if !is_valid_currency_code(base_currency) {
+        return Err(AppError::bad_request(ErrorCode::InvalidCurrency));
+    }
+
+    let parsed = parse_ecb_csv(&input.csv).map_err(|e| {
+        tracing::debug!("Rejected exchange rate file: {}", e);
+        AppError::bad_request(ErrorCode::InvalidRateFile)
+    })?;
+
+    let mut tx = pool.begin().await?;
+    let mut imported = 0;
+    let mut skipped = parsed.skipped;
+
+    for (date, quote_currency, rate) in &parsed.rates {
+        if quote_currency == base_currency {
+            skipped += 1;
+            continue;
+        }
+
+        sqlx::query(
+            "INSERT INTO exchange_rates (project_id, base_currency, quote_currency, rate_date, rate, source)
+             VALUES (?, ?, ?, ?, ?, 'import')
+             ON CONFLICT(project_id, base_currency, quote_currency, rate_date)
+             DO UPDATE SET rate = excluded.rate, source = excluded.source",
+        )
+        .bind(member.project_id)
+        .bind(base_currency)
+        .bind(quote_currency)
+        .bind(date.format("%Y-%m-%d").to_string())
+        .bind(rate)
+        .execute(&mut *tx)
+        .await?;
+
+        imported += 1;
+    }
+
+    tx.commit().await?;
+
+    let result = ImportExchangeRatesResult { imported, skipped };
+
+    // Log a single history entry for the whole import
+    let correlation_id = HistoryService::new_correlation_id();
+    let summary = serde_json::json!({
+        "base_currency": base_currency,
+        "imported": result.imported,
+        "skipped": result.skipped,
+    })
+    .to_string();
+    let _ = HistoryService::log_event(
+        &pool,
+        LogEventParams {
+            correlation_id: &correlation_id,
+            actor_user_id: Some(member.user_id),
+            project_id: Some(member.project_id),
+            entity_type: EntityType::ExchangeRate.as_str(),
+            entity_id: None,
+            action: "CREATE",
+            payload_before: None,
+            payload_after: Some(&summary),
+            reason: None,
+            undoes_history_id: None,
+        },
+    )
+    .await;
+
+    Ok(Json(result))
+}
+
+async fn delete_exchange_rate(
+    Path(path): Path<ExchangeRatePath>,
+    member: ProjectMember,
+    State(pool): State<SqlitePool>,
+) -> AppResult<Json<serde_json::Value>> {
+    if !member.can_edit() {
+        return Err(AppError::forbidden(ErrorCode::EditorRequired));
+    }
+
+    let existing: ExchangeRate =
+        sqlx::query_as("SELECT * FROM exchange_rates WHERE id = ? AND project_id = ?")
+            .bind(path.rate_id)
+            .bind(member.project_id)
+            .fetch_optional(&pool)
+            .await?
+            .ok_or_else(|| AppError::not_found(ErrorCode::ExchangeRateNotFound))?;
+
+    sqlx::query("DELETE FROM exchange_rates WHERE id = ?")
+        .bind(path.rate_id)
+        .execute(&pool)
+        .await?;
+
+    // Log the deletion to history
+    let correlation_id = HistoryService::new_correlation_id();
+    let _ = HistoryService::log_delete(
+        &pool,
+        &correlation_id,
+        member.user_id,
+        member.project_id,
+        EntityType::ExchangeRate,
+        path.rate_id,
+        &existing,
+    )
+    .await;
+
+    Ok(Json(serde_json::json!({ "deleted": true })))
+}
+
+async fn project_base_currency(pool: &SqlitePool, project_id: i64) -> AppResult<String> {
+    let currency: String = sqlx::query_scalar("SELECT base_currency FROM projects WHERE id = ?")
+        .bind(project_id)
+        .fetch_one(pool)
+        .await?;
+    Ok(currency)
+}
@@ -3,12 +3,15 @@ use axum::{
     routing::{get, post},
     Json, Router,
 };
+use serde::Deserialize;
 use sqlx::SqlitePool;
 
 use crate::{
     auth::ProjectMember,
     error::{AppError, AppResult, ErrorCode},
-    models::{ChainVerification, HistoryEntryResponse, HistoryQuery, UndoRequest},
+    models::{
+        ChainVerification, HistoryEntryResponse, HistoryQuery, Money, UndoRequest, DEFAULT_SCALE,
+    },
     services::{history::LogEventParams, HistoryService},
     AppState,
 };
@@ -21,6 +24,175 @@ pub fn router() -> Router<AppState> {
         .route("/{history_id}/undo", post(undo_action))
 }
 
+/// Read an amount from a history payload (serialized as a JSON number).
+/// Payloads from before integer amounts may carry floating-point noise,
+/// which is rounded to cents.
+fn json_money(value: Option<&serde_json::Value>) -> Money {
+    value
+        .and_then(|v| {
+            Money::deserialize(v)
+                .ok()
+                .or_else(|| v.as_f64().map(|v| Money::from_f64(v, DEFAULT_SCALE)))
+        })
+        .unwrap_or(Money::ZERO)
+}
+
+/// Re-insert contributions from a history payload.
+/// Shares are the stored amounts while they still add up to `amount` (so
+/// exact and capped splits come back as they were), otherwise they are
+/// recomputed from the stored weights.
+async fn restore_contributions(
+    pool: &SqlitePool,
+    payment_id: i64,
+    amount: Money,
+    contributions: &[serde_json::Value],
+) -> AppResult<()> {
+    let money = |contrib: &serde_json::Value, key: &str| {
+        contrib
+            .get(key)
+            .filter(|v| !v.is_null())
+            .map(|v| json_money(Some(v)))
+    };
+    let entries: Vec<(&serde_json::Value, i64, f64)> = contributions
+        .iter()
+        .filter_map(|contrib| {
+            let participant_id = contrib.get("participant_id").and_then(|v| v.as_i64())?;
+            let weight = contrib
+                .get("weight")
+                .and_then(|v| v.as_f64())
+                .unwrap_or(1.0);
+            Some((contrib, participant_id, weight))
+        })
+        .collect();
+    let weights: Vec<(i64, f64)> = entries
+        .iter()
+        .map(|(_, id, weight)| (*id, *weight))
+        .collect();
+
+    let stored: Option<Vec<Money>> = entries
+        .iter()
+        .map(|(contrib, _, _)| money(contrib, "amount"))
+        .collect();
+    let shares = match stored {
+        Some(shares) if shares.iter().copied().sum::<Money>() == amount => shares,
+        _ => amount.allocate(&weights),
+    };
+
+    for ((contrib, participant_id, weight), share) in entries.into_iter().zip(shares) {
+        sqlx::query(
+            "INSERT INTO contributions (participant_id, payment_id, amount, weight, cap, floor) VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(participant_id)
+        .bind(payment_id)
+        .bind(share)
+        .bind(weight)
+        .bind(money(contrib, "cap"))
+        .bind(money(contrib, "floor"))
+        .execute(pool)
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Re-insert the payers of a payment paid by several from a history payload
+async fn restore_payers(
+    pool: &SqlitePool,
+    payment_id: i64,
+    payers: &[serde_json::Value],
+) -> AppResult<()> {
+    for payer in payers {
+        let Some(participant_id) = payer.get("participant_id").and_then(|v| v.as_i64()) else {
+            continue;
+        };
+        sqlx::query(
+            "INSERT INTO payment_payers (payment_id, participant_id, amount) VALUES (?, ?, ?)",
+        )
+        .bind(payment_id)
+        .bind(participant_id)
+        .bind(json_money(payer.get("amount")))
+        .execute(pool)
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Re-insert the refunds of a payment from a history payload, under their
+/// original ids
+async fn restore_refunds(
+    pool: &SqlitePool,
+    payment_id: i64,
+    refunds: &[serde_json::Value],
+) -> AppResult<()> {
+    for refund in refunds {
+        insert_refund(
+            pool,
+            refund.get("id").and_then(|v| v.as_i64()),
+            payment_id,
+            refund,
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+async fn insert_refund(
+    pool: &SqlitePool,
+    id: Option<i64>,
+    payment_id: i64,
+    refund: &serde_json::Value,
+) -> AppResult<()> {
+    sqlx::query(
+        "INSERT OR REPLACE INTO refunds (id, payment_id, amount, refund_date, description, weights)
+         VALUES (?, ?, ?, ?, ?, ?)",
+    )
+    .bind(id)
+    .bind(payment_id)
+    .bind(json_money(refund.get("amount")))
+    .bind(refund.get("refund_date").and_then(|v| v.as_str()))
+    .bind(
+        refund
+            .get("description")
+            .and_then(|v| v.as_str())
+            .unwrap_or(""),
+    )
+    .bind(refund.get("weights").and_then(|v| v.as_str()))
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Re-insert the receipt lines of an itemized payment from a history payload
+async fn restore_items(
+    pool: &SqlitePool,
+    payment_id: i64,
+    items: &[serde_json::Value],
+) -> AppResult<()> {
+    for (position, item) in items.iter().enumerate() {
+        sqlx::query(
+            "INSERT INTO payment_items (payment_id, position, kind, description, amount, weights)
+             VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(payment_id)
+        .bind(position as i64)
+        .bind(item.get("kind").and_then(|v| v.as_str()).unwrap_or("item"))
+        .bind(
+            item.get("description")
+                .and_then(|v| v.as_str())
+                .unwrap_or(""),
+        )
+        .bind(json_money(item.get("amount")))
+        .bind(item.get("weights").and_then(|v| v.as_str()))
+        .execute(pool)
+        .await?;
+    }
+
+    Ok(())
+}
+
 /// GET /projects/{id}/history
 /// Get paginated history entries for the project
 async fn get_project_history(
@@ -142,7 +314,21 @@ async fn perform_undo(
         .ok_or_else(|| AppError::bad_request(ErrorCode::InvalidInput))?;
 
     match entry.entity_type.as_str() {
-        "payment" => undo_payment(pool, member, entry, entity_id, &correlation_id, reason).await,
+        "payment" => {
+            // A series split is undone as a whole: both halves go back together
+            let partner = split_partner(pool, entry).await?;
+            let undo_id =
+                undo_payment(pool, member, entry, entity_id, &correlation_id, reason).await?;
+            if let Some(partner) = partner {
+                if let Some(partner_id) = partner.entity_id {
+                    if !HistoryService::is_entry_undone(pool, partner.id).await? {
+                        undo_payment(pool, member, &partner, partner_id, &correlation_id, reason)
+                            .await?;
+                    }
+                }
+            }
+            Ok(undo_id)
+        }
         "participant" => {
             undo_participant(pool, member, entry, entity_id, &correlation_id, reason).await
         }
@@ -150,10 +336,51 @@ async fn perform_undo(
             undo_project_member(pool, member, entry, entity_id, &correlation_id, reason).await
         }
         "project" => undo_project(pool, member, entry, entity_id, &correlation_id, reason).await,
+        "occurrence_exception" => {
+            undo_occurrence_exception(pool, member, entry, entity_id, &correlation_id, reason).await
+        }
+        "presence" => undo_presence(pool, member, entry, entity_id, &correlation_id, reason).await,
+        "refund" => undo_refund(pool, member, entry, entity_id, &correlation_id, reason).await,
+        "pool_valuation" => {
+            undo_pool_valuation(pool, member, entry, entity_id, &correlation_id, reason).await
+        }
+        "pool_rule" => {
+            undo_pool_rule(pool, member, entry, entity_id, &correlation_id, reason).await
+        }
+        "pool_statement" => {
+            undo_pool_statement(pool, member, entry, entity_id, &correlation_id, reason).await
+        }
+        "contribution_rule" => {
+            undo_contribution_rule(pool, member, entry, entity_id, &correlation_id, reason).await
+        }
         _ => Err(AppError::bad_request(ErrorCode::InvalidInput)),
     }
 }
 
+/// The other half of a series split logged with `entry`: a split updates the
+/// first half and creates its continuation under one correlation id
+async fn split_partner(
+    pool: &SqlitePool,
+    entry: &crate::models::HistoryEntry,
+) -> AppResult<Option<crate::models::HistoryEntry>> {
+    // Payment the entry created as the continuation of a split, if any
+    let continues = |e: &crate::models::HistoryEntry| -> Option<i64> {
+        if e.action != "CREATE" {
+            return None;
+        }
+        let after: serde_json::Value = serde_json::from_str(e.payload_after.as_deref()?).ok()?;
+        after.get("split_from_id").and_then(|v| v.as_i64())
+    };
+
+    let entries = HistoryService::get_correlated_entries(pool, &entry.correlation_id).await?;
+    Ok(entries.into_iter().find(|other| {
+        other.id != entry.id
+            && other.entity_type == "payment"
+            && ((entry.action == "UPDATE" && continues(other) == entry.entity_id)
+                || (other.action == "UPDATE" && continues(entry) == other.entity_id))
+    }))
+}
+
 /// Undo a payment action
 async fn undo_payment(
     pool: &SqlitePool,
@@ -163,6 +390,20 @@ async fn undo_payment(
     correlation_id: &str,
     reason: Option<&str>,
 ) -> AppResult<i64> {
+    // A created or updated payment may be gone since: deleted, or converted
+    // to a contribution rule by Migration 042
+    if entry.action != "DELETE" {
+        let exists: Option<i64> =
+            sqlx::query_scalar("SELECT id FROM payments WHERE id = ? AND project_id = ?")
+                .bind(entity_id)
+                .bind(member.project_id)
+                .fetch_optional(pool)
+                .await?;
+        if exists.is_none() {
+            return Err(AppError::not_found(ErrorCode::PaymentNotFound));
+        }
+    }
+
     match entry.action.as_str() {
         "CREATE" => {
             // Undo create = delete the payment
@@ -171,6 +412,39 @@ async fn undo_payment(
                 .execute(pool)
                 .await?;
 
+            sqlx::query("DELETE FROM payment_items WHERE payment_id = ?")
+                .bind(entity_id)
+                .execute(pool)
+                .await?;
+
+            sqlx::query("DELETE FROM payment_payers WHERE payment_id = ?")
+                .bind(entity_id)
+                .execute(pool)
+                .await?;
+
+            sqlx::query("DELETE FROM refunds WHERE payment_id = ?")
+                .bind(entity_id)
+                .execute(pool)
+                .await?;
+
+            // A split continuation hands its exceptions back to the first half
+            sqlx::query(
+                "UPDATE occurrence_exceptions
+                 SET payment_id = (SELECT split_from_id FROM payments WHERE id = ?)
+                 WHERE payment_id = ?
+                 AND (SELECT split_from_id FROM payments WHERE id = ?) IS NOT NULL",
+            )
+            .bind(entity_id)
+            .bind(entity_id)
+            .bind(entity_id)
+            .execute(pool)
+            .await?;
+
+            sqlx::query("DELETE FROM occurrence_exceptions WHERE payment_id = ?")
+                .bind(entity_id)
+                .execute(pool)
+                .await?;
+
             sqlx::query("DELETE FROM payments WHERE id = ? AND project_id = ?")
                 .bind(entity_id)
                 .bind(member.project_id)
@@ -209,6 +483,7 @@ async fn undo_payment(
                 UPDATE payments SET
                     payer_id = ?,
                     amount = ?,
+                    currency = ?,
                     description = ?,
                     payment_date = ?,
                     receipt_image = ?,
@@ -216,12 +491,19 @@ async fn undo_payment(
                     recurrence_type = ?,
                     recurrence_interval = ?,
                     recurrence_end_date = ?,
+                    recurrence_rule = ?,
+                    amount_schedule = ?,
+                    business_day_shift = ?,
+                    prorate_presence = ?,
+                    weight_profile_id = ?,
+                    split_mode = ?,
                     receiver_account_id = ?
                 WHERE id = ? AND project_id = ?
                 "#,
             )
             .bind(before.get("payer_id").and_then(|v| v.as_i64()))
-            .bind(before.get("amount").and_then(|v| v.as_f64()).unwrap_or(0.0))
+            .bind(json_money(before.get("amount")))
+            .bind(before.get("currency").and_then(|v| v.as_str()))
             .bind(
                 before
                     .get("description")
@@ -244,12 +526,56 @@ async fn undo_payment(
                     .map(|v| v as i32),
             )
             .bind(before.get("recurrence_end_date").and_then(|v| v.as_str()))
+            .bind(before.get("recurrence_rule").and_then(|v| v.as_str()))
+            .bind(before.get("amount_schedule").and_then(|v| v.as_str()))
+            .bind(before.get("business_day_shift").and_then(|v| v.as_str()))
+            .bind(
+                before
+                    .get("prorate_presence")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false),
+            )
+            .bind(before.get("weight_profile_id").and_then(|v| v.as_i64()))
+            .bind(before.get("split_mode").and_then(|v| v.as_str()))
             .bind(before.get("receiver_account_id").and_then(|v| v.as_i64()))
             .bind(entity_id)
             .bind(member.project_id)
             .execute(pool)
             .await?;
 
+            // Restore the split as well, re-allocated against the restored amount
+            if let Some(contributions) = before.get("contributions").and_then(|v| v.as_array()) {
+                sqlx::query("DELETE FROM contributions WHERE payment_id = ?")
+                    .bind(entity_id)
+                    .execute(pool)
+                    .await?;
+
+                restore_contributions(
+                    pool,
+                    entity_id,
+                    json_money(before.get("amount")),
+                    contributions,
+                )
+                .await?;
+            }
+
+            // Receipt lines and payers as they were (older payloads have
+            // neither)
+            sqlx::query("DELETE FROM payment_items WHERE payment_id = ?")
+                .bind(entity_id)
+                .execute(pool)
+                .await?;
+            if let Some(items) = before.get("items").and_then(|v| v.as_array()) {
+                restore_items(pool, entity_id, items).await?;
+            }
+            sqlx::query("DELETE FROM payment_payers WHERE payment_id = ?")
+                .bind(entity_id)
+                .execute(pool)
+                .await?;
+            if let Some(payers) = before.get("payers").and_then(|v| v.as_array()) {
+                restore_payers(pool, entity_id, payers).await?;
+            }
+
             // Log the undo
             HistoryService::log_event(
                 pool,
@@ -283,22 +609,20 @@ async fn undo_payment(
             sqlx::query(
                 r#"
                 INSERT INTO payments (
-                    id, project_id, payer_id, amount, description, payment_date,
+                    id, project_id, payer_id, amount, currency, description, payment_date,
                     receipt_image, is_recurring, recurrence_type, recurrence_interval,
                     recurrence_end_date, recurrence_weekdays, recurrence_monthdays,
-                    recurrence_months, receiver_account_id
-                ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                    recurrence_months, recurrence_rule, amount_schedule, business_day_shift,
+                    prorate_presence, weight_profile_id, split_mode, receiver_account_id,
+                    split_from_id
+                ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
                 "#,
             )
             .bind(entity_id)
             .bind(member.project_id)
             .bind(payment_data.get("payer_id").and_then(|v| v.as_i64()))
-            .bind(
-                payment_data
-                    .get("amount")
-                    .and_then(|v| v.as_f64())
-                    .unwrap_or(0.0),
-            )
+            .bind(json_money(payment_data.get("amount")))
+            .bind(payment_data.get("currency").and_then(|v| v.as_str()))
             .bind(
                 payment_data
                     .get("description")
@@ -340,39 +664,52 @@ async fn undo_payment(
                     .get("recurrence_months")
                     .and_then(|v| v.as_str()),
             )
+            .bind(payment_data.get("recurrence_rule").and_then(|v| v.as_str()))
+            .bind(payment_data.get("amount_schedule").and_then(|v| v.as_str()))
+            .bind(
+                payment_data
+                    .get("business_day_shift")
+                    .and_then(|v| v.as_str()),
+            )
+            .bind(
+                payment_data
+                    .get("prorate_presence")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false),
+            )
+            .bind(
+                payment_data
+                    .get("weight_profile_id")
+                    .and_then(|v| v.as_i64()),
+            )
+            .bind(payment_data.get("split_mode").and_then(|v| v.as_str()))
             .bind(
                 payment_data
                     .get("receiver_account_id")
                     .and_then(|v| v.as_i64()),
             )
+            .bind(payment_data.get("split_from_id").and_then(|v| v.as_i64()))
             .execute(pool)
             .await?;
 
             // Restore contributions if they exist in the before state
             if let Some(contributions) = before.get("contributions").and_then(|v| v.as_array()) {
-                for contrib in contributions {
-                    let participant_id = contrib.get("participant_id").and_then(|v| v.as_i64());
-                    let amount = contrib
-                        .get("amount")
-                        .and_then(|v| v.as_f64())
-                        .unwrap_or(0.0);
-                    let weight = contrib
-                        .get("weight")
-                        .and_then(|v| v.as_f64())
-                        .unwrap_or(1.0);
-
-                    if let Some(pid) = participant_id {
-                        sqlx::query(
-                            "INSERT INTO contributions (participant_id, payment_id, amount, weight) VALUES (?, ?, ?, ?)"
-                        )
-                        .bind(pid)
-                        .bind(entity_id)
-                        .bind(amount)
-                        .bind(weight)
-                        .execute(pool)
-                        .await?;
-                    }
-                }
+                restore_contributions(
+                    pool,
+                    entity_id,
+                    json_money(payment_data.get("amount")),
+                    contributions,
+                )
+                .await?;
+            }
+            if let Some(items) = before.get("items").and_then(|v| v.as_array()) {
+                restore_items(pool, entity_id, items).await?;
+            }
+            if let Some(payers) = before.get("payers").and_then(|v| v.as_array()) {
+                restore_payers(pool, entity_id, payers).await?;
+            }
+            if let Some(refunds) = before.get("refunds").and_then(|v| v.as_array()) {
+                restore_refunds(pool, entity_id, refunds).await?;
             }
 
             // Log the undo
@@ -397,6 +734,494 @@ async fn undo_payment(
     }
 }
 
+/// Undo a row-level action: a CREATE is undone by `remove`, an UPDATE or
+/// DELETE by `restore`, which puts the logged before state back under its
+/// original id. Logs the UNDO entry with the entry's payloads swapped.
+async fn undo_row(
+    pool: &SqlitePool,
+    member: &ProjectMember,
+    entry: &crate::models::HistoryEntry,
+    correlation_id: &str,
+    reason: Option<&str>,
+    remove: impl AsyncFnOnce() -> AppResult<()>,
+    restore: impl AsyncFnOnce(serde_json::Value) -> AppResult<()>,
+) -> AppResult<i64> {
+    let (payload_before, payload_after) = match entry.action.as_str() {
+        "CREATE" => {
+            remove().await?;
+            (entry.payload_after.as_deref(), None)
+        }
+        "UPDATE" | "DELETE" => {
+            let before: serde_json::Value = entry
+                .payload_before
+                .as_ref()
+                .and_then(|s| serde_json::from_str(s).ok())
+                .ok_or_else(|| AppError::bad_request(ErrorCode::InvalidInput))?;
+            restore(before).await?;
+
+            if entry.action == "UPDATE" {
+                (
+                    entry.payload_after.as_deref(),
+                    entry.payload_before.as_deref(),
+                )
+            } else {
+                (None, entry.payload_before.as_deref())
+            }
+        }
+        _ => return Err(AppError::bad_request(ErrorCode::InvalidInput)),
+    };
+
+    // Log the undo
+    HistoryService::log_event(
+        pool,
+        LogEventParams {
+            correlation_id,
+            actor_user_id: Some(member.user_id),
+            project_id: Some(member.project_id),
+            entity_type: &entry.entity_type,
+            entity_id: entry.entity_id,
+            action: "UNDO",
+            payload_before,
+            payload_after,
+            reason,
+            undoes_history_id: Some(entry.id),
+        },
+    )
+    .await
+}
+
+/// Fail unless the pool a row belongs to is still in the project
+async fn ensure_pool_exists(
+    pool: &SqlitePool,
+    member: &ProjectMember,
+    pool_id: Option<i64>,
+) -> AppResult<()> {
+    let pool_exists: Option<i64> =
+        sqlx::query_scalar("SELECT id FROM participants WHERE id = ? AND project_id = ?")
+            .bind(pool_id)
+            .bind(member.project_id)
+            .fetch_optional(pool)
+            .await?;
+    if pool_exists.is_none() {
+        return Err(AppError::not_found(ErrorCode::ParticipantNotFound));
+    }
+    Ok(())
+}
+
+/// Undo an occurrence exception action
+async fn undo_occurrence_exception(
+    pool: &SqlitePool,
+    member: &ProjectMember,
+    entry: &crate::models::HistoryEntry,
+    entity_id: i64,
+    correlation_id: &str,
+    reason: Option<&str>,
+) -> AppResult<i64> {
+    undo_row(
+        pool,
+        member,
+        entry,
+        correlation_id,
+        reason,
+        // The occurrence is back to the series
+        async || {
+            sqlx::query(
+                "DELETE FROM occurrence_exceptions WHERE id = ?
+                 AND payment_id IN (SELECT id FROM payments WHERE project_id = ?)",
+            )
+            .bind(entity_id)
+            .bind(member.project_id)
+            .execute(pool)
+            .await?;
+            Ok(())
+        },
+        async |before| {
+            let payment_id = before.get("payment_id").and_then(|v| v.as_i64());
+            let payment_exists: Option<i64> =
+                sqlx::query_scalar("SELECT id FROM payments WHERE id = ? AND project_id = ?")
+                    .bind(payment_id)
+                    .bind(member.project_id)
+                    .fetch_optional(pool)
+                    .await?;
+            if payment_exists.is_none() {
+                return Err(AppError::not_found(ErrorCode::PaymentNotFound));
+            }
+
+            sqlx::query(
+                "INSERT OR REPLACE INTO occurrence_exceptions
+                 (id, payment_id, original_date, is_skipped, moved_to, amount, weights)
+                 VALUES (?, ?, ?, ?, ?, ?, ?)",
+            )
+            .bind(entity_id)
+            .bind(payment_id)
+            .bind(before.get("original_date").and_then(|v| v.as_str()))
+            .bind(
+                before
+                    .get("is_skipped")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false),
+            )
+            .bind(before.get("moved_to").and_then(|v| v.as_str()))
+            .bind(
+                before
+                    .get("amount")
+                    .filter(|v| !v.is_null())
+                    .map(|v| json_money(Some(v))),
+            )
+            .bind(before.get("weights").and_then(|v| v.as_str()))
+            .execute(pool)
+            .await?;
+            Ok(())
+        },
+    )
+    .await
+}
+
+/// Undo a refund action
+async fn undo_refund(
+    pool: &SqlitePool,
+    member: &ProjectMember,
+    entry: &crate::models::HistoryEntry,
+    entity_id: i64,
+    correlation_id: &str,
+    reason: Option<&str>,
+) -> AppResult<i64> {
+    undo_row(
+        pool,
+        member,
+        entry,
+        correlation_id,
+        reason,
+        async || {
+            sqlx::query(
+                "DELETE FROM refunds WHERE id = ?
+                 AND payment_id IN (SELECT id FROM payments WHERE project_id = ?)",
+            )
+            .bind(entity_id)
+            .bind(member.project_id)
+            .execute(pool)
+            .await?;
+            Ok(())
+        },
+        async |before| {
+            let payment_id: i64 =
+                sqlx::query_scalar("SELECT id FROM payments WHERE id = ? AND project_id = ?")
+                    .bind(before.get("payment_id").and_then(|v| v.as_i64()))
+                    .bind(member.project_id)
+                    .fetch_optional(pool)
+                    .await?
+                    .ok_or_else(|| AppError::not_found(ErrorCode::PaymentNotFound))?;
+
+            insert_refund(pool, Some(entity_id), payment_id, &before).await?;
+            Ok(())
+        },
+    )
+    .await
+}
+
+async fn undo_presence(
+    pool: &SqlitePool,
+    member: &ProjectMember,
+    entry: &crate::models::HistoryEntry,
+    entity_id: i64,
+    correlation_id: &str,
+    reason: Option<&str>,
+) -> AppResult<i64> {
+    undo_row(
+        pool,
+        member,
+        entry,
+        correlation_id,
+        reason,
+        async || {
+            sqlx::query("DELETE FROM participant_presence WHERE id = ? AND project_id = ?")
+                .bind(entity_id)
+                .bind(member.project_id)
+                .execute(pool)
+                .await?;
+            Ok(())
+        },
+        async |before| {
+            let participant_id = before.get("participant_id").and_then(|v| v.as_i64());
+            let participant_exists: Option<i64> =
+                sqlx::query_scalar("SELECT id FROM participants WHERE id = ? AND project_id = ?")
+                    .bind(participant_id)
+                    .bind(member.project_id)
+                    .fetch_optional(pool)
+                    .await?;
+            if participant_exists.is_none() {
+                return Err(AppError::not_found(ErrorCode::ParticipantNotFound));
+            }
+
+            sqlx::query(
+                "INSERT OR REPLACE INTO participant_presence
+                 (id, project_id, participant_id, from_date, to_date)
+                 VALUES (?, ?, ?, ?, ?)",
+            )
+            .bind(entity_id)
+            .bind(member.project_id)
+            .bind(participant_id)
+            .bind(before.get("from_date").and_then(|v| v.as_str()))
+            .bind(before.get("to_date").and_then(|v| v.as_str()))
+            .execute(pool)
+            .await?;
+            Ok(())
+        },
+    )
+    .await
+}
+
+async fn undo_pool_valuation(
+    pool: &SqlitePool,
+    member: &ProjectMember,
+    entry: &crate::models::HistoryEntry,
+    entity_id: i64,
+    correlation_id: &str,
+    reason: Option<&str>,
+) -> AppResult<i64> {
+    undo_row(
+        pool,
+        member,
+        entry,
+        correlation_id,
+        reason,
+        async || {
+            sqlx::query("DELETE FROM pool_valuations WHERE id = ? AND project_id = ?")
+                .bind(entity_id)
+                .bind(member.project_id)
+                .execute(pool)
+                .await?;
+            Ok(())
+        },
+        async |before| {
+            let pool_id = before.get("pool_id").and_then(|v| v.as_i64());
+            ensure_pool_exists(pool, member, pool_id).await?;
+
+            sqlx::query(
+                "INSERT OR REPLACE INTO pool_valuations
+                 (id, project_id, pool_id, valuation_date, kind, amount, description)
+                 VALUES (?, ?, ?, ?, ?, ?, ?)",
+            )
+            .bind(entity_id)
+            .bind(member.project_id)
+            .bind(pool_id)
+            .bind(before.get("valuation_date").and_then(|v| v.as_str()))
+            .bind(
+                before
+                    .get("kind")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("value"),
+            )
+            .bind(json_money(before.get("amount")))
+            .bind(
+                before
+                    .get("description")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or(""),
+            )
+            .execute(pool)
+            .await?;
+            Ok(())
+        },
+    )
+    .await
+}
+
+async fn undo_pool_rule(
+    pool: &SqlitePool,
+    member: &ProjectMember,
+    entry: &crate::models::HistoryEntry,
+    entity_id: i64,
+    correlation_id: &str,
+    reason: Option<&str>,
+) -> AppResult<i64> {
+    undo_row(
+        pool,
+        member,
+        entry,
+        correlation_id,
+        reason,
+        async || {
+            sqlx::query("DELETE FROM pool_rules WHERE id = ? AND project_id = ?")
+                .bind(entity_id)
+                .bind(member.project_id)
+                .execute(pool)
+                .await?;
+            Ok(())
+        },
+        async |before| {
+            let pool_id = before.get("pool_id").and_then(|v| v.as_i64());
+            ensure_pool_exists(pool, member, pool_id).await?;
+
+            sqlx::query(
+                "INSERT OR REPLACE INTO pool_rules
+                 (id, project_id, pool_id, kind, amount, rate, recurrence_rule, start_date, end_date, description)
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            )
+            .bind(entity_id)
+            .bind(member.project_id)
+            .bind(pool_id)
+            .bind(before.get("kind").and_then(|v| v.as_str()))
+            .bind(
+                before
+                    .get("amount")
+                    .filter(|v| !v.is_null())
+                    .map(|v| json_money(Some(v))),
+            )
+            .bind(before.get("rate").and_then(|v| v.as_f64()))
+            .bind(before.get("recurrence_rule").and_then(|v| v.as_str()))
+            .bind(before.get("start_date").and_then(|v| v.as_str()))
+            .bind(before.get("end_date").and_then(|v| v.as_str()))
+            .bind(
+                before
+                    .get("description")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or(""),
+            )
+            .execute(pool)
+            .await?;
+            Ok(())
+        },
+    )
+    .await
+}
+
+async fn undo_pool_statement(
+    pool: &SqlitePool,
+    member: &ProjectMember,
+    entry: &crate::models::HistoryEntry,
+    entity_id: i64,
+    correlation_id: &str,
+    reason: Option<&str>,
+) -> AppResult<i64> {
+    undo_row(
+        pool,
+        member,
+        entry,
+        correlation_id,
+        reason,
+        async || {
+            sqlx::query("DELETE FROM pool_statements WHERE id = ? AND project_id = ?")
+                .bind(entity_id)
+                .bind(member.project_id)
+                .execute(pool)
+                .await?;
+            Ok(())
+        },
+        async |before| {
+            let pool_id = before.get("pool_id").and_then(|v| v.as_i64());
+            ensure_pool_exists(pool, member, pool_id).await?;
+
+            sqlx::query(
+                "INSERT OR REPLACE INTO pool_statements
+                 (id, project_id, pool_id, statement_date, balance, description)
+                 VALUES (?, ?, ?, ?, ?, ?)",
+            )
+            .bind(entity_id)
+            .bind(member.project_id)
+            .bind(pool_id)
+            .bind(before.get("statement_date").and_then(|v| v.as_str()))
+            .bind(json_money(before.get("balance")))
+            .bind(
+                before
+                    .get("description")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or(""),
+            )
+            .execute(pool)
+            .await?;
+            Ok(())
+        },
+    )
+    .await
+}
+
+async fn undo_contribution_rule(
+    pool: &SqlitePool,
+    member: &ProjectMember,
+    entry: &crate::models::HistoryEntry,
+    entity_id: i64,
+    correlation_id: &str,
+    reason: Option<&str>,
+) -> AppResult<i64> {
+    undo_row(
+        pool,
+        member,
+        entry,
+        correlation_id,
+        reason,
+        async || {
+            let mut tx = pool.begin().await?;
+            sqlx::query(
+                "DELETE FROM contribution_rule_amounts WHERE rule_id IN
+                 (SELECT id FROM contribution_rules WHERE id = ? AND project_id = ?)",
+            )
+            .bind(entity_id)
+            .bind(member.project_id)
+            .execute(&mut *tx)
+            .await?;
+            sqlx::query("DELETE FROM contribution_rules WHERE id = ? AND project_id = ?")
+                .bind(entity_id)
+                .bind(member.project_id)
+                .execute(&mut *tx)
+                .await?;
+            tx.commit().await?;
+            Ok(())
+        },
+        async |before| {
+            let pool_id = before.get("pool_id").and_then(|v| v.as_i64());
+            ensure_pool_exists(pool, member, pool_id).await?;
+
+            let mut tx = pool.begin().await?;
+            sqlx::query(
+                "INSERT OR REPLACE INTO contribution_rules
+                 (id, project_id, pool_id, recurrence_rule, start_date, end_date, description)
+                 VALUES (?, ?, ?, ?, ?, ?, ?)",
+            )
+            .bind(entity_id)
+            .bind(member.project_id)
+            .bind(pool_id)
+            .bind(before.get("recurrence_rule").and_then(|v| v.as_str()))
+            .bind(before.get("start_date").and_then(|v| v.as_str()))
+            .bind(before.get("end_date").and_then(|v| v.as_str()))
+            .bind(
+                before
+                    .get("description")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or(""),
+            )
+            .execute(&mut *tx)
+            .await?;
+
+            sqlx::query("DELETE FROM contribution_rule_amounts WHERE rule_id = ?")
+                .bind(entity_id)
+                .execute(&mut *tx)
+                .await?;
+            // Participants deleted since are left out
+            for amount in before
+                .get("amounts")
+                .and_then(|v| v.as_array())
+                .into_iter()
+                .flatten()
+            {
+                sqlx::query(
+                    "INSERT INTO contribution_rule_amounts (rule_id, participant_id, amount)
+                     SELECT ?, id, ? FROM participants WHERE id = ? AND project_id = ?",
+                )
+                .bind(entity_id)
+                .bind(json_money(amount.get("amount")))
+                .bind(amount.get("participant_id").and_then(|v| v.as_i64()))
+                .bind(member.project_id)
+                .execute(&mut *tx)
+                .await?;
+            }
+            tx.commit().await?;
+            Ok(())
+        },
+    )
+    .await
+}
+
 /// Undo a participant action
 async fn undo_participant(
     pool: &SqlitePool,
@@ -0,0 +1,125 @@
+use axum::{
+    extract::{Path, State},
+    routing::{delete, get},
+    Json, Router,
+};
+use serde::Deserialize;
+use sqlx::SqlitePool;
+
+use crate::{
+    auth::ProjectMember,
+    error::{AppError, AppResult, ErrorCode},
+    models::{CreateHoliday, EntityType, Holiday},
+    services::HistoryService,
+    AppState,
+};
+
+#[derive(Deserialize)]
+struct HolidayPath {
+    holiday_id: i64,
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_holidays).post(create_holiday))
+        .route("/{holiday_id}", delete(delete_holiday))
+}
+
+async fn list_holidays(
+    member: ProjectMember,
+    State(pool): State<SqlitePool>,
+) -> AppResult<Json<Vec<Holiday>>> {
+    let holidays: Vec<Holiday> =
+        sqlx::query_as("SELECT * FROM project_holidays WHERE project_id = ? ORDER BY holiday_date")
+            .bind(member.project_id)
+            .fetch_all(&pool)
+            .await?;
+
+    Ok(Json(holidays))
+}
+
+/// POST /projects/{id}/holidays
+/// Add a holiday; renames the existing one on the same date
+async fn create_holiday(
+    member: ProjectMember,
+    State(pool): State<SqlitePool>,
+    Json(input): Json<CreateHoliday>,
+) -> AppResult<Json<Holiday>> {
+    if !member.can_edit() {
+        return Err(AppError::forbidden(ErrorCode::EditorRequired));
+    }
+
+    if chrono::NaiveDate::parse_from_str(input.holiday_date.as_str(), "%Y-%m-%d").is_err() {
+        return Err(AppError::bad_request(ErrorCode::InvalidDateFormat));
+    }
+
+    sqlx::query(
+        "INSERT INTO project_holidays (project_id, holiday_date, name) VALUES (?, ?, ?)
+         ON CONFLICT(project_id, holiday_date) DO UPDATE SET name = excluded.name",
+    )
+    .bind(member.project_id)
+    .bind(input.holiday_date.as_str())
+    .bind(input.name.as_ref().map(|n| n.as_str()))
+    .execute(&pool)
+    .await?;
+
+    let holiday: Holiday =
+        sqlx::query_as("SELECT * FROM project_holidays WHERE project_id = ? AND holiday_date = ?")
+            .bind(member.project_id)
+            .bind(input.holiday_date.as_str())
+            .fetch_one(&pool)
+            .await?;
+
+    // Log the creation to history
+    let correlation_id = HistoryService::new_correlation_id();
+    let _ = HistoryService::log_create(
+        &pool,
+        &correlation_id,
+        member.user_id,
+        member.project_id,
+        EntityType::Holiday,
+        holiday.id,
+        &holiday,
+    )
+    .await;
+
+    Ok(Json(holiday))
+}
+
+async fn delete_holiday(
+    Path(path): Path<HolidayPath>,
+    member: ProjectMember,
+    State(pool): State<SqlitePool>,
+) -> AppResult<Json<serde_json::Value>> {
+    if !member.can_edit() {
+        return Err(AppError::forbidden(ErrorCode::EditorRequired));
+    }
+
+    let existing: Holiday =
+        sqlx::query_as("SELECT * FROM project_holidays WHERE id = ? AND project_id = ?")
+            .bind(path.holiday_id)
+            .bind(member.project_id)
+            .fetch_optional(&pool)
+            .await?
+            .ok_or_else(|| AppError::not_found(ErrorCode::HolidayNotFound))?;
+
+    sqlx::query("DELETE FROM project_holidays WHERE id = ?")
+        .bind(path.holiday_id)
+        .execute(&pool)
+        .await?;
+
+    // Log the deletion to history
+    let correlation_id = HistoryService::new_correlation_id();
+    let _ = HistoryService::log_delete(
+        &pool,
+        &correlation_id,
+        member.user_id,
+        member.project_id,
+        EntityType::Holiday,
+        path.holiday_id,
+        &existing,
+    )
+    .await;
+
+    Ok(Json(serde_json::json!({ "deleted": true })))
+}
@@ -1,10 +1,21 @@
 pub mod approvals;
 pub mod auth;
+pub mod contribution_rules;
 pub mod debts;
+pub mod exchange_rates;
 pub mod history;
+pub mod holidays;
 pub mod members;
+pub mod occurrence_exceptions;
 pub mod participants;
 pub mod payments;
+pub mod pool_rules;
+pub mod pool_statements;
+pub mod pool_valuations;
+pub mod presence;
 pub mod projects;
 pub mod recovery;
+pub mod refunds;
 pub mod users;
+pub mod warnings;
+pub mod weight_profiles;
@@ -0,0 +1,241 @@
+use axum::{
+    extract::{Path, State},
+    routing::{get, put},
+    Json, Router,
+};
+use chrono::NaiveDate;
+use serde::Deserialize;
+use sqlx::SqlitePool;
+
+use crate::{
+    auth::ProjectMember,
+    error::{AppError, AppResult, ErrorCode},
+    models::{EntityType, OccurrenceException, Payment, SetOccurrenceException},
+    services::{debt_calculator::payment_occurs_on, BusinessCalendar, HistoryService},
+    AppState,
+};
+
+#[derive(Deserialize)]
+struct PaymentPath {
+    payment_id: i64,
+}
+
+#[derive(Deserialize)]
+struct ExceptionPath {
+    payment_id: i64,
+    original_date: String,
+}
+
+/// Nested under /projects/{id}/payments/{payment_id}/exceptions
+pub fn router() -> Router<AppState> {
+    Router::new().route("/", get(list_exceptions)).route(
+        "/{original_date}",
+        put(set_exception).delete(delete_exception),
+    )
+}
+
+/// Payment of the project, or PaymentNotFound
+async fn find_payment(pool: &SqlitePool, project_id: i64, payment_id: i64) -> AppResult<Payment> {
+    sqlx::query_as("SELECT * FROM payments WHERE id = ? AND project_id = ?")
+        .bind(payment_id)
+        .bind(project_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| AppError::not_found(ErrorCode::PaymentNotFound))
+}
+
+async fn find_exception(
+    pool: &SqlitePool,
+    payment_id: i64,
+    original_date: &str,
+) -> AppResult<Option<OccurrenceException>> {
+    Ok(sqlx::query_as(
+        "SELECT * FROM occurrence_exceptions WHERE payment_id = ? AND original_date = ?",
+    )
+    .bind(payment_id)
+    .bind(original_date)
+    .fetch_optional(pool)
+    .await?)
+}
+
+fn parse_date(date: &str) -> AppResult<NaiveDate> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map_err(|_| AppError::bad_request(ErrorCode::InvalidDateFormat))
+}
+
+/// GET /projects/{id}/payments/{payment_id}/exceptions
+async fn list_exceptions(
+    Path(path): Path<PaymentPath>,
+    member: ProjectMember,
+    State(pool): State<SqlitePool>,
+) -> AppResult<Json<Vec<OccurrenceException>>> {
+    find_payment(&pool, member.project_id, path.payment_id).await?;
+
+    let exceptions: Vec<OccurrenceException> = sqlx::query_as(
+        "SELECT * FROM occurrence_exceptions WHERE payment_id = ? ORDER BY original_date",
+    )
+    .bind(path.payment_id)
+    .fetch_all(&pool)
+    .await?;
+
+    Ok(Json(exceptions))
+}
+
+/// PUT /projects/{id}/payments/{payment_id}/exceptions/{original_date}
+/// Skip, move or change the occurrence the series puts on `original_date`,
+/// replacing any exception already recorded for it
+async fn set_exception(
+    Path(path): Path<ExceptionPath>,
+    member: ProjectMember,
+    State(pool): State<SqlitePool>,
+    Json(input): Json<SetOccurrenceException>,
+) -> AppResult<Json<OccurrenceException>> {
+    if !member.can_edit() {
+        return Err(AppError::forbidden(ErrorCode::EditorRequired));
+    }
+
+    let payment = find_payment(&pool, member.project_id, path.payment_id).await?;
+    if !payment.is_recurring {
+        return Err(AppError::bad_request(ErrorCode::PaymentNotRecurring));
+    }
+    let calendar = BusinessCalendar::load(&pool, member.project_id).await?;
+    if !payment_occurs_on(&payment, parse_date(&path.original_date)?, &calendar) {
+        return Err(AppError::not_found(ErrorCode::OccurrenceNotFound));
+    }
+
+    // A skipped occurrence has nothing else to change; otherwise something must change
+    let is_skipped = input.skip.unwrap_or(false);
+    let changes =
+        input.moved_to.is_some() || input.amount.is_some() || input.contributions.is_some();
+    if is_skipped == changes {
+        return Err(AppError::bad_request(ErrorCode::InvalidInput));
+    }
+    if let Some(ref moved_to) = input.moved_to {
+        parse_date(moved_to)?;
+    }
+    if input.amount.is_some_and(|amount| !amount.is_positive()) {
+        return Err(AppError::bad_request(ErrorCode::AmountMustBePositive));
+    }
+    let weights = match &input.contributions {
+        Some(contributions) => {
+            if contributions.is_empty() {
+                return Err(AppError::bad_request(ErrorCode::ContributionRequired));
+            }
+            for contrib in contributions {
+                let participant_exists: Option<i64> = sqlx::query_scalar(
+                    "SELECT id FROM participants WHERE id = ? AND project_id = ?",
+                )
+                .bind(contrib.participant_id)
+                .bind(member.project_id)
+                .fetch_optional(&pool)
+                .await?;
+
+                if participant_exists.is_none() {
+                    return Err(AppError::bad_request(ErrorCode::InvalidParticipant));
+                }
+            }
+            let total_weight: f64 = contributions.iter().map(|c| c.weight).sum();
+            if total_weight <= 0.0 || contributions.iter().any(|c| c.weight < 0.0) {
+                return Err(AppError::bad_request(ErrorCode::TotalWeightMustBePositive));
+            }
+            Some(
+                serde_json::to_string(contributions)
+                    .map_err(|e| AppError::Internal(e.to_string()))?,
+            )
+        }
+        None => None,
+    };
+
+    let before = find_exception(&pool, path.payment_id, &path.original_date).await?;
+
+    sqlx::query(
+        "INSERT INTO occurrence_exceptions (payment_id, original_date, is_skipped, moved_to, amount, weights)
+         VALUES (?, ?, ?, ?, ?, ?)
+         ON CONFLICT(payment_id, original_date)
+         DO UPDATE SET is_skipped = excluded.is_skipped, moved_to = excluded.moved_to,
+                       amount = excluded.amount, weights = excluded.weights",
+    )
+    .bind(path.payment_id)
+    .bind(&path.original_date)
+    .bind(is_skipped)
+    .bind(&input.moved_to)
+    .bind(input.amount)
+    .bind(&weights)
+    .execute(&pool)
+    .await?;
+
+    let exception = find_exception(&pool, path.payment_id, &path.original_date)
+        .await?
+        .ok_or_else(|| AppError::Internal("Failed to retrieve exception".to_string()))?;
+
+    // Log the change to history
+    let correlation_id = HistoryService::new_correlation_id();
+    let _ = match &before {
+        Some(before) => {
+            HistoryService::log_update(
+                &pool,
+                crate::services::history::LogUpdateParams {
+                    correlation_id: &correlation_id,
+                    actor_user_id: member.user_id,
+                    project_id: member.project_id,
+                    entity_type: EntityType::OccurrenceException,
+                    entity_id: exception.id,
+                    before,
+                    after: &exception,
+                },
+            )
+            .await
+        }
+        None => {
+            HistoryService::log_create(
+                &pool,
+                &correlation_id,
+                member.user_id,
+                member.project_id,
+                EntityType::OccurrenceException,
+                exception.id,
+                &exception,
+            )
+            .await
+        }
+    };
+
+    Ok(Json(exception))
+}
+
+/// DELETE /projects/{id}/payments/{payment_id}/exceptions/{original_date}
+/// Restore the occurrence as the series defines it
+async fn delete_exception(
+    Path(path): Path<ExceptionPath>,
+    member: ProjectMember,
+    State(pool): State<SqlitePool>,
+) -> AppResult<Json<serde_json::Value>> {
+    if !member.can_edit() {
+        return Err(AppError::forbidden(ErrorCode::EditorRequired));
+    }
+
+    find_payment(&pool, member.project_id, path.payment_id).await?;
+    let existing = find_exception(&pool, path.payment_id, &path.original_date)
+        .await?
+        .ok_or_else(|| AppError::not_found(ErrorCode::OccurrenceExceptionNotFound))?;
+
+    sqlx::query("DELETE FROM occurrence_exceptions WHERE id = ?")
+        .bind(existing.id)
+        .execute(&pool)
+        .await?;
+
+    // Log the deletion to history
+    let correlation_id = HistoryService::new_correlation_id();
+    let _ = HistoryService::log_delete(
+        &pool,
+        &correlation_id,
+        member.user_id,
+        member.project_id,
+        EntityType::OccurrenceException,
+        existing.id,
+        &existing,
+    )
+    .await;
+
+    Ok(Json(serde_json::json!({ "deleted": true })))
+}
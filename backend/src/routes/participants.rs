@@ -11,9 +11,10 @@ use crate::{
     auth::{AdminMember, ProjectMember},
     error::{AppError, AppResult, ErrorCode},
     models::{
-        CreateParticipant, EntityType, Participant, UpdateParticipant, UpdatePoolWarningSettings,
+        CreateParticipant, EntityType, Participant, UpdateParticipant, UpdatePoolMode,
+        UpdatePoolWarningSettings,
     },
-    services::HistoryService,
+    services::{warnings::WARNING_HORIZONS, HistoryService},
     AppState,
 };
 
@@ -40,6 +41,10 @@ pub fn router() -> Router<AppState> {
             "/{participant_id}/warning-settings",
             axum::routing::patch(update_pool_warning_settings),
         )
+        .route(
+            "/{participant_id}/pool-mode",
+            axum::routing::patch(update_pool_mode),
+        )
 }
 
 async fn list_participants(
@@ -544,15 +549,9 @@ async fn update_pool_warning_settings(
         ));
     }
 
-    // Valid warning horizon values
+    // Valid warning horizon values are listed in WARNING_HORIZONS
     // Empty string or null means "disable" (set to NULL in DB)
     // Note: Input length is already bounded at deserialization via BoundedString<30>
-    let valid_horizons = [
-        "end_of_current_month",
-        "end_of_next_month",
-        "3_months",
-        "6_months",
-    ];
 
     // Convert input to Option<Option<String>> where:
     // - None = field not provided, don't update
@@ -563,7 +562,7 @@ async fn update_pool_warning_settings(
             Some(None) // empty string means disable
         } else {
             // Validate value before accepting
-            if !valid_horizons.contains(&v.as_str()) {
+            if !WARNING_HORIZONS.contains(&v.as_str()) {
                 return Err(AppError::bad_request(ErrorCode::InvalidWarningHorizon));
             }
             Some(Some(v.into_inner())) // size is bounded by BoundedString<30>
@@ -577,7 +576,7 @@ async fn update_pool_warning_settings(
             Some(None) // empty string means disable
         } else {
             // Validate value before accepting
-            if !valid_horizons.contains(&v.as_str()) {
+            if !WARNING_HORIZONS.contains(&v.as_str()) {
                 return Err(AppError::bad_request(ErrorCode::InvalidWarningHorizon));
             }
             Some(Some(v.into_inner())) // size is bounded by BoundedString<30>
@@ -622,3 +621,43 @@ async fn update_pool_warning_settings(
 
     Ok(Json(updated))
 }
+
+/// PATCH /projects/{id}/participants/{participant_id}/pool-mode
+/// Switch a pool between plain and unitized ownership
+async fn update_pool_mode(
+    Path(path): Path<ParticipantPath>,
+    member: ProjectMember,
+    State(pool): State<SqlitePool>,
+    Json(input): Json<UpdatePoolMode>,
+) -> AppResult<Json<Participant>> {
+    if !member.can_edit() {
+        return Err(AppError::forbidden(ErrorCode::EditorRequired));
+    }
+
+    let existing: Participant =
+        sqlx::query_as("SELECT * FROM participants WHERE id = ? AND project_id = ?")
+            .bind(path.participant_id)
+            .bind(member.project_id)
+            .fetch_optional(&pool)
+            .await?
+            .ok_or_else(|| AppError::not_found(ErrorCode::ParticipantNotFound))?;
+
+    if existing.account_type != "pool" {
+        return Err(AppError::BadRequest(
+            "Only pool accounts can be unitized".to_string(),
+        ));
+    }
+
+    sqlx::query("UPDATE participants SET unitized = ? WHERE id = ?")
+        .bind(input.unitized)
+        .bind(path.participant_id)
+        .execute(&pool)
+        .await?;
+
+    let updated: Participant = sqlx::query_as("SELECT * FROM participants WHERE id = ?")
+        .bind(path.participant_id)
+        .fetch_one(&pool)
+        .await?;
+
+    Ok(Json(updated))
+}
@@ -1,18 +1,28 @@
 use axum::{
     extract::{Path, State},
-    routing::get,
+    routing::{get, post},
     Json, Router,
 };
+use chrono::NaiveDate;
 use serde::Deserialize;
-use sqlx::SqlitePool;
+use sqlx::{SqliteConnection, SqliteExecutor, SqlitePool};
+use std::collections::HashSet;
 
 use crate::{
     auth::ProjectMember,
     error::{AppError, AppResult, ErrorCode},
     models::{
-        ContributionWithParticipant, CreatePayment, EntityType, Payment, PaymentWithContributions,
+        is_valid_currency_code, itemized_contributions, AmountSchedule, BusinessDayShift,
+        ContributionWithParticipant, CreateContribution, CreatePayment, CreatePaymentItem,
+        CreatePaymentPayer, EntityType, ItemKind, ItemsError, Money, NetCost, Payment, PaymentItem,
+        PaymentPayerWithParticipant, PaymentWithContributions, RecurrenceSet, Refund, SplitError,
+        SplitMode, SplitPayment, SplitPaymentResult,
+    },
+    routes::{refunds::payment_refunds, weight_profiles::profile_contributions},
+    services::{
+        amount_in_currency, debt_calculator::payment_occurs_on, validate_image_base64,
+        BusinessCalendar, HistoryService,
     },
-    services::{validate_image_base64, HistoryService},
     AppState,
 };
 
@@ -28,6 +38,12 @@ pub fn router() -> Router<AppState> {
             "/{payment_id}",
             get(get_payment).put(update_payment).delete(delete_payment),
         )
+        .route("/{payment_id}/split", post(split_payment))
+        .nest(
+            "/{payment_id}/exceptions",
+            super::occurrence_exceptions::router(),
+        )
+        .nest("/{payment_id}/refunds", super::refunds::router())
 }
 
 async fn list_payments(
@@ -54,7 +70,7 @@ async fn list_payments(
 
         // Get contributions with participant names
         let contributions: Vec<ContributionWithParticipant> = sqlx::query_as(
-            "SELECT c.id, c.participant_id, p.name as participant_name, c.payment_id, c.amount, c.weight
+            "SELECT c.id, c.participant_id, p.name as participant_name, c.payment_id, c.amount, c.weight, c.cap, c.floor
              FROM contributions c
              JOIN participants p ON c.participant_id = p.id
              WHERE c.payment_id = ?"
@@ -62,11 +78,19 @@ async fn list_payments(
         .bind(payment.id)
         .fetch_all(&pool)
         .await?;
+        let payers = payment_payers(&pool, payment.id).await?;
+        let items = payment_items(&pool, payment.id).await?;
+        let refunds = payment_refunds(&pool, payment.id).await?;
+        let net_cost = net_cost(&payment, &contributions, &refunds);
 
         result.push(PaymentWithContributions {
             payment,
             payer_name,
+            payers,
             contributions,
+            items,
+            refunds,
+            net_cost,
         });
     }
 
@@ -98,7 +122,7 @@ async fn get_payment(
     };
 
     let contributions: Vec<ContributionWithParticipant> = sqlx::query_as(
-        "SELECT c.id, c.participant_id, p.name as participant_name, c.payment_id, c.amount, c.weight
+        "SELECT c.id, c.participant_id, p.name as participant_name, c.payment_id, c.amount, c.weight, c.cap, c.floor
          FROM contributions c
          JOIN participants p ON c.participant_id = p.id
          WHERE c.payment_id = ?"
@@ -106,18 +130,26 @@ async fn get_payment(
     .bind(payment.id)
     .fetch_all(&pool)
     .await?;
+    let payers = payment_payers(&pool, payment.id).await?;
+    let items = payment_items(&pool, payment.id).await?;
+    let refunds = payment_refunds(&pool, payment.id).await?;
+    let net_cost = net_cost(&payment, &contributions, &refunds);
 
     Ok(Json(PaymentWithContributions {
         payment,
         payer_name,
+        payers,
         contributions,
+        items,
+        refunds,
+        net_cost,
     }))
 }
 
 async fn create_payment(
     member: ProjectMember,
     State(pool): State<SqlitePool>,
-    Json(input): Json<CreatePayment>,
+    Json(mut input): Json<CreatePayment>,
 ) -> AppResult<Json<PaymentWithContributions>> {
     // Check editor permission
     if !member.can_edit() {
@@ -125,9 +157,33 @@ async fn create_payment(
     }
 
     // Validate
-    if input.amount <= 0.0 {
+    if !input.amount.is_positive() {
         return Err(AppError::bad_request(ErrorCode::AmountMustBePositive));
     }
+    // Expected deposits are contribution rules: a payment moves money
+    if input.affects_balance == Some(false) {
+        return Err(AppError::bad_request(ErrorCode::UseContributionRule));
+    }
+    if let Some(ref currency) = input.currency {
+        if !is_valid_currency_code(currency) {
+            return Err(AppError::bad_request(ErrorCode::InvalidCurrency));
+        }
+    }
+    input.amount = amount_in_currency(
+        &pool,
+        member.project_id,
+        input.amount,
+        input.currency.as_deref(),
+    )
+    .await?;
+    if let Some(profile_id) = input.weight_profile_id {
+        input.contributions =
+            payment_profile_contributions(&pool, &member, &input, profile_id).await?;
+    }
+    if !input.items.is_empty() {
+        itemize(&mut input)?;
+    }
+    validate_payers(&pool, member.project_id, &mut input).await?;
     if input.contributions.is_empty() {
         return Err(AppError::BadRequest(
             "At least one contribution required".to_string(),
@@ -176,13 +232,11 @@ async fn create_payment(
         }
     }
 
-    // Calculate total weight
-    let total_weight: f64 = input.contributions.iter().map(|c| c.weight).sum();
-    if total_weight <= 0.0 {
-        return Err(AppError::BadRequest(
-            "Total weight must be positive".to_string(),
-        ));
-    }
+    // Resolve each contribution's amount in the chosen split mode
+    let split_mode = split_mode(&input)?;
+    let shares = split_mode
+        .resolve(input.amount, &input.contributions)
+        .map_err(split_error)?;
 
     // Validate receipt image if provided
     if let Some(ref image) = input.receipt_image {
@@ -192,8 +246,15 @@ async fn create_payment(
     // Insert payment
     let payment_date = input
         .payment_date
+        .clone()
         .unwrap_or_else(|| chrono::Utc::now().format("%Y-%m-%d").to_string());
 
+    let recurrence = recurrence_rule(&input, &payment_date)?;
+    let (recurrence_type, recurrence_interval) = recurrence_type_and_interval(&input, &recurrence);
+    let amount_schedule = amount_schedule(&input)?;
+    let business_day_shift = business_day_shift(&input)?;
+    let prorate_presence = input.prorate_presence.unwrap_or(false);
+
     let is_recurring = input.is_recurring.unwrap_or(false);
     let is_final = input.is_final.unwrap_or(true);
     let affects_balance = input.affects_balance.unwrap_or(true);
@@ -201,23 +262,30 @@ async fn create_payment(
     let affects_receiver_expectation = input.affects_receiver_expectation.unwrap_or(false);
 
     let result = sqlx::query(
-        "INSERT INTO payments (project_id, payer_id, amount, description, payment_date, receipt_image, is_recurring, recurrence_type, recurrence_interval, recurrence_times_per, recurrence_end_date, recurrence_weekdays, recurrence_monthdays, recurrence_months, receiver_account_id, is_final, affects_balance, affects_payer_expectation, affects_receiver_expectation)
-         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+        "INSERT INTO payments (project_id, payer_id, amount, currency, description, payment_date, receipt_image, is_recurring, recurrence_type, recurrence_interval, recurrence_times_per, recurrence_end_date, recurrence_weekdays, recurrence_monthdays, recurrence_months, recurrence_rule, amount_schedule, business_day_shift, prorate_presence, weight_profile_id, split_mode, receiver_account_id, is_final, affects_balance, affects_payer_expectation, affects_receiver_expectation)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
     )
     .bind(member.project_id)
     .bind(input.payer_id)
     .bind(input.amount)
+    .bind(&input.currency)
     .bind(&input.description)
     .bind(&payment_date)
     .bind(&input.receipt_image)
     .bind(is_recurring)
-    .bind(&input.recurrence_type)
-    .bind(input.recurrence_interval)
+    .bind(&recurrence_type)
+    .bind(recurrence_interval)
     .bind(input.recurrence_times_per)
     .bind(&input.recurrence_end_date)
     .bind(&input.recurrence_weekdays)
     .bind(&input.recurrence_monthdays)
     .bind(&input.recurrence_months)
+    .bind(recurrence.as_ref().map(|r| r.to_string()))
+    .bind(&amount_schedule)
+    .bind(business_day_shift)
+    .bind(prorate_presence)
+    .bind(input.weight_profile_id)
+    .bind(split_mode.as_str())
     .bind(input.receiver_account_id)
     .bind(is_final)
     .bind(affects_balance)
@@ -227,13 +295,12 @@ async fn create_payment(
     .await?;
 
     let payment_id = result.last_insert_rowid();
+    insert_payers(&mut *pool.acquire().await?, payment_id, &input.payers).await?;
+    insert_items(&pool, payment_id, &input.items).await?;
 
     // Calculate and insert contributions
     let mut contributions = Vec::new();
-    for contrib in &input.contributions {
-        let share_amount =
-            (input.amount * contrib.weight / total_weight * 10000.0).round() / 10000.0;
-
+    for (contrib, share_amount) in input.contributions.iter().zip(shares) {
         // Get participant name
         let participant_name: String =
             sqlx::query_scalar("SELECT name FROM participants WHERE id = ?")
@@ -241,13 +308,16 @@ async fn create_payment(
                 .fetch_one(&pool)
                 .await?;
 
+        let weight = split_mode.stored_weight(contrib);
         let result = sqlx::query(
-            "INSERT INTO contributions (participant_id, payment_id, amount, weight) VALUES (?, ?, ?, ?)"
+            "INSERT INTO contributions (participant_id, payment_id, amount, weight, cap, floor) VALUES (?, ?, ?, ?, ?, ?)"
         )
         .bind(contrib.participant_id)
         .bind(payment_id)
         .bind(share_amount)
-        .bind(contrib.weight)
+        .bind(weight)
+        .bind(contrib.cap)
+        .bind(contrib.floor)
         .execute(&pool)
         .await?;
 
@@ -257,7 +327,9 @@ async fn create_payment(
             participant_name,
             payment_id,
             amount: share_amount,
-            weight: contrib.weight,
+            weight,
+            cap: contrib.cap,
+            floor: contrib.floor,
         });
     }
 
@@ -277,10 +349,17 @@ async fn create_payment(
         None
     };
 
+    let payers = payment_payers(&pool, payment_id).await?;
+    let items = payment_items(&pool, payment_id).await?;
+
     let result = PaymentWithContributions {
         payment,
         payer_name,
+        payers,
         contributions,
+        items,
+        refunds: Vec::new(),
+        net_cost: None,
     };
 
     // Log the creation to history
@@ -303,7 +382,7 @@ async fn update_payment(
     Path(path): Path<PaymentPath>,
     member: ProjectMember,
     State(pool): State<SqlitePool>,
-    Json(input): Json<CreatePayment>,
+    Json(mut input): Json<CreatePayment>,
 ) -> AppResult<Json<PaymentWithContributions>> {
     // Check editor permission
     if !member.can_edit() {
@@ -321,7 +400,7 @@ async fn update_payment(
 
     // Capture before state for history (including contributions)
     let existing_contributions: Vec<ContributionWithParticipant> = sqlx::query_as(
-        "SELECT c.id, c.participant_id, p.name as participant_name, c.payment_id, c.amount, c.weight
+        "SELECT c.id, c.participant_id, p.name as participant_name, c.payment_id, c.amount, c.weight, c.cap, c.floor
          FROM contributions c
          JOIN participants p ON c.participant_id = p.id
          WHERE c.payment_id = ?"
@@ -339,16 +418,46 @@ async fn update_payment(
         None
     };
 
+    let refunds = payment_refunds(&pool, path.payment_id).await?;
     let before_state = PaymentWithContributions {
+        net_cost: net_cost(&existing, &existing_contributions, &refunds),
         payment: existing,
         payer_name: existing_payer_name,
+        payers: payment_payers(&pool, path.payment_id).await?,
         contributions: existing_contributions,
+        items: payment_items(&pool, path.payment_id).await?,
+        refunds,
     };
 
     // Validate
-    if input.amount <= 0.0 {
+    if !input.amount.is_positive() {
         return Err(AppError::bad_request(ErrorCode::AmountMustBePositive));
     }
+    // Expected deposits are contribution rules; a rule payment kept from
+    // before them may still be edited, but no payment becomes one
+    if input.affects_balance == Some(false) && before_state.payment.affects_balance {
+        return Err(AppError::bad_request(ErrorCode::UseContributionRule));
+    }
+    if let Some(ref currency) = input.currency {
+        if !is_valid_currency_code(currency) {
+            return Err(AppError::bad_request(ErrorCode::InvalidCurrency));
+        }
+    }
+    input.amount = amount_in_currency(
+        &pool,
+        member.project_id,
+        input.amount,
+        input.currency.as_deref(),
+    )
+    .await?;
+    if let Some(profile_id) = input.weight_profile_id {
+        input.contributions =
+            payment_profile_contributions(&pool, &member, &input, profile_id).await?;
+    }
+    if !input.items.is_empty() {
+        itemize(&mut input)?;
+    }
+    validate_payers(&pool, member.project_id, &mut input).await?;
     if input.contributions.is_empty() {
         return Err(AppError::BadRequest(
             "At least one contribution required".to_string(),
@@ -397,13 +506,11 @@ async fn update_payment(
         }
     }
 
-    // Calculate total weight
-    let total_weight: f64 = input.contributions.iter().map(|c| c.weight).sum();
-    if total_weight <= 0.0 {
-        return Err(AppError::BadRequest(
-            "Total weight must be positive".to_string(),
-        ));
-    }
+    // Resolve each contribution's amount in the chosen split mode
+    let split_mode = split_mode(&input)?;
+    let shares = split_mode
+        .resolve(input.amount, &input.contributions)
+        .map_err(split_error)?;
 
     // Validate receipt image if provided
     if let Some(ref image) = input.receipt_image {
@@ -415,6 +522,12 @@ async fn update_payment(
         .clone()
         .unwrap_or_else(|| chrono::Utc::now().format("%Y-%m-%d").to_string());
 
+    let recurrence = recurrence_rule(&input, &payment_date)?;
+    let (recurrence_type, recurrence_interval) = recurrence_type_and_interval(&input, &recurrence);
+    let amount_schedule = amount_schedule(&input)?;
+    let business_day_shift = business_day_shift(&input)?;
+    let prorate_presence = input.prorate_presence.unwrap_or(false);
+
     let is_recurring = input.is_recurring.unwrap_or(false);
     let is_final = input.is_final.unwrap_or(true);
     let affects_balance = input.affects_balance.unwrap_or(true);
@@ -423,26 +536,35 @@ async fn update_payment(
 
     // Update payment
     sqlx::query(
-        "UPDATE payments SET payer_id = ?, amount = ?, description = ?, payment_date = ?,
+        "UPDATE payments SET payer_id = ?, amount = ?, currency = ?, description = ?, payment_date = ?,
          receipt_image = ?, is_recurring = ?, recurrence_type = ?, recurrence_interval = ?,
          recurrence_times_per = ?, recurrence_end_date = ?, recurrence_weekdays = ?,
-         recurrence_monthdays = ?, recurrence_months = ?, receiver_account_id = ?, is_final = ?,
+         recurrence_monthdays = ?, recurrence_months = ?, recurrence_rule = ?,
+         amount_schedule = ?, business_day_shift = ?, prorate_presence = ?,
+         weight_profile_id = ?, split_mode = ?, receiver_account_id = ?, is_final = ?,
          affects_balance = ?, affects_payer_expectation = ?, affects_receiver_expectation = ?
          WHERE id = ? AND project_id = ?",
     )
     .bind(input.payer_id)
     .bind(input.amount)
+    .bind(&input.currency)
     .bind(&input.description)
     .bind(&payment_date)
     .bind(&input.receipt_image)
     .bind(is_recurring)
-    .bind(&input.recurrence_type)
-    .bind(input.recurrence_interval)
+    .bind(&recurrence_type)
+    .bind(recurrence_interval)
     .bind(input.recurrence_times_per)
     .bind(&input.recurrence_end_date)
     .bind(&input.recurrence_weekdays)
     .bind(&input.recurrence_monthdays)
     .bind(&input.recurrence_months)
+    .bind(recurrence.as_ref().map(|r| r.to_string()))
+    .bind(&amount_schedule)
+    .bind(business_day_shift)
+    .bind(prorate_presence)
+    .bind(input.weight_profile_id)
+    .bind(split_mode.as_str())
     .bind(input.receiver_account_id)
     .bind(is_final)
     .bind(affects_balance)
@@ -453,31 +575,41 @@ async fn update_payment(
     .execute(&pool)
     .await?;
 
-    // Delete old contributions
+    // Delete old contributions, receipt lines and payers
     sqlx::query("DELETE FROM contributions WHERE payment_id = ?")
         .bind(path.payment_id)
         .execute(&pool)
         .await?;
+    sqlx::query("DELETE FROM payment_items WHERE payment_id = ?")
+        .bind(path.payment_id)
+        .execute(&pool)
+        .await?;
+    insert_items(&pool, path.payment_id, &input.items).await?;
+    sqlx::query("DELETE FROM payment_payers WHERE payment_id = ?")
+        .bind(path.payment_id)
+        .execute(&pool)
+        .await?;
+    insert_payers(&mut *pool.acquire().await?, path.payment_id, &input.payers).await?;
 
     // Insert new contributions
     let mut contributions = Vec::new();
-    for contrib in &input.contributions {
-        let share_amount =
-            (input.amount * contrib.weight / total_weight * 10000.0).round() / 10000.0;
-
+    for (contrib, share_amount) in input.contributions.iter().zip(shares) {
         let participant_name: String =
             sqlx::query_scalar("SELECT name FROM participants WHERE id = ?")
                 .bind(contrib.participant_id)
                 .fetch_one(&pool)
                 .await?;
 
+        let weight = split_mode.stored_weight(contrib);
         let result = sqlx::query(
-            "INSERT INTO contributions (participant_id, payment_id, amount, weight) VALUES (?, ?, ?, ?)"
+            "INSERT INTO contributions (participant_id, payment_id, amount, weight, cap, floor) VALUES (?, ?, ?, ?, ?, ?)"
         )
         .bind(contrib.participant_id)
         .bind(path.payment_id)
         .bind(share_amount)
-        .bind(contrib.weight)
+        .bind(weight)
+        .bind(contrib.cap)
+        .bind(contrib.floor)
         .execute(&pool)
         .await?;
 
@@ -487,7 +619,9 @@ async fn update_payment(
             participant_name,
             payment_id: path.payment_id,
             amount: share_amount,
-            weight: contrib.weight,
+            weight,
+            cap: contrib.cap,
+            floor: contrib.floor,
         });
     }
 
@@ -507,10 +641,15 @@ async fn update_payment(
         None
     };
 
+    let refunds = payment_refunds(&pool, path.payment_id).await?;
     let after_state = PaymentWithContributions {
+        net_cost: net_cost(&payment, &contributions, &refunds),
         payment,
         payer_name,
+        payers: payment_payers(&pool, path.payment_id).await?,
         contributions,
+        items: payment_items(&pool, path.payment_id).await?,
+        refunds,
     };
 
     // Log the update to history
@@ -554,7 +693,7 @@ async fn delete_payment(
 
     // Get contributions before deletion
     let existing_contributions: Vec<ContributionWithParticipant> = sqlx::query_as(
-        "SELECT c.id, c.participant_id, p.name as participant_name, c.payment_id, c.amount, c.weight
+        "SELECT c.id, c.participant_id, p.name as participant_name, c.payment_id, c.amount, c.weight, c.cap, c.floor
          FROM contributions c
          JOIN participants p ON c.participant_id = p.id
          WHERE c.payment_id = ?"
@@ -573,18 +712,49 @@ async fn delete_payment(
         None
     };
 
+    let refunds = payment_refunds(&pool, path.payment_id).await?;
     let before_state = PaymentWithContributions {
+        net_cost: net_cost(&existing, &existing_contributions, &refunds),
         payment: existing,
         payer_name,
+        payers: payment_payers(&pool, path.payment_id).await?,
         contributions: existing_contributions,
+        items: payment_items(&pool, path.payment_id).await?,
+        refunds,
     };
 
-    // Delete contributions first (cascade should handle this, but be explicit)
+    // Delete contributions and exceptions first (cascade should handle this, but be explicit)
     sqlx::query("DELETE FROM contributions WHERE payment_id = ?")
         .bind(path.payment_id)
         .execute(&pool)
         .await?;
 
+    sqlx::query("DELETE FROM payment_items WHERE payment_id = ?")
+        .bind(path.payment_id)
+        .execute(&pool)
+        .await?;
+
+    sqlx::query("DELETE FROM payment_payers WHERE payment_id = ?")
+        .bind(path.payment_id)
+        .execute(&pool)
+        .await?;
+
+    sqlx::query("DELETE FROM refunds WHERE payment_id = ?")
+        .bind(path.payment_id)
+        .execute(&pool)
+        .await?;
+
+    sqlx::query("DELETE FROM occurrence_exceptions WHERE payment_id = ?")
+        .bind(path.payment_id)
+        .execute(&pool)
+        .await?;
+
+    // Continuations of this series no longer have a first half to point at
+    sqlx::query("UPDATE payments SET split_from_id = NULL WHERE split_from_id = ?")
+        .bind(path.payment_id)
+        .execute(&pool)
+        .await?;
+
     // Delete the payment
     let result = sqlx::query("DELETE FROM payments WHERE id = ? AND project_id = ?")
         .bind(path.payment_id)
@@ -611,3 +781,580 @@ async fn delete_payment(
 
     Ok(Json(serde_json::json!({ "deleted": true })))
 }
+
+/// POST /projects/{id}/payments/{payment_id}/split
+/// "Edit this and following": end the series the day before `from_date` and
+/// continue it from there in a new payment with the given amount, payer or
+/// weights, leaving earlier occurrences untouched
+async fn split_payment(
+    Path(path): Path<PaymentPath>,
+    member: ProjectMember,
+    State(pool): State<SqlitePool>,
+    Json(input): Json<SplitPayment>,
+) -> AppResult<Json<SplitPaymentResult>> {
+    // Check editor permission
+    if !member.can_edit() {
+        return Err(AppError::forbidden(ErrorCode::EditorRequired));
+    }
+
+    let existing: Payment =
+        sqlx::query_as("SELECT * FROM payments WHERE id = ? AND project_id = ?")
+            .bind(path.payment_id)
+            .bind(member.project_id)
+            .fetch_optional(&pool)
+            .await?
+            .ok_or_else(|| AppError::not_found(ErrorCode::PaymentNotFound))?;
+
+    let Some(mut recurrence) = existing.recurrence() else {
+        return Err(AppError::bad_request(ErrorCode::PaymentNotRecurring));
+    };
+    let start =
+        NaiveDate::parse_from_str(existing.payment_date.get(..10).unwrap_or(""), "%Y-%m-%d")
+            .map_err(|_| AppError::bad_request(ErrorCode::InvalidDateFormat))?;
+    let from_date = NaiveDate::parse_from_str(&input.from_date, "%Y-%m-%d")
+        .map_err(|_| AppError::bad_request(ErrorCode::InvalidDateFormat))?;
+    if from_date <= start {
+        // Changing the series from its first occurrence is a plain update
+        return Err(AppError::bad_request(ErrorCode::CannotSplitFirstOccurrence));
+    }
+    let calendar = BusinessCalendar::load(&pool, member.project_id).await?;
+    if !payment_occurs_on(&existing, from_date, &calendar) {
+        return Err(AppError::not_found(ErrorCode::OccurrenceNotFound));
+    }
+
+    // Validate the changes
+    if input.amount.is_some_and(|amount| !amount.is_positive()) {
+        return Err(AppError::bad_request(ErrorCode::AmountMustBePositive));
+    }
+    if let Some(payer_id) = input.payer_id {
+        let payer_exists: Option<i64> =
+            sqlx::query_scalar("SELECT id FROM participants WHERE id = ? AND project_id = ?")
+                .bind(payer_id)
+                .bind(member.project_id)
+                .fetch_optional(&pool)
+                .await?;
+
+        if payer_exists.is_none() {
+            return Err(AppError::bad_request(ErrorCode::InvalidPayer));
+        }
+    }
+    if let Some(ref contributions) = input.contributions {
+        if contributions.is_empty() {
+            return Err(AppError::bad_request(ErrorCode::ContributionRequired));
+        }
+        for contrib in contributions {
+            let participant_exists: Option<i64> =
+                sqlx::query_scalar("SELECT id FROM participants WHERE id = ? AND project_id = ?")
+                    .bind(contrib.participant_id)
+                    .bind(member.project_id)
+                    .fetch_optional(&pool)
+                    .await?;
+
+            if participant_exists.is_none() {
+                return Err(AppError::bad_request(ErrorCode::InvalidParticipant));
+            }
+        }
+    }
+
+    let before_state = with_contributions(&mut *pool.acquire().await?, existing.clone()).await?;
+    // New weights replace the weight profile, which otherwise carries on
+    let weight_profile_id = existing
+        .weight_profile_id
+        .filter(|_| input.contributions.is_none());
+    let keeps_contributions = input.contributions.is_none() && weight_profile_id.is_none();
+    let contributions = match (input.contributions, weight_profile_id) {
+        (Some(contributions), _) => contributions,
+        (None, Some(profile_id)) => {
+            profile_contributions(&pool, member.project_id, profile_id, &input.from_date).await?
+        }
+        (None, None) => before_state
+            .contributions
+            .iter()
+            .map(|c| CreateContribution {
+                participant_id: c.participant_id,
+                weight: c.weight,
+                amount: Some(c.amount),
+                cap: c.cap,
+                floor: c.floor,
+            })
+            .collect(),
+    };
+
+    // The continuation keeps the series' own start so its phase, month days
+    // and COUNT carry on where the first half stops
+    for rule in &mut recurrence.rules {
+        rule.start.get_or_insert(start);
+    }
+    // The continuation bills what the series bills on that date, and takes
+    // over the changes still to come
+    let amount = input
+        .amount
+        .unwrap_or_else(|| existing.amount_on(from_date));
+    let amount_schedule = existing
+        .schedule()
+        .and_then(|schedule| schedule.continued_from(start, from_date))
+        .map(|schedule| serde_json::to_string(&schedule))
+        .transpose()
+        .map_err(|e| AppError::Internal(e.to_string()))?;
+    let payer_id = input.payer_id.or(existing.payer_id);
+
+    // Exact amounts carried over to another amount are split in proportion,
+    // as the series' own occurrences are
+    let split_mode = match existing.split_mode() {
+        _ if weight_profile_id.is_some() => SplitMode::Weights,
+        SplitMode::Exact if keeps_contributions && amount != existing.amount => SplitMode::Weights,
+        mode => mode,
+    };
+    let shares = split_mode
+        .resolve(amount, &contributions)
+        .map_err(split_error)?;
+
+    // Both halves change together or not at all
+    let mut tx = pool.begin().await?;
+
+    let result = sqlx::query(
+        "INSERT INTO payments (project_id, payer_id, amount, currency, description, payment_date,
+         receipt_image, is_recurring, recurrence_type, recurrence_interval, recurrence_times_per,
+         recurrence_end_date, recurrence_weekdays, recurrence_monthdays, recurrence_months,
+         recurrence_rule, amount_schedule, business_day_shift, prorate_presence,
+         weight_profile_id, split_mode, receiver_account_id, is_final, affects_balance,
+         affects_payer_expectation, affects_receiver_expectation, split_from_id)
+         VALUES (?, ?, ?, ?, ?, ?, ?, 1, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(member.project_id)
+    .bind(payer_id)
+    .bind(amount)
+    .bind(&existing.currency)
+    .bind(&existing.description)
+    .bind(&input.from_date)
+    .bind(&existing.receipt_image)
+    .bind(&existing.recurrence_type)
+    .bind(existing.recurrence_interval)
+    .bind(existing.recurrence_times_per)
+    .bind(&existing.recurrence_end_date)
+    .bind(&existing.recurrence_weekdays)
+    .bind(&existing.recurrence_monthdays)
+    .bind(&existing.recurrence_months)
+    .bind(recurrence.to_string())
+    .bind(&amount_schedule)
+    .bind(&existing.business_day_shift)
+    .bind(existing.prorate_presence)
+    .bind(weight_profile_id)
+    .bind(split_mode.as_str())
+    .bind(existing.receiver_account_id)
+    .bind(existing.is_final)
+    .bind(existing.affects_balance)
+    .bind(existing.affects_payer_expectation)
+    .bind(existing.affects_receiver_expectation)
+    .bind(existing.id)
+    .execute(&mut *tx)
+    .await?;
+
+    let continuation_id = result.last_insert_rowid();
+
+    for (contrib, share_amount) in contributions.iter().zip(shares) {
+        sqlx::query(
+            "INSERT INTO contributions (participant_id, payment_id, amount, weight, cap, floor) VALUES (?, ?, ?, ?, ?, ?)"
+        )
+        .bind(contrib.participant_id)
+        .bind(continuation_id)
+        .bind(share_amount)
+        .bind(split_mode.stored_weight(contrib))
+        .bind(contrib.cap)
+        .bind(contrib.floor)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    // Unless a new payer takes over, the payers carry on, sharing the
+    // continuation's amount as they shared the series'
+    if input.payer_id.is_none() {
+        let payers = &before_state.payers;
+        let weights: Vec<(i64, f64)> = payers
+            .iter()
+            .map(|p| (p.participant_id, p.amount.to_f64()))
+            .collect();
+        let parts: Vec<CreatePaymentPayer> = payers
+            .iter()
+            .zip(amount.allocate(&weights))
+            .map(|(payer, part)| CreatePaymentPayer {
+                participant_id: payer.participant_id,
+                amount: part,
+            })
+            .collect();
+        insert_payers(&mut tx, continuation_id, &parts).await?;
+    }
+
+    // Receipt lines still describe the continuation while it bills the same
+    if keeps_contributions && amount == existing.amount {
+        sqlx::query(
+            "INSERT INTO payment_items (payment_id, position, kind, description, amount, weights)
+             SELECT ?, position, kind, description, amount, weights
+             FROM payment_items WHERE payment_id = ?",
+        )
+        .bind(continuation_id)
+        .bind(existing.id)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    // End the first half the day before, and hand it the later exceptions
+    let end_date = (from_date - chrono::Duration::days(1))
+        .format("%Y-%m-%d")
+        .to_string();
+    sqlx::query("UPDATE payments SET recurrence_end_date = ? WHERE id = ?")
+        .bind(&end_date)
+        .bind(existing.id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query(
+        "UPDATE occurrence_exceptions SET payment_id = ? WHERE payment_id = ? AND original_date >= ?",
+    )
+    .bind(continuation_id)
+    .bind(existing.id)
+    .bind(&input.from_date)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    let mut conn = pool.acquire().await?;
+    let ended: Payment = sqlx::query_as("SELECT * FROM payments WHERE id = ?")
+        .bind(existing.id)
+        .fetch_one(&mut *conn)
+        .await?;
+    let continuation: Payment = sqlx::query_as("SELECT * FROM payments WHERE id = ?")
+        .bind(continuation_id)
+        .fetch_one(&mut *conn)
+        .await?;
+    let result = SplitPaymentResult {
+        ended: with_contributions(&mut conn, ended).await?,
+        continuation: with_contributions(&mut conn, continuation).await?,
+    };
+    drop(conn);
+
+    // Log both halves under one correlation id so they are undone together
+    let correlation_id = HistoryService::new_correlation_id();
+    let _ = HistoryService::log_update(
+        &pool,
+        crate::services::history::LogUpdateParams {
+            correlation_id: &correlation_id,
+            actor_user_id: member.user_id,
+            project_id: member.project_id,
+            entity_type: EntityType::Payment,
+            entity_id: existing.id,
+            before: &before_state,
+            after: &result.ended,
+        },
+    )
+    .await;
+    let _ = HistoryService::log_create(
+        &pool,
+        &correlation_id,
+        member.user_id,
+        member.project_id,
+        EntityType::Payment,
+        continuation_id,
+        &result.continuation,
+    )
+    .await;
+
+    Ok(Json(result))
+}
+
+/// A payment with its payer name and contributions, as the API returns it
+pub(crate) async fn with_contributions(
+    conn: &mut SqliteConnection,
+    payment: Payment,
+) -> AppResult<PaymentWithContributions> {
+    let payer_name: Option<String> = match payment.payer_id {
+        Some(payer_id) => {
+            sqlx::query_scalar("SELECT name FROM participants WHERE id = ?")
+                .bind(payer_id)
+                .fetch_optional(&mut *conn)
+                .await?
+        }
+        None => None,
+    };
+
+    let contributions: Vec<ContributionWithParticipant> = sqlx::query_as(
+        "SELECT c.id, c.participant_id, p.name as participant_name, c.payment_id, c.amount, c.weight, c.cap, c.floor
+         FROM contributions c
+         JOIN participants p ON c.participant_id = p.id
+         WHERE c.payment_id = ?"
+    )
+    .bind(payment.id)
+    .fetch_all(&mut *conn)
+    .await?;
+    let payers = payment_payers(&mut *conn, payment.id).await?;
+    let items = payment_items(&mut *conn, payment.id).await?;
+    let refunds = payment_refunds(&mut *conn, payment.id).await?;
+    let net_cost = net_cost(&payment, &contributions, &refunds);
+
+    Ok(PaymentWithContributions {
+        payment,
+        payer_name,
+        payers,
+        contributions,
+        items,
+        refunds,
+        net_cost,
+    })
+}
+
+/// What a payment comes to once its refunds are deducted
+fn net_cost(
+    payment: &Payment,
+    contributions: &[ContributionWithParticipant],
+    refunds: &[Refund],
+) -> Option<NetCost> {
+    let shares: Vec<(i64, Money)> = contributions
+        .iter()
+        .map(|c| (c.participant_id, c.amount))
+        .collect();
+    NetCost::of(payment.amount, &shares, refunds)
+}
+
+/// Payers of a payment paid by several, in the order they were given
+async fn payment_payers(
+    conn: impl SqliteExecutor<'_>,
+    payment_id: i64,
+) -> AppResult<Vec<PaymentPayerWithParticipant>> {
+    Ok(sqlx::query_as(
+        "SELECT pp.id, pp.payment_id, pp.participant_id, p.name as participant_name, pp.amount
+         FROM payment_payers pp
+         JOIN participants p ON pp.participant_id = p.id
+         WHERE pp.payment_id = ?
+         ORDER BY pp.id",
+    )
+    .bind(payment_id)
+    .fetch_all(conn)
+    .await?)
+}
+
+async fn insert_payers(
+    conn: &mut SqliteConnection,
+    payment_id: i64,
+    payers: &[CreatePaymentPayer],
+) -> AppResult<()> {
+    for payer in payers {
+        sqlx::query(
+            "INSERT INTO payment_payers (payment_id, participant_id, amount) VALUES (?, ?, ?)",
+        )
+        .bind(payment_id)
+        .bind(payer.participant_id)
+        .bind(payer.amount)
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(())
+}
+
+/// Check the payers of a payment paid by several: distinct participants of
+/// the project, each paying a positive part, the parts adding up to the
+/// amount. The first becomes the payment's payer; a lone payer is only that.
+async fn validate_payers(
+    pool: &SqlitePool,
+    project_id: i64,
+    input: &mut CreatePayment,
+) -> AppResult<()> {
+    let Some(first) = input.payers.first() else {
+        return Ok(());
+    };
+    // Transfers move money from one account to another
+    if input.receiver_account_id.is_some() {
+        return Err(AppError::bad_request(ErrorCode::InvalidPayers));
+    }
+    input.payer_id = Some(first.participant_id);
+
+    let mut seen = HashSet::new();
+    for payer in &input.payers {
+        if !payer.amount.is_positive() || !seen.insert(payer.participant_id) {
+            return Err(AppError::bad_request(ErrorCode::InvalidPayers));
+        }
+        let payer_exists: Option<i64> =
+            sqlx::query_scalar("SELECT id FROM participants WHERE id = ? AND project_id = ?")
+                .bind(payer.participant_id)
+                .bind(project_id)
+                .fetch_optional(pool)
+                .await?;
+        if payer_exists.is_none() {
+            return Err(AppError::bad_request(ErrorCode::InvalidPayer));
+        }
+    }
+    if input.payers.iter().map(|p| p.amount).sum::<Money>() != input.amount {
+        return Err(AppError::bad_request(ErrorCode::PayerAmountsMismatch));
+    }
+
+    if input.payers.len() == 1 {
+        input.payers.clear();
+    }
+    Ok(())
+}
+
+/// Receipt lines of a payment, in receipt order
+async fn payment_items(
+    conn: impl SqliteExecutor<'_>,
+    payment_id: i64,
+) -> AppResult<Vec<PaymentItem>> {
+    Ok(
+        sqlx::query_as("SELECT * FROM payment_items WHERE payment_id = ? ORDER BY position")
+            .bind(payment_id)
+            .fetch_all(conn)
+            .await?,
+    )
+}
+
+async fn insert_items(
+    pool: &SqlitePool,
+    payment_id: i64,
+    items: &[CreatePaymentItem],
+) -> AppResult<()> {
+    for (position, item) in items.iter().enumerate() {
+        let kind = item.kind().unwrap_or_default();
+        let weights = match kind {
+            ItemKind::Item => Some(
+                serde_json::to_string(&item.contributions)
+                    .map_err(|e| AppError::Internal(e.to_string()))?,
+            ),
+            _ => None,
+        };
+        sqlx::query(
+            "INSERT INTO payment_items (payment_id, position, kind, description, amount, weights)
+             VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(payment_id)
+        .bind(position as i64)
+        .bind(kind.as_str())
+        .bind(&item.description)
+        .bind(item.amount)
+        .bind(weights)
+        .execute(pool)
+        .await?;
+    }
+    Ok(())
+}
+
+/// Derive an itemized payment's contributions from its lines, as exact
+/// amounts. Items replace a weight profile's weights, so both cannot be given.
+fn itemize(input: &mut CreatePayment) -> AppResult<()> {
+    if input.weight_profile_id.is_some() {
+        return Err(AppError::bad_request(ErrorCode::InvalidPaymentItem));
+    }
+    input.contributions = itemized_contributions(input.amount, &input.items).map_err(|error| {
+        AppError::bad_request(match error {
+            ItemsError::InvalidItem => ErrorCode::InvalidPaymentItem,
+            ItemsError::InvalidAdjustment => ErrorCode::InvalidItemAdjustment,
+            ItemsError::TotalMismatch => ErrorCode::ItemsTotalMismatch,
+        })
+    })?;
+    input.split_mode = Some(SplitMode::Exact.as_str().to_string());
+    Ok(())
+}
+
+/// Recurrence to store for a payment: the RRULE it was given, normalized, or
+/// its legacy recurrence fields converted. None for one-off payments.
+fn recurrence_rule(input: &CreatePayment, payment_date: &str) -> AppResult<Option<RecurrenceSet>> {
+    if !input.is_recurring.unwrap_or(false) {
+        return Ok(None);
+    }
+    if let Some(rule) = &input.recurrence_rule {
+        return RecurrenceSet::parse(rule)
+            .map(Some)
+            .ok_or_else(|| AppError::bad_request(ErrorCode::InvalidRecurrenceRule));
+    }
+    let start = payment_date
+        .get(..10)
+        .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+        .ok_or_else(|| AppError::bad_request(ErrorCode::InvalidDateFormat))?;
+    Ok(Some(RecurrenceSet::from_legacy(
+        start,
+        &input.legacy_recurrence(),
+    )))
+}
+
+/// Amount schedule to store for a payment, normalized. Only recurring
+/// payments have one.
+fn amount_schedule(input: &CreatePayment) -> AppResult<Option<String>> {
+    let Some(json) = input
+        .amount_schedule
+        .as_deref()
+        .filter(|_| input.is_recurring.unwrap_or(false))
+    else {
+        return Ok(None);
+    };
+    let schedule = AmountSchedule::parse(json)
+        .ok_or_else(|| AppError::bad_request(ErrorCode::InvalidAmountSchedule))?;
+    serde_json::to_string(&schedule)
+        .map(Some)
+        .map_err(|e| AppError::Internal(e.to_string()))
+}
+
+/// Business day shift to store for a payment. Only recurring payments have one.
+fn business_day_shift(input: &CreatePayment) -> AppResult<Option<&'static str>> {
+    let Some(shift) = input
+        .business_day_shift
+        .as_deref()
+        .filter(|_| input.is_recurring.unwrap_or(false))
+    else {
+        return Ok(None);
+    };
+    BusinessDayShift::parse(shift)
+        .map(|shift| Some(shift.as_str()))
+        .ok_or_else(|| AppError::bad_request(ErrorCode::InvalidBusinessDayShift))
+}
+
+/// Legacy type and interval columns, filled from the RRULE when the client
+/// only sent a rule, so older clients still see the payment as recurring
+fn recurrence_type_and_interval(
+    input: &CreatePayment,
+    recurrence: &Option<RecurrenceSet>,
+) -> (Option<String>, Option<i32>) {
+    let first = recurrence.as_ref().and_then(|r| r.rules.first());
+    (
+        input
+            .recurrence_type
+            .clone()
+            .or_else(|| first.map(|rule| rule.freq.recurrence_type().to_string())),
+        input
+            .recurrence_interval
+            .or_else(|| first.map(|rule| rule.interval as i32)),
+    )
+}
+
+/// Contributions of a payment on a weight profile: the profile's weights in
+/// effect on the payment date
+async fn payment_profile_contributions(
+    pool: &SqlitePool,
+    member: &ProjectMember,
+    input: &CreatePayment,
+    profile_id: i64,
+) -> AppResult<Vec<CreateContribution>> {
+    let payment_date = input
+        .payment_date
+        .clone()
+        .unwrap_or_else(|| chrono::Utc::now().format("%Y-%m-%d").to_string());
+    profile_contributions(pool, member.project_id, profile_id, &payment_date).await
+}
+
+/// Split mode of a payment. A payment on a weight profile is split by weights.
+fn split_mode(input: &CreatePayment) -> AppResult<SplitMode> {
+    match input.split_mode.as_deref() {
+        _ if input.weight_profile_id.is_some() => Ok(SplitMode::Weights),
+        None => Ok(SplitMode::Weights),
+        Some(mode) => {
+            SplitMode::parse(mode).ok_or_else(|| AppError::bad_request(ErrorCode::InvalidSplitMode))
+        }
+    }
+}
+
+fn split_error(error: SplitError) -> AppError {
+    AppError::bad_request(match error {
+        SplitError::NoWeight => ErrorCode::TotalWeightMustBePositive,
+        SplitError::AmountsMismatch => ErrorCode::SplitAmountsMismatch,
+        SplitError::PercentagesNotHundred => ErrorCode::PercentagesMustTotal100,
+        SplitError::SharesNotWhole => ErrorCode::SharesMustBeWhole,
+        SplitError::InvalidLimits => ErrorCode::InvalidSplitLimits,
+    })
+}
@@ -0,0 +1,247 @@
+use axum::{
+    extract::{Path, State},
+    routing::{get, put},
+    Json, Router,
+};
+use chrono::NaiveDate;
+use serde::Deserialize;
+use sqlx::SqlitePool;
+
+use crate::{
+    auth::ProjectMember,
+    error::{AppError, AppResult, ErrorCode},
+    models::{CreatePoolRule, EntityType, Money, PoolRule, PoolRuleKind, RecurrenceSet},
+    services::HistoryService,
+    AppState,
+};
+
+#[derive(Deserialize)]
+struct RulePath {
+    rule_id: i64,
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_rules).post(create_rule))
+        .route("/{rule_id}", put(update_rule).delete(delete_rule))
+}
+
+async fn find_rule(pool: &SqlitePool, project_id: i64, rule_id: i64) -> AppResult<PoolRule> {
+    sqlx::query_as("SELECT * FROM pool_rules WHERE id = ? AND project_id = ?")
+        .bind(rule_id)
+        .bind(project_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| AppError::not_found(ErrorCode::PoolRuleNotFound))
+}
+
+/// A rule as stored: its kind, the fee or the rate it keeps and its schedule,
+/// normalized
+struct ValidRule {
+    kind: PoolRuleKind,
+    amount: Option<Money>,
+    rate: Option<f64>,
+    recurrence_rule: String,
+}
+
+/// Check a submitted rule: a pool of the project, a positive fee or a non-zero
+/// rate, a valid schedule and dates that do not end before they start
+async fn validate_rule(
+    pool: &SqlitePool,
+    project_id: i64,
+    input: &CreatePoolRule,
+) -> AppResult<ValidRule> {
+    let pool_exists: Option<i64> = sqlx::query_scalar(
+        "SELECT id FROM participants WHERE id = ? AND project_id = ? AND account_type = 'pool'",
+    )
+    .bind(input.pool_id)
+    .bind(project_id)
+    .fetch_optional(pool)
+    .await?;
+    if pool_exists.is_none() {
+        return Err(AppError::bad_request(ErrorCode::InvalidParticipant));
+    }
+
+    let kind = PoolRuleKind::parse(input.kind.as_str())
+        .ok_or_else(|| AppError::bad_request(ErrorCode::InvalidPoolRule))?;
+    let (amount, rate) = match kind {
+        PoolRuleKind::Fee => match input.amount {
+            Some(amount) if amount.is_positive() => (Some(amount), None),
+            _ => return Err(AppError::bad_request(ErrorCode::InvalidPoolRule)),
+        },
+        PoolRuleKind::Interest => match input.rate {
+            Some(rate) if rate.is_finite() && rate != 0.0 => (None, Some(rate)),
+            _ => return Err(AppError::bad_request(ErrorCode::InvalidPoolRule)),
+        },
+    };
+
+    let recurrence = RecurrenceSet::parse(&input.recurrence_rule)
+        .ok_or_else(|| AppError::bad_request(ErrorCode::InvalidRecurrenceRule))?;
+
+    let start = NaiveDate::parse_from_str(input.start_date.as_str(), "%Y-%m-%d")
+        .map_err(|_| AppError::bad_request(ErrorCode::InvalidDateFormat))?;
+    if let Some(end_date) = &input.end_date {
+        let end = NaiveDate::parse_from_str(end_date.as_str(), "%Y-%m-%d")
+            .map_err(|_| AppError::bad_request(ErrorCode::InvalidDateFormat))?;
+        if end < start {
+            return Err(AppError::bad_request(ErrorCode::InvalidPoolRule));
+        }
+    }
+
+    Ok(ValidRule {
+        kind,
+        amount,
+        rate,
+        recurrence_rule: recurrence.to_string(),
+    })
+}
+
+/// GET /projects/{id}/pool-rules
+async fn list_rules(
+    member: ProjectMember,
+    State(pool): State<SqlitePool>,
+) -> AppResult<Json<Vec<PoolRule>>> {
+    let rules: Vec<PoolRule> = sqlx::query_as(
+        "SELECT * FROM pool_rules WHERE project_id = ?
+         ORDER BY pool_id, start_date, id",
+    )
+    .bind(member.project_id)
+    .fetch_all(&pool)
+    .await?;
+
+    Ok(Json(rules))
+}
+
+/// POST /projects/{id}/pool-rules
+/// Add a recurring fee or interest to a pool, shared by its owners
+async fn create_rule(
+    member: ProjectMember,
+    State(pool): State<SqlitePool>,
+    Json(input): Json<CreatePoolRule>,
+) -> AppResult<Json<PoolRule>> {
+    if !member.can_edit() {
+        return Err(AppError::forbidden(ErrorCode::EditorRequired));
+    }
+
+    let rule = validate_rule(&pool, member.project_id, &input).await?;
+
+    let result = sqlx::query(
+        "INSERT INTO pool_rules
+         (project_id, pool_id, kind, amount, rate, recurrence_rule, start_date, end_date, description)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(member.project_id)
+    .bind(input.pool_id)
+    .bind(rule.kind.as_str())
+    .bind(rule.amount)
+    .bind(rule.rate)
+    .bind(&rule.recurrence_rule)
+    .bind(input.start_date.as_str())
+    .bind(input.end_date.as_ref().map(|d| d.as_str()))
+    .bind(input.description.as_ref().map_or("", |d| d.as_str()))
+    .execute(&pool)
+    .await?;
+
+    let rule = find_rule(&pool, member.project_id, result.last_insert_rowid()).await?;
+
+    // Log the creation to history
+    let correlation_id = HistoryService::new_correlation_id();
+    let _ = HistoryService::log_create(
+        &pool,
+        &correlation_id,
+        member.user_id,
+        member.project_id,
+        EntityType::PoolRule,
+        rule.id,
+        &rule,
+    )
+    .await;
+
+    Ok(Json(rule))
+}
+
+/// PUT /projects/{id}/pool-rules/{rule_id}
+async fn update_rule(
+    Path(path): Path<RulePath>,
+    member: ProjectMember,
+    State(pool): State<SqlitePool>,
+    Json(input): Json<CreatePoolRule>,
+) -> AppResult<Json<PoolRule>> {
+    if !member.can_edit() {
+        return Err(AppError::forbidden(ErrorCode::EditorRequired));
+    }
+
+    let before = find_rule(&pool, member.project_id, path.rule_id).await?;
+    let rule = validate_rule(&pool, member.project_id, &input).await?;
+
+    sqlx::query(
+        "UPDATE pool_rules
+         SET pool_id = ?, kind = ?, amount = ?, rate = ?, recurrence_rule = ?,
+             start_date = ?, end_date = ?, description = ?
+         WHERE id = ?",
+    )
+    .bind(input.pool_id)
+    .bind(rule.kind.as_str())
+    .bind(rule.amount)
+    .bind(rule.rate)
+    .bind(&rule.recurrence_rule)
+    .bind(input.start_date.as_str())
+    .bind(input.end_date.as_ref().map(|d| d.as_str()))
+    .bind(input.description.as_ref().map_or("", |d| d.as_str()))
+    .bind(path.rule_id)
+    .execute(&pool)
+    .await?;
+
+    let rule = find_rule(&pool, member.project_id, path.rule_id).await?;
+
+    // Log the update to history
+    let correlation_id = HistoryService::new_correlation_id();
+    let _ = HistoryService::log_update(
+        &pool,
+        crate::services::history::LogUpdateParams {
+            correlation_id: &correlation_id,
+            actor_user_id: member.user_id,
+            project_id: member.project_id,
+            entity_type: EntityType::PoolRule,
+            entity_id: rule.id,
+            before: &before,
+            after: &rule,
+        },
+    )
+    .await;
+
+    Ok(Json(rule))
+}
+
+/// DELETE /projects/{id}/pool-rules/{rule_id}
+async fn delete_rule(
+    Path(path): Path<RulePath>,
+    member: ProjectMember,
+    State(pool): State<SqlitePool>,
+) -> AppResult<Json<serde_json::Value>> {
+    if !member.can_edit() {
+        return Err(AppError::forbidden(ErrorCode::EditorRequired));
+    }
+
+    let existing = find_rule(&pool, member.project_id, path.rule_id).await?;
+
+    sqlx::query("DELETE FROM pool_rules WHERE id = ?")
+        .bind(path.rule_id)
+        .execute(&pool)
+        .await?;
+
+    // Log the deletion to history
+    let correlation_id = HistoryService::new_correlation_id();
+    let _ = HistoryService::log_delete(
+        &pool,
+        &correlation_id,
+        member.user_id,
+        member.project_id,
+        EntityType::PoolRule,
+        path.rule_id,
+        &existing,
+    )
+    .await;
+
+    Ok(Json(serde_json::json!({ "deleted": true })))
+}
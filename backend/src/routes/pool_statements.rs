@@ -0,0 +1,365 @@
+use axum::{
+    extract::{Path, Query, State},
+    routing::{get, post, put},
+    Json, Router,
+};
+use chrono::NaiveDate;
+use serde::Deserialize;
+use sqlx::SqlitePool;
+
+use crate::{
+    auth::ProjectMember,
+    error::{AppError, AppResult, ErrorCode},
+    models::{
+        ContributionWithParticipant, CreatePoolStatement, EntityType, Payment,
+        PaymentWithContributions, PoolStatement,
+    },
+    services::{HistoryService, PoolReconciliation},
+    AppState,
+};
+
+#[derive(Deserialize)]
+struct StatementPath {
+    statement_id: i64,
+}
+
+#[derive(Deserialize)]
+struct PoolQuery {
+    pool_id: Option<i64>,
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_statements).post(create_statement))
+        .route("/reconciliation", get(get_reconciliation))
+        .route(
+            "/{statement_id}",
+            put(update_statement).delete(delete_statement),
+        )
+        .route("/{statement_id}/adjust", post(adjust_to_statement))
+}
+
+async fn find_statement(
+    pool: &SqlitePool,
+    project_id: i64,
+    statement_id: i64,
+) -> AppResult<PoolStatement> {
+    sqlx::query_as("SELECT * FROM pool_statements WHERE id = ? AND project_id = ?")
+        .bind(statement_id)
+        .bind(project_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| AppError::not_found(ErrorCode::PoolStatementNotFound))
+}
+
+/// Statements of a pool, oldest first
+async fn pool_statements(pool: &SqlitePool, pool_id: i64) -> AppResult<Vec<PoolStatement>> {
+    Ok(sqlx::query_as(
+        "SELECT * FROM pool_statements WHERE pool_id = ? ORDER BY statement_date, id",
+    )
+    .bind(pool_id)
+    .fetch_all(pool)
+    .await?)
+}
+
+/// Pool of the project, or InvalidParticipant
+async fn check_pool(pool: &SqlitePool, project_id: i64, pool_id: i64) -> AppResult<()> {
+    let pool_exists: Option<i64> = sqlx::query_scalar(
+        "SELECT id FROM participants WHERE id = ? AND project_id = ? AND account_type = 'pool'",
+    )
+    .bind(pool_id)
+    .bind(project_id)
+    .fetch_optional(pool)
+    .await?;
+    if pool_exists.is_none() {
+        return Err(AppError::bad_request(ErrorCode::InvalidParticipant));
+    }
+    Ok(())
+}
+
+/// Check a submitted statement: a pool of the project and a valid date
+async fn validate_statement(
+    pool: &SqlitePool,
+    project_id: i64,
+    input: &CreatePoolStatement,
+) -> AppResult<()> {
+    check_pool(pool, project_id, input.pool_id).await?;
+    NaiveDate::parse_from_str(input.statement_date.as_str(), "%Y-%m-%d")
+        .map_err(|_| AppError::bad_request(ErrorCode::InvalidDateFormat))?;
+    Ok(())
+}
+
+/// GET /projects/{id}/pool-statements?pool_id=
+async fn list_statements(
+    member: ProjectMember,
+    State(pool): State<SqlitePool>,
+    Query(query): Query<PoolQuery>,
+) -> AppResult<Json<Vec<PoolStatement>>> {
+    let statements: Vec<PoolStatement> = sqlx::query_as(
+        "SELECT * FROM pool_statements
+         WHERE project_id = ? AND (? IS NULL OR pool_id = ?)
+         ORDER BY pool_id, statement_date, id",
+    )
+    .bind(member.project_id)
+    .bind(query.pool_id)
+    .bind(query.pool_id)
+    .fetch_all(&pool)
+    .await?;
+
+    Ok(Json(statements))
+}
+
+/// GET /projects/{id}/pool-statements/reconciliation?pool_id=
+/// Each statement of the pool against the balance on record on its date
+async fn get_reconciliation(
+    member: ProjectMember,
+    State(pool): State<SqlitePool>,
+    Query(query): Query<PoolQuery>,
+) -> AppResult<Json<PoolReconciliation>> {
+    let pool_id = query
+        .pool_id
+        .ok_or_else(|| AppError::bad_request(ErrorCode::InvalidParticipant))?;
+    check_pool(&pool, member.project_id, pool_id).await?;
+
+    let statements = pool_statements(&pool, pool_id).await?;
+    let reconciliation =
+        crate::services::reconcile_pool(&pool, member.project_id, pool_id, &statements).await?;
+    Ok(Json(reconciliation))
+}
+
+/// POST /projects/{id}/pool-statements
+/// Record a pool's balance from a bank statement
+async fn create_statement(
+    member: ProjectMember,
+    State(pool): State<SqlitePool>,
+    Json(input): Json<CreatePoolStatement>,
+) -> AppResult<Json<PoolStatement>> {
+    if !member.can_edit() {
+        return Err(AppError::forbidden(ErrorCode::EditorRequired));
+    }
+
+    validate_statement(&pool, member.project_id, &input).await?;
+
+    let result = sqlx::query(
+        "INSERT INTO pool_statements (project_id, pool_id, statement_date, balance, description)
+         VALUES (?, ?, ?, ?, ?)",
+    )
+    .bind(member.project_id)
+    .bind(input.pool_id)
+    .bind(input.statement_date.as_str())
+    .bind(input.balance)
+    .bind(input.description.as_ref().map_or("", |d| d.as_str()))
+    .execute(&pool)
+    .await?;
+
+    let statement = find_statement(&pool, member.project_id, result.last_insert_rowid()).await?;
+
+    // Log the creation to history
+    let correlation_id = HistoryService::new_correlation_id();
+    let _ = HistoryService::log_create(
+        &pool,
+        &correlation_id,
+        member.user_id,
+        member.project_id,
+        EntityType::PoolStatement,
+        statement.id,
+        &statement,
+    )
+    .await;
+
+    Ok(Json(statement))
+}
+
+/// PUT /projects/{id}/pool-statements/{statement_id}
+async fn update_statement(
+    Path(path): Path<StatementPath>,
+    member: ProjectMember,
+    State(pool): State<SqlitePool>,
+    Json(input): Json<CreatePoolStatement>,
+) -> AppResult<Json<PoolStatement>> {
+    if !member.can_edit() {
+        return Err(AppError::forbidden(ErrorCode::EditorRequired));
+    }
+
+    let before = find_statement(&pool, member.project_id, path.statement_id).await?;
+    validate_statement(&pool, member.project_id, &input).await?;
+
+    sqlx::query(
+        "UPDATE pool_statements
+         SET pool_id = ?, statement_date = ?, balance = ?, description = ?
+         WHERE id = ?",
+    )
+    .bind(input.pool_id)
+    .bind(input.statement_date.as_str())
+    .bind(input.balance)
+    .bind(input.description.as_ref().map_or("", |d| d.as_str()))
+    .bind(path.statement_id)
+    .execute(&pool)
+    .await?;
+
+    let statement = find_statement(&pool, member.project_id, path.statement_id).await?;
+
+    // Log the update to history
+    let correlation_id = HistoryService::new_correlation_id();
+    let _ = HistoryService::log_update(
+        &pool,
+        crate::services::history::LogUpdateParams {
+            correlation_id: &correlation_id,
+            actor_user_id: member.user_id,
+            project_id: member.project_id,
+            entity_type: EntityType::PoolStatement,
+            entity_id: statement.id,
+            before: &before,
+            after: &statement,
+        },
+    )
+    .await;
+
+    Ok(Json(statement))
+}
+
+/// DELETE /projects/{id}/pool-statements/{statement_id}
+async fn delete_statement(
+    Path(path): Path<StatementPath>,
+    member: ProjectMember,
+    State(pool): State<SqlitePool>,
+) -> AppResult<Json<serde_json::Value>> {
+    if !member.can_edit() {
+        return Err(AppError::forbidden(ErrorCode::EditorRequired));
+    }
+
+    let existing = find_statement(&pool, member.project_id, path.statement_id).await?;
+
+    sqlx::query("DELETE FROM pool_statements WHERE id = ?")
+        .bind(path.statement_id)
+        .execute(&pool)
+        .await?;
+
+    // Log the deletion to history
+    let correlation_id = HistoryService::new_correlation_id();
+    let _ = HistoryService::log_delete(
+        &pool,
+        &correlation_id,
+        member.user_id,
+        member.project_id,
+        EntityType::PoolStatement,
+        path.statement_id,
+        &existing,
+    )
+    .await;
+
+    Ok(Json(serde_json::json!({ "deleted": true })))
+}
+
+/// POST /projects/{id}/pool-statements/{statement_id}/adjust
+/// Post the drift at a statement as a payment shared by the pool's owners in
+/// proportion to what they own: money coming in when the bank holds more
+/// than recorded, an expense of the pool when it holds less
+async fn adjust_to_statement(
+    Path(path): Path<StatementPath>,
+    member: ProjectMember,
+    State(pool): State<SqlitePool>,
+) -> AppResult<Json<PaymentWithContributions>> {
+    if !member.can_edit() {
+        return Err(AppError::forbidden(ErrorCode::EditorRequired));
+    }
+
+    let statement = find_statement(&pool, member.project_id, path.statement_id).await?;
+    let statements: Vec<PoolStatement> = pool_statements(&pool, statement.pool_id)
+        .await?
+        .into_iter()
+        .filter(|s| s.statement_date < statement.statement_date || s.id == statement.id)
+        .collect();
+    let reconciliation =
+        crate::services::reconcile_pool(&pool, member.project_id, statement.pool_id, &statements)
+            .await?;
+    let reconciled = reconciliation
+        .statements
+        .into_iter()
+        .find(|s| s.statement_id == statement.id)
+        .ok_or_else(|| AppError::not_found(ErrorCode::PoolStatementNotFound))?;
+    if reconciled.drift.is_zero() || reconciled.adjustment.is_empty() {
+        return Err(AppError::bad_request(ErrorCode::NothingToAdjust));
+    }
+
+    // Money found goes to the pool from outside; money missing left it
+    let (payer_id, receiver_account_id) = if reconciled.drift.is_positive() {
+        (None, Some(statement.pool_id))
+    } else {
+        (Some(statement.pool_id), None)
+    };
+    let description = if statement.description.is_empty() {
+        "Reconciliation adjustment".to_string()
+    } else {
+        format!("Reconciliation adjustment: {}", statement.description)
+    };
+
+    let mut tx = pool.begin().await?;
+    let result = sqlx::query(
+        "INSERT INTO payments (project_id, payer_id, amount, description, payment_date, receiver_account_id)
+         VALUES (?, ?, ?, ?, ?, ?)",
+    )
+    .bind(member.project_id)
+    .bind(payer_id)
+    .bind(reconciled.drift.abs())
+    .bind(&description)
+    .bind(&reconciled.statement_date)
+    .bind(receiver_account_id)
+    .execute(&mut *tx)
+    .await?;
+    let payment_id = result.last_insert_rowid();
+
+    let mut contributions = Vec::with_capacity(reconciled.adjustment.len());
+    for share in &reconciled.adjustment {
+        let amount = share.amount.abs();
+        let result = sqlx::query(
+            "INSERT INTO contributions (participant_id, payment_id, amount, weight) VALUES (?, ?, ?, ?)",
+        )
+        .bind(share.participant_id)
+        .bind(payment_id)
+        .bind(amount)
+        .bind(amount.to_f64())
+        .execute(&mut *tx)
+        .await?;
+        contributions.push(ContributionWithParticipant {
+            id: result.last_insert_rowid(),
+            participant_id: share.participant_id,
+            participant_name: share.participant_name.clone(),
+            payment_id,
+            amount,
+            weight: amount.to_f64(),
+            cap: None,
+            floor: None,
+        });
+    }
+    tx.commit().await?;
+
+    let payment: Payment = sqlx::query_as("SELECT * FROM payments WHERE id = ?")
+        .bind(payment_id)
+        .fetch_one(&pool)
+        .await?;
+    let result = PaymentWithContributions {
+        payment,
+        payer_name: payer_id.map(|_| reconciliation.pool_name.clone()),
+        payers: Vec::new(),
+        contributions,
+        items: Vec::new(),
+        refunds: Vec::new(),
+        net_cost: None,
+    };
+
+    // Log the creation to history
+    let correlation_id = HistoryService::new_correlation_id();
+    let _ = HistoryService::log_create(
+        &pool,
+        &correlation_id,
+        member.user_id,
+        member.project_id,
+        EntityType::Payment,
+        payment_id,
+        &result,
+    )
+    .await;
+
+    Ok(Json(result))
+}
@@ -0,0 +1,225 @@
+use axum::{
+    extract::{Path, State},
+    routing::{get, put},
+    Json, Router,
+};
+use chrono::NaiveDate;
+use serde::Deserialize;
+use sqlx::SqlitePool;
+
+use crate::{
+    auth::ProjectMember,
+    error::{AppError, AppResult, ErrorCode},
+    models::{CreatePoolValuation, EntityType, PoolValuation, ValuationKind},
+    services::HistoryService,
+    AppState,
+};
+
+#[derive(Deserialize)]
+struct ValuationPath {
+    valuation_id: i64,
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_valuations).post(create_valuation))
+        .route(
+            "/{valuation_id}",
+            put(update_valuation).delete(delete_valuation),
+        )
+}
+
+async fn find_valuation(
+    pool: &SqlitePool,
+    project_id: i64,
+    valuation_id: i64,
+) -> AppResult<PoolValuation> {
+    sqlx::query_as("SELECT * FROM pool_valuations WHERE id = ? AND project_id = ?")
+        .bind(valuation_id)
+        .bind(project_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| AppError::not_found(ErrorCode::PoolValuationNotFound))
+}
+
+/// Check a submitted valuation: a pool of the project, a valid date, a known
+/// kind, a value that is not negative or a gain that is not zero
+async fn validate_valuation(
+    pool: &SqlitePool,
+    project_id: i64,
+    input: &CreatePoolValuation,
+) -> AppResult<ValuationKind> {
+    let pool_exists: Option<i64> = sqlx::query_scalar(
+        "SELECT id FROM participants WHERE id = ? AND project_id = ? AND account_type = 'pool'",
+    )
+    .bind(input.pool_id)
+    .bind(project_id)
+    .fetch_optional(pool)
+    .await?;
+    if pool_exists.is_none() {
+        return Err(AppError::bad_request(ErrorCode::InvalidParticipant));
+    }
+
+    NaiveDate::parse_from_str(input.valuation_date.as_str(), "%Y-%m-%d")
+        .map_err(|_| AppError::bad_request(ErrorCode::InvalidDateFormat))?;
+
+    let kind = input
+        .kind
+        .as_ref()
+        .map_or(Some(ValuationKind::Value), |kind| {
+            ValuationKind::parse(kind.as_str())
+        })
+        .ok_or_else(|| AppError::bad_request(ErrorCode::InvalidPoolValuation))?;
+    let valid = match kind {
+        ValuationKind::Value => !input.amount.is_negative(),
+        ValuationKind::Interest => !input.amount.is_zero(),
+    };
+    if !valid {
+        return Err(AppError::bad_request(ErrorCode::InvalidPoolValuation));
+    }
+
+    Ok(kind)
+}
+
+/// GET /projects/{id}/pool-valuations
+async fn list_valuations(
+    member: ProjectMember,
+    State(pool): State<SqlitePool>,
+) -> AppResult<Json<Vec<PoolValuation>>> {
+    let valuations: Vec<PoolValuation> = sqlx::query_as(
+        "SELECT * FROM pool_valuations WHERE project_id = ?
+         ORDER BY pool_id, valuation_date, id",
+    )
+    .bind(member.project_id)
+    .fetch_all(&pool)
+    .await?;
+
+    Ok(Json(valuations))
+}
+
+/// POST /projects/{id}/pool-valuations
+/// Record what a pool is worth, or gained, on a date; unitized pools share
+/// the difference among their owners by units
+async fn create_valuation(
+    member: ProjectMember,
+    State(pool): State<SqlitePool>,
+    Json(input): Json<CreatePoolValuation>,
+) -> AppResult<Json<PoolValuation>> {
+    if !member.can_edit() {
+        return Err(AppError::forbidden(ErrorCode::EditorRequired));
+    }
+
+    let kind = validate_valuation(&pool, member.project_id, &input).await?;
+
+    let result = sqlx::query(
+        "INSERT INTO pool_valuations (project_id, pool_id, valuation_date, kind, amount, description)
+         VALUES (?, ?, ?, ?, ?, ?)",
+    )
+    .bind(member.project_id)
+    .bind(input.pool_id)
+    .bind(input.valuation_date.as_str())
+    .bind(kind.as_str())
+    .bind(input.amount)
+    .bind(input.description.as_ref().map_or("", |d| d.as_str()))
+    .execute(&pool)
+    .await?;
+
+    let valuation = find_valuation(&pool, member.project_id, result.last_insert_rowid()).await?;
+
+    // Log the creation to history
+    let correlation_id = HistoryService::new_correlation_id();
+    let _ = HistoryService::log_create(
+        &pool,
+        &correlation_id,
+        member.user_id,
+        member.project_id,
+        EntityType::PoolValuation,
+        valuation.id,
+        &valuation,
+    )
+    .await;
+
+    Ok(Json(valuation))
+}
+
+/// PUT /projects/{id}/pool-valuations/{valuation_id}
+async fn update_valuation(
+    Path(path): Path<ValuationPath>,
+    member: ProjectMember,
+    State(pool): State<SqlitePool>,
+    Json(input): Json<CreatePoolValuation>,
+) -> AppResult<Json<PoolValuation>> {
+    if !member.can_edit() {
+        return Err(AppError::forbidden(ErrorCode::EditorRequired));
+    }
+
+    let before = find_valuation(&pool, member.project_id, path.valuation_id).await?;
+    let kind = validate_valuation(&pool, member.project_id, &input).await?;
+
+    sqlx::query(
+        "UPDATE pool_valuations
+         SET pool_id = ?, valuation_date = ?, kind = ?, amount = ?, description = ?
+         WHERE id = ?",
+    )
+    .bind(input.pool_id)
+    .bind(input.valuation_date.as_str())
+    .bind(kind.as_str())
+    .bind(input.amount)
+    .bind(input.description.as_ref().map_or("", |d| d.as_str()))
+    .bind(path.valuation_id)
+    .execute(&pool)
+    .await?;
+
+    let valuation = find_valuation(&pool, member.project_id, path.valuation_id).await?;
+
+    // Log the update to history
+    let correlation_id = HistoryService::new_correlation_id();
+    let _ = HistoryService::log_update(
+        &pool,
+        crate::services::history::LogUpdateParams {
+            correlation_id: &correlation_id,
+            actor_user_id: member.user_id,
+            project_id: member.project_id,
+            entity_type: EntityType::PoolValuation,
+            entity_id: valuation.id,
+            before: &before,
+            after: &valuation,
+        },
+    )
+    .await;
+
+    Ok(Json(valuation))
+}
+
+/// DELETE /projects/{id}/pool-valuations/{valuation_id}
+async fn delete_valuation(
+    Path(path): Path<ValuationPath>,
+    member: ProjectMember,
+    State(pool): State<SqlitePool>,
+) -> AppResult<Json<serde_json::Value>> {
+    if !member.can_edit() {
+        return Err(AppError::forbidden(ErrorCode::EditorRequired));
+    }
+
+    let existing = find_valuation(&pool, member.project_id, path.valuation_id).await?;
+
+    sqlx::query("DELETE FROM pool_valuations WHERE id = ?")
+        .bind(path.valuation_id)
+        .execute(&pool)
+        .await?;
+
+    // Log the deletion to history
+    let correlation_id = HistoryService::new_correlation_id();
+    let _ = HistoryService::log_delete(
+        &pool,
+        &correlation_id,
+        member.user_id,
+        member.project_id,
+        EntityType::PoolValuation,
+        path.valuation_id,
+        &existing,
+    )
+    .await;
+
+    Ok(Json(serde_json::json!({ "deleted": true })))
+}
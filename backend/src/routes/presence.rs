@@ -0,0 +1,214 @@
+use axum::{
+    extract::{Path, State},
+    routing::{get, put},
+    Json, Router,
+};
+use chrono::NaiveDate;
+use serde::Deserialize;
+use sqlx::SqlitePool;
+
+use crate::{
+    auth::ProjectMember,
+    error::{AppError, AppResult, ErrorCode},
+    models::{CreatePresencePeriod, EntityType, PresencePeriod},
+    services::HistoryService,
+    AppState,
+};
+
+#[derive(Deserialize)]
+struct PresencePath {
+    presence_id: i64,
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_presence).post(create_presence))
+        .route(
+            "/{presence_id}",
+            put(update_presence).delete(delete_presence),
+        )
+}
+
+async fn find_presence(
+    pool: &SqlitePool,
+    project_id: i64,
+    presence_id: i64,
+) -> AppResult<PresencePeriod> {
+    sqlx::query_as("SELECT * FROM participant_presence WHERE id = ? AND project_id = ?")
+        .bind(presence_id)
+        .bind(project_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| AppError::not_found(ErrorCode::PresenceNotFound))
+}
+
+/// Check a submitted period: a participant of the project and at least one
+/// valid bound, the start not after the end
+async fn validate_period(
+    pool: &SqlitePool,
+    project_id: i64,
+    input: &CreatePresencePeriod,
+) -> AppResult<()> {
+    let participant_exists: Option<i64> =
+        sqlx::query_scalar("SELECT id FROM participants WHERE id = ? AND project_id = ?")
+            .bind(input.participant_id)
+            .bind(project_id)
+            .fetch_optional(pool)
+            .await?;
+    if participant_exists.is_none() {
+        return Err(AppError::bad_request(ErrorCode::InvalidParticipant));
+    }
+
+    let parse = |date: Option<&str>| {
+        date.map(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d"))
+            .transpose()
+            .map_err(|_| AppError::bad_request(ErrorCode::InvalidDateFormat))
+    };
+    let from = parse(input.from_date.as_ref().map(|d| d.as_str()))?;
+    let to = parse(input.to_date.as_ref().map(|d| d.as_str()))?;
+    match (from, to) {
+        (None, None) => Err(AppError::bad_request(ErrorCode::InvalidPresencePeriod)),
+        (Some(from), Some(to)) if from > to => {
+            Err(AppError::bad_request(ErrorCode::InvalidPresencePeriod))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// GET /projects/{id}/presence
+async fn list_presence(
+    member: ProjectMember,
+    State(pool): State<SqlitePool>,
+) -> AppResult<Json<Vec<PresencePeriod>>> {
+    let periods: Vec<PresencePeriod> = sqlx::query_as(
+        "SELECT * FROM participant_presence WHERE project_id = ?
+         ORDER BY participant_id, from_date",
+    )
+    .bind(member.project_id)
+    .fetch_all(&pool)
+    .await?;
+
+    Ok(Json(periods))
+}
+
+/// POST /projects/{id}/presence
+/// Add a presence period; once a participant has one, they only share
+/// recurring occurrences dated within their periods
+async fn create_presence(
+    member: ProjectMember,
+    State(pool): State<SqlitePool>,
+    Json(input): Json<CreatePresencePeriod>,
+) -> AppResult<Json<PresencePeriod>> {
+    if !member.can_edit() {
+        return Err(AppError::forbidden(ErrorCode::EditorRequired));
+    }
+
+    validate_period(&pool, member.project_id, &input).await?;
+
+    let result = sqlx::query(
+        "INSERT INTO participant_presence (project_id, participant_id, from_date, to_date)
+         VALUES (?, ?, ?, ?)",
+    )
+    .bind(member.project_id)
+    .bind(input.participant_id)
+    .bind(input.from_date.as_ref().map(|d| d.as_str()))
+    .bind(input.to_date.as_ref().map(|d| d.as_str()))
+    .execute(&pool)
+    .await?;
+
+    let period = find_presence(&pool, member.project_id, result.last_insert_rowid()).await?;
+
+    // Log the creation to history
+    let correlation_id = HistoryService::new_correlation_id();
+    let _ = HistoryService::log_create(
+        &pool,
+        &correlation_id,
+        member.user_id,
+        member.project_id,
+        EntityType::Presence,
+        period.id,
+        &period,
+    )
+    .await;
+
+    Ok(Json(period))
+}
+
+/// PUT /projects/{id}/presence/{presence_id}
+async fn update_presence(
+    Path(path): Path<PresencePath>,
+    member: ProjectMember,
+    State(pool): State<SqlitePool>,
+    Json(input): Json<CreatePresencePeriod>,
+) -> AppResult<Json<PresencePeriod>> {
+    if !member.can_edit() {
+        return Err(AppError::forbidden(ErrorCode::EditorRequired));
+    }
+
+    let before = find_presence(&pool, member.project_id, path.presence_id).await?;
+    validate_period(&pool, member.project_id, &input).await?;
+
+    sqlx::query(
+        "UPDATE participant_presence SET participant_id = ?, from_date = ?, to_date = ?
+         WHERE id = ?",
+    )
+    .bind(input.participant_id)
+    .bind(input.from_date.as_ref().map(|d| d.as_str()))
+    .bind(input.to_date.as_ref().map(|d| d.as_str()))
+    .bind(path.presence_id)
+    .execute(&pool)
+    .await?;
+
+    let period = find_presence(&pool, member.project_id, path.presence_id).await?;
+
+    // Log the update to history
+    let correlation_id = HistoryService::new_correlation_id();
+    let _ = HistoryService::log_update(
+        &pool,
+        crate::services::history::LogUpdateParams {
+            correlation_id: &correlation_id,
+            actor_user_id: member.user_id,
+            project_id: member.project_id,
+            entity_type: EntityType::Presence,
+            entity_id: period.id,
+            before: &before,
+            after: &period,
+        },
+    )
+    .await;
+
+    Ok(Json(period))
+}
+
+/// DELETE /projects/{id}/presence/{presence_id}
+async fn delete_presence(
+    Path(path): Path<PresencePath>,
+    member: ProjectMember,
+    State(pool): State<SqlitePool>,
+) -> AppResult<Json<serde_json::Value>> {
+    if !member.can_edit() {
+        return Err(AppError::forbidden(ErrorCode::EditorRequired));
+    }
+
+    let existing = find_presence(&pool, member.project_id, path.presence_id).await?;
+
+    sqlx::query("DELETE FROM participant_presence WHERE id = ?")
+        .bind(path.presence_id)
+        .execute(&pool)
+        .await?;
+
+    // Log the deletion to history
+    let correlation_id = HistoryService::new_correlation_id();
+    let _ = HistoryService::log_delete(
+        &pool,
+        &correlation_id,
+        member.user_id,
+        member.project_id,
+        EntityType::Presence,
+        path.presence_id,
+        &existing,
+    )
+    .await;
+
+    Ok(Json(serde_json::json!({ "deleted": true })))
+}
@@ -11,10 +11,10 @@ use crate::{
     auth::{AdminMember, AuthUser, ProjectMember},
     error::{AppError, AppResult, ErrorCode},
     models::{
-        CreateProject, EntityType, JoinProject, Project, ProjectListItem, UpdateProject,
-        UpdateProjectSettings,
+        is_valid_currency_code, CreateProject, EntityType, JoinProject, Money, Project,
+        ProjectListItem, UpdateProject, UpdateProjectSettings,
     },
-    services::{debt_calculator, HistoryService},
+    services::{self, debt_calculator, HistoryService},
     AppState,
 };
 
@@ -58,6 +58,7 @@ struct ProjectListRow {
     require_approval: bool,
     pool_warning_horizon: String,
     pending_member_access: String,
+    base_currency: String,
     role: String,
     owner_name: String,
     user_participant_id: Option<i64>,
@@ -76,6 +77,7 @@ async fn list_projects(
         "SELECT p.id, p.name, p.description, p.invite_code, p.created_by, p.created_at,
                 p.invites_enabled, p.require_approval, p.pool_warning_horizon,
                 COALESCE(p.pending_member_access, 'read_only') as pending_member_access,
+                p.base_currency,
                 pm.role,
                 COALESCE(u.display_name, u.username) as owner_name,
                 pm.participant_id as user_participant_id,
@@ -91,10 +93,13 @@ async fn list_projects(
     .await?;
 
     let mut items = Vec::with_capacity(rows.len());
+    let today = chrono::Utc::now().date_naive();
+    let today_str = today.format("%Y-%m-%d").to_string();
 
     for row in rows {
-        let mut user_balance: Option<f64> = None;
+        let mut user_balance: Option<Money> = None;
         let mut user_pools: Vec<PoolSummary> = Vec::new();
+        let mut warnings = Vec::new();
 
         // Only calculate debt summary if user has access (not pending with 'none' access)
         let has_data_access = row.member_status == "active"
@@ -104,10 +109,11 @@ async fn list_projects(
         // If user has a participant in this project and has data access, get their debt summary
         if let Some(participant_id) = row.user_participant_id {
             if has_data_access {
-                // Use the debt calculator to get accurate balances (including recurring payments)
+                // Use the cached ledger totals to get accurate balances (including recurring payments)
                 // Exclude drafts from project list summary
                 if let Ok(debt_summary) =
-                    debt_calculator::calculate_debts(&pool, row.id, false).await
+                    debt_calculator::calculate_totals_at_date(&pool, row.id, &today_str, false)
+                        .await
                 {
                     // Find user's balance
                     if let Some(balance) = debt_summary
@@ -125,7 +131,7 @@ async fn list_projects(
                             .iter()
                             .find(|e| e.participant_id == participant_id)
                         {
-                            if entry.ownership.abs() >= 0.01 {
+                            if !entry.ownership.is_zero() {
                                 user_pools.push(PoolSummary {
                                     pool_name: pool_ownership.pool_name.clone(),
                                     ownership: entry.ownership,
@@ -137,6 +143,12 @@ async fn list_projects(
             }
         }
 
+        if has_data_access {
+            warnings = services::evaluate_warnings(&pool, row.id, today)
+                .await
+                .unwrap_or_default();
+        }
+
         items.push(ProjectListItem {
             id: row.id,
             name: row.name,
@@ -148,11 +160,13 @@ async fn list_projects(
             require_approval: row.require_approval,
             pool_warning_horizon: row.pool_warning_horizon,
             pending_member_access: row.pending_member_access,
+            base_currency: row.base_currency,
             role: row.role,
             owner_name: row.owner_name,
             user_balance,
             user_pools,
             member_status: row.member_status,
+            warnings,
         });
     }
 
@@ -180,6 +194,15 @@ async fn create_project(
         }
     }
 
+    let base_currency = input
+        .base_currency
+        .as_ref()
+        .map(|c| c.as_str())
+        .unwrap_or("EUR");
+    if !is_valid_currency_code(base_currency) {
+        return Err(AppError::bad_request(ErrorCode::InvalidCurrency));
+    }
+
     let invite_code = generate_invite_code();
 
     // Start transaction
@@ -187,12 +210,13 @@ async fn create_project(
 
     // Create project
     let result = sqlx::query(
-        "INSERT INTO projects (name, description, invite_code, created_by) VALUES (?, ?, ?, ?)",
+        "INSERT INTO projects (name, description, invite_code, created_by, base_currency) VALUES (?, ?, ?, ?, ?)",
     )
     .bind(input.name.as_str())
     .bind(input.description.as_ref().map(|s| s.as_str()))
     .bind(&invite_code)
     .bind(auth.user_id)
+    .bind(base_currency)
     .execute(&mut *tx)
     .await?;
 
@@ -367,6 +391,20 @@ async fn update_project_settings(
         }
     }
 
+    // Validate base_currency if provided
+    if let Some(ref base_currency) = input.base_currency {
+        if !is_valid_currency_code(base_currency) {
+            return Err(AppError::bad_request(ErrorCode::InvalidCurrency));
+        }
+        // Amounts without a currency of their own are in the base currency,
+        // so it is fixed once any are recorded
+        if base_currency.as_str() != before.base_currency
+            && has_base_currency_amounts(&pool, member.project_id).await?
+        {
+            return Err(AppError::conflict(ErrorCode::BaseCurrencyInUse));
+        }
+    }
+
     // Build dynamic update - we need to track bool and string binds separately
     // since they go into different bind positions
     let mut updates = Vec::new();
@@ -385,6 +423,10 @@ async fn update_project_settings(
         updates.push(("pending_member_access = ?", "string"));
         string_binds.push(pending_member_access.to_string());
     }
+    if let Some(ref base_currency) = input.base_currency {
+        updates.push(("base_currency = ?", "string"));
+        string_binds.push(base_currency.to_string());
+    }
 
     if updates.is_empty() {
         return Err(AppError::bad_request(ErrorCode::NoFieldsToUpdate));
@@ -618,3 +660,23 @@ async fn leave_project(
         "message": message
     })))
 }
+
+/// Whether the project records any amount in its base currency: a payment,
+/// a fixed pool fee, a pool valuation or statement, or a contribution rule
+async fn has_base_currency_amounts(pool: &SqlitePool, project_id: i64) -> AppResult<bool> {
+    let exists: bool = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM payments WHERE project_id = ?)
+             OR EXISTS (SELECT 1 FROM pool_rules WHERE project_id = ? AND amount IS NOT NULL)
+             OR EXISTS (SELECT 1 FROM pool_valuations WHERE project_id = ?)
+             OR EXISTS (SELECT 1 FROM pool_statements WHERE project_id = ?)
+             OR EXISTS (SELECT 1 FROM contribution_rules WHERE project_id = ?)",
+    )
+    .bind(project_id)
+    .bind(project_id)
+    .bind(project_id)
+    .bind(project_id)
+    .bind(project_id)
+    .fetch_one(pool)
+    .await?;
+    Ok(exists)
+}
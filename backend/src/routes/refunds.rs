@@ -0,0 +1,279 @@
+use axum::{
+    extract::{Path, State},
+    routing::{get, put},
+    Json, Router,
+};
+use chrono::NaiveDate;
+use serde::Deserialize;
+use sqlx::{SqliteExecutor, SqlitePool};
+
+use crate::{
+    auth::ProjectMember,
+    error::{AppError, AppResult, ErrorCode},
+    models::{CreateRefund, EntityType, Money, Payment, Refund},
+    services::{amount_in_currency, HistoryService},
+    AppState,
+};
+
+#[derive(Deserialize)]
+struct PaymentPath {
+    payment_id: i64,
+}
+
+#[derive(Deserialize)]
+struct RefundPath {
+    payment_id: i64,
+    refund_id: i64,
+}
+
+/// Nested under /projects/{id}/payments/{payment_id}/refunds
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_refunds).post(create_refund))
+        .route("/{refund_id}", put(update_refund).delete(delete_refund))
+}
+
+/// Refunds of a payment, oldest first
+pub(crate) async fn payment_refunds(
+    conn: impl SqliteExecutor<'_>,
+    payment_id: i64,
+) -> AppResult<Vec<Refund>> {
+    Ok(
+        sqlx::query_as("SELECT * FROM refunds WHERE payment_id = ? ORDER BY refund_date, id")
+            .bind(payment_id)
+            .fetch_all(conn)
+            .await?,
+    )
+}
+
+/// Payment of the project, or PaymentNotFound
+async fn find_payment(pool: &SqlitePool, project_id: i64, payment_id: i64) -> AppResult<Payment> {
+    sqlx::query_as("SELECT * FROM payments WHERE id = ? AND project_id = ?")
+        .bind(payment_id)
+        .bind(project_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| AppError::not_found(ErrorCode::PaymentNotFound))
+}
+
+async fn find_refund(pool: &SqlitePool, payment_id: i64, refund_id: i64) -> AppResult<Refund> {
+    sqlx::query_as("SELECT * FROM refunds WHERE id = ? AND payment_id = ?")
+        .bind(refund_id)
+        .bind(payment_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| AppError::not_found(ErrorCode::RefundNotFound))
+}
+
+/// Check a refund against its payment and the payment's other refunds;
+/// returns the date and the overriding weights as stored
+async fn validate_refund(
+    pool: &SqlitePool,
+    project_id: i64,
+    payment: &Payment,
+    input: &CreateRefund,
+    other_refunds: &[Refund],
+) -> AppResult<(String, Option<String>)> {
+    // Only money spent outside the project can come back
+    if payment.receiver_account_id.is_some() || payment.payer_id.is_none() {
+        return Err(AppError::bad_request(ErrorCode::PaymentNotRefundable));
+    }
+    if !input.amount.is_positive() {
+        return Err(AppError::bad_request(ErrorCode::AmountMustBePositive));
+    }
+    amount_in_currency(pool, project_id, input.amount, payment.currency.as_deref()).await?;
+    let refunded: Money = other_refunds.iter().map(|r| r.amount).sum();
+    if refunded + input.amount > payment.amount {
+        return Err(AppError::bad_request(ErrorCode::RefundExceedsPayment));
+    }
+
+    let refund_date = match &input.refund_date {
+        Some(date) => {
+            NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                .map_err(|_| AppError::bad_request(ErrorCode::InvalidDateFormat))?;
+            date.clone()
+        }
+        None => chrono::Utc::now()
+            .date_naive()
+            .format("%Y-%m-%d")
+            .to_string(),
+    };
+
+    let weights = match &input.contributions {
+        Some(contributions) => {
+            if contributions.is_empty() {
+                return Err(AppError::bad_request(ErrorCode::ContributionRequired));
+            }
+            for contrib in contributions {
+                let participant_exists: Option<i64> = sqlx::query_scalar(
+                    "SELECT id FROM participants WHERE id = ? AND project_id = ?",
+                )
+                .bind(contrib.participant_id)
+                .bind(project_id)
+                .fetch_optional(pool)
+                .await?;
+
+                if participant_exists.is_none() {
+                    return Err(AppError::bad_request(ErrorCode::InvalidParticipant));
+                }
+            }
+            let total_weight: f64 = contributions.iter().map(|c| c.weight).sum();
+            if total_weight <= 0.0 || contributions.iter().any(|c| c.weight < 0.0) {
+                return Err(AppError::bad_request(ErrorCode::TotalWeightMustBePositive));
+            }
+            Some(
+                serde_json::to_string(contributions)
+                    .map_err(|e| AppError::Internal(e.to_string()))?,
+            )
+        }
+        None => None,
+    };
+
+    Ok((refund_date, weights))
+}
+
+/// GET /projects/{id}/payments/{payment_id}/refunds
+async fn list_refunds(
+    Path(path): Path<PaymentPath>,
+    member: ProjectMember,
+    State(pool): State<SqlitePool>,
+) -> AppResult<Json<Vec<Refund>>> {
+    find_payment(&pool, member.project_id, path.payment_id).await?;
+
+    Ok(Json(payment_refunds(&pool, path.payment_id).await?))
+}
+
+/// POST /projects/{id}/payments/{payment_id}/refunds
+/// Record money given back on an expense, on its own date
+async fn create_refund(
+    Path(path): Path<PaymentPath>,
+    member: ProjectMember,
+    State(pool): State<SqlitePool>,
+    Json(input): Json<CreateRefund>,
+) -> AppResult<Json<Refund>> {
+    if !member.can_edit() {
+        return Err(AppError::forbidden(ErrorCode::EditorRequired));
+    }
+
+    let payment = find_payment(&pool, member.project_id, path.payment_id).await?;
+    let refunds = payment_refunds(&pool, path.payment_id).await?;
+    let (refund_date, weights) =
+        validate_refund(&pool, member.project_id, &payment, &input, &refunds).await?;
+
+    let result = sqlx::query(
+        "INSERT INTO refunds (payment_id, amount, refund_date, description, weights)
+         VALUES (?, ?, ?, ?, ?)",
+    )
+    .bind(path.payment_id)
+    .bind(input.amount)
+    .bind(&refund_date)
+    .bind(input.description.as_deref().unwrap_or(""))
+    .bind(&weights)
+    .execute(&pool)
+    .await?;
+
+    let refund = find_refund(&pool, path.payment_id, result.last_insert_rowid()).await?;
+
+    // Log the creation to history
+    let correlation_id = HistoryService::new_correlation_id();
+    let _ = HistoryService::log_create(
+        &pool,
+        &correlation_id,
+        member.user_id,
+        member.project_id,
+        EntityType::Refund,
+        refund.id,
+        &refund,
+    )
+    .await;
+
+    Ok(Json(refund))
+}
+
+/// PUT /projects/{id}/payments/{payment_id}/refunds/{refund_id}
+async fn update_refund(
+    Path(path): Path<RefundPath>,
+    member: ProjectMember,
+    State(pool): State<SqlitePool>,
+    Json(input): Json<CreateRefund>,
+) -> AppResult<Json<Refund>> {
+    if !member.can_edit() {
+        return Err(AppError::forbidden(ErrorCode::EditorRequired));
+    }
+
+    let payment = find_payment(&pool, member.project_id, path.payment_id).await?;
+    let before = find_refund(&pool, path.payment_id, path.refund_id).await?;
+    let others: Vec<Refund> = payment_refunds(&pool, path.payment_id)
+        .await?
+        .into_iter()
+        .filter(|r| r.id != before.id)
+        .collect();
+    let (refund_date, weights) =
+        validate_refund(&pool, member.project_id, &payment, &input, &others).await?;
+
+    sqlx::query(
+        "UPDATE refunds SET amount = ?, refund_date = ?, description = ?, weights = ?
+         WHERE id = ?",
+    )
+    .bind(input.amount)
+    .bind(&refund_date)
+    .bind(input.description.as_deref().unwrap_or(""))
+    .bind(&weights)
+    .bind(before.id)
+    .execute(&pool)
+    .await?;
+
+    let refund = find_refund(&pool, path.payment_id, before.id).await?;
+
+    // Log the update to history
+    let correlation_id = HistoryService::new_correlation_id();
+    let _ = HistoryService::log_update(
+        &pool,
+        crate::services::history::LogUpdateParams {
+            correlation_id: &correlation_id,
+            actor_user_id: member.user_id,
+            project_id: member.project_id,
+            entity_type: EntityType::Refund,
+            entity_id: refund.id,
+            before: &before,
+            after: &refund,
+        },
+    )
+    .await;
+
+    Ok(Json(refund))
+}
+
+/// DELETE /projects/{id}/payments/{payment_id}/refunds/{refund_id}
+async fn delete_refund(
+    Path(path): Path<RefundPath>,
+    member: ProjectMember,
+    State(pool): State<SqlitePool>,
+) -> AppResult<Json<serde_json::Value>> {
+    if !member.can_edit() {
+        return Err(AppError::forbidden(ErrorCode::EditorRequired));
+    }
+
+    find_payment(&pool, member.project_id, path.payment_id).await?;
+    let existing = find_refund(&pool, path.payment_id, path.refund_id).await?;
+
+    sqlx::query("DELETE FROM refunds WHERE id = ?")
+        .bind(existing.id)
+        .execute(&pool)
+        .await?;
+
+    // Log the deletion to history
+    let correlation_id = HistoryService::new_correlation_id();
+    let _ = HistoryService::log_delete(
+        &pool,
+        &correlation_id,
+        member.user_id,
+        member.project_id,
+        EntityType::Refund,
+        existing.id,
+        &existing,
+    )
+    .await;
+
+    Ok(Json(serde_json::json!({ "deleted": true })))
+}
@@ -0,0 +1,22 @@
+use axum::{extract::State, routing::get, Json, Router};
+use sqlx::SqlitePool;
+
+use crate::{
+    auth::ProjectMember, error::AppResult, models::PoolWarning, services::evaluate_warnings,
+    AppState,
+};
+
+pub fn router() -> Router<AppState> {
+    Router::new().route("/", get(list_warnings))
+}
+
+/// GET /projects/{id}/warnings
+/// Pool warnings due within each pool's configured horizons, as of today
+async fn list_warnings(
+    member: ProjectMember,
+    State(pool): State<SqlitePool>,
+) -> AppResult<Json<Vec<PoolWarning>>> {
+    let today = chrono::Utc::now().date_naive();
+    let warnings = evaluate_warnings(&pool, member.project_id, today).await?;
+    Ok(Json(warnings))
+}
@@ -0,0 +1,445 @@
+use axum::{
+    extract::{Path, State},
+    routing::get,
+    Json, Router,
+};
+use chrono::NaiveDate;
+use serde::Deserialize;
+use sqlx::{SqliteConnection, SqlitePool};
+
+use crate::{
+    auth::ProjectMember,
+    error::{AppError, AppResult, ErrorCode},
+    models::{
+        CreateContribution, CreateWeightProfile, EntityType, Payment, UpdateWeightProfile,
+        WeightProfile, WeightProfileVersion, WeightProfileWithVersions,
+    },
+    routes::payments::with_contributions,
+    services::{history::LogUpdateParams, HistoryService},
+    AppState,
+};
+
+#[derive(Deserialize)]
+struct ProfilePath {
+    profile_id: i64,
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_profiles).post(create_profile))
+        .route(
+            "/{profile_id}",
+            get(get_profile).put(update_profile).delete(delete_profile),
+        )
+}
+
+async fn find_profile(
+    conn: &mut SqliteConnection,
+    project_id: i64,
+    profile_id: i64,
+) -> AppResult<WeightProfileWithVersions> {
+    let profile: WeightProfile =
+        sqlx::query_as("SELECT * FROM weight_profiles WHERE id = ? AND project_id = ?")
+            .bind(profile_id)
+            .bind(project_id)
+            .fetch_optional(&mut *conn)
+            .await?
+            .ok_or_else(|| AppError::not_found(ErrorCode::WeightProfileNotFound))?;
+
+    let versions: Vec<WeightProfileVersion> = sqlx::query_as(
+        "SELECT * FROM weight_profile_versions WHERE profile_id = ?
+         ORDER BY effective_from IS NOT NULL, effective_from",
+    )
+    .bind(profile_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(WeightProfileWithVersions { profile, versions })
+}
+
+/// Weights of a profile in effect on `date` (a payment date), as the
+/// contributions of a payment referencing it
+pub(crate) async fn profile_contributions(
+    pool: &SqlitePool,
+    project_id: i64,
+    profile_id: i64,
+    date: &str,
+) -> AppResult<Vec<CreateContribution>> {
+    let profile = find_profile(&mut *pool.acquire().await?, project_id, profile_id).await?;
+    Ok(version_contributions(&profile, date))
+}
+
+fn version_contributions(
+    profile: &WeightProfileWithVersions,
+    date: &str,
+) -> Vec<CreateContribution> {
+    let date = date.get(..10).unwrap_or(date);
+    profile
+        .versions
+        .iter()
+        .rev()
+        .find(|v| v.effective_from.as_deref().is_none_or(|from| from <= date))
+        .map(WeightProfileVersion::contributions)
+        .unwrap_or_default()
+}
+
+async fn validate_weights(
+    pool: &SqlitePool,
+    project_id: i64,
+    contributions: &[CreateContribution],
+) -> AppResult<String> {
+    if contributions.is_empty() {
+        return Err(AppError::bad_request(ErrorCode::ContributionRequired));
+    }
+    for contrib in contributions {
+        let participant_exists: Option<i64> =
+            sqlx::query_scalar("SELECT id FROM participants WHERE id = ? AND project_id = ?")
+                .bind(contrib.participant_id)
+                .bind(project_id)
+                .fetch_optional(pool)
+                .await?;
+
+        if participant_exists.is_none() {
+            return Err(AppError::bad_request(ErrorCode::InvalidParticipant));
+        }
+    }
+    let total_weight: f64 = contributions.iter().map(|c| c.weight).sum();
+    if total_weight <= 0.0 || contributions.iter().any(|c| c.weight < 0.0) {
+        return Err(AppError::bad_request(ErrorCode::TotalWeightMustBePositive));
+    }
+    serde_json::to_string(contributions).map_err(|e| AppError::Internal(e.to_string()))
+}
+
+async fn check_name_available(
+    pool: &SqlitePool,
+    project_id: i64,
+    name: &str,
+    profile_id: Option<i64>,
+) -> AppResult<()> {
+    let existing: Option<i64> =
+        sqlx::query_scalar("SELECT id FROM weight_profiles WHERE project_id = ? AND name = ?")
+            .bind(project_id)
+            .bind(name)
+            .fetch_optional(pool)
+            .await?;
+
+    match existing {
+        Some(id) if Some(id) != profile_id => {
+            Err(AppError::bad_request(ErrorCode::WeightProfileNameExists))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Re-split the payments using the profile dated on or after `from` (all of
+/// them without a date), so their contributions follow the version in
+/// effect on their date. Each re-split payment is logged as an update under
+/// `correlation_id`.
+async fn resplit_payments(
+    conn: &mut SqliteConnection,
+    member: &ProjectMember,
+    profile: &WeightProfileWithVersions,
+    from: Option<&str>,
+    correlation_id: &str,
+) -> AppResult<()> {
+    let payments: Vec<Payment> = sqlx::query_as(
+        "SELECT * FROM payments WHERE project_id = ? AND weight_profile_id = ?
+         AND (? IS NULL OR substr(payment_date, 1, 10) >= ?)",
+    )
+    .bind(member.project_id)
+    .bind(profile.profile.id)
+    .bind(from)
+    .bind(from)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    for payment in payments {
+        let before = with_contributions(conn, payment.clone()).await?;
+        let contributions = version_contributions(profile, &payment.payment_date);
+        let weights: Vec<(i64, f64)> = contributions
+            .iter()
+            .map(|c| (c.participant_id, c.weight))
+            .collect();
+        let shares = payment.amount.allocate(&weights);
+
+        sqlx::query("DELETE FROM contributions WHERE payment_id = ?")
+            .bind(payment.id)
+            .execute(&mut *conn)
+            .await?;
+        for (contrib, share_amount) in contributions.iter().zip(shares) {
+            sqlx::query(
+                "INSERT INTO contributions (participant_id, payment_id, amount, weight) VALUES (?, ?, ?, ?)",
+            )
+            .bind(contrib.participant_id)
+            .bind(payment.id)
+            .bind(share_amount)
+            .bind(contrib.weight)
+            .execute(&mut *conn)
+            .await?;
+        }
+
+        let after = with_contributions(conn, payment).await?;
+        HistoryService::log_update(
+            &mut *conn,
+            LogUpdateParams {
+                correlation_id,
+                actor_user_id: member.user_id,
+                project_id: member.project_id,
+                entity_type: EntityType::Payment,
+                entity_id: after.payment.id,
+                before: &before,
+                after: &after,
+            },
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+/// GET /projects/{id}/weight-profiles
+async fn list_profiles(
+    member: ProjectMember,
+    State(pool): State<SqlitePool>,
+) -> AppResult<Json<Vec<WeightProfileWithVersions>>> {
+    let ids: Vec<i64> =
+        sqlx::query_scalar("SELECT id FROM weight_profiles WHERE project_id = ? ORDER BY name")
+            .bind(member.project_id)
+            .fetch_all(&pool)
+            .await?;
+
+    let mut conn = pool.acquire().await?;
+    let mut profiles = Vec::new();
+    for id in ids {
+        profiles.push(find_profile(&mut conn, member.project_id, id).await?);
+    }
+
+    Ok(Json(profiles))
+}
+
+/// GET /projects/{id}/weight-profiles/{profile_id}
+async fn get_profile(
+    Path(path): Path<ProfilePath>,
+    member: ProjectMember,
+    State(pool): State<SqlitePool>,
+) -> AppResult<Json<WeightProfileWithVersions>> {
+    Ok(Json(
+        find_profile(
+            &mut *pool.acquire().await?,
+            member.project_id,
+            path.profile_id,
+        )
+        .await?,
+    ))
+}
+
+/// POST /projects/{id}/weight-profiles
+async fn create_profile(
+    member: ProjectMember,
+    State(pool): State<SqlitePool>,
+    Json(input): Json<CreateWeightProfile>,
+) -> AppResult<Json<WeightProfileWithVersions>> {
+    if !member.can_edit() {
+        return Err(AppError::forbidden(ErrorCode::EditorRequired));
+    }
+
+    let weights = validate_weights(&pool, member.project_id, &input.contributions).await?;
+    check_name_available(&pool, member.project_id, input.name.as_str(), None).await?;
+
+    let result = sqlx::query("INSERT INTO weight_profiles (project_id, name) VALUES (?, ?)")
+        .bind(member.project_id)
+        .bind(input.name.as_str())
+        .execute(&pool)
+        .await?;
+    let profile_id = result.last_insert_rowid();
+
+    sqlx::query("INSERT INTO weight_profile_versions (profile_id, weights) VALUES (?, ?)")
+        .bind(profile_id)
+        .bind(&weights)
+        .execute(&pool)
+        .await?;
+
+    let profile = find_profile(&mut *pool.acquire().await?, member.project_id, profile_id).await?;
+
+    // Log the creation to history
+    let correlation_id = HistoryService::new_correlation_id();
+    let _ = HistoryService::log_create(
+        &pool,
+        &correlation_id,
+        member.user_id,
+        member.project_id,
+        EntityType::WeightProfile,
+        profile_id,
+        &profile,
+    )
+    .await;
+
+    Ok(Json(profile))
+}
+
+/// PUT /projects/{id}/weight-profiles/{profile_id}
+/// Rename the profile and/or set its weights from `effective_from` on; without
+/// a date the first version changes, i.e. every occurrence not covered by a
+/// later version
+async fn update_profile(
+    Path(path): Path<ProfilePath>,
+    member: ProjectMember,
+    State(pool): State<SqlitePool>,
+    Json(input): Json<UpdateWeightProfile>,
+) -> AppResult<Json<WeightProfileWithVersions>> {
+    if !member.can_edit() {
+        return Err(AppError::forbidden(ErrorCode::EditorRequired));
+    }
+
+    let before = find_profile(
+        &mut *pool.acquire().await?,
+        member.project_id,
+        path.profile_id,
+    )
+    .await?;
+
+    if let Some(ref effective_from) = input.effective_from {
+        if NaiveDate::parse_from_str(effective_from, "%Y-%m-%d").is_err() {
+            return Err(AppError::bad_request(ErrorCode::InvalidDateFormat));
+        }
+        if input.contributions.is_none() {
+            return Err(AppError::bad_request(ErrorCode::ContributionRequired));
+        }
+    }
+    let weights = match input.contributions {
+        Some(ref contributions) => {
+            Some(validate_weights(&pool, member.project_id, contributions).await?)
+        }
+        None => None,
+    };
+    if let Some(ref name) = input.name {
+        check_name_available(
+            &pool,
+            member.project_id,
+            name.as_str(),
+            Some(path.profile_id),
+        )
+        .await?;
+    }
+
+    // The profile, the payments re-split from it and their history change
+    // together, under one correlation id
+    let correlation_id = HistoryService::new_correlation_id();
+    let mut tx = pool.begin().await?;
+
+    if let Some(ref name) = input.name {
+        sqlx::query("UPDATE weight_profiles SET name = ? WHERE id = ?")
+            .bind(name.as_str())
+            .bind(path.profile_id)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    if let Some(weights) = weights {
+        match input.effective_from {
+            Some(ref effective_from) => {
+                sqlx::query(
+                    "INSERT INTO weight_profile_versions (profile_id, effective_from, weights)
+                     VALUES (?, ?, ?)
+                     ON CONFLICT(profile_id, effective_from) DO UPDATE SET weights = excluded.weights",
+                )
+                .bind(path.profile_id)
+                .bind(effective_from)
+                .bind(&weights)
+                .execute(&mut *tx)
+                .await?;
+            }
+            None => {
+                sqlx::query(
+                    "UPDATE weight_profile_versions SET weights = ?
+                     WHERE profile_id = ? AND effective_from IS NULL",
+                )
+                .bind(&weights)
+                .bind(path.profile_id)
+                .execute(&mut *tx)
+                .await?;
+            }
+        }
+        let profile = find_profile(&mut tx, member.project_id, path.profile_id).await?;
+        resplit_payments(
+            &mut tx,
+            &member,
+            &profile,
+            input.effective_from.as_deref(),
+            &correlation_id,
+        )
+        .await?;
+    }
+
+    let profile = find_profile(&mut tx, member.project_id, path.profile_id).await?;
+
+    // Log the update to history, with the versions before and after
+    HistoryService::log_update(
+        &mut *tx,
+        LogUpdateParams {
+            correlation_id: &correlation_id,
+            actor_user_id: member.user_id,
+            project_id: member.project_id,
+            entity_type: EntityType::WeightProfile,
+            entity_id: path.profile_id,
+            before: &before,
+            after: &profile,
+        },
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(Json(profile))
+}
+
+/// DELETE /projects/{id}/weight-profiles/{profile_id}
+/// Only profiles no payment references can be deleted
+async fn delete_profile(
+    Path(path): Path<ProfilePath>,
+    member: ProjectMember,
+    State(pool): State<SqlitePool>,
+) -> AppResult<Json<serde_json::Value>> {
+    if !member.can_edit() {
+        return Err(AppError::forbidden(ErrorCode::EditorRequired));
+    }
+
+    let existing = find_profile(
+        &mut *pool.acquire().await?,
+        member.project_id,
+        path.profile_id,
+    )
+    .await?;
+
+    let in_use: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM payments WHERE weight_profile_id = ?")
+            .bind(path.profile_id)
+            .fetch_one(&pool)
+            .await?;
+    if in_use > 0 {
+        return Err(AppError::bad_request(ErrorCode::WeightProfileInUse));
+    }
+
+    sqlx::query("DELETE FROM weight_profile_versions WHERE profile_id = ?")
+        .bind(path.profile_id)
+        .execute(&pool)
+        .await?;
+    sqlx::query("DELETE FROM weight_profiles WHERE id = ?")
+        .bind(path.profile_id)
+        .execute(&pool)
+        .await?;
+
+    // Log the deletion to history
+    let correlation_id = HistoryService::new_correlation_id();
+    let _ = HistoryService::log_delete(
+        &pool,
+        &correlation_id,
+        member.user_id,
+        member.project_id,
+        EntityType::WeightProfile,
+        path.profile_id,
+        &existing,
+    )
+    .await;
+
+    Ok(Json(serde_json::json!({ "deleted": true })))
+}
@@ -0,0 +1,92 @@
+use chrono::{Datelike, NaiveDate, Weekday};
+use sqlx::SqlitePool;
+use std::collections::HashSet;
+
+use crate::error::AppResult;
+use crate::models::BusinessDayShift;
+
+/// Shifts never look further than this for a business day
+pub const MAX_SHIFT_DAYS: u64 = 31;
+
+/// Business days of a project: weekdays that are not project holidays
+#[derive(Debug, Default)]
+pub struct BusinessCalendar {
+    holidays: HashSet<NaiveDate>,
+}
+
+impl BusinessCalendar {
+    /// Load the holidays of a project
+    pub async fn load(pool: &SqlitePool, project_id: i64) -> AppResult<Self> {
+        let dates: Vec<String> =
+            sqlx::query_scalar("SELECT holiday_date FROM project_holidays WHERE project_id = ?")
+                .bind(project_id)
+                .fetch_all(pool)
+                .await?;
+
+        Ok(Self::from_holidays(dates.iter().filter_map(|date| {
+            NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()
+        })))
+    }
+
+    pub fn from_holidays(holidays: impl IntoIterator<Item = NaiveDate>) -> Self {
+        Self {
+            holidays: holidays.into_iter().collect(),
+        }
+    }
+
+    pub fn is_business_day(&self, date: NaiveDate) -> bool {
+        !matches!(date.weekday(), Weekday::Sat | Weekday::Sun) && !self.holidays.contains(&date)
+    }
+
+    /// `date` if it is a business day, else the next or previous one
+    pub fn shift(&self, date: NaiveDate, shift: BusinessDayShift) -> NaiveDate {
+        let mut shifted = date;
+        for _ in 0..MAX_SHIFT_DAYS {
+            if self.is_business_day(shifted) {
+                return shifted;
+            }
+            let step = match shift {
+                BusinessDayShift::Next => shifted.succ_opt(),
+                BusinessDayShift::Previous => shifted.pred_opt(),
+            };
+            match step {
+                Some(day) => shifted = day,
+                None => break,
+            }
+        }
+        date
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_shift_skips_weekends_and_holidays() {
+        // Friday 2025-07-04 is a holiday
+        let calendar = BusinessCalendar::from_holidays([date("2025-07-04")]);
+
+        assert!(calendar.is_business_day(date("2025-07-03")));
+        assert!(!calendar.is_business_day(date("2025-07-04")));
+        assert!(!calendar.is_business_day(date("2025-07-05")));
+
+        let saturday = date("2025-07-05");
+        assert_eq!(
+            calendar.shift(saturday, BusinessDayShift::Next),
+            date("2025-07-07")
+        );
+        assert_eq!(
+            calendar.shift(saturday, BusinessDayShift::Previous),
+            date("2025-07-03")
+        );
+        assert_eq!(
+            calendar.shift(date("2025-07-07"), BusinessDayShift::Previous),
+            date("2025-07-07")
+        );
+    }
+}
@@ -1,18 +1,32 @@
-use chrono::{Datelike, Months, NaiveDate};
-use serde::Serialize;
+use chrono::{Datelike, Months, NaiveDate, Weekday};
+use serde::{Deserialize, Serialize};
 use sqlx::SqlitePool;
-use std::collections::HashMap;
-
-use crate::error::AppResult;
-use crate::models::Payment;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, LazyLock};
+
+use crate::error::{AppError, AppResult, ErrorCode};
+use crate::models::{
+    currency_scale, ByDay, ContributionRule, Frequency, Money, OccurrenceException, Payment,
+    PoolRule, PoolRuleKind, PoolStatement, PoolValuation, RecurrenceRule, RecurrenceSet, Refund,
+    ValuationKind,
+};
+use crate::services::business_days::{BusinessCalendar, MAX_SHIFT_DAYS};
+use crate::services::exchange_rates::ExchangeRateTable;
+use crate::services::ledger_cache::{ledger_stamp, LedgerCache};
+use crate::services::presence::PresenceCalendar;
+use crate::services::settlement::{plan_settlements, SettlementOptions, SettlementStrategy};
+use crate::services::weight_profiles::ProfileWeights;
+
+/// Base currency assumed when a project has none recorded
+const DEFAULT_BASE_CURRENCY: &str = "EUR";
 
 #[derive(Debug, Serialize)]
 pub struct ParticipantBalance {
     pub participant_id: i64,
     pub participant_name: String,
-    pub total_paid: f64,
-    pub total_owed: f64,
-    pub net_balance: f64,
+    pub total_paid: Money,
+    pub total_owed: Money,
+    pub net_balance: Money,
 }
 
 #[derive(Debug, Serialize)]
@@ -21,7 +35,141 @@ pub struct Debt {
     pub from_participant_name: String,
     pub to_participant_id: i64,
     pub to_participant_name: String,
-    pub amount: f64,
+    pub amount: Money,
+}
+
+/// Transfers proposed by the selected settlement strategy
+#[derive(Debug, Serialize)]
+pub struct SettlementPlan {
+    /// Strategy actually used: `optimal`, or `greedy` when the group was too
+    /// large for the exact solver or no exact plan respects the exclusions
+    pub strategy: SettlementStrategy,
+    pub transfers: Vec<Debt>,
+    /// Balances left after the transfers (excluded pairs, threshold, rounding)
+    pub unsettled: Vec<UnsettledBalance>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UnsettledBalance {
+    pub participant_id: i64,
+    pub participant_name: String,
+    pub amount: Money,
+}
+
+/// Interval between the points of a balance series
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SeriesStep {
+    Day,
+    Week,
+    Month,
+}
+
+/// Most points a single balance series may contain
+pub const MAX_SERIES_POINTS: usize = 1000;
+
+#[derive(Debug, Serialize)]
+pub struct BalanceSeriesPoint {
+    pub date: String,
+    pub balances: Vec<ParticipantBalance>,
+}
+
+/// Participant balances at regular dates, as `calculate_debts_at_date` would
+/// report them on each date
+#[derive(Debug, Serialize)]
+pub struct BalanceSeries {
+    pub from: String,
+    pub to: String,
+    pub step: SeriesStep,
+    pub base_currency: String,
+    pub points: Vec<BalanceSeriesPoint>,
+}
+
+/// Pool balance and expected minimum on a date
+#[derive(Debug, Clone, Serialize)]
+pub struct ForecastPoint {
+    pub date: String,
+    pub balance: Money,
+    pub expected_minimum: Money,
+}
+
+/// Where a balance curve first dips below its expected minimum, and its lowest point
+#[derive(Debug, Serialize)]
+pub struct ForecastCurve {
+    pub points: Vec<ForecastPoint>,
+    /// First date the balance is below the expected minimum (the start date if already below)
+    pub first_below_expected_date: Option<String>,
+    /// Point with the lowest balance (earliest one on ties)
+    pub lowest: Option<ForecastPoint>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UserShareForecast {
+    pub participant_id: i64,
+    pub participant_name: String,
+    #[serde(flatten)]
+    pub curve: ForecastCurve,
+}
+
+/// Projected balance of a pool and of each participant's share, from a start
+/// date up to a horizon
+#[derive(Debug, Serialize)]
+pub struct PoolForecast {
+    pub pool_id: i64,
+    pub pool_name: String,
+    pub from: String,
+    pub horizon: String,
+    #[serde(flatten)]
+    pub curve: ForecastCurve,
+    pub users: Vec<UserShareForecast>,
+}
+
+/// A pool's bank statements, each compared with the balance on record
+#[derive(Debug, Serialize)]
+pub struct PoolReconciliation {
+    pub pool_id: i64,
+    pub pool_name: String,
+    pub statements: Vec<StatementReconciliation>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StatementReconciliation {
+    pub statement_id: i64,
+    pub statement_date: String,
+    pub statement_balance: Money,
+    pub total_balance: Money, // On record at the end of the statement date
+    pub drift: Money,         // statement_balance - total_balance
+    pub unexplained: Money,   // Drift added since the previous statement
+    // What moved the pool since the previous statement
+    pub occurrences: Vec<PaymentOccurrence>,
+    // How an adjustment would share the drift among the pool's owners
+    pub adjustment: Vec<AdjustmentShare>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AdjustmentShare {
+    pub participant_id: i64,
+    pub participant_name: String,
+    pub amount: Money, // Positive when the bank holds more than recorded
+}
+
+/// Amount as entered in a foreign currency, before conversion to the base currency
+#[derive(Debug, Clone, Serialize)]
+pub struct OriginalAmount {
+    pub currency: String,
+    pub amount: Money,
+    pub exchange_rate: f64, // Base currency units per one unit of `currency`
+}
+
+/// Occurrence of a foreign-currency payment left out of the balances because
+/// no exchange rate converts it to the base currency on its date
+#[derive(Debug, Clone, Serialize)]
+pub struct UnconvertedOccurrence {
+    pub payment_id: i64,
+    pub description: String,
+    pub occurrence_date: String,
+    pub currency: String,
+    pub amount: Money,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -29,7 +177,9 @@ pub struct PairwisePaymentBreakdown {
     pub payment_id: i64,
     pub description: String,
     pub occurrence_date: String,
-    pub amount: f64,
+    pub amount: Money,                    // In the project's base currency
+    pub original: Option<OriginalAmount>, // Set when the payment is in another currency
+    pub pool_rule_id: Option<i64>,        // Set for a fee or interest of a pool rule
 }
 
 #[derive(Debug, Serialize)]
@@ -38,9 +188,9 @@ pub struct PairwiseBalance {
     pub participant_name: String,
     pub other_participant_id: i64,
     pub other_participant_name: String,
-    pub amount_paid_for: f64, // Amount this participant paid for other
-    pub amount_owed_by: f64,  // Amount other paid for this participant
-    pub net: f64,             // paid_for - owed_by (positive = they owe you)
+    pub amount_paid_for: Money, // Amount this participant paid for other
+    pub amount_owed_by: Money,  // Amount other paid for this participant
+    pub net: Money,             // paid_for - owed_by (positive = they owe you)
     pub paid_for_breakdown: Vec<PairwisePaymentBreakdown>, // Details of what we paid for them
     pub owed_by_breakdown: Vec<PairwisePaymentBreakdown>, // Details of what they paid for us
 }
@@ -49,9 +199,13 @@ pub struct PairwiseBalance {
 pub struct PoolOwnershipEntry {
     pub participant_id: i64,
     pub participant_name: String,
-    pub contributed: f64, // Total deposited to pool
-    pub consumed: f64,    // Total share of pool-paid expenses
-    pub ownership: f64,   // contributed - consumed
+    pub contributed: Money, // Total deposited to pool
+    pub consumed: Money,    // Total share of pool-paid expenses
+    pub ownership: Money,   // contributed - consumed, or units × unit value
+    pub units: Option<f64>, // Units held in a unitized pool
+    // Deposits expected of the participant less approved withdrawals
+    pub expected_minimum: Money,
+    pub shortfall: Option<Money>, // expected_minimum - ownership (if positive)
     pub contributed_breakdown: Vec<PairwisePaymentBreakdown>, // Details of contributions
     pub consumed_breakdown: Vec<PairwisePaymentBreakdown>, // Details of consumption
 }
@@ -61,18 +215,20 @@ pub struct PoolOwnership {
     pub pool_id: i64,
     pub pool_name: String,
     pub entries: Vec<PoolOwnershipEntry>,
-    pub total_balance: f64,
+    pub total_balance: Money,
+    // Value of one unit of a unitized pool
+    pub unit_value: Option<f64>,
     // Dual ledger: expected minimum from affects_payer/receiver_expectation flags
-    pub expected_minimum: f64,
-    pub is_below_expected: bool, // total_balance < expected_minimum
-    pub shortfall: Option<f64>,  // expected_minimum - total_balance (if positive)
+    pub expected_minimum: Money,
+    pub is_below_expected: bool,  // total_balance < expected_minimum
+    pub shortfall: Option<Money>, // expected_minimum - total_balance (if positive)
 }
 
 #[derive(Debug, Clone, Serialize)]
 pub struct PaymentOccurrence {
     pub payment_id: i64,
     pub description: String,
-    pub amount: f64,
+    pub amount: Money,
     pub occurrence_date: String,
     pub payer_id: Option<i64>,
     pub is_recurring: bool,
@@ -89,6 +245,27 @@ pub struct PaymentOccurrence {
     pub affects_payer_expectation: bool,
     // affects_receiver_expectation: When receiver is a pool and true, increases receiver's expected minimum
     pub affects_receiver_expectation: bool,
+    // Set when the payment is in a foreign currency; `amount` is then converted
+    pub original: Option<OriginalAmount>,
+    // Date the series put this occurrence on, when an exception moved or changed it
+    pub original_date: Option<String>,
+    // Set on the (negative) occurrence of a refund of the payment
+    pub refund_id: Option<i64>,
+    // Set on a fee or interest of a pool rule, which has no payment (id 0)
+    pub pool_rule_id: Option<i64>,
+    // Set on a deposit expected by a contribution rule (no payment either)
+    pub contribution_rule_id: Option<i64>,
+    // Contributions replacing the payment's, when an exception changed the
+    // amount or weights (in the payment's currency)
+    #[serde(skip)]
+    split: Option<Vec<(i64, Money)>>,
+    // Part of the amount each payer paid, for a payment paid by several
+    #[serde(skip)]
+    payers: Vec<(i64, Money)>,
+    // Part of a pool-to-pool transfer taken from each owner of the sending
+    // pool (empty when nobody owned anything there)
+    #[serde(skip)]
+    pool_parts: Vec<(i64, Money)>,
 }
 
 #[derive(Debug, Serialize)]
@@ -96,10 +273,309 @@ pub struct DebtSummary {
     pub balances: Vec<ParticipantBalance>,
     pub settlements: Vec<Debt>,
     pub direct_settlements: Vec<Debt>,
+    pub settlement_plan: SettlementPlan,
     pub target_date: String,
+    pub base_currency: String,
     pub occurrences: Vec<PaymentOccurrence>,
     pub pairwise_balances: Vec<PairwiseBalance>,
     pub pool_ownerships: Vec<PoolOwnership>,
+    pub unconverted: Vec<UnconvertedOccurrence>, // Not counted in any balance
+}
+
+/// Balances and pool ownership as of a date, without breakdowns or settlements
+#[derive(Debug, Serialize)]
+pub struct LedgerTotals {
+    pub target_date: String,
+    pub base_currency: String,
+    pub balances: Vec<ParticipantBalance>,
+    pub pool_ownerships: Vec<PoolOwnership>,
+    pub unconverted: Vec<UnconvertedOccurrence>, // Not counted in any balance
+}
+
+impl PaymentOccurrence {
+    /// Occurrence of `payment` on `occurrence_date`, in the payment's own currency
+    fn new(payment: &Payment, occurrence_date: String, is_recurring: bool) -> Self {
+        Self {
+            payment_id: payment.id,
+            description: payment.description.clone(),
+            amount: payment.amount,
+            occurrence_date,
+            payer_id: payment.payer_id,
+            is_recurring,
+            receiver_account_id: payment.receiver_account_id,
+            is_final: payment.is_final,
+            affects_balance: payment.affects_balance,
+            affects_payer_expectation: payment.affects_payer_expectation,
+            affects_receiver_expectation: payment.affects_receiver_expectation,
+            original: None,
+            original_date: None,
+            refund_id: None,
+            pool_rule_id: None,
+            contribution_rule_id: None,
+            split: None,
+            payers: Vec::new(),
+            pool_parts: Vec::new(),
+        }
+    }
+
+    /// Occurrence of a pool rule on `date`: a fee the pool pays or interest
+    /// it receives, in the base currency
+    fn pool_rule(rule: &PoolRule, kind: PoolRuleKind, date: NaiveDate, amount: Money) -> Self {
+        let (payer_id, receiver_account_id) = match kind {
+            PoolRuleKind::Fee => (Some(rule.pool_id), None),
+            PoolRuleKind::Interest => (None, Some(rule.pool_id)),
+        };
+        Self {
+            payment_id: 0,
+            description: if rule.description.is_empty() {
+                kind.as_str().to_string()
+            } else {
+                rule.description.clone()
+            },
+            amount,
+            occurrence_date: date.format("%Y-%m-%d").to_string(),
+            payer_id,
+            is_recurring: true,
+            receiver_account_id,
+            is_final: true,
+            affects_balance: true,
+            affects_payer_expectation: false,
+            affects_receiver_expectation: false,
+            original: None,
+            original_date: None,
+            refund_id: None,
+            pool_rule_id: Some(rule.id),
+            contribution_rule_id: None,
+            split: None,
+            payers: Vec::new(),
+            pool_parts: Vec::new(),
+        }
+    }
+
+    /// Deposit of `amount` to the pool of a contribution rule expected from
+    /// a participant on `date`: it raises the pool's expected minimum only
+    fn contribution_rule(
+        rule: &ContributionRule,
+        participant_id: i64,
+        amount: Money,
+        date: NaiveDate,
+    ) -> Self {
+        Self {
+            payment_id: 0,
+            description: rule.description.clone(),
+            amount,
+            occurrence_date: date.format("%Y-%m-%d").to_string(),
+            payer_id: Some(participant_id),
+            is_recurring: rule.recurrence_rule.is_some(),
+            receiver_account_id: Some(rule.pool_id),
+            is_final: true,
+            affects_balance: false,
+            affects_payer_expectation: false,
+            affects_receiver_expectation: true,
+            original: None,
+            original_date: None,
+            refund_id: None,
+            pool_rule_id: None,
+            contribution_rule_id: Some(rule.id),
+            split: None,
+            payers: Vec::new(),
+            pool_parts: Vec::new(),
+        }
+    }
+
+    /// Convert the amount from `currency` to `base_currency` using the rate on the
+    /// occurrence date, keeping the original amount. Returns the occurrence as
+    /// it was when no rate covers its date.
+    fn convert_to(
+        mut self,
+        base_currency: &str,
+        currency: &str,
+        rates: &ExchangeRateTable,
+    ) -> Result<Self, UnconvertedOccurrence> {
+        let converted = parse_date(&self.occurrence_date)
+            .and_then(|date| rates.convert(self.amount, currency, base_currency, date));
+        let Some((converted, rate)) = converted else {
+            return Err(UnconvertedOccurrence {
+                payment_id: self.payment_id,
+                description: self.description,
+                occurrence_date: self.occurrence_date,
+                currency: currency.to_string(),
+                amount: self.amount,
+            });
+        };
+        self.original = Some(OriginalAmount {
+            currency: currency.to_string(),
+            amount: self.amount,
+            exchange_rate: rate,
+        });
+        self.amount = converted;
+        Ok(self)
+    }
+
+    /// Who paid the occurrence and how much: the part of each of its payers,
+    /// or the whole amount for a single payer
+    fn paid_by(&self) -> Vec<(i64, Money)> {
+        if !self.payers.is_empty() {
+            return self.payers.clone();
+        }
+        self.payer_id
+            .map(|payer_id| (payer_id, self.amount))
+            .into_iter()
+            .collect()
+    }
+
+    /// Breakdown line for the whole occurrence amount
+    fn breakdown(&self) -> PairwisePaymentBreakdown {
+        PairwisePaymentBreakdown {
+            payment_id: self.payment_id,
+            description: self.description.clone(),
+            occurrence_date: self.occurrence_date.clone(),
+            amount: self.amount,
+            original: self.original.clone(),
+            pool_rule_id: self.pool_rule_id,
+        }
+    }
+
+    /// Breakdown line for `part` of the occurrence amount
+    fn part_breakdown(&self, part: Money) -> PairwisePaymentBreakdown {
+        let original = self.original.clone().map(|original| OriginalAmount {
+            amount: original
+                .amount
+                .mul_ratio(part.to_f64(), self.amount.to_f64()),
+            ..original
+        });
+        PairwisePaymentBreakdown {
+            amount: part,
+            original,
+            ..self.breakdown()
+        }
+    }
+
+    /// Whether the occurrence moves money from one pool to another
+    fn is_pool_transfer(&self, pool_participants: &HashSet<i64>) -> bool {
+        match (self.payer_id, self.receiver_account_id) {
+            (Some(payer_id), Some(receiver_id)) => {
+                payer_id != receiver_id
+                    && pool_participants.contains(&payer_id)
+                    && pool_participants.contains(&receiver_id)
+            }
+            _ => false,
+        }
+    }
+}
+
+/// One contributor's share of an occurrence, in the base currency
+#[derive(Debug, Clone)]
+struct OccurrenceShare {
+    participant_id: i64,
+    amount: Money,
+    original: Option<OriginalAmount>,
+}
+
+impl OccurrenceShare {
+    /// Breakdown line for this share of `occurrence`
+    fn breakdown(&self, occurrence: &PaymentOccurrence) -> PairwisePaymentBreakdown {
+        self.part_breakdown(occurrence, self.amount)
+    }
+
+    /// Breakdown line for the `part` of this share one of several payers paid
+    fn part_breakdown(
+        &self,
+        occurrence: &PaymentOccurrence,
+        part: Money,
+    ) -> PairwisePaymentBreakdown {
+        let original = self.original.clone().map(|original| OriginalAmount {
+            amount: if part == self.amount {
+                original.amount
+            } else {
+                original
+                    .amount
+                    .mul_ratio(part.to_f64(), self.amount.to_f64())
+            },
+            ..original
+        });
+        PairwisePaymentBreakdown {
+            payment_id: occurrence.payment_id,
+            description: occurrence.description.clone(),
+            occurrence_date: occurrence.occurrence_date.clone(),
+            amount: part,
+            original,
+            pool_rule_id: occurrence.pool_rule_id,
+        }
+    }
+}
+
+/// Part of each share paid by each payer, in payer order. A payer's paid
+/// amount goes over the shares still unpaid in proportion to them and the
+/// last payer takes the rest, so both a payer's parts and a share's parts add
+/// up exactly.
+fn payer_parts(paid_by: &[(i64, Money)], shares: &[OccurrenceShare]) -> Vec<Vec<Money>> {
+    let mut unpaid: Vec<Money> = shares.iter().map(|s| s.amount).collect();
+    let mut parts = Vec::with_capacity(paid_by.len());
+    for (i, (_, paid)) in paid_by.iter().enumerate() {
+        let part = if i + 1 == paid_by.len() {
+            unpaid.clone()
+        } else {
+            let weights: Vec<(i64, f64)> = shares
+                .iter()
+                .zip(&unpaid)
+                .map(|(share, unpaid)| (share.participant_id, unpaid.to_f64().abs()))
+                .collect();
+            paid.allocate(&weights)
+        };
+        for (unpaid, part) in unpaid.iter_mut().zip(&part) {
+            *unpaid -= *part;
+        }
+        parts.push(part);
+    }
+    parts
+}
+
+/// Contributions of an occurrence expressed in the base currency.
+/// For converted occurrences the converted total is re-split in proportion to the
+/// original shares, so the shares still add up to the occurrence amount.
+fn occurrence_shares(
+    occurrence: &PaymentOccurrence,
+    contribution_map: &HashMap<i64, Vec<(i64, Money)>>,
+) -> Vec<OccurrenceShare> {
+    let Some(contribs) = occurrence
+        .split
+        .as_ref()
+        .or_else(|| contribution_map.get(&occurrence.payment_id))
+    else {
+        return Vec::new();
+    };
+
+    let Some(original) = &occurrence.original else {
+        return contribs
+            .iter()
+            .map(|(participant_id, amount)| OccurrenceShare {
+                participant_id: *participant_id,
+                amount: *amount,
+                original: None,
+            })
+            .collect();
+    };
+
+    let weights: Vec<(i64, f64)> = contribs
+        .iter()
+        .map(|(participant_id, amount)| (*participant_id, amount.to_f64().abs()))
+        .collect();
+    let converted = occurrence.amount.allocate(&weights);
+
+    contribs
+        .iter()
+        .zip(converted)
+        .map(|((participant_id, amount), converted)| OccurrenceShare {
+            participant_id: *participant_id,
+            amount: converted,
+            original: Some(OriginalAmount {
+                currency: original.currency.clone(),
+                amount: *amount,
+                exchange_rate: original.exchange_rate,
+            }),
+        })
+        .collect()
 }
 
 /// Calculate debts as of today
@@ -118,218 +594,72 @@ pub async fn calculate_debts_at_date(
     project_id: i64,
     target_date: &str,
     include_drafts: bool,
+) -> AppResult<DebtSummary> {
+    calculate_debts_with_options(
+        pool,
+        project_id,
+        target_date,
+        include_drafts,
+        &SettlementOptions::default(),
+    )
+    .await
+}
+
+/// Calculate debts as of a specific target date, planning settlements with `options`
+pub async fn calculate_debts_with_options(
+    pool: &SqlitePool,
+    project_id: i64,
+    target_date: &str,
+    include_drafts: bool,
+    options: &SettlementOptions,
 ) -> AppResult<DebtSummary> {
     let target = parse_date(target_date).unwrap_or_else(|| chrono::Utc::now().date_naive());
 
-    // Get all participants for this project (including account_type)
-    let participants: Vec<(i64, String, String)> =
-        sqlx::query_as("SELECT id, name, account_type FROM participants WHERE project_id = ?")
-            .bind(project_id)
-            .fetch_all(pool)
-            .await?;
+    let ledger = load_ledger(pool, project_id, target, include_drafts)
+        .await?
+        .ledger
+        .until(target);
+    let pool_ledgers = ledger.pool_ledgers(true);
+    let Ledger {
+        participants,
+        pool_participants,
+        base_currency,
+        occurrences: all_occurrences,
+        shares: occurrence_shares,
+        unconverted,
+        ..
+    } = ledger;
 
     let participant_map: HashMap<i64, String> = participants
         .iter()
         .map(|(id, name, _)| (*id, name.clone()))
         .collect();
 
-    // Track which participants are pool accounts (excluded from settlements)
-    let pool_participants: std::collections::HashSet<i64> = participants
-        .iter()
-        .filter(|(_, _, account_type)| account_type == "pool")
-        .map(|(id, _, _)| *id)
-        .collect();
-
-    // Get payments for this project (optionally filtering out drafts)
-    let payments: Vec<Payment> = if include_drafts {
-        sqlx::query_as("SELECT * FROM payments WHERE project_id = ?")
-            .bind(project_id)
-            .fetch_all(pool)
-            .await?
-    } else {
-        sqlx::query_as("SELECT * FROM payments WHERE project_id = ? AND is_final = 1")
-            .bind(project_id)
-            .fetch_all(pool)
-            .await?
-    };
-
-    // Generate all payment occurrences (including recurring expansions)
-    let mut all_occurrences: Vec<PaymentOccurrence> = Vec::new();
-
-    for payment in &payments {
-        let occurrences = generate_payment_occurrences(payment, target);
-        all_occurrences.extend(occurrences);
-    }
-
-    // Get contributions for each payment
-    let contributions: Vec<(i64, i64, f64)> = sqlx::query_as(
-        "SELECT c.payment_id, c.participant_id, c.amount
-         FROM contributions c
-         JOIN payments p ON c.payment_id = p.id
-         WHERE p.project_id = ?",
-    )
-    .bind(project_id)
-    .fetch_all(pool)
-    .await?;
-
-    // Build contribution map: payment_id -> [(participant_id, amount)]
-    let mut contribution_map: HashMap<i64, Vec<(i64, f64)>> = HashMap::new();
-    for (payment_id, participant_id, amount) in contributions {
-        contribution_map
-            .entry(payment_id)
-            .or_default()
-            .push((participant_id, amount));
-    }
-
     // Calculate total paid and owed based on occurrences
     // Also track pairwise amounts: (payer_id, contributor_id) -> (total_amount, breakdown)
-    //
-    // Transfer types:
-    // 1. External expense (receiver_account_id IS NULL): Normal expense, affects settlements
-    // 2. User → User transfer: Direct payment, affects settlements (reduces debt)
-    // 3. User → Pool transfer: Only affects pool ownership, NOT settlements
-    // 4. Pool → User transfer: Only affects pool ownership, NOT settlements
-    let mut paid_map: HashMap<i64, f64> = HashMap::new();
-    let mut owed_map: HashMap<i64, f64> = HashMap::new();
-    let mut pairwise_map: HashMap<(i64, i64), (f64, Vec<PairwisePaymentBreakdown>)> =
-        HashMap::new();
-
-    for occurrence in &all_occurrences {
-        // Check if this is a pool-related transfer (should not affect settlements)
-        if let Some(receiver_id) = occurrence.receiver_account_id {
-            let receiver_is_pool = pool_participants.contains(&receiver_id);
-            let payer_is_pool = occurrence
-                .payer_id
-                .map(|id| pool_participants.contains(&id))
-                .unwrap_or(false);
-
-            if receiver_is_pool || payer_is_pool {
-                // Pool transfer - skip for settlement calculations (handled in pool ownership)
-                continue;
-            }
-
-            // User-to-user transfer: treat as direct payment
-            // Payer gives money directly to receiver, reducing payer's debt to receiver
-            if let Some(payer_id) = occurrence.payer_id {
-                // Add to payer's "paid" total
-                *paid_map.entry(payer_id).or_insert(0.0) += occurrence.amount;
-
-                // Add to receiver's "owed" total (they received the money)
-                *owed_map.entry(receiver_id).or_insert(0.0) += occurrence.amount;
-
-                // Track pairwise: payer paid this amount directly to receiver
-                let entry = pairwise_map
-                    .entry((payer_id, receiver_id))
-                    .or_insert((0.0, Vec::new()));
-                entry.0 += occurrence.amount;
-                entry.1.push(PairwisePaymentBreakdown {
-                    payment_id: occurrence.payment_id,
-                    description: occurrence.description.clone(),
-                    occurrence_date: occurrence.occurrence_date.clone(),
-                    amount: occurrence.amount,
-                });
-            } else if occurrence.payer_id.is_none() {
-                // External inflow to user: receiver holds money for the group
-                // Receiver is debited (holds/owes the full amount)
-                *owed_map.entry(receiver_id).or_insert(0.0) += occurrence.amount;
-
-                // Contributors are credited (owed their share from receiver)
-                if let Some(contribs) = contribution_map.get(&occurrence.payment_id) {
-                    for (participant_id, amount) in contribs {
-                        *paid_map.entry(*participant_id).or_insert(0.0) += amount;
-                    }
-
-                    // Track pairwise: each contributor is owed by receiver
-                    for (contributor_id, amount) in contribs {
-                        if *contributor_id != receiver_id {
-                            let entry = pairwise_map
-                                .entry((*contributor_id, receiver_id))
-                                .or_insert((0.0, Vec::new()));
-                            entry.0 += amount;
-                            entry.1.push(PairwisePaymentBreakdown {
-                                payment_id: occurrence.payment_id,
-                                description: occurrence.description.clone(),
-                                occurrence_date: occurrence.occurrence_date.clone(),
-                                amount: *amount,
-                            });
-                        }
-                    }
-                }
-            }
-            continue;
-        }
-
-        // External expense (receiver_account_id IS NULL)
-        // Add to paid total for payer
-        let payer_is_pool = occurrence
-            .payer_id
-            .map(|id| pool_participants.contains(&id))
-            .unwrap_or(false);
-
-        if let Some(payer_id) = occurrence.payer_id {
-            *paid_map.entry(payer_id).or_insert(0.0) += occurrence.amount;
-
-            // Track pairwise amounts: how much payer paid for each contributor
-            // Skip if payer is pool (pool relationships are tracked in pool ownership)
-            if !payer_is_pool {
-                if let Some(contribs) = contribution_map.get(&occurrence.payment_id) {
-                    for (contributor_id, amount) in contribs {
-                        // payer paid this amount for contributor
-                        let entry = pairwise_map
-                            .entry((payer_id, *contributor_id))
-                            .or_insert((0.0, Vec::new()));
-                        entry.0 += amount;
-                        entry.1.push(PairwisePaymentBreakdown {
-                            payment_id: occurrence.payment_id,
-                            description: occurrence.description.clone(),
-                            occurrence_date: occurrence.occurrence_date.clone(),
-                            amount: *amount,
-                        });
-                    }
-                }
-            }
-        }
-
-        // Add to owed totals from contributions
-        // IMPORTANT: Only add to owed if the payer is a USER (not pool)
-        // When pool pays for expenses, the debt is owed TO the pool, which is
-        // tracked separately in pool ownership. Including pool-paid debts in
-        // owed_map would create an imbalance in user-to-user settlements since
-        // pool is excluded from settlement calculations.
-        if !payer_is_pool {
-            if let Some(contribs) = contribution_map.get(&occurrence.payment_id) {
-                for (participant_id, amount) in contribs {
-                    *owed_map.entry(*participant_id).or_insert(0.0) += amount;
-                }
-            }
-        }
+    let mut paid_map: HashMap<i64, Money> = HashMap::new();
+    let mut owed_map: HashMap<i64, Money> = HashMap::new();
+    let mut pairwise_map: PairwiseMap = HashMap::new();
+
+    for (occurrence, shares) in all_occurrences.iter().zip(&occurrence_shares) {
+        apply_occurrence(
+            occurrence,
+            shares,
+            &pool_participants,
+            &mut paid_map,
+            &mut owed_map,
+            Some(&mut pairwise_map),
+        );
     }
 
-    // Calculate balances
-    let mut balances: Vec<ParticipantBalance> = participants
-        .iter()
-        .map(|(id, name, _)| {
-            let total_paid = paid_map.get(id).copied().unwrap_or(0.0);
-            let total_owed = owed_map.get(id).copied().unwrap_or(0.0);
-            ParticipantBalance {
-                participant_id: *id,
-                participant_name: name.clone(),
-                total_paid,
-                total_owed,
-                net_balance: total_paid - total_owed,
-            }
-        })
-        .collect();
-
-    // Sort by net balance for settlement calculation
-    balances.sort_by(|a, b| a.net_balance.partial_cmp(&b.net_balance).unwrap());
+    // Calculate balances, sorted by net balance for settlement calculation
+    let balances = participant_balances(&participants, &paid_map, &owed_map);
 
     // Calculate optimal settlements (greedy algorithm)
     // Exclude pool accounts from settlements
     let settlements = calculate_settlements(&balances, &participant_map, &pool_participants);
-
-    // Sort occurrences by date
-    all_occurrences.sort_by(|a, b| a.occurrence_date.cmp(&b.occurrence_date));
+    let settlement_plan =
+        calculate_settlement_plan(&balances, &participant_map, &pool_participants, options);
 
     // Build pairwise balances from the pairwise_map
     // For each participant, show their relationship with every other participant
@@ -341,22 +671,22 @@ pub async fn calculate_debts_at_date(
             }
 
             // How much did 'id' pay for 'other_id'?
-            let (amount_paid_for, paid_for_breakdown): (f64, Vec<PairwisePaymentBreakdown>) =
+            let (amount_paid_for, paid_for_breakdown): (Money, Vec<PairwisePaymentBreakdown>) =
                 if let Some((amt, breakdown)) = pairwise_map.get(&(*id, *other_id)) {
                     (*amt, breakdown.clone())
                 } else {
-                    (0.0, Vec::new())
+                    (Money::ZERO, Vec::new())
                 };
             // How much did 'other_id' pay for 'id'?
-            let (amount_owed_by, owed_by_breakdown): (f64, Vec<PairwisePaymentBreakdown>) =
+            let (amount_owed_by, owed_by_breakdown): (Money, Vec<PairwisePaymentBreakdown>) =
                 if let Some((amt, breakdown)) = pairwise_map.get(&(*other_id, *id)) {
                     (*amt, breakdown.clone())
                 } else {
-                    (0.0, Vec::new())
+                    (Money::ZERO, Vec::new())
                 };
 
             // Only include if there's any relationship
-            if amount_paid_for > 0.01 || amount_owed_by > 0.01 {
+            if !amount_paid_for.is_zero() || !amount_owed_by.is_zero() {
                 pairwise_balances.push(PairwiseBalance {
                     participant_id: *id,
                     participant_name: name.clone(),
@@ -373,210 +703,1346 @@ pub async fn calculate_debts_at_date(
     }
 
     // Calculate pool ownership for all pool accounts
-    let pool_account_list: Vec<(i64, String)> = participants
-        .iter()
-        .filter(|(_, _, account_type)| account_type == "pool")
-        .map(|(id, name, _)| (*id, name.clone()))
-        .collect();
+    let mut pool_ownerships: Vec<PoolOwnership> = Vec::new();
+
+    for mut ledger in pool_ledgers {
+        let pool_name = participant_map
+            .get(&ledger.pool_id)
+            .cloned()
+            .unwrap_or_default();
+        for (occurrence, shares) in all_occurrences.iter().zip(&occurrence_shares) {
+            ledger.apply(occurrence, shares);
+        }
+        pool_ownerships.push(ledger.into_ownership(pool_name, &participants, target));
+    }
+
+    // Calculate direct-only settlements based on pairwise relationships
+    // Pass balances to ensure we respect net balance constraints
+    let direct_settlements =
+        calculate_direct_settlements(&pairwise_balances, &balances, &pool_participants);
+
+    Ok(DebtSummary {
+        balances,
+        settlements,
+        direct_settlements,
+        settlement_plan,
+        target_date: target_date.to_string(),
+        base_currency,
+        occurrences: all_occurrences,
+        pairwise_balances,
+        pool_ownerships,
+        unconverted,
+    })
+}
+
+/// Balances and pool ownership as of a date.
+///
+/// Cheaper than a full debt summary: it starts from the cached ledger's nearest
+/// monthly checkpoint and skips breakdowns and settlements. Pool ownership
+/// entries have empty breakdowns.
+pub async fn calculate_totals_at_date(
+    pool: &SqlitePool,
+    project_id: i64,
+    target_date: &str,
+    include_drafts: bool,
+) -> AppResult<LedgerTotals> {
+    let target = parse_date(target_date).unwrap_or_else(|| chrono::Utc::now().date_naive());
+    let cached = load_ledger(pool, project_id, target, include_drafts).await?;
+    let ledger = &cached.ledger;
+
+    let mut state = cached.checkpoint_before(target);
+    let count = ledger.count_until(target);
+    for (occurrence, shares) in ledger.occurrences[state.applied..count]
+        .iter()
+        .zip(&ledger.shares[state.applied..count])
+    {
+        state.apply(occurrence, shares, &ledger.pool_participants);
+    }
+
+    let pool_ownerships = state
+        .pools
+        .into_iter()
+        .filter_map(|pool_ledger| {
+            let (_, name, _) = ledger
+                .participants
+                .iter()
+                .find(|(id, _, _)| *id == pool_ledger.pool_id)?;
+            Some(pool_ledger.into_ownership(name.clone(), &ledger.participants, target))
+        })
+        .collect();
+
+    Ok(LedgerTotals {
+        target_date: target_date.to_string(),
+        base_currency: ledger.base_currency.clone(),
+        balances: participant_balances(&ledger.participants, &state.paid, &state.owed),
+        pool_ownerships,
+        unconverted: ledger.unconverted_until(target),
+    })
+}
+
+/// Compare a pool's bank statements, sorted by date, with the pool's final
+/// balance on record at the end of each statement date
+pub async fn reconcile_pool(
+    pool: &SqlitePool,
+    project_id: i64,
+    pool_id: i64,
+    statements: &[PoolStatement],
+) -> AppResult<PoolReconciliation> {
+    let last = statements
+        .iter()
+        .filter_map(|s| parse_date(&s.statement_date))
+        .max()
+        .unwrap_or_else(|| chrono::Utc::now().date_naive());
+    let cached = load_ledger(pool, project_id, last, false).await?;
+    let ledger = &cached.ledger;
+    let name_of = |id: i64| {
+        ledger
+            .participants
+            .iter()
+            .find(|(pid, _, _)| *pid == id)
+            .map(|(_, name, _)| name.clone())
+            .unwrap_or_default()
+    };
+
+    let count = ledger.count_until(last);
+    let mut pool_ledger = ledger.pool_ledger(pool_id, false);
+    let mut next = 0;
+    let mut previous_drift = Money::ZERO;
+    let mut reconciled = Vec::with_capacity(statements.len());
+    for statement in statements {
+        let Some(date) = parse_date(&statement.statement_date) else {
+            continue;
+        };
+        let date = date.format("%Y-%m-%d").to_string();
+
+        let mut occurrences = Vec::new();
+        while next < count && ledger.occurrences[next].occurrence_date <= date {
+            let (occurrence, shares) = (&ledger.occurrences[next], &ledger.shares[next]);
+            if pool_ledger.touches(occurrence, shares) {
+                pool_ledger.apply(occurrence, shares);
+                occurrences.push(occurrence.clone());
+            }
+            next += 1;
+        }
+        pool_ledger.revalue(&date, true);
+
+        let total_balance = pool_ledger.balance(&ledger.pool_participants);
+        let drift = statement.balance - total_balance;
+        let adjustment = if drift.is_zero() {
+            Vec::new()
+        } else {
+            pool_ledger
+                .owner_parts(drift, &ledger.pool_participants)
+                .into_iter()
+                .map(|(participant_id, amount)| AdjustmentShare {
+                    participant_id,
+                    participant_name: name_of(participant_id),
+                    amount,
+                })
+                .collect()
+        };
+        reconciled.push(StatementReconciliation {
+            statement_id: statement.id,
+            statement_date: date,
+            statement_balance: statement.balance,
+            total_balance,
+            drift,
+            unexplained: drift - previous_drift,
+            occurrences,
+            adjustment,
+        });
+        previous_drift = drift;
+    }
+
+    Ok(PoolReconciliation {
+        pool_id,
+        pool_name: name_of(pool_id),
+        statements: reconciled,
+    })
+}
+
+/// Dates of a series from `from` to `to` inclusive. Monthly steps are counted
+/// from `from`, so a series starting on the 31st lands on each month's last day.
+/// Returns `None` when the range is reversed or has too many points.
+pub fn series_dates(from: NaiveDate, to: NaiveDate, step: SeriesStep) -> Option<Vec<NaiveDate>> {
+    if from > to {
+        return None;
+    }
+    let mut dates = Vec::new();
+    for i in 0u32.. {
+        let date = match step {
+            SeriesStep::Day => from.checked_add_days(chrono::Days::new(i as u64)),
+            SeriesStep::Week => from.checked_add_days(chrono::Days::new(i as u64 * 7)),
+            SeriesStep::Month => from.checked_add_months(Months::new(i)),
+        }?;
+        if date > to {
+            break;
+        }
+        if dates.len() == MAX_SERIES_POINTS {
+            return None;
+        }
+        dates.push(date);
+    }
+    Some(dates)
+}
+
+/// Participant balances at every step between `from` and `to`.
+/// Occurrences are expanded once and applied in date order, taking a snapshot of
+/// the running totals at each step.
+pub async fn calculate_balance_series(
+    pool: &SqlitePool,
+    project_id: i64,
+    from: NaiveDate,
+    to: NaiveDate,
+    step: SeriesStep,
+    include_drafts: bool,
+) -> AppResult<BalanceSeries> {
+    let dates = series_dates(from, to, step)
+        .ok_or_else(|| AppError::bad_request(ErrorCode::InvalidSeriesRange))?;
+
+    let cached = load_ledger(pool, project_id, to, include_drafts).await?;
+    let ledger = &cached.ledger;
+
+    let mut paid_map: HashMap<i64, Money> = HashMap::new();
+    let mut owed_map: HashMap<i64, Money> = HashMap::new();
+    let mut applied = ledger.occurrences.iter().zip(&ledger.shares).peekable();
+    let mut points = Vec::with_capacity(dates.len());
+
+    for date in dates {
+        while let Some((occurrence, shares)) = applied.next_if(|(occurrence, _)| {
+            parse_date(&occurrence.occurrence_date).is_none_or(|d| d <= date)
+        }) {
+            apply_occurrence(
+                occurrence,
+                shares,
+                &ledger.pool_participants,
+                &mut paid_map,
+                &mut owed_map,
+                None,
+            );
+        }
+
+        let balances = ledger
+            .participants
+            .iter()
+            .map(|(id, name, _)| participant_balance(*id, name, &paid_map, &owed_map))
+            .collect();
+
+        points.push(BalanceSeriesPoint {
+            date: date.format("%Y-%m-%d").to_string(),
+            balances,
+        });
+    }
+
+    Ok(BalanceSeries {
+        from: from.format("%Y-%m-%d").to_string(),
+        to: to.format("%Y-%m-%d").to_string(),
+        step,
+        base_currency: ledger.base_currency.clone(),
+        points,
+    })
+}
+
+impl ForecastCurve {
+    fn new() -> Self {
+        Self {
+            points: Vec::new(),
+            first_below_expected_date: None,
+            lowest: None,
+        }
+    }
+
+    fn push(&mut self, point: ForecastPoint) {
+        if self.first_below_expected_date.is_none() && point.balance < point.expected_minimum {
+            self.first_below_expected_date = Some(point.date.clone());
+        }
+        if self
+            .lowest
+            .as_ref()
+            .is_none_or(|lowest| point.balance < lowest.balance)
+        {
+            self.lowest = Some(point.clone());
+        }
+        self.points.push(point);
+    }
+}
+
+/// Forecast every pool of a project from `from` up to `horizon`.
+///
+/// The first point of each curve is the state on `from` (all occurrences up to
+/// and including that date); a new point is added on every later date where an
+/// occurrence touches the pool. User curves cover the participants holding a
+/// share or an expectation in the pool at any point of the window.
+pub async fn forecast_pools(
+    pool: &SqlitePool,
+    project_id: i64,
+    from: NaiveDate,
+    horizon: NaiveDate,
+    include_drafts: bool,
+) -> AppResult<Vec<PoolForecast>> {
+    if from > horizon {
+        return Err(AppError::bad_request(ErrorCode::InvalidSeriesRange));
+    }
+
+    let cached = load_ledger(pool, project_id, horizon, include_drafts).await?;
+    let ledger = &cached.ledger;
+    let count = ledger.count_until(horizon);
+    let occurrence_dates: Vec<Option<NaiveDate>> = ledger.occurrences[..count]
+        .iter()
+        .map(|o| parse_date(&o.occurrence_date))
+        .collect();
+
+    let mut forecasts = Vec::new();
+    for (pool_id, pool_name, _) in ledger
+        .participants
+        .iter()
+        .filter(|(_, _, account_type)| account_type == "pool")
+    {
+        let mut pool_ledger = ledger.pool_ledger(*pool_id, false);
+        // Curves are keyed by the dates the pool was touched; start with `from`
+        let mut snapshots: Vec<(NaiveDate, PoolSnapshot)> = Vec::new();
+        let mut pending_date: Option<NaiveDate> = None;
+
+        for (idx, (occurrence, shares)) in ledger.occurrences[..count]
+            .iter()
+            .zip(&ledger.shares)
+            .enumerate()
+        {
+            let date = occurrence_dates[idx].unwrap_or(from);
+            if date > from && snapshots.is_empty() {
+                snapshots.push((from, pool_ledger.snapshot(from, &ledger.pool_participants)));
+            }
+            if let Some(pending) = pending_date {
+                if date > pending {
+                    snapshots.push((
+                        pending,
+                        pool_ledger.snapshot(pending, &ledger.pool_participants),
+                    ));
+                    pending_date = None;
+                }
+            }
+            if !pool_ledger.touches(occurrence, shares) {
+                continue;
+            }
+            pool_ledger.apply(occurrence, shares);
+            if date > from {
+                pending_date = Some(date);
+            }
+        }
+        if snapshots.is_empty() {
+            snapshots.push((from, pool_ledger.snapshot(from, &ledger.pool_participants)));
+        }
+        if let Some(pending) = pending_date {
+            snapshots.push((
+                pending,
+                pool_ledger.snapshot(pending, &ledger.pool_participants),
+            ));
+        }
+
+        let mut curve = ForecastCurve::new();
+        for (date, snapshot) in &snapshots {
+            curve.push(ForecastPoint {
+                date: date.format("%Y-%m-%d").to_string(),
+                balance: snapshot.balance,
+                expected_minimum: snapshot.expected_minimum,
+            });
+        }
+
+        let users = ledger
+            .participants
+            .iter()
+            .filter(|(id, _, _)| !ledger.pool_participants.contains(id))
+            .filter(|(id, _, _)| snapshots.iter().any(|(_, s)| s.members.contains_key(id)))
+            .map(|(id, name, _)| {
+                let mut curve = ForecastCurve::new();
+                for (date, snapshot) in &snapshots {
+                    let (balance, expected_minimum) = snapshot
+                        .members
+                        .get(id)
+                        .copied()
+                        .unwrap_or((Money::ZERO, Money::ZERO));
+                    curve.push(ForecastPoint {
+                        date: date.format("%Y-%m-%d").to_string(),
+                        balance,
+                        expected_minimum,
+                    });
+                }
+                UserShareForecast {
+                    participant_id: *id,
+                    participant_name: name.clone(),
+                    curve,
+                }
+            })
+            .collect();
+
+        forecasts.push(PoolForecast {
+            pool_id: *pool_id,
+            pool_name: pool_name.clone(),
+            from: from.format("%Y-%m-%d").to_string(),
+            horizon: horizon.format("%Y-%m-%d").to_string(),
+            curve,
+            users,
+        });
+    }
+
+    Ok(forecasts)
+}
+
+/// Everything the balance calculations need: participants and every occurrence up
+/// to a date, sorted by date, with its contributions in the base currency
+#[derive(Clone)]
+struct Ledger {
+    // (id, name, account_type)
+    participants: Vec<(i64, String, String)>,
+    pool_participants: HashSet<i64>,
+    base_currency: String,
+    occurrences: Vec<PaymentOccurrence>,
+    shares: Vec<Vec<OccurrenceShare>>,
+    // Foreign-currency occurrences with no rate, by date
+    unconverted: Vec<UnconvertedOccurrence>,
+    // Unitized pools and their valuations, by date
+    unitized: HashMap<i64, Arc<[PoolValuation]>>,
+}
+
+/// Load participants and payments and expand them into occurrences up to `target`
+async fn expand_ledger(
+    pool: &SqlitePool,
+    project_id: i64,
+    target: NaiveDate,
+    include_drafts: bool,
+) -> AppResult<Ledger> {
+    // Get all participants for this project (including account_type)
+    let participants: Vec<(i64, String, String)> =
+        sqlx::query_as("SELECT id, name, account_type FROM participants WHERE project_id = ?")
+            .bind(project_id)
+            .fetch_all(pool)
+            .await?;
+
+    // Track which participants are pool accounts (excluded from settlements)
+    let pool_participants: HashSet<i64> = participants
+        .iter()
+        .filter(|(_, _, account_type)| account_type == "pool")
+        .map(|(id, _, _)| *id)
+        .collect();
+
+    // Unitized pools, priced by their valuations
+    let unitized_pools: Vec<i64> = sqlx::query_scalar(
+        "SELECT id FROM participants
+         WHERE project_id = ? AND account_type = 'pool' AND unitized = 1",
+    )
+    .bind(project_id)
+    .fetch_all(pool)
+    .await?;
+    let mut valuation_map: HashMap<i64, Vec<PoolValuation>> = unitized_pools
+        .into_iter()
+        .map(|id| (id, Vec::new()))
+        .collect();
+    if !valuation_map.is_empty() {
+        let valuations: Vec<PoolValuation> = sqlx::query_as(
+            "SELECT * FROM pool_valuations WHERE project_id = ? ORDER BY valuation_date, id",
+        )
+        .bind(project_id)
+        .fetch_all(pool)
+        .await?;
+        for valuation in valuations {
+            if let Some(pool_valuations) = valuation_map.get_mut(&valuation.pool_id) {
+                pool_valuations.push(valuation);
+            }
+        }
+    }
+
+    // Fees and interest of pools, from the rules started by the target
+    let pool_rules: Vec<PoolRule> = sqlx::query_as(
+        "SELECT * FROM pool_rules WHERE project_id = ? AND start_date <= ? ORDER BY id",
+    )
+    .bind(project_id)
+    .bind(target.format("%Y-%m-%d").to_string())
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .filter(|rule: &PoolRule| pool_participants.contains(&rule.pool_id))
+    .collect();
+
+    // Deposits expected by contribution rules started by the target, and
+    // from whom
+    let contribution_rules: Vec<ContributionRule> =
+        sqlx::query_as("SELECT * FROM contribution_rules WHERE project_id = ? AND start_date <= ?")
+            .bind(project_id)
+            .bind(target.format("%Y-%m-%d").to_string())
+            .fetch_all(pool)
+            .await?;
+    let rule_amounts: Vec<(i64, i64, Money)> = if contribution_rules.is_empty() {
+        Vec::new()
+    } else {
+        sqlx::query_as(
+            "SELECT a.rule_id, a.participant_id, a.amount
+             FROM contribution_rule_amounts a
+             JOIN contribution_rules r ON a.rule_id = r.id
+             WHERE r.project_id = ?
+             ORDER BY a.id",
+        )
+        .bind(project_id)
+        .fetch_all(pool)
+        .await?
+    };
+
+    // Get payments for this project (optionally filtering out drafts)
+    let payments: Vec<Payment> = if include_drafts {
+        sqlx::query_as("SELECT * FROM payments WHERE project_id = ?")
+            .bind(project_id)
+            .fetch_all(pool)
+            .await?
+    } else {
+        sqlx::query_as("SELECT * FROM payments WHERE project_id = ? AND is_final = 1")
+            .bind(project_id)
+            .fetch_all(pool)
+            .await?
+    };
+
+    // Balances are reported in the project's base currency
+    let base_currency: String =
+        sqlx::query_scalar("SELECT base_currency FROM projects WHERE id = ?")
+            .bind(project_id)
+            .fetch_optional(pool)
+            .await?
+            .unwrap_or_else(|| DEFAULT_BASE_CURRENCY.to_string());
+
+    // Exchange rates are only needed if some payment is in a foreign currency
+    let has_foreign_payments = payments
+        .iter()
+        .any(|p| p.currency.as_deref().is_some_and(|c| c != base_currency));
+    let rates = if has_foreign_payments {
+        ExchangeRateTable::load(pool, project_id).await?
+    } else {
+        ExchangeRateTable::default()
+    };
+
+    // Get contributions for each payment
+    let contributions: Vec<(i64, i64, Money)> = sqlx::query_as(
+        "SELECT c.payment_id, c.participant_id, c.amount
+         FROM contributions c
+         JOIN payments p ON c.payment_id = p.id
+         WHERE p.project_id = ?",
+    )
+    .bind(project_id)
+    .fetch_all(pool)
+    .await?;
+
+    // Build contribution map: payment_id -> [(participant_id, amount)]
+    let mut contribution_map: HashMap<i64, Vec<(i64, Money)>> = HashMap::new();
+    for (payment_id, participant_id, amount) in contributions {
+        contribution_map
+            .entry(payment_id)
+            .or_default()
+            .push((participant_id, amount));
+    }
+
+    // Parts paid by each payer of payments paid by several
+    let payers: Vec<(i64, i64, Money)> = sqlx::query_as(
+        "SELECT pp.payment_id, pp.participant_id, pp.amount
+         FROM payment_payers pp
+         JOIN payments p ON pp.payment_id = p.id
+         WHERE p.project_id = ?
+         ORDER BY pp.id",
+    )
+    .bind(project_id)
+    .fetch_all(pool)
+    .await?;
+    let mut payer_map: HashMap<i64, Vec<(i64, f64)>> = HashMap::new();
+    for (payment_id, participant_id, amount) in payers {
+        payer_map
+            .entry(payment_id)
+            .or_default()
+            .push((participant_id, amount.to_f64()));
+    }
+
+    // Refunds, each an occurrence of its own on the refund date
+    let refunds: Vec<Refund> = sqlx::query_as(
+        "SELECT r.* FROM refunds r
+         JOIN payments p ON r.payment_id = p.id
+         WHERE p.project_id = ? AND r.refund_date <= ?",
+    )
+    .bind(project_id)
+    .bind(target.format("%Y-%m-%d").to_string())
+    .fetch_all(pool)
+    .await?;
+    let mut refund_map: HashMap<i64, Vec<Refund>> = HashMap::new();
+    for refund in refunds {
+        refund_map
+            .entry(refund.payment_id)
+            .or_default()
+            .push(refund);
+    }
+
+    // Skipped, moved and changed occurrences of recurring payments
+    let exceptions: Vec<OccurrenceException> = sqlx::query_as(
+        "SELECT e.* FROM occurrence_exceptions e
+         JOIN payments p ON e.payment_id = p.id
+         WHERE p.project_id = ?",
+    )
+    .bind(project_id)
+    .fetch_all(pool)
+    .await?;
+    let mut exception_map: HashMap<i64, Vec<OccurrenceException>> = HashMap::new();
+    for exception in exceptions {
+        exception_map
+            .entry(exception.payment_id)
+            .or_default()
+            .push(exception);
+    }
+
+    // Holidays are only needed if some payment shifts to business days
+    let calendar = if payments.iter().any(|p| p.business_day_shift().is_some()) {
+        BusinessCalendar::load(pool, project_id).await?
+    } else {
+        BusinessCalendar::default()
+    };
+
+    // Who is present when, for splitting recurring payments
+    let presence = if payments.iter().any(|p| p.is_recurring) {
+        PresenceCalendar::load(pool, project_id).await?
+    } else {
+        PresenceCalendar::default()
+    };
+
+    // Versions of the weight profiles payments are split by
+    let profiles = if payments.iter().any(|p| p.weight_profile_id.is_some()) {
+        ProfileWeights::load(pool, project_id).await?
+    } else {
+        ProfileWeights::default()
+    };
+
+    // Generate all payment occurrences (including recurring expansions)
+    let mut all_occurrences: Vec<PaymentOccurrence> = Vec::new();
+    let mut unconverted_occurrences: Vec<UnconvertedOccurrence> = Vec::new();
+
+    for payment in &payments {
+        let mut occurrences = match exception_map.get(&payment.id) {
+            Some(exceptions) => apply_exceptions(payment, target, &calendar, exceptions),
+            None => generate_payment_occurrences(payment, target, &calendar),
+        };
+
+        // Occurrences are split in minor units of the payment's currency
+        let scale = currency_scale(payment.currency.as_deref().unwrap_or(&base_currency));
+        for occurrence in &mut occurrences {
+            occurrence.amount = occurrence.amount.rescale(scale);
+        }
+
+        // Occurrences are split by the profile version of their date and,
+        // when recurring, among the contributors present, billing their own
+        // (scheduled or changed) amount
+        if payment.is_recurring || payment.weight_profile_id.is_some() {
+            let contributions = contribution_map
+                .get(&payment.id)
+                .map_or(&[][..], Vec::as_slice);
+            for occurrence in &mut occurrences {
+                if occurrence.split.is_none() {
+                    occurrence.split =
+                        occurrence_split(occurrence, payment, contributions, &profiles, &presence);
+                }
+            }
+        }
+
+        // A refund reverses its part of the payment: the payers give it back
+        // and the contributors it goes to owe that much less
+        for refund in refund_map.get(&payment.id).into_iter().flatten() {
+            let refund = Refund {
+                amount: refund.amount.rescale(scale),
+                ..refund.clone()
+            };
+            let contributions = contribution_map
+                .get(&payment.id)
+                .map_or(&[][..], Vec::as_slice);
+            let mut occurrence = PaymentOccurrence::new(payment, refund.refund_date.clone(), false);
+            if !refund.description.is_empty() {
+                occurrence.description = refund.description.clone();
+            }
+            occurrence.amount = -refund.amount;
+            occurrence.refund_id = Some(refund.id);
+            occurrence.split = Some(
+                refund
+                    .split(contributions)
+                    .into_iter()
+                    .map(|(participant_id, share)| (participant_id, -share))
+                    .collect(),
+            );
+            occurrences.push(occurrence);
+        }
+
+        // Convert foreign-currency occurrences at the rate of their own date;
+        // those no rate covers are set aside rather than counted at a guess
+        if let Some(currency) = payment.currency.as_deref() {
+            if currency != base_currency {
+                let mut converted = Vec::with_capacity(occurrences.len());
+                for occurrence in occurrences {
+                    match occurrence.convert_to(&base_currency, currency, &rates) {
+                        Ok(occurrence) => converted.push(occurrence),
+                        Err(unconverted) => unconverted_occurrences.push(unconverted),
+                    }
+                }
+                occurrences = converted;
+            }
+        }
+
+        // Several payers pay each occurrence in proportion to their parts
+        if let Some(payers) = payer_map.get(&payment.id) {
+            for occurrence in &mut occurrences {
+                let parts = occurrence.amount.allocate(payers);
+                occurrence.payers = payers.iter().map(|(id, _)| *id).zip(parts).collect();
+            }
+        }
+
+        all_occurrences.extend(occurrences);
+    }
+
+    // Each date of a contribution rule expects every participant's amount
+    for rule in contribution_rules
+        .iter()
+        .filter(|rule| pool_participants.contains(&rule.pool_id))
+    {
+        let Some(start) = parse_date(&rule.start_date) else {
+            continue;
+        };
+        let end = rule
+            .end_date
+            .as_deref()
+            .and_then(parse_date)
+            .map_or(target, |end| end.min(target));
+        let dates = match rule.recurrence() {
+            Some(recurrence) => expand_recurrence(&recurrence, start, end),
+            None if rule.recurrence_rule.is_none() => vec![start],
+            None => Vec::new(),
+        };
+        for date in dates {
+            for (_, participant_id, amount) in
+                rule_amounts.iter().filter(|(id, _, _)| *id == rule.id)
+            {
+                all_occurrences.push(PaymentOccurrence::contribution_rule(
+                    rule,
+                    *participant_id,
+                    *amount,
+                    date,
+                ));
+            }
+        }
+    }
+
+    // Sort occurrences by date
+    all_occurrences.sort_by(|a, b| a.occurrence_date.cmp(&b.occurrence_date));
+    unconverted_occurrences.sort_by(|a, b| a.occurrence_date.cmp(&b.occurrence_date));
+
+    // Each occurrence's contributions, in the base currency
+    let occurrence_shares: Vec<Vec<OccurrenceShare>> = all_occurrences
+        .iter()
+        .map(|occurrence| occurrence_shares(occurrence, &contribution_map))
+        .collect();
+
+    let mut ledger = Ledger {
+        participants,
+        pool_participants,
+        base_currency,
+        occurrences: all_occurrences,
+        shares: occurrence_shares,
+        unconverted: unconverted_occurrences,
+        unitized: valuation_map
+            .into_iter()
+            .map(|(id, valuations)| (id, Arc::from(valuations)))
+            .collect(),
+    };
+    ledger.run_pools(&pool_rules, target);
+    Ok(ledger)
+}
+
+impl Ledger {
+    /// Run every pool over the ledger once, in date order, to settle what
+    /// depends on who owns what when it happens: each pool-to-pool transfer
+    /// is split over the owners of the sending pool, so their ownership moves
+    /// with the money, and pool rules add their fees and interest up to
+    /// `until`, shared by the owners at the time.
+    fn run_pools(&mut self, rules: &[PoolRule], until: NaiveDate) {
+        let postings = pool_rule_postings(rules, until);
+        if postings.is_empty()
+            && !self
+                .occurrences
+                .iter()
+                .any(|o| o.is_pool_transfer(&self.pool_participants))
+        {
+            return;
+        }
+
+        let mut run = PoolRun {
+            pools: self
+                .pool_participants
+                .iter()
+                .map(|id| (*id, self.pool_ledger(*id, false)))
+                .collect(),
+            accruals: rules.iter().filter_map(InterestAccrual::new).collect(),
+            pool_participants: &self.pool_participants,
+            occurrences: Vec::with_capacity(self.occurrences.len() + postings.len()),
+            shares: Vec::with_capacity(self.shares.len() + postings.len()),
+        };
+        let occurrences = std::mem::take(&mut self.occurrences);
+        let shares = std::mem::take(&mut self.shares);
+        let mut postings = postings.into_iter().peekable();
+        for (mut occurrence, shares) in occurrences.into_iter().zip(shares) {
+            // Fees and interest are posted at the end of their day
+            let date = parse_date(&occurrence.occurrence_date);
+            while let Some((posted, rule)) =
+                postings.next_if(|(posted, _)| date.is_some_and(|date| *posted < date))
+            {
+                run.post(rule, posted);
+            }
+            if let Some(day_before) = date.and_then(|date| date.pred_opt()) {
+                run.accrue(day_before);
+            }
+
+            if occurrence.is_pool_transfer(run.pool_participants) {
+                if let Some(source) = occurrence.payer_id.and_then(|id| run.pools.get_mut(&id)) {
+                    source.revalue(&occurrence.occurrence_date, false);
+                    occurrence.pool_parts =
+                        source.owner_parts(occurrence.amount, run.pool_participants);
+                }
+            }
+            run.push(occurrence, shares);
+        }
+        for (posted, rule) in postings {
+            run.post(rule, posted);
+        }
+
+        self.occurrences = run.occurrences;
+        self.shares = run.shares;
+    }
+}
+
+/// Dates pool rules post a fee or credit interest on, up to `until`, in
+/// order
+fn pool_rule_postings(rules: &[PoolRule], until: NaiveDate) -> Vec<(NaiveDate, &PoolRule)> {
+    let mut postings: Vec<(NaiveDate, &PoolRule)> = rules
+        .iter()
+        .flat_map(|rule| {
+            let dates = match (rule.recurrence(), parse_date(&rule.start_date)) {
+                (Some(recurrence), Some(start)) => {
+                    let end = rule
+                        .end_date
+                        .as_deref()
+                        .and_then(parse_date)
+                        .map_or(until, |end| end.min(until));
+                    expand_recurrence(&recurrence, start, end)
+                }
+                _ => Vec::new(),
+            };
+            dates.into_iter().map(move |date| (date, rule))
+        })
+        .collect();
+    postings.sort_by_key(|(date, rule)| (*date, rule.id));
+    postings
+}
+
+/// Interest of one rule accrued since its last credit: every day, each
+/// owner earns the daily rate on what they own at the end of the day
+struct InterestAccrual {
+    rule_id: i64,
+    pool_id: i64,
+    daily_rate: f64,
+    // Last day accrued
+    through: NaiveDate,
+    end: Option<NaiveDate>,
+    // Per owner, in major units, rounded at the finest scale accrued on
+    accrued: HashMap<i64, f64>,
+    scale: u32,
+}
+
+impl InterestAccrual {
+    /// Accrual of an interest rule, from its start date
+    fn new(rule: &PoolRule) -> Option<Self> {
+        if rule.kind() != Some(PoolRuleKind::Interest) {
+            return None;
+        }
+        Some(Self {
+            rule_id: rule.id,
+            pool_id: rule.pool_id,
+            daily_rate: rule.rate? / 100.0 / 365.0,
+            through: parse_date(&rule.start_date)?.pred_opt()?,
+            end: rule.end_date.as_deref().and_then(parse_date),
+            accrued: HashMap::new(),
+            scale: 0,
+        })
+    }
+
+    /// Interest accrued so far, per owner, starting again from nothing
+    fn credit(&mut self) -> Vec<(i64, Money)> {
+        let mut accrued: Vec<(i64, f64)> = self.accrued.drain().collect();
+        // Same ties on every run, whatever the map order
+        accrued.sort_by_key(|(id, _)| *id);
+        let total: f64 = accrued.iter().map(|(_, interest)| interest).sum();
+        let weights: Vec<(i64, f64)> = accrued
+            .iter()
+            .map(|(id, interest)| (*id, interest.abs()))
+            .collect();
+        let minor = (total * 10f64.powi(self.scale as i32)).round() as i64;
+        let parts = Money::from_minor(minor, self.scale).allocate(&weights);
+        accrued.iter().map(|(id, _)| *id).zip(parts).collect()
+    }
+}
+
+/// State of a `Ledger::run_pools` pass: the running pools, the interest
+/// accrued in them and the occurrences settled so far
+struct PoolRun<'a> {
+    pools: HashMap<i64, PoolLedger>,
+    accruals: Vec<InterestAccrual>,
+    pool_participants: &'a HashSet<i64>,
+    occurrences: Vec<PaymentOccurrence>,
+    shares: Vec<Vec<OccurrenceShare>>,
+}
+
+impl PoolRun<'_> {
+    /// Accrue interest on every day up to `through`, at the ownership of each
+    /// day's end
+    fn accrue(&mut self, through: NaiveDate) {
+        for accrual in &mut self.accruals {
+            let to = accrual.end.map_or(through, |end| end.min(through));
+            let Some(from) = accrual.through.succ_opt().filter(|from| *from <= to) else {
+                continue;
+            };
+            accrual.through = to;
+            let Some(pool) = self.pools.get_mut(&accrual.pool_id) else {
+                continue;
+            };
+            pool.revalue(&from.format("%Y-%m-%d").to_string(), false);
+            let days = ((to - from).num_days() + 1) as f64;
+            for (id, member) in &pool.members {
+                let ownership = pool.ownership_of(member);
+                if self.pool_participants.contains(id) || !ownership.is_positive() {
+                    continue;
+                }
+                accrual.scale = accrual.scale.max(ownership.scale());
+                *accrual.accrued.entry(*id).or_default() +=
+                    ownership.to_f64() * accrual.daily_rate * days;
+            }
+        }
+    }
+
+    /// Post what `rule` charges or credits on `date`, shared by the pool's
+    /// owners: a fee in proportion to what they own, interest as it accrued
+    fn post(&mut self, rule: &PoolRule, date: NaiveDate) {
+        let Some(kind) = rule.kind() else {
+            return;
+        };
+        let parts = match kind {
+            PoolRuleKind::Fee => {
+                let Some(pool) = self.pools.get_mut(&rule.pool_id) else {
+                    return;
+                };
+                pool.revalue(&date.format("%Y-%m-%d").to_string(), true);
+                pool.owner_parts(rule.amount.unwrap_or(Money::ZERO), self.pool_participants)
+            }
+            PoolRuleKind::Interest => {
+                self.accrue(date);
+                match self.accruals.iter_mut().find(|a| a.rule_id == rule.id) {
+                    Some(accrual) => accrual.credit(),
+                    None => return,
+                }
+            }
+        };
+        let amount: Money = parts.iter().map(|(_, part)| *part).sum();
+        if amount.is_zero() {
+            return;
+        }
+
+        let occurrence = PaymentOccurrence::pool_rule(rule, kind, date, amount);
+        let shares = parts
+            .into_iter()
+            .map(|(participant_id, amount)| OccurrenceShare {
+                participant_id,
+                amount,
+                original: None,
+            })
+            .collect();
+        self.push(occurrence, shares);
+    }
+
+    /// Apply an occurrence to the pools it touches and keep it
+    fn push(&mut self, occurrence: PaymentOccurrence, shares: Vec<OccurrenceShare>) {
+        for pool in self.pools.values_mut() {
+            if pool.touches(&occurrence, &shares) {
+                pool.apply(&occurrence, &shares);
+            }
+        }
+        self.occurrences.push(occurrence);
+        self.shares.push(shares);
+    }
+}
+
+/// Furthest the shared cache expands a ledger, counted from today; requests
+/// beyond it get an expansion of their own that is not kept
+const MAX_CACHED_HORIZON: Months = Months::new(12 * 10);
+
+/// Expanded ledgers shared by all requests, see `ledger_cache`
+static LEDGERS: LazyLock<LedgerCache<CachedLedger>> = LazyLock::new(LedgerCache::default);
+
+/// Ledger of a project, reusing the cached expansion while the project's ledger
+/// stamp is unchanged. The returned ledger may extend past `target`.
+async fn load_ledger(
+    pool: &SqlitePool,
+    project_id: i64,
+    target: NaiveDate,
+    include_drafts: bool,
+) -> AppResult<Arc<CachedLedger>> {
+    // The stamp is read before the data, so a change racing with the load can
+    // only leave an entry under a stamp that is already stale
+    let Some(stamp) = ledger_stamp(pool, project_id).await? else {
+        let ledger = expand_ledger(pool, project_id, target, include_drafts).await?;
+        return Ok(Arc::new(CachedLedger::new(ledger)));
+    };
+    if let Some(cached) = LEDGERS.get(project_id, include_drafts, &stamp, target) {
+        return Ok(cached);
+    }
+
+    let limit = chrono::Utc::now().date_naive() + MAX_CACHED_HORIZON;
+    if target > limit {
+        let ledger = expand_ledger(pool, project_id, target, include_drafts).await?;
+        return Ok(Arc::new(CachedLedger::new(ledger)));
+    }
+
+    // Keep at least the previous horizon so forecasts and today's summary share
+    // one entry, but never one pushed past the limit before it was introduced
+    let horizon = LEDGERS
+        .horizon(project_id, include_drafts)
+        .map_or(target, |horizon| horizon.max(target))
+        .min(limit);
+    let ledger = expand_ledger(pool, project_id, horizon, include_drafts).await?;
+    let cached = Arc::new(CachedLedger::new(ledger));
+    LEDGERS.insert(project_id, include_drafts, stamp, horizon, cached.clone());
+    Ok(cached)
+}
+
+/// Drop all cached ledgers, forcing the next calculations to reload
+pub fn clear_ledger_cache() {
+    LEDGERS.clear();
+}
+
+impl Ledger {
+    /// Number of occurrences on or before `date`
+    fn count_until(&self, date: NaiveDate) -> usize {
+        let date = date.format("%Y-%m-%d").to_string();
+        self.occurrences
+            .partition_point(|o| o.occurrence_date.as_str() <= date.as_str())
+    }
+
+    /// Occurrences left unconverted on or before `date`
+    fn unconverted_until(&self, date: NaiveDate) -> Vec<UnconvertedOccurrence> {
+        let date = date.format("%Y-%m-%d").to_string();
+        let count = self
+            .unconverted
+            .partition_point(|o| o.occurrence_date.as_str() <= date.as_str());
+        self.unconverted[..count].to_vec()
+    }
+
+    /// Copy of the ledger limited to occurrences on or before `date`
+    fn until(&self, date: NaiveDate) -> Ledger {
+        let count = self.count_until(date);
+        Ledger {
+            participants: self.participants.clone(),
+            pool_participants: self.pool_participants.clone(),
+            base_currency: self.base_currency.clone(),
+            occurrences: self.occurrences[..count].to_vec(),
+            shares: self.shares[..count].to_vec(),
+            unconverted: self.unconverted_until(date),
+            unitized: self.unitized.clone(),
+        }
+    }
+
+    /// Empty running state of one pool, in units when the pool is unitized
+    fn pool_ledger(&self, pool_id: i64, keep_breakdowns: bool) -> PoolLedger {
+        let mut ledger = PoolLedger::new(pool_id, keep_breakdowns);
+        ledger.units = self.unitized.get(&pool_id).cloned().map(PoolUnits::new);
+        ledger
+    }
+
+    /// Empty running state of every pool
+    fn pool_ledgers(&self, keep_breakdowns: bool) -> Vec<PoolLedger> {
+        self.participants
+            .iter()
+            .filter(|(_, _, account_type)| account_type == "pool")
+            .map(|(id, _, _)| self.pool_ledger(*id, keep_breakdowns))
+            .collect()
+    }
+}
+
+/// An expanded ledger with running totals checkpointed at the start of every
+/// month that has occurrences
+struct CachedLedger {
+    ledger: Ledger,
+    checkpoints: Vec<Checkpoint>,
+}
+
+/// Paid/owed totals and pool state before the first occurrence on or after `date`
+#[derive(Clone)]
+struct Checkpoint {
+    date: NaiveDate,
+    // Number of occurrences already applied
+    applied: usize,
+    paid: HashMap<i64, Money>,
+    owed: HashMap<i64, Money>,
+    pools: Vec<PoolLedger>,
+}
+
+impl Checkpoint {
+    fn apply(
+        &mut self,
+        occurrence: &PaymentOccurrence,
+        shares: &[OccurrenceShare],
+        pool_participants: &HashSet<i64>,
+    ) {
+        apply_occurrence(
+            occurrence,
+            shares,
+            pool_participants,
+            &mut self.paid,
+            &mut self.owed,
+            None,
+        );
+        for pool_ledger in &mut self.pools {
+            pool_ledger.apply(occurrence, shares);
+        }
+        self.applied += 1;
+    }
+}
+
+impl CachedLedger {
+    fn new(ledger: Ledger) -> Self {
+        let mut state = Checkpoint {
+            date: NaiveDate::MIN,
+            applied: 0,
+            paid: HashMap::new(),
+            owed: HashMap::new(),
+            pools: ledger.pool_ledgers(false),
+        };
+        let mut checkpoints: Vec<Checkpoint> = Vec::new();
+
+        for (occurrence, shares) in ledger.occurrences.iter().zip(&ledger.shares) {
+            let month_start =
+                parse_date(&occurrence.occurrence_date).and_then(|date| date.with_day(1));
+            if let Some(month_start) = month_start {
+                if checkpoints.last().is_none_or(|c| c.date < month_start) {
+                    checkpoints.push(Checkpoint {
+                        date: month_start,
+                        ..state.clone()
+                    });
+                }
+            }
+            state.apply(occurrence, shares, &ledger.pool_participants);
+        }
+
+        Self {
+            ledger,
+            checkpoints,
+        }
+    }
 
-    let mut pool_ownerships: Vec<PoolOwnership> = Vec::new();
+    /// Latest checkpoint on or before `date`, or the empty starting state
+    fn checkpoint_before(&self, date: NaiveDate) -> Checkpoint {
+        let idx = self.checkpoints.partition_point(|c| c.date <= date);
+        match idx.checked_sub(1) {
+            Some(idx) => self.checkpoints[idx].clone(),
+            None => Checkpoint {
+                date: NaiveDate::MIN,
+                applied: 0,
+                paid: HashMap::new(),
+                owed: HashMap::new(),
+                pools: self.ledger.pool_ledgers(false),
+            },
+        }
+    }
+}
 
-    for (pool_id, pool_name) in pool_account_list {
-        // Track contributions to pool (deposited) and consumption from pool (expenses)
-        //
-        // Pool ownership is affected by:
-        // 1. EXTERNAL expenses where pool is payer: decreases contributor ownership (consumption)
-        // 2. EXTERNAL expenses where pool is contributor: increases payer's ownership
-        // 3. INTERNAL transfers TO pool: increases sender's ownership (deposit)
-        // 4. INTERNAL transfers FROM pool: decreases receiver's ownership (withdrawal)
-        //
-        // Dual ledger tracking:
-        // - affects_balance=true transactions affect actual pool balance (ownership_map)
-        // - affects_payer_expectation=true: when payer is a pool, reduces payer pool's expected minimum
-        // - affects_receiver_expectation=true: when receiver is a pool, increases receiver pool's expected minimum
-
-        // Track ownership amounts and transaction breakdowns for ACTUAL balance
-        // HashMap<participant_id, (contributed_total, consumed_total, contributed_breakdown, consumed_breakdown)>
-        let mut ownership_map: HashMap<
-            i64,
-            (
-                f64,
-                f64,
-                Vec<PairwisePaymentBreakdown>,
-                Vec<PairwisePaymentBreakdown>,
-            ),
-        > = HashMap::new();
-
-        // Track expected minimum separately
-        let mut expected_minimum: f64 = 0.0;
-
-        for occurrence in &all_occurrences {
-            // Determine which ledger(s) this occurrence affects
-            let affects_balance = occurrence.affects_balance;
-
-            // Calculate expected minimum delta based on separate payer/receiver flags
-            // For withdrawals from this pool (payer = pool_id): affects_payer_expectation reduces expected minimum
-            // For deposits to this pool (receiver = pool_id): affects_receiver_expectation increases expected minimum
-            if let Some(receiver_id) = occurrence.receiver_account_id {
-                // Internal transfer
-                if let Some(payer_id) = occurrence.payer_id {
-                    if receiver_id == pool_id && payer_id != pool_id {
-                        // Transfer TO this pool: receiver_expectation affects expected min
-                        if occurrence.affects_receiver_expectation {
-                            expected_minimum += occurrence.amount;
-                        }
-                    } else if payer_id == pool_id && receiver_id != pool_id {
-                        // Transfer FROM this pool: payer_expectation affects expected min
-                        if occurrence.affects_payer_expectation {
-                            expected_minimum -= occurrence.amount;
-                        }
-                    }
-                } else if occurrence.payer_id.is_none() && receiver_id == pool_id {
-                    // External inflow to this pool: receiver_expectation affects expected min
-                    if occurrence.affects_receiver_expectation {
-                        expected_minimum += occurrence.amount;
-                    }
-                }
+/// Paid, owed and net totals of one participant
+fn participant_balance(
+    id: i64,
+    name: &str,
+    paid_map: &HashMap<i64, Money>,
+    owed_map: &HashMap<i64, Money>,
+) -> ParticipantBalance {
+    let total_paid = paid_map.get(&id).copied().unwrap_or(Money::ZERO);
+    let total_owed = owed_map.get(&id).copied().unwrap_or(Money::ZERO);
+    ParticipantBalance {
+        participant_id: id,
+        participant_name: name.to_string(),
+        total_paid,
+        total_owed,
+        net_balance: total_paid - total_owed,
+    }
+}
+
+/// Balances of all participants, sorted by net balance
+fn participant_balances(
+    participants: &[(i64, String, String)],
+    paid_map: &HashMap<i64, Money>,
+    owed_map: &HashMap<i64, Money>,
+) -> Vec<ParticipantBalance> {
+    let mut balances: Vec<ParticipantBalance> = participants
+        .iter()
+        .map(|(id, name, _)| participant_balance(*id, name, paid_map, owed_map))
+        .collect();
+    balances.sort_by_key(|b| b.net_balance);
+    balances
+}
+
+/// Running state of one pool: its expected minimum and, per participant, the
+/// amounts put in and taken out plus their own expected minimum
+#[derive(Clone)]
+struct PoolLedger {
+    pool_id: i64,
+    expected_minimum: Money,
+    members: HashMap<i64, PoolMemberLedger>,
+    // Breakdowns are only needed for the debt summary, not for forecasts
+    keep_breakdowns: bool,
+    // Set for a unitized pool
+    units: Option<PoolUnits>,
+}
+
+/// Unit accounting of a unitized pool: deposits buy units and withdrawals
+/// sell them at the current unit value, which valuations move as their
+/// dates pass
+#[derive(Clone)]
+struct PoolUnits {
+    value: Money,
+    units: f64,
+    valuations: Arc<[PoolValuation]>,
+    // Index of the first valuation not applied yet
+    next: usize,
+}
+
+/// Units below this are treated as none left
+const UNIT_EPSILON: f64 = 1e-9;
+
+impl PoolUnits {
+    fn new(valuations: Arc<[PoolValuation]>) -> Self {
+        Self {
+            value: Money::ZERO,
+            units: 0.0,
+            valuations,
+            next: 0,
+        }
+    }
+
+    /// Value of one unit; a pool with no units (or nothing left) starts
+    /// again at 1
+    fn unit_value(&self) -> f64 {
+        if self.units > UNIT_EPSILON && self.value.is_positive() {
+            self.value.to_f64() / self.units
+        } else {
+            1.0
+        }
+    }
+
+    /// Units `amount` buys (or, negative, sells) at the current unit value
+    fn trade(&mut self, amount: Money) -> f64 {
+        let units = amount.to_f64() / self.unit_value();
+        self.value += amount;
+        self.units += units;
+        units
+    }
+
+    /// Apply the valuations dated before `date`, or on it too when `inclusive`
+    fn revalue(&mut self, date: &str, inclusive: bool) {
+        while let Some(valuation) = self.valuations.get(self.next) {
+            let due = if inclusive {
+                valuation.valuation_date.as_str() <= date
             } else {
-                // External expense
-                if let Some(payer_id) = occurrence.payer_id {
-                    if payer_id == pool_id {
-                        // Pool paying external expense: payer_expectation affects expected min
-                        if occurrence.affects_payer_expectation {
-                            expected_minimum -= occurrence.amount;
-                        }
-                    }
-                }
+                valuation.valuation_date.as_str() < date
+            };
+            if !due {
+                break;
             }
-
-            // Skip actual balance tracking if doesn't affect balance
-            if !affects_balance {
+            self.next += 1;
+            // Nobody owns a gain made while no units are out
+            if self.units <= UNIT_EPSILON {
                 continue;
             }
-
-            // Handle internal transfers (receiver_account_id IS NOT NULL)
-            if let Some(receiver_id) = occurrence.receiver_account_id {
-                if let Some(payer_id) = occurrence.payer_id {
-                    if receiver_id == pool_id && payer_id != pool_id {
-                        // Internal transfer TO pool: payer's ownership increases
-                        let entry = ownership_map.entry(payer_id).or_insert((
-                            0.0,
-                            0.0,
-                            Vec::new(),
-                            Vec::new(),
-                        ));
-                        entry.0 += occurrence.amount; // contributed (deposited to pool)
-                        entry.2.push(PairwisePaymentBreakdown {
-                            payment_id: occurrence.payment_id,
-                            description: occurrence.description.clone(),
-                            occurrence_date: occurrence.occurrence_date.clone(),
-                            amount: occurrence.amount,
-                        });
-                    } else if payer_id == pool_id && receiver_id != pool_id {
-                        // Internal transfer FROM pool: receiver's ownership decreases
-                        let entry = ownership_map.entry(receiver_id).or_insert((
-                            0.0,
-                            0.0,
-                            Vec::new(),
-                            Vec::new(),
-                        ));
-                        entry.1 += occurrence.amount; // consumed (withdrawn from pool)
-                        entry.3.push(PairwisePaymentBreakdown {
-                            payment_id: occurrence.payment_id,
-                            description: occurrence.description.clone(),
-                            occurrence_date: occurrence.occurrence_date.clone(),
-                            amount: occurrence.amount,
-                        });
-                    }
-                } else if occurrence.payer_id.is_none() && receiver_id == pool_id {
-                    // External inflow to pool: each contributor's ownership increases
-                    if let Some(contribs) = contribution_map.get(&occurrence.payment_id) {
-                        for (contributor_id, amount) in contribs {
-                            if *contributor_id != pool_id {
-                                let entry = ownership_map.entry(*contributor_id).or_insert((
-                                    0.0,
-                                    0.0,
-                                    Vec::new(),
-                                    Vec::new(),
-                                ));
-                                entry.0 += amount; // contributed (ownership increases)
-                                entry.2.push(PairwisePaymentBreakdown {
-                                    payment_id: occurrence.payment_id,
-                                    description: occurrence.description.clone(),
-                                    occurrence_date: occurrence.occurrence_date.clone(),
-                                    amount: *amount,
-                                });
-                            }
-                        }
-                    }
-                }
-                continue; // Skip contribution-based logic for internal transfers
+            match valuation.kind() {
+                Some(ValuationKind::Value) => self.value = valuation.amount,
+                Some(ValuationKind::Interest) => self.value += valuation.amount,
+                None => {}
             }
+        }
+    }
 
-            // Handle external expenses (receiver_account_id IS NULL)
-            if let Some(contribs) = contribution_map.get(&occurrence.payment_id) {
-                // Check if pool is involved in this payment
-                let pool_contrib = contribs.iter().find(|(pid, _)| *pid == pool_id);
-
-                if let Some(payer_id) = occurrence.payer_id {
-                    if payer_id == pool_id {
-                        // Pool is the payer: each contributor's ownership decreases
-                        for (contributor_id, amount) in contribs {
-                            if *contributor_id != pool_id {
-                                let entry = ownership_map.entry(*contributor_id).or_insert((
-                                    0.0,
-                                    0.0,
-                                    Vec::new(),
-                                    Vec::new(),
-                                ));
-                                entry.1 += amount; // consumed
-                                entry.3.push(PairwisePaymentBreakdown {
-                                    payment_id: occurrence.payment_id,
-                                    description: occurrence.description.clone(),
-                                    occurrence_date: occurrence.occurrence_date.clone(),
-                                    amount: *amount,
-                                });
-                            }
-                        }
-                    } else if let Some((_, pool_amount)) = pool_contrib {
-                        // Pool is a contributor: the payer's ownership increases
-                        let entry = ownership_map.entry(payer_id).or_insert((
-                            0.0,
-                            0.0,
-                            Vec::new(),
-                            Vec::new(),
-                        ));
-                        entry.0 += pool_amount; // contributed
-                        entry.2.push(PairwisePaymentBreakdown {
-                            payment_id: occurrence.payment_id,
-                            description: occurrence.description.clone(),
-                            occurrence_date: occurrence.occurrence_date.clone(),
-                            amount: *pool_amount,
-                        });
-                    }
-                }
-            }
+    /// Part of the pool's value `units` are worth
+    fn worth(&self, units: f64) -> Money {
+        if self.units.abs() <= UNIT_EPSILON {
+            return Money::ZERO;
         }
+        self.value.mul_ratio(units, self.units)
+    }
+}
+
+/// Pool state on one date; members map to (ownership, expected minimum)
+struct PoolSnapshot {
+    balance: Money,
+    expected_minimum: Money,
+    members: HashMap<i64, (Money, Money)>,
+}
+
+#[derive(Debug, Default, Clone)]
+struct PoolMemberLedger {
+    contributed: Money,
+    consumed: Money,
+    expected_minimum: Money,
+    // Units held, in a unitized pool
+    units: f64,
+    contributed_breakdown: Vec<PairwisePaymentBreakdown>,
+    consumed_breakdown: Vec<PairwisePaymentBreakdown>,
+}
+
+impl PoolMemberLedger {
+    fn ownership(&self) -> Money {
+        self.contributed - self.consumed
+    }
+}
+
+impl PoolLedger {
+    /// Ownership summary of the pool for its non-pool participants
+    fn into_ownership(
+        mut self,
+        pool_name: String,
+        participants: &[(i64, String, String)],
+        through: NaiveDate,
+    ) -> PoolOwnership {
+        self.revalue(&through.format("%Y-%m-%d").to_string(), true);
+        let pool_id = self.pool_id;
+        let unitized = self.units.is_some();
+        let unit_value = self.units.as_ref().map(PoolUnits::unit_value);
+        let ownerships: HashMap<i64, Money> = self
+            .members
+            .iter()
+            .map(|(id, member)| (*id, self.ownership_of(member)))
+            .collect();
+        let expected_minimum = self.expected_minimum;
 
         // Build ownership entries for non-pool participants
         let mut entries: Vec<PoolOwnershipEntry> = participants
             .iter()
             .filter(|(id, _, account_type)| *id != pool_id && account_type != "pool")
             .filter_map(|(id, name, _)| {
-                let (contributed, consumed, contributed_breakdown, consumed_breakdown) =
-                    ownership_map
-                        .get(id)
-                        .map(|(c, con, cb, conb)| (*c, *con, cb.clone(), conb.clone()))
-                        .unwrap_or((0.0, 0.0, Vec::new(), Vec::new()));
-                if contributed > 0.01 || consumed > 0.01 {
+                let member = self.members.remove(id)?;
+                if member.contributed.is_positive()
+                    || member.consumed.is_positive()
+                    || !member.expected_minimum.is_zero()
+                {
+                    let ownership = ownerships[id];
                     Some(PoolOwnershipEntry {
                         participant_id: *id,
                         participant_name: name.clone(),
-                        contributed,
-                        consumed,
-                        ownership: contributed - consumed,
-                        contributed_breakdown,
-                        consumed_breakdown,
+                        contributed: member.contributed,
+                        consumed: member.consumed,
+                        ownership,
+                        units: unitized.then_some(member.units),
+                        expected_minimum: member.expected_minimum,
+                        shortfall: Some(member.expected_minimum - ownership)
+                            .filter(Money::is_positive),
+                        contributed_breakdown: member.contributed_breakdown,
+                        consumed_breakdown: member.consumed_breakdown,
                     })
                 } else {
                     None
@@ -585,13 +2051,9 @@ pub async fn calculate_debts_at_date(
             .collect();
 
         // Sort by ownership descending
-        entries.sort_by(|a, b| {
-            b.ownership
-                .partial_cmp(&a.ownership)
-                .unwrap_or(std::cmp::Ordering::Equal)
-        });
+        entries.sort_by_key(|e| std::cmp::Reverse(e.ownership));
 
-        let total_balance: f64 = entries.iter().map(|e| e.ownership).sum();
+        let total_balance: Money = entries.iter().map(|e| e.ownership).sum();
         let is_below_expected = total_balance < expected_minimum;
         let shortfall = if is_below_expected {
             Some(expected_minimum - total_balance)
@@ -599,471 +2061,830 @@ pub async fn calculate_debts_at_date(
             None
         };
 
-        pool_ownerships.push(PoolOwnership {
+        PoolOwnership {
             pool_id,
             pool_name,
             entries,
             total_balance,
+            unit_value,
             expected_minimum,
             is_below_expected,
             shortfall,
-        });
+        }
     }
 
-    // Calculate direct-only settlements based on pairwise relationships
-    // Pass balances to ensure we respect net balance constraints
-    let direct_settlements =
-        calculate_direct_settlements(&pairwise_balances, &balances, &pool_participants);
+    fn new(pool_id: i64, keep_breakdowns: bool) -> Self {
+        Self {
+            pool_id,
+            expected_minimum: Money::ZERO,
+            members: HashMap::new(),
+            keep_breakdowns,
+            units: None,
+        }
+    }
 
-    Ok(DebtSummary {
-        balances,
-        settlements,
-        direct_settlements,
-        target_date: target_date.to_string(),
-        occurrences: all_occurrences,
-        pairwise_balances,
-        pool_ownerships,
-    })
-}
+    /// What a member owns: contributed less consumed, or what their units
+    /// are worth in a unitized pool
+    fn ownership_of(&self, member: &PoolMemberLedger) -> Money {
+        match &self.units {
+            Some(units) => units.worth(member.units),
+            None => member.ownership(),
+        }
+    }
 
-/// Generate all occurrences of a payment up to target_date
-fn generate_payment_occurrences(
-    payment: &Payment,
-    target_date: NaiveDate,
-) -> Vec<PaymentOccurrence> {
-    let mut occurrences = Vec::new();
+    /// Apply the valuations of a unitized pool dated before `date`, or on it
+    /// too when `inclusive`
+    fn revalue(&mut self, date: &str, inclusive: bool) {
+        if let Some(units) = &mut self.units {
+            units.revalue(date, inclusive);
+        }
+    }
 
-    let start_date = match parse_date(&payment.payment_date) {
-        Some(d) => d,
-        None => return occurrences,
-    };
+    /// Whether the occurrence moves money or expectations of this pool
+    fn touches(&self, occurrence: &PaymentOccurrence, shares: &[OccurrenceShare]) -> bool {
+        occurrence.payer_id == Some(self.pool_id)
+            || occurrence.payers.iter().any(|(id, _)| *id == self.pool_id)
+            || occurrence.receiver_account_id == Some(self.pool_id)
+            || shares.iter().any(|s| s.participant_id == self.pool_id)
+    }
 
-    // If start date is after target, no occurrences
-    if start_date > target_date {
-        return occurrences;
+    /// Balances and expected minimums at the end of `date`
+    fn snapshot(&mut self, date: NaiveDate, pool_participants: &HashSet<i64>) -> PoolSnapshot {
+        self.revalue(&date.format("%Y-%m-%d").to_string(), true);
+        PoolSnapshot {
+            balance: self.balance(pool_participants),
+            expected_minimum: self.expected_minimum,
+            members: self
+                .members
+                .iter()
+                .filter(|(id, _)| !pool_participants.contains(id))
+                .map(|(id, member)| (*id, (self.ownership_of(member), member.expected_minimum)))
+                .collect(),
+        }
     }
 
-    if !payment.is_recurring {
-        // Single payment - just one occurrence
-        occurrences.push(PaymentOccurrence {
-            payment_id: payment.id,
-            description: payment.description.clone(),
-            amount: payment.amount,
-            occurrence_date: payment.payment_date.clone(),
-            payer_id: payment.payer_id,
-            is_recurring: false,
-            receiver_account_id: payment.receiver_account_id,
-            is_final: payment.is_final,
-            affects_balance: payment.affects_balance,
-            affects_payer_expectation: payment.affects_payer_expectation,
-            affects_receiver_expectation: payment.affects_receiver_expectation,
-        });
-        return occurrences;
+    /// Actual pool balance: the sum of its non-pool members' ownership
+    fn balance(&self, pool_participants: &HashSet<i64>) -> Money {
+        self.members
+            .iter()
+            .filter(|(id, _)| !pool_participants.contains(id))
+            .map(|(_, member)| self.ownership_of(member))
+            .sum()
     }
 
-    // Recurring payment - generate occurrences
-    let recurrence_type = payment.recurrence_type.as_deref().unwrap_or("monthly");
-    let interval = payment.recurrence_interval.unwrap_or(1) as u32;
-    let end_date = payment
-        .recurrence_end_date
-        .as_ref()
-        .and_then(|d| parse_date(d))
-        .unwrap_or(target_date);
-    let effective_end = end_date.min(target_date);
-
-    // Handle enhanced recurrence patterns
-    match recurrence_type {
-        "weekly" => {
-            // Check for weekday selection pattern
-            if let Some(weekdays_json) = &payment.recurrence_weekdays {
-                return generate_weekly_with_weekdays(
-                    payment,
-                    start_date,
-                    effective_end,
-                    interval,
-                    weekdays_json,
-                );
-            }
+    /// `amount` split over the non-pool members in proportion to what they
+    /// own, or nothing when nobody owns anything
+    fn owner_parts(&self, amount: Money, pool_participants: &HashSet<i64>) -> Vec<(i64, Money)> {
+        let mut weights: Vec<(i64, f64)> = self
+            .members
+            .iter()
+            .map(|(id, member)| (*id, self.ownership_of(member)))
+            .filter(|(id, ownership)| !pool_participants.contains(id) && ownership.is_positive())
+            .map(|(id, ownership)| (id, ownership.to_f64()))
+            .collect();
+        if weights.is_empty() {
+            return Vec::new();
         }
-        "monthly" if interval == 1 => {
-            // Check for monthday selection pattern (only valid when interval = 1)
-            if let Some(monthdays_json) = &payment.recurrence_monthdays {
-                return generate_monthly_with_monthdays(
-                    payment,
-                    start_date,
-                    effective_end,
-                    monthdays_json,
-                );
-            }
+        // Same ties on every run, whatever the map order
+        weights.sort_by_key(|(id, _)| *id);
+        let parts = amount.allocate(&weights);
+        weights.iter().map(|(id, _)| *id).zip(parts).collect()
+    }
+
+    fn contribute(
+        &mut self,
+        participant_id: i64,
+        amount: Money,
+        breakdown: impl FnOnce() -> PairwisePaymentBreakdown,
+    ) {
+        let keep = self.keep_breakdowns;
+        let units = self.units.as_mut().map_or(0.0, |units| units.trade(amount));
+        let member = self.members.entry(participant_id).or_default();
+        member.contributed += amount;
+        member.units += units;
+        if keep {
+            member.contributed_breakdown.push(breakdown());
         }
-        "yearly" if interval == 1 => {
-            // Check for month selection pattern (only valid when interval = 1)
-            if let Some(months_json) = &payment.recurrence_months {
-                return generate_yearly_with_months(
-                    payment,
-                    start_date,
-                    effective_end,
-                    months_json,
-                    payment.recurrence_monthdays.as_deref(),
-                );
-            }
+    }
+
+    fn consume(
+        &mut self,
+        participant_id: i64,
+        amount: Money,
+        breakdown: impl FnOnce() -> PairwisePaymentBreakdown,
+    ) {
+        let keep = self.keep_breakdowns;
+        let units = self
+            .units
+            .as_mut()
+            .map_or(0.0, |units| units.trade(-amount));
+        let member = self.members.entry(participant_id).or_default();
+        member.consumed += amount;
+        member.units += units;
+        if keep {
+            member.consumed_breakdown.push(breakdown());
         }
-        _ => {}
     }
 
-    // Legacy/simple recurrence - every X periods
-    // Also handle deprecated times_per for backwards compatibility
-    let times_per = payment.recurrence_times_per;
-    let (effective_interval, effective_type) = if let Some(times) = times_per {
-        calculate_times_per_interval(recurrence_type, interval, times as u32)
-    } else {
-        (interval, recurrence_type.to_string())
-    };
+    fn expect(&mut self, participant_id: i64, amount: Money) {
+        self.members
+            .entry(participant_id)
+            .or_default()
+            .expected_minimum += amount;
+    }
 
-    let mut current = start_date;
-    while current <= effective_end {
-        occurrences.push(PaymentOccurrence {
-            payment_id: payment.id,
-            description: payment.description.clone(),
-            amount: payment.amount,
-            occurrence_date: current.format("%Y-%m-%d").to_string(),
-            payer_id: payment.payer_id,
-            is_recurring: true,
-            receiver_account_id: payment.receiver_account_id,
-            is_final: payment.is_final,
-            affects_balance: payment.affects_balance,
-            affects_payer_expectation: payment.affects_payer_expectation,
-            affects_receiver_expectation: payment.affects_receiver_expectation,
-        });
+    /// Apply one occurrence to this pool.
+    ///
+    /// Pool ownership is affected by:
+    /// 1. EXTERNAL expenses where pool is payer: decreases contributor ownership (consumption)
+    /// 2. EXTERNAL expenses where pool is contributor: increases payer's ownership
+    /// 3. INTERNAL transfers TO pool: increases sender's ownership (deposit)
+    /// 4. INTERNAL transfers FROM pool: decreases receiver's ownership (withdrawal)
+    /// 5. Transfers between pools: moves each owner's part from one pool to the other
+    ///
+    /// Dual ledger tracking:
+    /// - affects_balance=true transactions affect actual pool balance (ownership)
+    /// - affects_payer_expectation=true: when payer is a pool, reduces payer pool's expected minimum
+    /// - affects_receiver_expectation=true: when receiver is a pool, increases receiver pool's expected minimum
+    ///
+    /// Each expectation change is attributed to the participants whose ownership
+    /// the same transaction would move, giving per-user expected minimums.
+    fn apply(&mut self, occurrence: &PaymentOccurrence, shares: &[OccurrenceShare]) {
+        let pool_id = self.pool_id;
+        let amount = occurrence.amount;
+        // Deposits and withdrawals trade at the unit value before the day's
+        // valuations
+        self.revalue(&occurrence.occurrence_date, false);
+
+        // Expected minimum, based on separate payer/receiver flags
+        if let Some(receiver_id) = occurrence.receiver_account_id {
+            // Internal transfer
+            if let Some(payer_id) = occurrence.payer_id {
+                if receiver_id == pool_id && payer_id != pool_id {
+                    // Transfer TO this pool: receiver_expectation affects expected min
+                    if occurrence.affects_receiver_expectation {
+                        self.expected_minimum += amount;
+                        if occurrence.pool_parts.is_empty() {
+                            self.expect(payer_id, amount);
+                        }
+                        for (owner_id, part) in &occurrence.pool_parts {
+                            self.expect(*owner_id, *part);
+                        }
+                    }
+                } else if payer_id == pool_id && receiver_id != pool_id {
+                    // Transfer FROM this pool: payer_expectation affects expected min
+                    if occurrence.affects_payer_expectation {
+                        self.expected_minimum -= amount;
+                        if occurrence.pool_parts.is_empty() {
+                            self.expect(receiver_id, -amount);
+                        }
+                        for (owner_id, part) in &occurrence.pool_parts {
+                            self.expect(*owner_id, -*part);
+                        }
+                    }
+                }
+            } else if receiver_id == pool_id {
+                // External inflow to this pool: receiver_expectation affects expected min
+                if occurrence.affects_receiver_expectation {
+                    self.expected_minimum += amount;
+                    for share in shares.iter().filter(|s| s.participant_id != pool_id) {
+                        self.expect(share.participant_id, share.amount);
+                    }
+                }
+            }
+        }
 
-        current = match add_interval(current, &effective_type, effective_interval) {
-            Some(d) => d,
-            None => break,
+        // External expenses: the payers and the part of each share they paid
+        let paid_by = match occurrence.receiver_account_id {
+            None if self.touches(occurrence, shares) => occurrence.paid_by(),
+            _ => Vec::new(),
         };
-    }
+        let parts = payer_parts(&paid_by, shares);
+        let pool_paid = paid_by
+            .iter()
+            .zip(&parts)
+            .find(|((payer_id, _), _)| *payer_id == pool_id);
+
+        if let Some(((_, paid), parts)) = pool_paid {
+            // Pool paying external expense: payer_expectation affects expected min
+            if occurrence.affects_payer_expectation {
+                self.expected_minimum -= *paid;
+                for (share, part) in shares.iter().zip(parts) {
+                    if share.participant_id != pool_id {
+                        self.expect(share.participant_id, -*part);
+                    }
+                }
+            }
+        }
+
+        // Skip actual balance tracking if doesn't affect balance
+        if !occurrence.affects_balance {
+            return;
+        }
+
+        // Handle internal transfers (receiver_account_id IS NOT NULL)
+        if let Some(receiver_id) = occurrence.receiver_account_id {
+            if let Some(payer_id) = occurrence.payer_id {
+                if !occurrence.pool_parts.is_empty() {
+                    // Pool-to-pool transfer: the sending pool's owners carry
+                    // their parts over to the receiving pool
+                    for (owner_id, part) in &occurrence.pool_parts {
+                        if receiver_id == pool_id {
+                            self.contribute(*owner_id, *part, || occurrence.part_breakdown(*part));
+                        } else if payer_id == pool_id {
+                            self.consume(*owner_id, *part, || occurrence.part_breakdown(*part));
+                        }
+                    }
+                } else if receiver_id == pool_id && payer_id != pool_id {
+                    // Internal transfer TO pool: payer's ownership increases
+                    self.contribute(payer_id, amount, || occurrence.breakdown());
+                } else if payer_id == pool_id && receiver_id != pool_id {
+                    // Internal transfer FROM pool: receiver's ownership decreases
+                    self.consume(receiver_id, amount, || occurrence.breakdown());
+                }
+            } else if receiver_id == pool_id {
+                // External inflow to pool: each contributor's ownership increases
+                for share in shares.iter().filter(|s| s.participant_id != pool_id) {
+                    self.contribute(share.participant_id, share.amount, || {
+                        share.breakdown(occurrence)
+                    });
+                }
+            }
+            return;
+        }
 
-    occurrences
+        // Handle external expenses (receiver_account_id IS NULL), for the part
+        // of each share every payer paid
+        for ((payer_id, _), parts) in paid_by.iter().zip(&parts) {
+            if *payer_id == pool_id {
+                // Pool is the payer: each contributor's ownership decreases
+                for (share, part) in shares.iter().zip(parts) {
+                    if share.participant_id != pool_id {
+                        self.consume(share.participant_id, *part, || {
+                            share.part_breakdown(occurrence, *part)
+                        });
+                    }
+                }
+            } else if let Some((pool_share, part)) = shares
+                .iter()
+                .zip(parts)
+                .find(|(s, _)| s.participant_id == pool_id)
+            {
+                // Pool is a contributor: the payer's ownership increases
+                self.contribute(*payer_id, *part, || {
+                    pool_share.part_breakdown(occurrence, *part)
+                });
+            }
+        }
+    }
 }
 
-/// Generate weekly occurrences with specific weekday selections
-/// weekdays_json format: [[1,3],[5]] - array of arrays, one per week in cycle
-/// Each inner array contains weekday numbers (0=Sun, 1=Mon, ..., 6=Sat)
-fn generate_weekly_with_weekdays(
-    payment: &Payment,
-    start_date: NaiveDate,
-    end_date: NaiveDate,
-    interval: u32,
-    weekdays_json: &str,
-) -> Vec<PaymentOccurrence> {
-    let mut occurrences = Vec::new();
+/// Pairwise amounts: (payer_id, contributor_id) -> (total_amount, breakdown)
+type PairwiseMap = HashMap<(i64, i64), (Money, Vec<PairwisePaymentBreakdown>)>;
+
+/// Add one occurrence to the paid/owed totals and, when given, to the pairwise map.
+///
+/// Transfer types:
+/// 1. External expense (receiver_account_id IS NULL): Normal expense, affects settlements
+/// 2. User → User transfer: Direct payment, affects settlements (reduces debt)
+/// 3. User → Pool transfer: Only affects pool ownership, NOT settlements
+/// 4. Pool → User transfer: Only affects pool ownership, NOT settlements
+fn apply_occurrence(
+    occurrence: &PaymentOccurrence,
+    shares: &[OccurrenceShare],
+    pool_participants: &HashSet<i64>,
+    paid_map: &mut HashMap<i64, Money>,
+    owed_map: &mut HashMap<i64, Money>,
+    mut pairwise_map: Option<&mut PairwiseMap>,
+) {
+    // Check if this is a pool-related transfer (should not affect settlements)
+    if let Some(receiver_id) = occurrence.receiver_account_id {
+        let receiver_is_pool = pool_participants.contains(&receiver_id);
+        let payer_is_pool = occurrence
+            .payer_id
+            .map(|id| pool_participants.contains(&id))
+            .unwrap_or(false);
 
-    // Parse weekdays JSON - array of arrays
-    let week_patterns: Vec<Vec<u32>> = match serde_json::from_str(weekdays_json) {
-        Ok(p) => p,
-        Err(_) => return occurrences, // Invalid JSON, return empty
-    };
+        if receiver_is_pool || payer_is_pool {
+            // Pool transfer - skip for settlement calculations (handled in pool ownership)
+            return;
+        }
 
-    if week_patterns.is_empty() {
-        return occurrences;
-    }
-
-    // Calculate the start of the week containing start_date
-    let start_weekday = start_date.weekday().num_days_from_sunday();
-    let week_start = start_date - chrono::Duration::days(start_weekday as i64);
-
-    // Total weeks in the pattern cycle
-    let cycle_weeks = interval;
-
-    // Iterate through weeks from start
-    let mut cycle_week = 0u32; // Which week in the cycle (0-indexed)
-    let mut current_week_start = week_start;
-
-    // Maximum iterations to prevent infinite loops (10 years of weeks)
-    let max_iterations = 52 * 10;
-    let mut iterations = 0;
-
-    while current_week_start <= end_date && iterations < max_iterations {
-        iterations += 1;
-
-        // Get the pattern for this week in the cycle
-        let pattern_idx = (cycle_week % week_patterns.len() as u32) as usize;
-        let weekdays = &week_patterns[pattern_idx];
-
-        // Generate occurrences for each selected weekday
-        for &weekday in weekdays {
-            let occurrence_date = current_week_start + chrono::Duration::days(weekday as i64);
-
-            // Must be >= start_date and <= end_date
-            if occurrence_date >= start_date && occurrence_date <= end_date {
-                occurrences.push(PaymentOccurrence {
-                    payment_id: payment.id,
-                    description: payment.description.clone(),
-                    amount: payment.amount,
-                    occurrence_date: occurrence_date.format("%Y-%m-%d").to_string(),
-                    payer_id: payment.payer_id,
-                    is_recurring: true,
-                    receiver_account_id: payment.receiver_account_id,
-                    is_final: payment.is_final,
-                    affects_balance: payment.affects_balance,
-                    affects_payer_expectation: payment.affects_payer_expectation,
-                    affects_receiver_expectation: payment.affects_receiver_expectation,
-                });
+        // User-to-user transfer: treat as direct payment
+        // Payer gives money directly to receiver, reducing payer's debt to receiver
+        if let Some(payer_id) = occurrence.payer_id {
+            // Add to payer's "paid" total
+            *paid_map.entry(payer_id).or_insert(Money::ZERO) += occurrence.amount;
+
+            // Add to receiver's "owed" total (they received the money)
+            *owed_map.entry(receiver_id).or_insert(Money::ZERO) += occurrence.amount;
+
+            // Track pairwise: payer paid this amount directly to receiver
+            record_pairwise(
+                pairwise_map.as_deref_mut(),
+                (payer_id, receiver_id),
+                occurrence.amount,
+                || occurrence.breakdown(),
+            );
+        } else if occurrence.payer_id.is_none() {
+            // External inflow to user: receiver holds money for the group
+            // Receiver is debited (holds/owes the full amount)
+            *owed_map.entry(receiver_id).or_insert(Money::ZERO) += occurrence.amount;
+
+            // Contributors are credited (owed their share from receiver)
+            for share in shares {
+                *paid_map.entry(share.participant_id).or_insert(Money::ZERO) += share.amount;
+            }
+
+            // Track pairwise: each contributor is owed by receiver
+            for share in shares {
+                if share.participant_id != receiver_id {
+                    record_pairwise(
+                        pairwise_map.as_deref_mut(),
+                        (share.participant_id, receiver_id),
+                        share.amount,
+                        || share.breakdown(occurrence),
+                    );
+                }
             }
         }
+        return;
+    }
 
-        // Move to next week
-        cycle_week += 1;
+    // External expense (receiver_account_id IS NULL): each payer is credited
+    // what they paid and, unless a pool, is owed their part of every share
+    let Some(payer_id) = occurrence.payer_id else {
+        for share in shares {
+            *owed_map.entry(share.participant_id).or_insert(Money::ZERO) += share.amount;
+        }
+        return;
+    };
+    if occurrence.payers.is_empty() {
+        let payer_is_pool = pool_participants.contains(&payer_id);
+        *paid_map.entry(payer_id).or_insert(Money::ZERO) += occurrence.amount;
 
-        // After completing the cycle, skip weeks based on interval
-        if cycle_week >= cycle_weeks {
-            cycle_week = 0;
-            // Move forward by cycle_weeks weeks (already processed them)
+        // IMPORTANT: Only add to owed if the payer is a USER (not pool)
+        // When pool pays for expenses, the debt is owed TO the pool, which is
+        // tracked separately in pool ownership. Including pool-paid debts in
+        // owed_map would create an imbalance in user-to-user settlements since
+        // pool is excluded from settlement calculations.
+        if !payer_is_pool {
+            for share in shares {
+                // payer paid this amount for contributor
+                record_pairwise(
+                    pairwise_map.as_deref_mut(),
+                    (payer_id, share.participant_id),
+                    share.amount,
+                    || share.breakdown(occurrence),
+                );
+                *owed_map.entry(share.participant_id).or_insert(Money::ZERO) += share.amount;
+            }
         }
+        return;
+    }
 
-        current_week_start += chrono::Duration::weeks(1);
+    // Several payers: the same, for the part of each share each one paid
+    for ((payer_id, paid), parts) in occurrence
+        .payers
+        .iter()
+        .zip(payer_parts(&occurrence.payers, shares))
+    {
+        *paid_map.entry(*payer_id).or_insert(Money::ZERO) += *paid;
+        if pool_participants.contains(payer_id) {
+            continue;
+        }
+        for (share, part) in shares.iter().zip(parts) {
+            if part.is_zero() {
+                continue;
+            }
+            record_pairwise(
+                pairwise_map.as_deref_mut(),
+                (*payer_id, share.participant_id),
+                part,
+                || share.part_breakdown(occurrence, part),
+            );
+            *owed_map.entry(share.participant_id).or_insert(Money::ZERO) += part;
+        }
     }
+}
 
-    // Sort by date
-    occurrences.sort_by(|a, b| a.occurrence_date.cmp(&b.occurrence_date));
-    occurrences
+fn record_pairwise(
+    pairwise_map: Option<&mut PairwiseMap>,
+    key: (i64, i64),
+    amount: Money,
+    breakdown: impl FnOnce() -> PairwisePaymentBreakdown,
+) {
+    if let Some(map) = pairwise_map {
+        let entry = map.entry(key).or_insert((Money::ZERO, Vec::new()));
+        entry.0 += amount;
+        entry.1.push(breakdown());
+    }
 }
 
-/// Generate monthly occurrences on specific days
-/// monthdays_json format: [1, 15, 28] - array of day numbers
-fn generate_monthly_with_monthdays(
+/// Generate all occurrences of a payment up to target_date, shifted to
+/// business days of `calendar` if the payment asks for it
+fn generate_payment_occurrences(
     payment: &Payment,
-    start_date: NaiveDate,
-    end_date: NaiveDate,
-    monthdays_json: &str,
+    target_date: NaiveDate,
+    calendar: &BusinessCalendar,
 ) -> Vec<PaymentOccurrence> {
-    let mut occurrences = Vec::new();
-
-    // Parse monthdays JSON - array of day numbers
-    let monthdays: Vec<u32> = match serde_json::from_str(monthdays_json) {
-        Ok(d) => d,
-        Err(_) => return occurrences,
+    let start_date = match parse_date(&payment.payment_date) {
+        Some(d) => d,
+        None => return Vec::new(),
     };
 
-    if monthdays.is_empty() {
-        return occurrences;
+    // If start date is after target, no occurrences
+    if start_date > target_date {
+        return Vec::new();
     }
 
-    // Start from the month of start_date
-    let mut current_year = start_date.year();
-    let mut current_month = start_date.month();
-
-    // Maximum iterations (20 years of months)
-    let max_iterations = 12 * 20;
-    let mut iterations = 0;
-
-    while iterations < max_iterations {
-        iterations += 1;
-
-        // Get the last day of current month
-        let days_in_month = get_days_in_month(current_year, current_month);
-
-        // Generate occurrences for each selected day
-        for &day in &monthdays {
-            // Clamp day to valid range (handle dates > 28 for short months)
-            let actual_day = day.min(days_in_month);
-
-            if let Some(occurrence_date) =
-                NaiveDate::from_ymd_opt(current_year, current_month, actual_day)
-            {
-                // Must be >= start_date and <= end_date
-                if occurrence_date >= start_date && occurrence_date <= end_date {
-                    occurrences.push(PaymentOccurrence {
-                        payment_id: payment.id,
-                        description: payment.description.clone(),
-                        amount: payment.amount,
-                        occurrence_date: occurrence_date.format("%Y-%m-%d").to_string(),
-                        payer_id: payment.payer_id,
-                        is_recurring: true,
-                        receiver_account_id: payment.receiver_account_id,
-                        is_final: payment.is_final,
-                        affects_balance: payment.affects_balance,
-                        affects_payer_expectation: payment.affects_payer_expectation,
-                        affects_receiver_expectation: payment.affects_receiver_expectation,
-                    });
-                }
-            }
-        }
+    let Some(recurrence) = payment.recurrence() else {
+        // Single payment - just one occurrence
+        return vec![PaymentOccurrence::new(
+            payment,
+            payment.payment_date.clone(),
+            false,
+        )];
+    };
 
-        // Check if we've passed end_date
-        if let Some(first_of_next) = NaiveDate::from_ymd_opt(current_year, current_month, 1)
-            .and_then(|d| d.checked_add_months(Months::new(1)))
-        {
-            if first_of_next > end_date {
-                break;
-            }
-            current_year = first_of_next.year();
-            current_month = first_of_next.month();
-        } else {
-            break;
-        }
+    // An occurrence shifted back to a previous business day may come from
+    // after the target
+    let shift = payment.business_day_shift();
+    let horizon = match shift {
+        Some(_) => target_date
+            .checked_add_days(chrono::Days::new(MAX_SHIFT_DAYS))
+            .unwrap_or(target_date),
+        None => target_date,
+    };
+    let end_date = payment
+        .recurrence_end_date
+        .as_ref()
+        .and_then(|d| parse_date(d))
+        .map_or(horizon, |end| end.min(horizon));
+
+    let mut dates = expand_recurrence(&recurrence, start_date, end_date);
+    if let Some(shift) = shift {
+        dates = dates
+            .into_iter()
+            .map(|date| calendar.shift(date, shift))
+            .filter(|date| *date <= target_date)
+            .collect();
+        dates.dedup();
     }
 
-    // Sort by date
-    occurrences.sort_by(|a, b| a.occurrence_date.cmp(&b.occurrence_date));
-    occurrences
+    let schedule = payment.schedule();
+    dates
+        .into_iter()
+        .map(|date| {
+            let mut occurrence =
+                PaymentOccurrence::new(payment, date.format("%Y-%m-%d").to_string(), true);
+            if let Some(schedule) = &schedule {
+                occurrence.amount = schedule.amount_on(payment.amount, start_date, date);
+            }
+            occurrence
+        })
+        .collect()
 }
 
-/// Generate yearly occurrences in specific months (and optionally specific days)
-/// months_json format: [1, 6, 12] - array of month numbers (1-12)
-/// monthdays_json format: [1, 15] - array of day numbers (1-31), optional
-fn generate_yearly_with_months(
+/// Occurrences of a recurring payment up to `target` with its exceptions
+/// applied: skipped ones dropped, moved ones re-dated (including ones moved
+/// back from beyond `target`), changed amounts set and changed weights
+/// carried as the occurrence's own split
+fn apply_exceptions(
     payment: &Payment,
-    start_date: NaiveDate,
-    end_date: NaiveDate,
-    months_json: &str,
-    monthdays_json: Option<&str>,
+    target: NaiveDate,
+    calendar: &BusinessCalendar,
+    exceptions: &[OccurrenceException],
 ) -> Vec<PaymentOccurrence> {
-    let mut occurrences = Vec::new();
+    let by_date: HashMap<&str, &OccurrenceException> = exceptions
+        .iter()
+        .map(|e| (e.original_date.as_str(), e))
+        .collect();
+    let horizon = exceptions
+        .iter()
+        .filter(|e| {
+            e.moved_to
+                .as_deref()
+                .and_then(parse_date)
+                .is_some_and(|date| date <= target)
+        })
+        .filter_map(|e| parse_date(&e.original_date))
+        .fold(target, NaiveDate::max);
+    let target = target.format("%Y-%m-%d").to_string();
 
-    // Parse months JSON - array of month numbers
-    let months: Vec<u32> = match serde_json::from_str(months_json) {
-        Ok(m) => m,
-        Err(_) => return occurrences,
-    };
+    generate_payment_occurrences(payment, horizon, calendar)
+        .into_iter()
+        .filter_map(|mut occurrence| {
+            if let Some(exception) = by_date.get(occurrence.occurrence_date.as_str()) {
+                if exception.is_skipped {
+                    return None;
+                }
+                let original_date = occurrence.occurrence_date.clone();
+                if let Some(moved_to) = &exception.moved_to {
+                    occurrence.occurrence_date = moved_to.clone();
+                }
+                if let Some(amount) = exception.amount {
+                    occurrence.amount = amount;
+                }
+                if let Some(weights) = exception.weights() {
+                    let shares = occurrence.amount.allocate(&weights);
+                    occurrence.split =
+                        Some(weights.iter().map(|(id, _)| *id).zip(shares).collect());
+                }
+                occurrence.original_date = Some(original_date);
+            }
+            (occurrence.occurrence_date <= target).then_some(occurrence)
+        })
+        .collect()
+}
 
-    if months.is_empty() {
-        return occurrences;
+/// Split of an occurrence: its amount shared by the weights of the payment's
+/// profile in effect on its date, or in proportion to the payment's
+/// contributions. A recurring occurrence only goes to the contributors
+/// present on its date (pro-rated over its month if the payment asks for it).
+/// None when the payment's contributions apply as they are.
+fn occurrence_split(
+    occurrence: &PaymentOccurrence,
+    payment: &Payment,
+    contributions: &[(i64, Money)],
+    profiles: &ProfileWeights,
+    presence: &PresenceCalendar,
+) -> Option<Vec<(i64, Money)>> {
+    let date = parse_date(&occurrence.occurrence_date)?;
+    let profile = payment
+        .weight_profile_id
+        .and_then(|id| profiles.weights_on(id, date));
+    let unchanged = profile.is_none() && occurrence.amount == payment.amount;
+    if unchanged && (presence.is_empty() || !payment.is_recurring) {
+        return None;
     }
 
-    // Parse monthdays JSON if provided, otherwise use start_date's day
-    let monthdays: Vec<u32> = if let Some(json) = monthdays_json {
-        match serde_json::from_str(json) {
-            Ok(d) => d,
-            Err(_) => vec![start_date.day()],
-        }
-    } else {
-        vec![start_date.day()]
+    let full: Vec<(i64, f64)> = match profile {
+        Some(weights) => weights.to_vec(),
+        None => contributions
+            .iter()
+            .map(|(id, share)| (*id, share.to_f64()))
+            .collect(),
     };
+    let present: Vec<(i64, f64)> = full
+        .iter()
+        .map(|(id, weight)| {
+            let share = if payment.is_recurring {
+                presence.share(*id, date, payment.prorate_presence)
+            } else {
+                1.0
+            };
+            (*id, weight * share)
+        })
+        .filter(|(_, weight)| *weight > 0.0)
+        .collect();
 
-    // Start from the year of start_date
-    let mut current_year = start_date.year();
-
-    // Maximum iterations (50 years)
-    let max_iterations = 50;
-    let mut iterations = 0;
-
-    while iterations < max_iterations {
-        iterations += 1;
-
-        // Generate occurrences for each selected month
-        for &month in &months {
-            if !(1..=12).contains(&month) {
-                continue;
-            }
+    // Nobody present: the payment's contributors still share it
+    let weights = if present.is_empty() { &full } else { &present };
+    if unchanged && *weights == full {
+        return None;
+    }
+    let shares = occurrence.amount.allocate(weights);
+    Some(weights.iter().map(|(id, _)| *id).zip(shares).collect())
+}
 
-            // Get the last day of this month
-            let days_in_month = get_days_in_month(current_year, month);
+/// Whether the series of `payment` has an occurrence on `date`
+pub fn payment_occurs_on(payment: &Payment, date: NaiveDate, calendar: &BusinessCalendar) -> bool {
+    let date_str = date.format("%Y-%m-%d").to_string();
+    generate_payment_occurrences(payment, date, calendar)
+        .iter()
+        .any(|o| o.occurrence_date == date_str)
+}
 
-            // Generate occurrences for each selected day
-            for &day in &monthdays {
-                let actual_day = day.min(days_in_month);
+/// Dates of a recurrence set from `start` (the payment date) to `end` inclusive,
+/// sorted and without duplicates.
+///
+/// A rule with its own DTSTART is walked from there, so a series continued
+/// from a later payment date keeps its phase and its COUNT. RDATEs are added
+/// and EXDATEs removed after the rules are expanded.
+fn expand_recurrence(
+    recurrence: &RecurrenceSet,
+    start: NaiveDate,
+    end: NaiveDate,
+) -> Vec<NaiveDate> {
+    let mut dates: Vec<NaiveDate> = recurrence
+        .rules
+        .iter()
+        .flat_map(|rule| expand_rule(rule, rule.start.unwrap_or(start), end))
+        .chain(
+            recurrence
+                .rdates
+                .iter()
+                .copied()
+                .filter(|date| *date <= end),
+        )
+        .filter(|date| *date >= start && recurrence.exdates.binary_search(date).is_err())
+        .collect();
+    dates.sort_unstable();
+    dates.dedup();
+    dates
+}
 
-                if let Some(occurrence_date) =
-                    NaiveDate::from_ymd_opt(current_year, month, actual_day)
-                {
-                    // Must be >= start_date and <= end_date
-                    if occurrence_date >= start_date && occurrence_date <= end_date {
-                        occurrences.push(PaymentOccurrence {
-                            payment_id: payment.id,
-                            description: payment.description.clone(),
-                            amount: payment.amount,
-                            occurrence_date: occurrence_date.format("%Y-%m-%d").to_string(),
-                            payer_id: payment.payer_id,
-                            is_recurring: true,
-                            receiver_account_id: payment.receiver_account_id,
-                            is_final: payment.is_final,
-                            affects_balance: payment.affects_balance,
-                            affects_payer_expectation: payment.affects_payer_expectation,
-                            affects_receiver_expectation: payment.affects_receiver_expectation,
-                        });
-                    }
-                }
-            }
+/// Expand one RRULE: walk its periods (days, weeks, months or years, every
+/// `interval`) from the one containing `start` and collect the dates each
+/// period yields, honouring COUNT and UNTIL.
+fn expand_rule(rule: &RecurrenceRule, start: NaiveDate, end: NaiveDate) -> Vec<NaiveDate> {
+    let end = rule.until.map_or(end, |until| until.min(end));
+    let first_period = match rule.freq {
+        Frequency::Daily => start,
+        Frequency::Weekly => {
+            let offset = days_since(rule.week_start, start.weekday());
+            start - chrono::Duration::days(offset)
         }
+        Frequency::Monthly => start.with_day(1).unwrap_or(start),
+        Frequency::Yearly => NaiveDate::from_ymd_opt(start.year(), 1, 1).unwrap_or(start),
+    };
 
-        // Move to next year
-        current_year += 1;
-
-        // Check if we've passed end_date
-        if let Some(jan_first) = NaiveDate::from_ymd_opt(current_year, 1, 1) {
-            if jan_first > end_date {
-                break;
+    let mut dates = Vec::new();
+    let mut emitted = 0u32;
+    for k in 0u32.. {
+        let step = k.saturating_mul(rule.interval);
+        let period = match rule.freq {
+            Frequency::Daily => first_period.checked_add_days(chrono::Days::new(step as u64)),
+            Frequency::Weekly => first_period.checked_add_days(chrono::Days::new(7 * step as u64)),
+            Frequency::Monthly => first_period.checked_add_months(Months::new(step)),
+            Frequency::Yearly => {
+                first_period.checked_add_months(Months::new(step.saturating_mul(12)))
             }
-        } else {
+        };
+        let Some(period) = period.filter(|p| *p <= end) else {
             break;
+        };
+
+        for date in period_dates(rule, period, start) {
+            if date < start {
+                continue;
+            }
+            if date > end || rule.count.is_some_and(|count| emitted >= count) {
+                return dates;
+            }
+            dates.push(date);
+            emitted += 1;
         }
     }
-
-    // Sort by date
-    occurrences.sort_by(|a, b| a.occurrence_date.cmp(&b.occurrence_date));
-    occurrences
+    dates
 }
 
-/// Get the number of days in a month
-fn get_days_in_month(year: i32, month: u32) -> u32 {
-    match month {
-        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
-        4 | 6 | 9 | 11 => 30,
-        2 => {
-            if (year % 4 == 0 && year % 100 != 0) || (year % 400 == 0) {
-                29 // Leap year
+/// Candidate dates of one period of a rule, sorted. Without BY* parts the
+/// rule repeats the start date's weekday, day or month and day.
+///
+/// Day numbers past the end of a month fall on its last day (a rule starting
+/// on the 31st bills on February 28th), where RFC 5545 would skip the month.
+fn period_dates(rule: &RecurrenceRule, period: NaiveDate, start: NaiveDate) -> Vec<NaiveDate> {
+    let mut dates: Vec<NaiveDate> = match rule.freq {
+        Frequency::Daily => vec![period],
+        Frequency::Weekly => {
+            let weekdays: Vec<Weekday> = if rule.by_day.is_empty() {
+                vec![start.weekday()]
+            } else {
+                rule.by_day.iter().map(|d| d.weekday).collect()
+            };
+            weekdays
+                .into_iter()
+                .map(|w| period + chrono::Duration::days(days_since(rule.week_start, w)))
+                .collect()
+        }
+        Frequency::Monthly => month_dates(rule, period.year(), period.month(), start),
+        Frequency::Yearly => {
+            let year = period.year();
+            if !rule.by_month.is_empty() {
+                rule.by_month
+                    .iter()
+                    .flat_map(|&month| month_dates(rule, year, month, start))
+                    .collect()
+            } else if !rule.by_month_day.is_empty() {
+                (1..=12)
+                    .flat_map(|month| month_dates(rule, year, month, start))
+                    .collect()
+            } else if !rule.by_day.is_empty() {
+                match (
+                    NaiveDate::from_ymd_opt(year, 1, 1),
+                    NaiveDate::from_ymd_opt(year, 12, 31),
+                ) {
+                    (Some(first), Some(last)) => weekday_dates(&rule.by_day, first, last),
+                    _ => Vec::new(),
+                }
             } else {
-                28
+                clamped_date(year, start.month(), start.day() as i32)
+                    .into_iter()
+                    .collect()
             }
         }
-        _ => 30, // Invalid month, default to 30
+    };
+
+    // BYMONTH limits daily, weekly and monthly rules; BYMONTHDAY and BYDAY limit daily ones
+    if !rule.by_month.is_empty() {
+        dates.retain(|d| rule.by_month.contains(&d.month()));
+    }
+    if rule.freq == Frequency::Daily {
+        if !rule.by_month_day.is_empty() {
+            dates.retain(|d| {
+                rule.by_month_day
+                    .iter()
+                    .any(|&day| clamped_date(d.year(), d.month(), day) == Some(*d))
+            });
+        }
+        if !rule.by_day.is_empty() {
+            dates.retain(|d| rule.by_day.iter().any(|b| b.weekday == d.weekday()));
+        }
+    }
+
+    dates.sort_unstable();
+    dates.dedup();
+    if !rule.by_set_pos.is_empty() {
+        let len = dates.len() as i32;
+        let mut picked: Vec<NaiveDate> = rule
+            .by_set_pos
+            .iter()
+            .map(|&pos| if pos > 0 { pos - 1 } else { len + pos })
+            .filter(|i| (0..len).contains(i))
+            .map(|i| dates[i as usize])
+            .collect();
+        picked.sort_unstable();
+        picked.dedup();
+        return picked;
     }
+    dates
 }
 
-/// Calculate effective interval for "X times per period"
-fn calculate_times_per_interval(
-    recurrence_type: &str,
-    interval: u32,
-    times_per: u32,
-) -> (u32, String) {
-    if times_per == 0 {
-        return (interval, recurrence_type.to_string());
+/// Dates of a monthly or yearly rule within one month: its month days (limited
+/// to the BYDAY weekdays when both are given), else its weekdays, else the
+/// start date's day
+fn month_dates(rule: &RecurrenceRule, year: i32, month: u32, start: NaiveDate) -> Vec<NaiveDate> {
+    if !rule.by_month_day.is_empty() {
+        return rule
+            .by_month_day
+            .iter()
+            .filter_map(|&day| clamped_date(year, month, day))
+            .filter(|d| {
+                rule.by_day.is_empty() || rule.by_day.iter().any(|b| b.weekday == d.weekday())
+            })
+            .collect();
     }
+    if !rule.by_day.is_empty() {
+        let first = NaiveDate::from_ymd_opt(year, month, 1);
+        let last = clamped_date(year, month, -1);
+        return match (first, last) {
+            (Some(first), Some(last)) => weekday_dates(&rule.by_day, first, last),
+            _ => Vec::new(),
+        };
+    }
+    clamped_date(year, month, start.day() as i32)
+        .into_iter()
+        .collect()
+}
 
-    // Convert "3 times per month" to "every ~10 days"
-    match recurrence_type {
-        "daily" => {
-            // X times per day doesn't make sense, treat as every day
-            (1, "daily".to_string())
-        }
-        "weekly" => {
-            // X times per week = every (7 / X) days
-            let days = (7 * interval) / times_per;
-            (days.max(1), "daily".to_string())
-        }
-        "monthly" => {
-            // X times per month = every (30 / X) days approximately
-            let days = (30 * interval) / times_per;
-            (days.max(1), "daily".to_string())
-        }
-        "yearly" => {
-            // X times per year = every (365 / X) days approximately
-            let days = (365 * interval) / times_per;
-            (days.max(1), "daily".to_string())
+/// Dates from `first` to `last` matching BYDAY entries: every matching weekday,
+/// or only the nth (nth from last when negative) for entries with an ordinal
+fn weekday_dates(by_day: &[ByDay], first: NaiveDate, last: NaiveDate) -> Vec<NaiveDate> {
+    let mut dates = Vec::new();
+    for entry in by_day {
+        let first_match =
+            first + chrono::Duration::days(days_since(first.weekday(), entry.weekday));
+        let matches: Vec<NaiveDate> = first_match
+            .iter_weeks()
+            .take_while(|d| *d <= last)
+            .collect();
+        match entry.ordinal {
+            None => dates.extend(matches),
+            Some(n) if n > 0 => dates.extend(matches.get(n as usize - 1)),
+            Some(n) => dates.extend(
+                matches
+                    .len()
+                    .checked_sub(n.unsigned_abs() as usize)
+                    .map(|i| matches[i]),
+            ),
         }
-        _ => (interval, recurrence_type.to_string()),
     }
+    dates
 }
 
-/// Add interval to a date
-fn add_interval(date: NaiveDate, recurrence_type: &str, interval: u32) -> Option<NaiveDate> {
-    match recurrence_type {
-        "daily" => date.checked_add_days(chrono::Days::new(interval as u64)),
-        "weekly" => date.checked_add_days(chrono::Days::new((interval * 7) as u64)),
-        "monthly" => date.checked_add_months(Months::new(interval)),
-        "yearly" => date.checked_add_months(Months::new(interval * 12)),
-        _ => None,
+/// Day `day` of a month, counted from its end when negative (-1 = last day).
+/// Positive days past the end of the month are clamped to its last day.
+fn clamped_date(year: i32, month: u32, day: i32) -> Option<NaiveDate> {
+    let days_in_month = NaiveDate::from_ymd_opt(year, month, 1)?
+        .checked_add_months(Months::new(1))?
+        .pred_opt()?
+        .day() as i32;
+    let day = if day > 0 {
+        day.min(days_in_month)
+    } else {
+        days_in_month + 1 + day
+    };
+    if day < 1 {
+        return None;
     }
+    NaiveDate::from_ymd_opt(year, month, day as u32)
+}
+
+/// Days from `from` forward to the next `to` weekday (0 when equal)
+fn days_since(from: Weekday, to: Weekday) -> i64 {
+    (to.num_days_from_monday() as i64 - from.num_days_from_monday() as i64).rem_euclid(7)
 }
 
 /// Parse date string to NaiveDate
@@ -1077,27 +2898,27 @@ fn parse_date(date_str: &str) -> Option<NaiveDate> {
 fn calculate_settlements(
     balances: &[ParticipantBalance],
     participant_map: &HashMap<i64, String>,
-    pool_participants: &std::collections::HashSet<i64>,
+    pool_participants: &HashSet<i64>,
 ) -> Vec<Debt> {
     let mut settlements = Vec::new();
 
     // Separate into debtors (negative balance) and creditors (positive balance)
     // Exclude pool accounts from settlements
-    let mut debtors: Vec<(i64, f64)> = balances
+    let mut debtors: Vec<(i64, Money)> = balances
         .iter()
-        .filter(|b| b.net_balance < -0.01 && !pool_participants.contains(&b.participant_id))
+        .filter(|b| b.net_balance.is_negative() && !pool_participants.contains(&b.participant_id))
         .map(|b| (b.participant_id, -b.net_balance)) // Convert to positive amount owed
         .collect();
 
-    let mut creditors: Vec<(i64, f64)> = balances
+    let mut creditors: Vec<(i64, Money)> = balances
         .iter()
-        .filter(|b| b.net_balance > 0.01 && !pool_participants.contains(&b.participant_id))
+        .filter(|b| b.net_balance.is_positive() && !pool_participants.contains(&b.participant_id))
         .map(|b| (b.participant_id, b.net_balance))
         .collect();
 
     // Greedy matching: match largest debtor with largest creditor
-    debtors.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
-    creditors.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+    debtors.sort_by_key(|d| std::cmp::Reverse(d.1));
+    creditors.sort_by_key(|c| std::cmp::Reverse(c.1));
 
     let mut d_idx = 0;
     let mut c_idx = 0;
@@ -1106,9 +2927,9 @@ fn calculate_settlements(
         let (debtor_id, debtor_amount) = &mut debtors[d_idx];
         let (creditor_id, creditor_amount) = &mut creditors[c_idx];
 
-        let transfer = debtor_amount.min(*creditor_amount);
+        let transfer = (*debtor_amount).min(*creditor_amount);
 
-        if transfer > 0.01 {
+        if transfer.is_positive() {
             settlements.push(Debt {
                 from_participant_id: *debtor_id,
                 from_participant_name: participant_map.get(debtor_id).cloned().unwrap_or_default(),
@@ -1117,17 +2938,17 @@ fn calculate_settlements(
                     .get(creditor_id)
                     .cloned()
                     .unwrap_or_default(),
-                amount: (transfer * 100.0).round() / 100.0,
+                amount: transfer,
             });
         }
 
         *debtor_amount -= transfer;
         *creditor_amount -= transfer;
 
-        if *debtor_amount < 0.01 {
+        if !debtor_amount.is_positive() {
             d_idx += 1;
         }
-        if *creditor_amount < 0.01 {
+        if !creditor_amount.is_positive() {
             c_idx += 1;
         }
     }
@@ -1135,6 +2956,56 @@ fn calculate_settlements(
     settlements
 }
 
+/// Settlement plan for non-pool participants using the configured strategy
+fn calculate_settlement_plan(
+    balances: &[ParticipantBalance],
+    participant_map: &HashMap<i64, String>,
+    pool_participants: &HashSet<i64>,
+    options: &SettlementOptions,
+) -> SettlementPlan {
+    let user_balances: Vec<(i64, Money)> = balances
+        .iter()
+        .filter(|b| !pool_participants.contains(&b.participant_id))
+        .map(|b| (b.participant_id, b.net_balance))
+        .collect();
+
+    let (strategy, planned) = plan_settlements(&user_balances, options);
+
+    let mut remaining: HashMap<i64, Money> = user_balances.iter().copied().collect();
+    let name = |id: &i64| participant_map.get(id).cloned().unwrap_or_default();
+    let transfers = planned
+        .into_iter()
+        .map(|t| {
+            *remaining.entry(t.from).or_default() += t.amount;
+            *remaining.entry(t.to).or_default() -= t.amount;
+            Debt {
+                from_participant_id: t.from,
+                from_participant_name: name(&t.from),
+                to_participant_id: t.to,
+                to_participant_name: name(&t.to),
+                amount: t.amount,
+            }
+        })
+        .collect();
+
+    let mut unsettled: Vec<UnsettledBalance> = remaining
+        .into_iter()
+        .filter(|(_, amount)| !amount.is_zero())
+        .map(|(participant_id, amount)| UnsettledBalance {
+            participant_id,
+            participant_name: name(&participant_id),
+            amount,
+        })
+        .collect();
+    unsettled.sort_by_key(|u| u.participant_id);
+
+    SettlementPlan {
+        strategy,
+        transfers,
+        unsettled,
+    }
+}
+
 /// Calculate direct-only settlements based on pairwise relationships
 /// Shows ALL pairwise debts directly without intermediary optimization
 /// This means if A owes B based on their direct transactions, A pays B directly,
@@ -1142,7 +3013,7 @@ fn calculate_settlements(
 fn calculate_direct_settlements(
     pairwise_balances: &[PairwiseBalance],
     _balances: &[ParticipantBalance],
-    pool_participants: &std::collections::HashSet<i64>,
+    pool_participants: &HashSet<i64>,
 ) -> Vec<Debt> {
     let mut settlements = Vec::new();
 
@@ -1170,33 +3041,29 @@ fn calculate_direct_settlements(
         // by overall balance. This shows the "natural" flow of money based
         // on who actually paid for whom.
 
-        if pw.net > 0.01 {
+        if pw.net.is_positive() {
             // other_participant owes participant
             settlements.push(Debt {
                 from_participant_id: pw.other_participant_id,
                 from_participant_name: pw.other_participant_name.clone(),
                 to_participant_id: pw.participant_id,
                 to_participant_name: pw.participant_name.clone(),
-                amount: (pw.net * 100.0).round() / 100.0,
+                amount: pw.net,
             });
-        } else if pw.net < -0.01 {
+        } else if pw.net.is_negative() {
             // participant owes other_participant
             settlements.push(Debt {
                 from_participant_id: pw.participant_id,
                 from_participant_name: pw.participant_name.clone(),
                 to_participant_id: pw.other_participant_id,
                 to_participant_name: pw.other_participant_name.clone(),
-                amount: ((-pw.net) * 100.0).round() / 100.0,
+                amount: -pw.net,
             });
         }
     }
 
     // Sort by amount descending for consistency
-    settlements.sort_by(|a, b| {
-        b.amount
-            .partial_cmp(&a.amount)
-            .unwrap_or(std::cmp::Ordering::Equal)
-    });
+    settlements.sort_by_key(|s| std::cmp::Reverse(s.amount));
 
     settlements
 }
@@ -1213,16 +3080,16 @@ mod tests {
             ParticipantBalance {
                 participant_id: 1,
                 participant_name: "Carl".to_string(),
-                total_paid: 100.0,
-                total_owed: 0.0,
-                net_balance: 100.0,
+                total_paid: Money::from_major(100),
+                total_owed: Money::from_major(0),
+                net_balance: Money::from_major(100),
             },
             ParticipantBalance {
                 participant_id: 2,
                 participant_name: "David".to_string(),
-                total_paid: 0.0,
-                total_owed: 100.0,
-                net_balance: -100.0,
+                total_paid: Money::from_major(0),
+                total_owed: Money::from_major(100),
+                net_balance: Money::from_major(-100),
             },
         ];
 
@@ -1231,14 +3098,14 @@ mod tests {
                 .into_iter()
                 .collect();
 
-        let pool_participants: std::collections::HashSet<i64> = std::collections::HashSet::new();
+        let pool_participants: HashSet<i64> = std::collections::HashSet::new();
 
         let settlements = calculate_settlements(&balances, &participant_map, &pool_participants);
 
         assert_eq!(settlements.len(), 1);
         assert_eq!(settlements[0].from_participant_id, 2); // David
         assert_eq!(settlements[0].to_participant_id, 1); // Carl
-        assert_eq!(settlements[0].amount, 100.0);
+        assert_eq!(settlements[0].amount, Money::from_major(100));
     }
 
     #[test]
@@ -1250,16 +3117,16 @@ mod tests {
             ParticipantBalance {
                 participant_id: 1,
                 participant_name: "Carl".to_string(),
-                total_paid: 1100.0,
-                total_owed: 1000.0,
-                net_balance: 100.0, // 1100 - 1000 = +100
+                total_paid: Money::from_major(1100),
+                total_owed: Money::from_major(1000),
+                net_balance: Money::from_major(100), // 1100 - 1000 = +100
             },
             ParticipantBalance {
                 participant_id: 2,
                 participant_name: "David".to_string(),
-                total_paid: 1000.0,
-                total_owed: 1100.0,
-                net_balance: -100.0, // 1000 - 1100 = -100
+                total_paid: Money::from_major(1000),
+                total_owed: Money::from_major(1100),
+                net_balance: Money::from_major(-100), // 1000 - 1100 = -100
             },
         ];
 
@@ -1268,14 +3135,14 @@ mod tests {
                 .into_iter()
                 .collect();
 
-        let pool_participants: std::collections::HashSet<i64> = std::collections::HashSet::new();
+        let pool_participants: HashSet<i64> = std::collections::HashSet::new();
 
         let settlements = calculate_settlements(&balances, &participant_map, &pool_participants);
 
         assert_eq!(settlements.len(), 1);
         assert_eq!(settlements[0].from_participant_id, 2); // David
         assert_eq!(settlements[0].to_participant_id, 1); // Carl
-        assert_eq!(settlements[0].amount, 100.0);
+        assert_eq!(settlements[0].amount, Money::from_major(100));
     }
 
     #[test]
@@ -1288,23 +3155,23 @@ mod tests {
             ParticipantBalance {
                 participant_id: 1,
                 participant_name: "A".to_string(),
-                total_paid: 300.0,
-                total_owed: 100.0,
-                net_balance: 200.0,
+                total_paid: Money::from_major(300),
+                total_owed: Money::from_major(100),
+                net_balance: Money::from_major(200),
             },
             ParticipantBalance {
                 participant_id: 2,
                 participant_name: "B".to_string(),
-                total_paid: 0.0,
-                total_owed: 100.0,
-                net_balance: -100.0,
+                total_paid: Money::from_major(0),
+                total_owed: Money::from_major(100),
+                net_balance: Money::from_major(-100),
             },
             ParticipantBalance {
                 participant_id: 3,
                 participant_name: "C".to_string(),
-                total_paid: 0.0,
-                total_owed: 100.0,
-                net_balance: -100.0,
+                total_paid: Money::from_major(0),
+                total_owed: Money::from_major(100),
+                net_balance: Money::from_major(-100),
             },
         ];
 
@@ -1316,15 +3183,15 @@ mod tests {
         .into_iter()
         .collect();
 
-        let pool_participants: std::collections::HashSet<i64> = std::collections::HashSet::new();
+        let pool_participants: HashSet<i64> = std::collections::HashSet::new();
 
         let settlements = calculate_settlements(&balances, &participant_map, &pool_participants);
 
         // Should have 2 settlements: B->A $100, C->A $100
         assert_eq!(settlements.len(), 2);
 
-        let total: f64 = settlements.iter().map(|s| s.amount).sum();
-        assert_eq!(total, 200.0);
+        let total: Money = settlements.iter().map(|s| s.amount).sum();
+        assert_eq!(total, Money::from_major(200));
 
         // All settlements should go to A (id=1)
         for s in &settlements {
@@ -1341,16 +3208,16 @@ mod tests {
             ParticipantBalance {
                 participant_id: 1,
                 participant_name: "Carl".to_string(),
-                total_paid: 100.0,
-                total_owed: 0.0,
-                net_balance: 100.0,
+                total_paid: Money::from_major(100),
+                total_owed: Money::from_major(0),
+                net_balance: Money::from_major(100),
             },
             ParticipantBalance {
                 participant_id: 2,
                 participant_name: "David".to_string(),
-                total_paid: 0.0,
-                total_owed: 100.0,
-                net_balance: -100.0,
+                total_paid: Money::from_major(0),
+                total_owed: Money::from_major(100),
+                net_balance: Money::from_major(-100),
             },
         ];
 
@@ -1360,9 +3227,9 @@ mod tests {
                 participant_name: "Carl".to_string(),
                 other_participant_id: 2,
                 other_participant_name: "David".to_string(),
-                amount_paid_for: 100.0,
-                amount_owed_by: 0.0,
-                net: 100.0, // David owes Carl
+                amount_paid_for: Money::from_major(100),
+                amount_owed_by: Money::from_major(0),
+                net: Money::from_major(100), // David owes Carl
                 paid_for_breakdown: vec![],
                 owed_by_breakdown: vec![],
             },
@@ -1371,15 +3238,15 @@ mod tests {
                 participant_name: "David".to_string(),
                 other_participant_id: 1,
                 other_participant_name: "Carl".to_string(),
-                amount_paid_for: 0.0,
-                amount_owed_by: 100.0,
-                net: -100.0, // David owes Carl
+                amount_paid_for: Money::from_major(0),
+                amount_owed_by: Money::from_major(100),
+                net: Money::from_major(-100), // David owes Carl
                 paid_for_breakdown: vec![],
                 owed_by_breakdown: vec![],
             },
         ];
 
-        let pool_participants: std::collections::HashSet<i64> = std::collections::HashSet::new();
+        let pool_participants: HashSet<i64> = std::collections::HashSet::new();
 
         let settlements =
             calculate_direct_settlements(&pairwise_balances, &balances, &pool_participants);
@@ -1387,7 +3254,7 @@ mod tests {
         assert_eq!(settlements.len(), 1);
         assert_eq!(settlements[0].from_participant_id, 2); // David
         assert_eq!(settlements[0].to_participant_id, 1); // Carl
-        assert_eq!(settlements[0].amount, 100.0);
+        assert_eq!(settlements[0].amount, Money::from_major(100));
     }
 
     // =====================================================
@@ -1408,16 +3275,16 @@ mod tests {
             ParticipantBalance {
                 participant_id: 1,
                 participant_name: "Carl".to_string(),
-                total_paid: 0.0, // Pool transfer doesn't count as "paid"
-                total_owed: 0.0,
-                net_balance: 0.0,
+                total_paid: Money::from_major(0), // Pool transfer doesn't count as "paid"
+                total_owed: Money::from_major(0),
+                net_balance: Money::from_major(0),
             },
             ParticipantBalance {
                 participant_id: 2,
                 participant_name: "Pool".to_string(),
-                total_paid: 0.0,
-                total_owed: 0.0,
-                net_balance: 0.0,
+                total_paid: Money::from_major(0),
+                total_owed: Money::from_major(0),
+                net_balance: Money::from_major(0),
             },
         ];
 
@@ -1426,7 +3293,7 @@ mod tests {
                 .into_iter()
                 .collect();
 
-        let pool_participants: std::collections::HashSet<i64> = [2].into_iter().collect();
+        let pool_participants: HashSet<i64> = [2].into_iter().collect();
 
         let settlements = calculate_settlements(&balances, &participant_map, &pool_participants);
 
@@ -1454,16 +3321,16 @@ mod tests {
             ParticipantBalance {
                 participant_id: 1,
                 participant_name: "Carl".to_string(),
-                total_paid: 100.0, // Transfer of $100
-                total_owed: 100.0, // His share of groceries
-                net_balance: 0.0,
+                total_paid: Money::from_major(100), // Transfer of $100
+                total_owed: Money::from_major(100), // His share of groceries
+                net_balance: Money::from_major(0),
             },
             ParticipantBalance {
                 participant_id: 2,
                 participant_name: "David".to_string(),
-                total_paid: 200.0, // Groceries
-                total_owed: 200.0, // His share ($100) + received transfer ($100)
-                net_balance: 0.0,
+                total_paid: Money::from_major(200), // Groceries
+                total_owed: Money::from_major(200), // His share ($100) + received transfer ($100)
+                net_balance: Money::from_major(0),
             },
         ];
 
@@ -1472,7 +3339,7 @@ mod tests {
                 .into_iter()
                 .collect();
 
-        let pool_participants: std::collections::HashSet<i64> = std::collections::HashSet::new();
+        let pool_participants: HashSet<i64> = std::collections::HashSet::new();
 
         let settlements = calculate_settlements(&balances, &participant_map, &pool_participants);
 
@@ -1497,16 +3364,16 @@ mod tests {
             ParticipantBalance {
                 participant_id: 1,
                 participant_name: "Carl".to_string(),
-                total_paid: 50.0,  // Transfer of $50
-                total_owed: 100.0, // His share of groceries
-                net_balance: -50.0,
+                total_paid: Money::from_major(50), // Transfer of $50
+                total_owed: Money::from_major(100), // His share of groceries
+                net_balance: Money::from_major(-50),
             },
             ParticipantBalance {
                 participant_id: 2,
                 participant_name: "David".to_string(),
-                total_paid: 200.0, // Groceries
-                total_owed: 150.0, // His share ($100) + received transfer ($50)
-                net_balance: 50.0,
+                total_paid: Money::from_major(200), // Groceries
+                total_owed: Money::from_major(150), // His share ($100) + received transfer ($50)
+                net_balance: Money::from_major(50),
             },
         ];
 
@@ -1515,7 +3382,7 @@ mod tests {
                 .into_iter()
                 .collect();
 
-        let pool_participants: std::collections::HashSet<i64> = std::collections::HashSet::new();
+        let pool_participants: HashSet<i64> = std::collections::HashSet::new();
 
         let settlements = calculate_settlements(&balances, &participant_map, &pool_participants);
 
@@ -1523,7 +3390,7 @@ mod tests {
         assert_eq!(settlements.len(), 1);
         assert_eq!(settlements[0].from_participant_id, 1); // Carl
         assert_eq!(settlements[0].to_participant_id, 2); // David
-        assert_eq!(settlements[0].amount, 50.0);
+        assert_eq!(settlements[0].amount, Money::from_major(50));
     }
 
     #[test]
@@ -1543,30 +3410,30 @@ mod tests {
             ParticipantBalance {
                 participant_id: 1,
                 participant_name: "Carl".to_string(),
-                total_paid: 0.0,
-                total_owed: 100.0,
-                net_balance: -100.0,
+                total_paid: Money::from_major(0),
+                total_owed: Money::from_major(100),
+                net_balance: Money::from_major(-100),
             },
             ParticipantBalance {
                 participant_id: 2,
                 participant_name: "David".to_string(),
-                total_paid: 0.0,
-                total_owed: 100.0,
-                net_balance: -100.0,
+                total_paid: Money::from_major(0),
+                total_owed: Money::from_major(100),
+                net_balance: Money::from_major(-100),
             },
             ParticipantBalance {
                 participant_id: 3,
                 participant_name: "Lise".to_string(),
-                total_paid: 0.0,
-                total_owed: 100.0,
-                net_balance: -100.0,
+                total_paid: Money::from_major(0),
+                total_owed: Money::from_major(100),
+                net_balance: Money::from_major(-100),
             },
             ParticipantBalance {
                 participant_id: 4,
                 participant_name: "Pool".to_string(),
-                total_paid: 300.0,
-                total_owed: 0.0,
-                net_balance: 300.0,
+                total_paid: Money::from_major(300),
+                total_owed: Money::from_major(0),
+                net_balance: Money::from_major(300),
             },
         ];
 
@@ -1580,7 +3447,7 @@ mod tests {
         .collect();
 
         // Pool is participant 4
-        let pool_participants: std::collections::HashSet<i64> = [4].into_iter().collect();
+        let pool_participants: HashSet<i64> = [4].into_iter().collect();
 
         let settlements = calculate_settlements(&balances, &participant_map, &pool_participants);
 
@@ -1611,30 +3478,30 @@ mod tests {
             ParticipantBalance {
                 participant_id: 1,
                 participant_name: "Carl".to_string(),
-                total_paid: 150.0,
-                total_owed: 50.0, // Only Carl's share from his own payment
-                net_balance: 100.0,
+                total_paid: Money::from_major(150),
+                total_owed: Money::from_major(50), // Only Carl's share from his own payment
+                net_balance: Money::from_major(100),
             },
             ParticipantBalance {
                 participant_id: 2,
                 participant_name: "David".to_string(),
-                total_paid: 0.0,
-                total_owed: 50.0, // Only David's share from Carl's payment
-                net_balance: -50.0,
+                total_paid: Money::from_major(0),
+                total_owed: Money::from_major(50), // Only David's share from Carl's payment
+                net_balance: Money::from_major(-50),
             },
             ParticipantBalance {
                 participant_id: 3,
                 participant_name: "Lise".to_string(),
-                total_paid: 0.0,
-                total_owed: 50.0, // Only Lise's share from Carl's payment
-                net_balance: -50.0,
+                total_paid: Money::from_major(0),
+                total_owed: Money::from_major(50), // Only Lise's share from Carl's payment
+                net_balance: Money::from_major(-50),
             },
             ParticipantBalance {
                 participant_id: 4,
                 participant_name: "Pool".to_string(),
-                total_paid: 300.0,
-                total_owed: 0.0,
-                net_balance: 300.0,
+                total_paid: Money::from_major(300),
+                total_owed: Money::from_major(0),
+                net_balance: Money::from_major(300),
             },
         ];
 
@@ -1647,15 +3514,15 @@ mod tests {
         .into_iter()
         .collect();
 
-        let pool_participants: std::collections::HashSet<i64> = [4].into_iter().collect();
+        let pool_participants: HashSet<i64> = [4].into_iter().collect();
 
         let settlements = calculate_settlements(&balances, &participant_map, &pool_participants);
 
         // Carl is creditor, David and Lise are debtors
         assert_eq!(settlements.len(), 2);
 
-        let total: f64 = settlements.iter().map(|s| s.amount).sum();
-        assert_eq!(total, 100.0);
+        let total: Money = settlements.iter().map(|s| s.amount).sum();
+        assert_eq!(total, Money::from_major(100));
 
         // All settlements should go to Carl
         for s in &settlements {
@@ -1681,23 +3548,23 @@ mod tests {
             ParticipantBalance {
                 participant_id: 1,
                 participant_name: "Carl".to_string(),
-                total_paid: 300.0,
-                total_owed: 100.0,
-                net_balance: 200.0,
+                total_paid: Money::from_major(300),
+                total_owed: Money::from_major(100),
+                net_balance: Money::from_major(200),
             },
             ParticipantBalance {
                 participant_id: 2,
                 participant_name: "David".to_string(),
-                total_paid: 0.0,
-                total_owed: 100.0,
-                net_balance: -100.0,
+                total_paid: Money::from_major(0),
+                total_owed: Money::from_major(100),
+                net_balance: Money::from_major(-100),
             },
             ParticipantBalance {
                 participant_id: 3,
                 participant_name: "Lise".to_string(),
-                total_paid: 0.0,
-                total_owed: 100.0,
-                net_balance: -100.0,
+                total_paid: Money::from_major(0),
+                total_owed: Money::from_major(100),
+                net_balance: Money::from_major(-100),
             },
         ];
 
@@ -1709,13 +3576,13 @@ mod tests {
         .into_iter()
         .collect();
 
-        let pool_participants: std::collections::HashSet<i64> = std::collections::HashSet::new();
+        let pool_participants: HashSet<i64> = std::collections::HashSet::new();
 
         let settlements = calculate_settlements(&balances, &participant_map, &pool_participants);
 
         assert_eq!(settlements.len(), 2);
-        let total: f64 = settlements.iter().map(|s| s.amount).sum();
-        assert_eq!(total, 200.0);
+        let total: Money = settlements.iter().map(|s| s.amount).sum();
+        assert_eq!(total, Money::from_major(200));
 
         // All settlements should go to Carl
         for s in &settlements {
@@ -1735,30 +3602,30 @@ mod tests {
             ParticipantBalance {
                 participant_id: 1,
                 participant_name: "Carl".to_string(),
-                total_paid: 0.0, // Pool transfers don't count
-                total_owed: 0.0,
-                net_balance: 0.0,
+                total_paid: Money::from_major(0), // Pool transfers don't count
+                total_owed: Money::from_major(0),
+                net_balance: Money::from_major(0),
             },
             ParticipantBalance {
                 participant_id: 2,
                 participant_name: "David".to_string(),
-                total_paid: 0.0,
-                total_owed: 0.0,
-                net_balance: 0.0,
+                total_paid: Money::from_major(0),
+                total_owed: Money::from_major(0),
+                net_balance: Money::from_major(0),
             },
             ParticipantBalance {
                 participant_id: 3,
                 participant_name: "Lise".to_string(),
-                total_paid: 0.0,
-                total_owed: 0.0,
-                net_balance: 0.0,
+                total_paid: Money::from_major(0),
+                total_owed: Money::from_major(0),
+                net_balance: Money::from_major(0),
             },
             ParticipantBalance {
                 participant_id: 4,
                 participant_name: "Pool".to_string(),
-                total_paid: 0.0,
-                total_owed: 0.0,
-                net_balance: 0.0,
+                total_paid: Money::from_major(0),
+                total_owed: Money::from_major(0),
+                net_balance: Money::from_major(0),
             },
         ];
 
@@ -1771,7 +3638,7 @@ mod tests {
         .into_iter()
         .collect();
 
-        let pool_participants: std::collections::HashSet<i64> = [4].into_iter().collect();
+        let pool_participants: HashSet<i64> = [4].into_iter().collect();
 
         let settlements = calculate_settlements(&balances, &participant_map, &pool_participants);
 
@@ -1790,23 +3657,23 @@ mod tests {
             ParticipantBalance {
                 participant_id: 1,
                 participant_name: "Carl".to_string(),
-                total_paid: 100.0, // Transfer
-                total_owed: 0.0,
-                net_balance: 100.0,
+                total_paid: Money::from_major(100), // Transfer
+                total_owed: Money::from_major(0),
+                net_balance: Money::from_major(100),
             },
             ParticipantBalance {
                 participant_id: 2,
                 participant_name: "David".to_string(),
-                total_paid: 0.0,
-                total_owed: 100.0, // Received transfer
-                net_balance: -100.0,
+                total_paid: Money::from_major(0),
+                total_owed: Money::from_major(100), // Received transfer
+                net_balance: Money::from_major(-100),
             },
             ParticipantBalance {
                 participant_id: 3,
                 participant_name: "Lise".to_string(),
-                total_paid: 0.0, // Not involved
-                total_owed: 0.0,
-                net_balance: 0.0,
+                total_paid: Money::from_major(0), // Not involved
+                total_owed: Money::from_major(0),
+                net_balance: Money::from_major(0),
             },
         ];
 
@@ -1818,7 +3685,7 @@ mod tests {
         .into_iter()
         .collect();
 
-        let pool_participants: std::collections::HashSet<i64> = std::collections::HashSet::new();
+        let pool_participants: HashSet<i64> = std::collections::HashSet::new();
 
         let settlements = calculate_settlements(&balances, &participant_map, &pool_participants);
 
@@ -1841,7 +3708,7 @@ mod tests {
         let occurrence = PaymentOccurrence {
             payment_id: 1,
             description: "Transfer to pool".to_string(),
-            amount: 100.0,
+            amount: Money::from_major(100),
             occurrence_date: "2024-01-01".to_string(),
             payer_id: Some(1),
             is_recurring: false,
@@ -1850,6 +3717,14 @@ mod tests {
             affects_balance: true,
             affects_payer_expectation: false,
             affects_receiver_expectation: false,
+            original: None,
+            original_date: None,
+            refund_id: None,
+            pool_rule_id: None,
+            contribution_rule_id: None,
+            split: None,
+            payers: Vec::new(),
+            pool_parts: Vec::new(),
         };
 
         assert!(occurrence.receiver_account_id.is_some());
@@ -1861,7 +3736,7 @@ mod tests {
         let occurrence = PaymentOccurrence {
             payment_id: 1,
             description: "Groceries".to_string(),
-            amount: 100.0,
+            amount: Money::from_major(100),
             occurrence_date: "2024-01-01".to_string(),
             payer_id: Some(1),
             is_recurring: false,
@@ -1870,6 +3745,14 @@ mod tests {
             affects_balance: true,
             affects_payer_expectation: false,
             affects_receiver_expectation: false,
+            original: None,
+            original_date: None,
+            refund_id: None,
+            pool_rule_id: None,
+            contribution_rule_id: None,
+            split: None,
+            payers: Vec::new(),
+            pool_parts: Vec::new(),
         };
 
         assert!(occurrence.receiver_account_id.is_none());
@@ -1882,7 +3765,7 @@ mod tests {
         let occurrence = PaymentOccurrence {
             payment_id: 1,
             description: "Bank interest deposit".to_string(),
-            amount: 300.0,
+            amount: Money::from_major(300),
             occurrence_date: "2024-01-01".to_string(),
             payer_id: None, // External inflow - no payer
             is_recurring: false,
@@ -1891,6 +3774,14 @@ mod tests {
             affects_balance: true,
             affects_payer_expectation: false,
             affects_receiver_expectation: false,
+            original: None,
+            original_date: None,
+            refund_id: None,
+            pool_rule_id: None,
+            contribution_rule_id: None,
+            split: None,
+            payers: Vec::new(),
+            pool_parts: Vec::new(),
         };
 
         assert!(occurrence.payer_id.is_none());
@@ -1916,23 +3807,23 @@ mod tests {
             ParticipantBalance {
                 participant_id: 1,
                 participant_name: "Carl".to_string(),
-                total_paid: 100.0, // His share (credited)
-                total_owed: 300.0, // Holds full amount (debited)
-                net_balance: -200.0,
+                total_paid: Money::from_major(100), // His share (credited)
+                total_owed: Money::from_major(300), // Holds full amount (debited)
+                net_balance: Money::from_major(-200),
             },
             ParticipantBalance {
                 participant_id: 2,
                 participant_name: "David".to_string(),
-                total_paid: 100.0, // His share (credited)
-                total_owed: 0.0,
-                net_balance: 100.0,
+                total_paid: Money::from_major(100), // His share (credited)
+                total_owed: Money::from_major(0),
+                net_balance: Money::from_major(100),
             },
             ParticipantBalance {
                 participant_id: 3,
                 participant_name: "Lise".to_string(),
-                total_paid: 100.0, // Her share (credited)
-                total_owed: 0.0,
-                net_balance: 100.0,
+                total_paid: Money::from_major(100), // Her share (credited)
+                total_owed: Money::from_major(0),
+                net_balance: Money::from_major(100),
             },
         ];
 
@@ -1944,7 +3835,7 @@ mod tests {
         .into_iter()
         .collect();
 
-        let pool_participants: std::collections::HashSet<i64> = std::collections::HashSet::new();
+        let pool_participants: HashSet<i64> = std::collections::HashSet::new();
 
         let settlements = calculate_settlements(&balances, &participant_map, &pool_participants);
 
@@ -1952,8 +3843,8 @@ mod tests {
         assert_eq!(settlements.len(), 2);
 
         // Total amount should be $200 (Carl pays $100 to David + $100 to Lise)
-        let total: f64 = settlements.iter().map(|s| s.amount).sum();
-        assert!((total - 200.0).abs() < 0.01);
+        let total: Money = settlements.iter().map(|s| s.amount).sum();
+        assert_eq!(total, Money::from_major(200));
 
         // All settlements should come from Carl (id=1)
         for s in &settlements {
@@ -1973,30 +3864,30 @@ mod tests {
             ParticipantBalance {
                 participant_id: 1,
                 participant_name: "Carl".to_string(),
-                total_paid: 0.0,
-                total_owed: 0.0,
-                net_balance: 0.0,
+                total_paid: Money::from_major(0),
+                total_owed: Money::from_major(0),
+                net_balance: Money::from_major(0),
             },
             ParticipantBalance {
                 participant_id: 2,
                 participant_name: "David".to_string(),
-                total_paid: 0.0,
-                total_owed: 0.0,
-                net_balance: 0.0,
+                total_paid: Money::from_major(0),
+                total_owed: Money::from_major(0),
+                net_balance: Money::from_major(0),
             },
             ParticipantBalance {
                 participant_id: 3,
                 participant_name: "Lise".to_string(),
-                total_paid: 0.0,
-                total_owed: 0.0,
-                net_balance: 0.0,
+                total_paid: Money::from_major(0),
+                total_owed: Money::from_major(0),
+                net_balance: Money::from_major(0),
             },
             ParticipantBalance {
                 participant_id: 4,
                 participant_name: "Pool".to_string(),
-                total_paid: 0.0,
-                total_owed: 0.0,
-                net_balance: 0.0,
+                total_paid: Money::from_major(0),
+                total_owed: Money::from_major(0),
+                net_balance: Money::from_major(0),
             },
         ];
 
@@ -2009,7 +3900,7 @@ mod tests {
         .into_iter()
         .collect();
 
-        let pool_participants: std::collections::HashSet<i64> = [4].into_iter().collect();
+        let pool_participants: HashSet<i64> = [4].into_iter().collect();
 
         let settlements = calculate_settlements(&balances, &participant_map, &pool_participants);
 
@@ -2041,28 +3932,28 @@ mod tests {
         // - A owes B $333.33
         // - A owes C $333.33
 
-        let amount_per_person = 1000.0 / 3.0; // 333.33...
+        let amount_per_person = Money::from_minor(33333, 2); // 1000 / 3, rounded to the cent
 
         let balances = vec![
             ParticipantBalance {
                 participant_id: 1,
                 participant_name: "A".to_string(),
                 total_paid: amount_per_person, // A's share (credited)
-                total_owed: 1000.0,            // A holds full amount (debited)
-                net_balance: amount_per_person - 1000.0, // -666.67
+                total_owed: Money::from_major(1000), // A holds full amount (debited)
+                net_balance: amount_per_person - Money::from_major(1000), // -666.67
             },
             ParticipantBalance {
                 participant_id: 2,
                 participant_name: "B".to_string(),
                 total_paid: amount_per_person, // B's share (credited)
-                total_owed: 0.0,
+                total_owed: Money::from_major(0),
                 net_balance: amount_per_person, // +333.33
             },
             ParticipantBalance {
                 participant_id: 3,
                 participant_name: "C".to_string(),
                 total_paid: amount_per_person, // C's share (credited)
-                total_owed: 0.0,
+                total_owed: Money::from_major(0),
                 net_balance: amount_per_person, // +333.33
             },
         ];
@@ -2075,36 +3966,39 @@ mod tests {
         .into_iter()
         .collect();
 
-        let pool_participants: std::collections::HashSet<i64> = std::collections::HashSet::new();
+        let pool_participants: HashSet<i64> = std::collections::HashSet::new();
 
         let settlements = calculate_settlements(&balances, &participant_map, &pool_participants);
 
         // Should have 2 settlements: A→B and A→C
         assert_eq!(settlements.len(), 2);
 
-        // Total amount should be ~$666.67 (A pays $333.33 to B + $333.33 to C)
-        let total: f64 = settlements.iter().map(|s| s.amount).sum();
-        assert!((total - 2.0 * amount_per_person).abs() < 0.01);
+        // Total amount should be $666.66 (A pays $333.33 to B + $333.33 to C);
+        // the leftover cent from A's -666.67 stays unsettled
+        let total: Money = settlements.iter().map(|s| s.amount).sum();
+        assert_eq!(total, amount_per_person + amount_per_person);
 
         // All settlements should come FROM A (id=1)
         for s in &settlements {
             assert_eq!(s.from_participant_id, 1, "A should be the one paying");
         }
 
-        // Verify B and C each receive ~$333.33
+        // Verify B and C each receive $333.33
         let b_settlement = settlements.iter().find(|s| s.to_participant_id == 2);
         let c_settlement = settlements.iter().find(|s| s.to_participant_id == 3);
 
         assert!(b_settlement.is_some(), "B should receive a settlement");
         assert!(c_settlement.is_some(), "C should receive a settlement");
 
-        assert!(
-            (b_settlement.unwrap().amount - amount_per_person).abs() < 0.01,
-            "B should receive ~$333.33"
+        assert_eq!(
+            b_settlement.unwrap().amount,
+            amount_per_person,
+            "B should receive $333.33"
         );
-        assert!(
-            (c_settlement.unwrap().amount - amount_per_person).abs() < 0.01,
-            "C should receive ~$333.33"
+        assert_eq!(
+            c_settlement.unwrap().amount,
+            amount_per_person,
+            "C should receive $333.33"
         );
     }
 
@@ -2115,25 +4009,31 @@ mod tests {
             PoolOwnershipEntry {
                 participant_id: 1,
                 participant_name: "Alice".to_string(),
-                contributed: 3000.0,
-                consumed: 500.0,
-                ownership: 2500.0,
+                contributed: Money::from_major(3000),
+                consumed: Money::from_major(500),
+                ownership: Money::from_major(2500),
+                units: None,
+                expected_minimum: Money::ZERO,
+                shortfall: None,
                 contributed_breakdown: vec![],
                 consumed_breakdown: vec![],
             },
             PoolOwnershipEntry {
                 participant_id: 2,
                 participant_name: "Bob".to_string(),
-                contributed: 2000.0,
-                consumed: 1000.0,
-                ownership: 1000.0,
+                contributed: Money::from_major(2000),
+                consumed: Money::from_major(1000),
+                ownership: Money::from_major(1000),
+                units: None,
+                expected_minimum: Money::ZERO,
+                shortfall: None,
                 contributed_breakdown: vec![],
                 consumed_breakdown: vec![],
             },
         ];
 
-        let total_balance = entries.iter().map(|e| e.ownership).sum::<f64>();
-        let expected_minimum = 5000.0; // Set a $5000 reserve requirement
+        let total_balance = entries.iter().map(|e| e.ownership).sum::<Money>();
+        let expected_minimum = Money::from_major(5000); // Set a $5000 reserve requirement
 
         let is_below_expected = total_balance < expected_minimum;
         let shortfall = if is_below_expected {
@@ -2147,6 +4047,7 @@ mod tests {
             pool_name: "Shared Account".to_string(),
             entries,
             total_balance,
+            unit_value: None,
             expected_minimum,
             is_below_expected,
             shortfall,
@@ -2155,10 +4056,10 @@ mod tests {
         // total_balance = 2500 + 1000 = 3500
         // expected_minimum = 5000
         // shortfall = 5000 - 3500 = 1500
-        assert_eq!(pool_ownership.total_balance, 3500.0);
-        assert_eq!(pool_ownership.expected_minimum, 5000.0);
+        assert_eq!(pool_ownership.total_balance, Money::from_major(3500));
+        assert_eq!(pool_ownership.expected_minimum, Money::from_major(5000));
         assert!(pool_ownership.is_below_expected);
-        assert_eq!(pool_ownership.shortfall, Some(1500.0));
+        assert_eq!(pool_ownership.shortfall, Some(Money::from_major(1500)));
     }
 
     #[test]
@@ -2167,15 +4068,18 @@ mod tests {
         let entries = vec![PoolOwnershipEntry {
             participant_id: 1,
             participant_name: "Alice".to_string(),
-            contributed: 6000.0,
-            consumed: 500.0,
-            ownership: 5500.0,
+            contributed: Money::from_major(6000),
+            consumed: Money::from_major(500),
+            ownership: Money::from_major(5500),
+            units: None,
+            expected_minimum: Money::ZERO,
+            shortfall: None,
             contributed_breakdown: vec![],
             consumed_breakdown: vec![],
         }];
 
-        let total_balance = 5500.0;
-        let expected_minimum = 5000.0;
+        let total_balance = Money::from_major(5500);
+        let expected_minimum = Money::from_major(5000);
 
         let is_below_expected = total_balance < expected_minimum;
         let shortfall = if is_below_expected {
@@ -2189,6 +4093,7 @@ mod tests {
             pool_name: "Shared Account".to_string(),
             entries,
             total_balance,
+            unit_value: None,
             expected_minimum,
             is_below_expected,
             shortfall,
@@ -2205,7 +4110,7 @@ mod tests {
         let occurrence = PaymentOccurrence {
             payment_id: 1,
             description: "Regular deposit".to_string(),
-            amount: 1000.0,
+            amount: Money::from_major(1000),
             occurrence_date: "2024-01-01".to_string(),
             payer_id: Some(1),
             is_recurring: false,
@@ -2214,6 +4119,14 @@ mod tests {
             affects_balance: true,
             affects_payer_expectation: false,
             affects_receiver_expectation: false,
+            original: None,
+            original_date: None,
+            refund_id: None,
+            pool_rule_id: None,
+            contribution_rule_id: None,
+            split: None,
+            payers: Vec::new(),
+            pool_parts: Vec::new(),
         };
 
         assert!(occurrence.affects_balance);
@@ -2228,7 +4141,7 @@ mod tests {
         let occurrence = PaymentOccurrence {
             payment_id: 2,
             description: "Set $5000 reserve".to_string(),
-            amount: 5000.0,
+            amount: Money::from_major(5000),
             occurrence_date: "2024-01-01".to_string(),
             payer_id: None,
             is_recurring: false,
@@ -2237,6 +4150,14 @@ mod tests {
             affects_balance: false,
             affects_payer_expectation: false,
             affects_receiver_expectation: true, // Increases pool's expected minimum
+            original: None,
+            original_date: None,
+            refund_id: None,
+            pool_rule_id: None,
+            contribution_rule_id: None,
+            split: None,
+            payers: Vec::new(),
+            pool_parts: Vec::new(),
         };
 
         assert!(!occurrence.affects_balance);
@@ -2249,7 +4170,7 @@ mod tests {
         let occurrence = PaymentOccurrence {
             payment_id: 3,
             description: "Earmarked deposit".to_string(),
-            amount: 2000.0,
+            amount: Money::from_major(2000),
             occurrence_date: "2024-01-01".to_string(),
             payer_id: Some(1),
             is_recurring: false,
@@ -2258,6 +4179,14 @@ mod tests {
             affects_balance: true,
             affects_payer_expectation: false,
             affects_receiver_expectation: true, // Earmarked: increases pool's expected minimum
+            original: None,
+            original_date: None,
+            refund_id: None,
+            pool_rule_id: None,
+            contribution_rule_id: None,
+            split: None,
+            payers: Vec::new(),
+            pool_parts: Vec::new(),
         };
 
         assert!(occurrence.affects_balance);
@@ -2270,7 +4199,7 @@ mod tests {
         let occurrence = PaymentOccurrence {
             payment_id: 4,
             description: "Approved renovation expense".to_string(),
-            amount: 3000.0,
+            amount: Money::from_major(3000),
             occurrence_date: "2024-01-01".to_string(),
             payer_id: Some(2), // Pool pays
             is_recurring: false,
@@ -2279,6 +4208,14 @@ mod tests {
             affects_balance: true,
             affects_payer_expectation: true, // Approved: reduces pool's expected minimum
             affects_receiver_expectation: false,
+            original: None,
+            original_date: None,
+            refund_id: None,
+            pool_rule_id: None,
+            contribution_rule_id: None,
+            split: None,
+            payers: Vec::new(),
+            pool_parts: Vec::new(),
         };
 
         assert!(occurrence.affects_balance);
@@ -2286,7 +4223,305 @@ mod tests {
         assert!(!occurrence.affects_receiver_expectation);
     }
 
+    #[test]
+    fn test_pool_to_pool_transfer_moves_ownership() {
+        // Alice (1) and Bob (2) put 60 and 40 in checking (10), then half of
+        // checking goes to savings (11)
+        let transfer = |payer_id: i64, receiver_id: i64, amount: i64| PaymentOccurrence {
+            payment_id: receiver_id,
+            description: "Transfer".to_string(),
+            amount: Money::from_major(amount),
+            occurrence_date: "2024-01-01".to_string(),
+            payer_id: Some(payer_id),
+            is_recurring: false,
+            receiver_account_id: Some(receiver_id),
+            is_final: true,
+            affects_balance: true,
+            affects_payer_expectation: false,
+            affects_receiver_expectation: true,
+            original: None,
+            original_date: None,
+            refund_id: None,
+            pool_rule_id: None,
+            contribution_rule_id: None,
+            split: None,
+            payers: Vec::new(),
+            pool_parts: Vec::new(),
+        };
+        let occurrences = vec![
+            transfer(1, 10, 60),
+            transfer(2, 10, 40),
+            transfer(10, 11, 50),
+        ];
+        let pools = HashSet::from([10, 11]);
+        let mut ledger = Ledger {
+            participants: Vec::new(),
+            pool_participants: pools.clone(),
+            base_currency: "EUR".to_string(),
+            shares: vec![Vec::new(); occurrences.len()],
+            occurrences,
+            unconverted: Vec::new(),
+            unitized: HashMap::new(),
+        };
+        ledger.run_pools(&[], NaiveDate::from_ymd_opt(2024, 12, 31).unwrap());
+        assert_eq!(
+            ledger.occurrences[2].pool_parts,
+            [(1, Money::from_major(30)), (2, Money::from_major(20))]
+        );
+
+        let mut checking = PoolLedger::new(10, true);
+        let mut savings = PoolLedger::new(11, true);
+        for (occurrence, shares) in ledger.occurrences.iter().zip(&ledger.shares) {
+            checking.apply(occurrence, shares);
+            savings.apply(occurrence, shares);
+        }
+        assert_eq!(checking.members[&1].ownership(), Money::from_major(30));
+        assert_eq!(checking.members[&2].ownership(), Money::from_major(20));
+        assert_eq!(checking.balance(&pools), Money::from_major(50));
+        assert_eq!(savings.members[&1].ownership(), Money::from_major(30));
+        assert_eq!(savings.members[&2].ownership(), Money::from_major(20));
+        assert_eq!(savings.balance(&pools), Money::from_major(50));
+        assert!(!savings.members.contains_key(&10));
+
+        // Both sides show each owner's part of the transfer
+        assert_eq!(
+            checking.members[&1].consumed_breakdown[0].amount,
+            Money::from_major(30)
+        );
+        assert_eq!(
+            savings.members[&2].contributed_breakdown[0].amount,
+            Money::from_major(20)
+        );
+        assert_eq!(savings.expected_minimum, Money::from_major(50));
+        assert_eq!(savings.members[&1].expected_minimum, Money::from_major(30));
+    }
+
+    #[test]
+    fn test_pool_rules_share_interest_and_fees_by_ownership() {
+        // Alice (1) puts 1000 in the pool (10) on Jan 1st, Bob (2) as much on
+        // Jan 11th; the pool earns 3.65% a year (0.01% a day) credited at the
+        // end of the month, then pays a 4.00 fee
+        let deposit = |payer_id: i64, date: &str| PaymentOccurrence {
+            payment_id: payer_id,
+            description: "Deposit".to_string(),
+            amount: Money::from_major(1000),
+            occurrence_date: date.to_string(),
+            payer_id: Some(payer_id),
+            is_recurring: false,
+            receiver_account_id: Some(10),
+            is_final: true,
+            affects_balance: true,
+            affects_payer_expectation: false,
+            affects_receiver_expectation: false,
+            original: None,
+            original_date: None,
+            refund_id: None,
+            pool_rule_id: None,
+            contribution_rule_id: None,
+            split: None,
+            payers: Vec::new(),
+            pool_parts: Vec::new(),
+        };
+        let rule = |id: i64, kind: &str, amount: Option<f64>, rate: Option<f64>| PoolRule {
+            id,
+            project_id: 1,
+            pool_id: 10,
+            kind: kind.to_string(),
+            amount: amount.map(|a| Money::from_f64(a, 2)),
+            rate,
+            recurrence_rule: "DTSTART:20240131\nRRULE:FREQ=MONTHLY".to_string(),
+            start_date: "2024-01-01".to_string(),
+            end_date: None,
+            description: String::new(),
+            created_at: String::new(),
+        };
+        let occurrences = vec![deposit(1, "2024-01-01"), deposit(2, "2024-01-11")];
+        let pools = HashSet::from([10]);
+        let mut ledger = Ledger {
+            participants: Vec::new(),
+            pool_participants: pools.clone(),
+            base_currency: "EUR".to_string(),
+            shares: vec![Vec::new(); occurrences.len()],
+            occurrences,
+            unconverted: Vec::new(),
+            unitized: HashMap::new(),
+        };
+        let rules = [
+            rule(1, "interest", None, Some(3.65)),
+            rule(2, "fee", Some(4.0), None),
+        ];
+        ledger.run_pools(&rules, NaiveDate::from_ymd_opt(2024, 2, 15).unwrap());
+
+        // 31 days of interest for Alice, 21 for Bob, then the fee split by
+        // what each owns once credited
+        assert_eq!(ledger.occurrences.len(), 4);
+        let interest = &ledger.occurrences[2];
+        assert_eq!(interest.pool_rule_id, Some(1));
+        assert_eq!(interest.receiver_account_id, Some(10));
+        assert_eq!(interest.amount, Money::from_f64(5.2, 2));
+        let amounts = |i: usize| -> Vec<(i64, Money)> {
+            ledger.shares[i]
+                .iter()
+                .map(|s| (s.participant_id, s.amount))
+                .collect()
+        };
+        assert_eq!(
+            amounts(2),
+            [(1, Money::from_f64(3.1, 2)), (2, Money::from_f64(2.1, 2))]
+        );
+        let fee = &ledger.occurrences[3];
+        assert_eq!(fee.pool_rule_id, Some(2));
+        assert_eq!(fee.payer_id, Some(10));
+        assert_eq!(
+            amounts(3),
+            [(1, Money::from_major(2)), (2, Money::from_major(2))]
+        );
+
+        // Both show in the owners' breakdowns
+        let mut pool = PoolLedger::new(10, true);
+        for (occurrence, shares) in ledger.occurrences.iter().zip(&ledger.shares) {
+            pool.apply(occurrence, shares);
+        }
+        assert_eq!(pool.members[&1].ownership(), Money::from_f64(1001.1, 2));
+        assert_eq!(pool.members[&2].ownership(), Money::from_f64(1000.1, 2));
+        assert_eq!(
+            pool.members[&1].contributed_breakdown[1].pool_rule_id,
+            Some(1)
+        );
+        assert_eq!(pool.members[&2].consumed_breakdown[0].pool_rule_id, Some(2));
+    }
+
+    #[test]
+    fn test_unitized_pool_shares_gains_by_units() {
+        let valuation = |id: i64, date: &str, kind: &str, amount: i64| PoolValuation {
+            id,
+            project_id: 1,
+            pool_id: 10,
+            valuation_date: date.to_string(),
+            kind: kind.to_string(),
+            amount: Money::from_major(amount),
+            description: String::new(),
+            created_at: String::new(),
+        };
+        let movement = |date: &str, payer_id: i64, receiver_id: i64, amount: i64| {
+            let mut occurrence = PaymentOccurrence::new(
+                &Payment {
+                    payer_id: Some(payer_id),
+                    receiver_account_id: Some(receiver_id),
+                    amount: Money::from_major(amount),
+                    ..make_recurring_payment(date, "monthly", 1, None)
+                },
+                date.to_string(),
+                false,
+            );
+            occurrence.affects_receiver_expectation = false;
+            occurrence
+        };
+
+        let mut ledger = PoolLedger::new(10, false);
+        ledger.units = Some(PoolUnits::new(Arc::from(vec![
+            valuation(1, "2024-01-31", "value", 120),
+            valuation(2, "2024-03-01", "interest", 30),
+        ])));
+        // Alice (1) buys 100 units at 1; the pool is then worth 120, so Bob
+        // (2) buys 50 units with 60
+        ledger.apply(&movement("2024-01-01", 1, 10, 100), &[]);
+        ledger.apply(&movement("2024-02-01", 2, 10, 60), &[]);
+        // 30 of interest brings a unit to 1.4; Alice takes 70 out
+        ledger.apply(&movement("2024-03-05", 10, 1, 70), &[]);
+
+        let ownership = ledger.into_ownership(
+            "Savings".to_string(),
+            &[
+                (1, "Alice".to_string(), "user".to_string()),
+                (2, "Bob".to_string(), "user".to_string()),
+            ],
+            NaiveDate::from_ymd_opt(2024, 3, 31).unwrap(),
+        );
+        assert_eq!(ownership.unit_value, Some(1.4));
+        assert_eq!(ownership.total_balance, Money::from_major(140));
+        let alice = &ownership.entries[0];
+        assert_eq!(alice.contributed, Money::from_major(100));
+        assert_eq!(alice.consumed, Money::from_major(70));
+        assert_eq!(alice.ownership, Money::from_major(70));
+        assert!((alice.units.unwrap() - 50.0).abs() < 1e-9);
+        assert_eq!(ownership.entries[1].ownership, Money::from_major(70));
+    }
+
+    #[test]
+    fn test_several_payers_share_credit_and_pool_consumption() {
+        // Alice (1) paid 60 of a 100 dinner, the pool (3) the other 40;
+        // Alice and Bob (2) ate half each
+        let occurrence = PaymentOccurrence {
+            payment_id: 1,
+            description: "Dinner".to_string(),
+            amount: Money::from_major(100),
+            occurrence_date: "2024-01-01".to_string(),
+            payer_id: Some(1),
+            is_recurring: false,
+            receiver_account_id: None,
+            is_final: true,
+            affects_balance: true,
+            affects_payer_expectation: false,
+            affects_receiver_expectation: false,
+            original: None,
+            original_date: None,
+            refund_id: None,
+            pool_rule_id: None,
+            contribution_rule_id: None,
+            split: None,
+            payers: vec![(1, Money::from_major(60)), (3, Money::from_major(40))],
+            pool_parts: Vec::new(),
+        };
+        let shares: Vec<OccurrenceShare> = [(1, 50), (2, 50)]
+            .into_iter()
+            .map(|(participant_id, amount)| OccurrenceShare {
+                participant_id,
+                amount: Money::from_major(amount),
+                original: None,
+            })
+            .collect();
+
+        let mut paid_map = HashMap::new();
+        let mut owed_map = HashMap::new();
+        let mut pairwise_map = PairwiseMap::new();
+        apply_occurrence(
+            &occurrence,
+            &shares,
+            &HashSet::from([3]),
+            &mut paid_map,
+            &mut owed_map,
+            Some(&mut pairwise_map),
+        );
+
+        // Alice paid for 30 of each share, the pool for the other 20
+        assert_eq!(paid_map[&1], Money::from_major(60));
+        assert_eq!(owed_map[&1], Money::from_major(30));
+        assert_eq!(owed_map[&2], Money::from_major(30));
+        assert_eq!(pairwise_map[&(1, 2)].0, Money::from_major(30));
+
+        let mut ledger = PoolLedger::new(3, true);
+        ledger.apply(&occurrence, &shares);
+        assert_eq!(ledger.members[&1].consumed, Money::from_major(20));
+        assert_eq!(ledger.members[&2].consumed, Money::from_major(20));
+
+        // The pool as the contributor: each payer owns what they paid for it
+        let for_pool = [OccurrenceShare {
+            participant_id: 3,
+            amount: Money::from_major(100),
+            original: None,
+        }];
+        let mut ledger = PoolLedger::new(3, false);
+        ledger.apply(&occurrence, &for_pool);
+        assert_eq!(ledger.members[&1].contributed, Money::from_major(60));
+        assert!(!ledger.members.contains_key(&3));
+    }
+
     // Helper to create a minimal recurring payment for testing
+    fn occurrences_of(payment: &Payment, target: NaiveDate) -> Vec<PaymentOccurrence> {
+        generate_payment_occurrences(payment, target, &BusinessCalendar::default())
+    }
+
     fn make_recurring_payment(
         start_date: &str,
         recurrence_type: &str,
@@ -2297,7 +4532,7 @@ mod tests {
             id: 1,
             project_id: Some(1),
             payer_id: Some(1),
-            amount: 100.0,
+            amount: Money::from_major(100),
             description: "Test recurring".to_string(),
             payment_date: start_date.to_string(),
             created_at: "2024-01-01".to_string(),
@@ -2310,11 +4545,19 @@ mod tests {
             recurrence_weekdays: None,
             recurrence_monthdays: None,
             recurrence_months: None,
+            recurrence_rule: None,
             receiver_account_id: None,
             is_final: true,
             affects_balance: true,
             affects_payer_expectation: false,
             affects_receiver_expectation: false,
+            currency: None,
+            split_from_id: None,
+            amount_schedule: None,
+            business_day_shift: None,
+            prorate_presence: false,
+            weight_profile_id: None,
+            split_mode: None,
         }
     }
 
@@ -2324,7 +4567,7 @@ mod tests {
         // Expected occurrences: April 20, June 20, August 20
         let payment = make_recurring_payment("2025-04-20", "monthly", 2, Some("2025-08-22"));
         let target = NaiveDate::from_ymd_opt(2025, 12, 31).unwrap();
-        let occurrences = generate_payment_occurrences(&payment, target);
+        let occurrences = occurrences_of(&payment, target);
 
         assert_eq!(occurrences.len(), 3);
         assert_eq!(occurrences[0].occurrence_date, "2025-04-20");
@@ -2337,7 +4580,7 @@ mod tests {
         // Start April 20, every 2 months, end August 20 (exactly on last occurrence)
         let payment = make_recurring_payment("2025-04-20", "monthly", 2, Some("2025-08-20"));
         let target = NaiveDate::from_ymd_opt(2025, 12, 31).unwrap();
-        let occurrences = generate_payment_occurrences(&payment, target);
+        let occurrences = occurrences_of(&payment, target);
 
         assert_eq!(occurrences.len(), 3);
         assert_eq!(occurrences[0].occurrence_date, "2025-04-20");
@@ -2351,7 +4594,7 @@ mod tests {
         // Expected: April 20, June 20 only (August 20 > August 19)
         let payment = make_recurring_payment("2025-04-20", "monthly", 2, Some("2025-08-19"));
         let target = NaiveDate::from_ymd_opt(2025, 12, 31).unwrap();
-        let occurrences = generate_payment_occurrences(&payment, target);
+        let occurrences = occurrences_of(&payment, target);
 
         assert_eq!(occurrences.len(), 2);
         assert_eq!(occurrences[0].occurrence_date, "2025-04-20");
@@ -2364,7 +4607,7 @@ mod tests {
         // Expected: Jan 15, Apr 15, Jul 15, Oct 15
         let payment = make_recurring_payment("2025-01-15", "monthly", 3, Some("2025-12-31"));
         let target = NaiveDate::from_ymd_opt(2025, 12, 31).unwrap();
-        let occurrences = generate_payment_occurrences(&payment, target);
+        let occurrences = occurrences_of(&payment, target);
 
         assert_eq!(occurrences.len(), 4);
         assert_eq!(occurrences[0].occurrence_date, "2025-01-15");
@@ -2379,7 +4622,7 @@ mod tests {
         // Expected: Jan 1, Feb 1, Mar 1 (Apr 1 > Mar 15)
         let payment = make_recurring_payment("2025-01-01", "monthly", 1, Some("2025-03-15"));
         let target = NaiveDate::from_ymd_opt(2025, 12, 31).unwrap();
-        let occurrences = generate_payment_occurrences(&payment, target);
+        let occurrences = occurrences_of(&payment, target);
 
         assert_eq!(occurrences.len(), 3);
         assert_eq!(occurrences[0].occurrence_date, "2025-01-01");
@@ -2393,7 +4636,7 @@ mod tests {
         // Expected: Jan 1, Feb 1, Mar 1 (target limits expansion)
         let payment = make_recurring_payment("2025-01-01", "monthly", 1, None);
         let target = NaiveDate::from_ymd_opt(2025, 3, 15).unwrap();
-        let occurrences = generate_payment_occurrences(&payment, target);
+        let occurrences = occurrences_of(&payment, target);
 
         assert_eq!(occurrences.len(), 3);
         assert_eq!(occurrences[0].occurrence_date, "2025-01-01");
@@ -2407,7 +4650,7 @@ mod tests {
         // Expected: Jan 6, Jan 20, Feb 3
         let payment = make_recurring_payment("2025-01-06", "weekly", 2, Some("2025-02-03"));
         let target = NaiveDate::from_ymd_opt(2025, 12, 31).unwrap();
-        let occurrences = generate_payment_occurrences(&payment, target);
+        let occurrences = occurrences_of(&payment, target);
 
         assert_eq!(occurrences.len(), 3);
         assert_eq!(occurrences[0].occurrence_date, "2025-01-06");
@@ -2421,7 +4664,7 @@ mod tests {
         // Expected: Jan 1, Jan 4, Jan 7, Jan 10
         let payment = make_recurring_payment("2025-01-01", "daily", 3, Some("2025-01-10"));
         let target = NaiveDate::from_ymd_opt(2025, 12, 31).unwrap();
-        let occurrences = generate_payment_occurrences(&payment, target);
+        let occurrences = occurrences_of(&payment, target);
 
         assert_eq!(occurrences.len(), 4);
         assert_eq!(occurrences[0].occurrence_date, "2025-01-01");
@@ -2436,7 +4679,7 @@ mod tests {
         // Expected: 2024-06-15, 2026-06-15, 2028-06-15
         let payment = make_recurring_payment("2024-06-15", "yearly", 2, Some("2030-01-01"));
         let target = NaiveDate::from_ymd_opt(2030, 12, 31).unwrap();
-        let occurrences = generate_payment_occurrences(&payment, target);
+        let occurrences = occurrences_of(&payment, target);
 
         assert_eq!(occurrences.len(), 3);
         assert_eq!(occurrences[0].occurrence_date, "2024-06-15");
@@ -2450,7 +4693,7 @@ mod tests {
         // Expected: Jan 1 only
         let payment = make_recurring_payment("2025-01-01", "monthly", 1, Some("2025-01-15"));
         let target = NaiveDate::from_ymd_opt(2025, 12, 31).unwrap();
-        let occurrences = generate_payment_occurrences(&payment, target);
+        let occurrences = occurrences_of(&payment, target);
 
         assert_eq!(occurrences.len(), 1);
         assert_eq!(occurrences[0].occurrence_date, "2025-01-01");
@@ -2463,7 +4706,7 @@ mod tests {
         // clamping to Feb 28, subsequent months use 28 as the day
         let payment = make_recurring_payment("2025-01-31", "monthly", 1, Some("2025-05-31"));
         let target = NaiveDate::from_ymd_opt(2025, 12, 31).unwrap();
-        let occurrences = generate_payment_occurrences(&payment, target);
+        let occurrences = occurrences_of(&payment, target);
 
         assert_eq!(occurrences.len(), 5);
         assert_eq!(occurrences[0].occurrence_date, "2025-01-31");
@@ -2471,5 +4714,380 @@ mod tests {
         assert_eq!(occurrences[2].occurrence_date, "2025-03-28");
         assert_eq!(occurrences[3].occurrence_date, "2025-04-28");
         assert_eq!(occurrences[4].occurrence_date, "2025-05-28");
+
+        // A leap year holds the day on the 29th until the next February
+        let payment = make_recurring_payment("2024-01-31", "monthly", 1, Some("2025-03-31"));
+        let dates: Vec<String> = occurrences_of(&payment, target)
+            .into_iter()
+            .map(|o| o.occurrence_date)
+            .collect();
+        assert_eq!(dates.len(), 15);
+        assert_eq!(dates[..3], ["2024-01-31", "2024-02-29", "2024-03-29"]);
+        assert_eq!(dates[12..], ["2025-01-29", "2025-02-28", "2025-03-28"]);
+    }
+
+    fn rule_dates(start: &str, rule: &str, target: &str) -> Vec<String> {
+        let mut payment = make_recurring_payment(start, "monthly", 1, None);
+        payment.recurrence_rule = Some(rule.to_string());
+        occurrences_of(&payment, parse_date(target).unwrap())
+            .into_iter()
+            .map(|o| o.occurrence_date)
+            .collect()
+    }
+
+    #[test]
+    fn test_rrule_month_end_day_handling() {
+        // An RRULE counts every occurrence from the start date, so months
+        // shorter than 31 days land on their last day without shifting later ones
+        assert_eq!(
+            rule_dates("2025-01-31", "FREQ=MONTHLY;COUNT=5", "2025-12-31"),
+            [
+                "2025-01-31",
+                "2025-02-28",
+                "2025-03-31",
+                "2025-04-30",
+                "2025-05-31"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rrule_rdate_and_exdate() {
+        assert_eq!(
+            rule_dates(
+                "2025-01-10",
+                "RRULE:FREQ=MONTHLY;COUNT=4\n\
+                 RDATE;VALUE=DATE:20250215,20260101\n\
+                 EXDATE;VALUE=DATE:20250310",
+                "2025-12-31"
+            ),
+            ["2025-01-10", "2025-02-10", "2025-02-15", "2025-04-10"]
+        );
+    }
+
+    #[test]
+    fn test_rrule_ordinal_weekdays() {
+        // Last Friday and second Tuesday of each month
+        assert_eq!(
+            rule_dates("2025-01-01", "FREQ=MONTHLY;BYDAY=-1FR", "2025-04-30"),
+            ["2025-01-31", "2025-02-28", "2025-03-28", "2025-04-25"]
+        );
+        assert_eq!(
+            rule_dates("2025-01-15", "FREQ=MONTHLY;BYDAY=2TU;COUNT=3", "2025-12-31"),
+            ["2025-02-11", "2025-03-11", "2025-04-08"]
+        );
+        // First Monday of September, yearly
+        assert_eq!(
+            rule_dates(
+                "2024-01-01",
+                "FREQ=YEARLY;BYMONTH=9;BYDAY=1MO",
+                "2026-12-31"
+            ),
+            ["2024-09-02", "2025-09-01", "2026-09-07"]
+        );
+    }
+
+    #[test]
+    fn test_rrule_month_days() {
+        // First and last day of every other month, until March
+        assert_eq!(
+            rule_dates(
+                "2025-01-01",
+                "FREQ=MONTHLY;INTERVAL=2;BYMONTHDAY=1,-1;UNTIL=20250331",
+                "2025-12-31"
+            ),
+            ["2025-01-01", "2025-01-31", "2025-03-01", "2025-03-31"]
+        );
+        // Second to last day, only in February and March
+        assert_eq!(
+            rule_dates(
+                "2024-01-01",
+                "FREQ=MONTHLY;BYMONTHDAY=-2;BYMONTH=2,3",
+                "2024-12-31"
+            ),
+            ["2024-02-28", "2024-03-30"]
+        );
+        // Day 31 falls on the last day of shorter months
+        assert_eq!(
+            rule_dates("2025-01-01", "FREQ=MONTHLY;BYMONTHDAY=31", "2025-03-31"),
+            ["2025-01-31", "2025-02-28", "2025-03-31"]
+        );
+    }
+
+    #[test]
+    fn test_rrule_weekly_and_daily() {
+        // Every other week on Monday and Thursday, weeks starting Monday
+        assert_eq!(
+            rule_dates(
+                "2025-01-08",
+                "FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,TH",
+                "2025-01-31"
+            ),
+            ["2025-01-09", "2025-01-20", "2025-01-23"]
+        );
+        // Weekdays only, five of them
+        assert_eq!(
+            rule_dates(
+                "2025-01-03",
+                "FREQ=DAILY;BYDAY=MO,TU,WE,TH,FR;COUNT=5",
+                "2025-12-31"
+            ),
+            [
+                "2025-01-03",
+                "2025-01-06",
+                "2025-01-07",
+                "2025-01-08",
+                "2025-01-09"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rrule_set_positions() {
+        // Last weekday and first weekend day of each month
+        assert_eq!(
+            rule_dates(
+                "2025-05-01",
+                "FREQ=MONTHLY;BYDAY=MO,TU,WE,TH,FR;BYSETPOS=-1",
+                "2025-08-31"
+            ),
+            ["2025-05-30", "2025-06-30", "2025-07-31", "2025-08-29"]
+        );
+        assert_eq!(
+            rule_dates(
+                "2025-05-01",
+                "FREQ=MONTHLY;BYDAY=SA,SU;BYSETPOS=1",
+                "2025-07-31"
+            ),
+            ["2025-05-03", "2025-06-01", "2025-07-05"]
+        );
+    }
+
+    #[test]
+    fn test_business_day_shift() {
+        // Last day of the month, on the previous business day; Oct 31st is a holiday
+        let mut payment = make_recurring_payment("2025-05-01", "monthly", 1, None);
+        payment.recurrence_rule = Some("FREQ=MONTHLY;BYMONTHDAY=-1".to_string());
+        payment.business_day_shift = Some("previous".to_string());
+        let calendar = BusinessCalendar::from_holidays([parse_date("2025-10-31").unwrap()]);
+        let dates = |payment: &Payment, target: &str| -> Vec<String> {
+            generate_payment_occurrences(payment, parse_date(target).unwrap(), &calendar)
+                .into_iter()
+                .map(|o| o.occurrence_date)
+                .collect()
+        };
+
+        assert_eq!(
+            dates(&payment, "2025-10-31"),
+            [
+                "2025-05-30",
+                "2025-06-30",
+                "2025-07-31",
+                "2025-08-29",
+                "2025-09-30",
+                "2025-10-30"
+            ]
+        );
+        // Shifted back from after the target date
+        assert_eq!(dates(&payment, "2025-08-29").last().unwrap(), "2025-08-29");
+
+        // The 1st of the month, on the next business day
+        payment.recurrence_rule = Some("FREQ=MONTHLY;BYMONTHDAY=1".to_string());
+        payment.business_day_shift = Some("next".to_string());
+        assert_eq!(
+            dates(&payment, "2025-07-31"),
+            ["2025-05-01", "2025-06-02", "2025-07-01"]
+        );
+    }
+
+    #[test]
+    fn test_recurring_split_follows_presence() {
+        // Rent of 100 on the 1st, split evenly; participant 2 moves out on
+        // May 15th and participant 3 moves in on June 1st
+        let mut payment = make_recurring_payment("2025-04-01", "monthly", 1, None);
+        let contributions = [(1, Money::from_major(50)), (2, Money::from_major(50))];
+        let presence = PresenceCalendar::from_periods([
+            (2, None, parse_date("2025-05-15")),
+            (3, parse_date("2025-06-01"), None),
+        ]);
+        let splits = |payment: &Payment| -> Vec<Option<Vec<(i64, Money)>>> {
+            occurrences_of(payment, parse_date("2025-06-30").unwrap())
+                .iter()
+                .map(|o| {
+                    occurrence_split(
+                        o,
+                        payment,
+                        &contributions,
+                        &ProfileWeights::default(),
+                        &presence,
+                    )
+                })
+                .collect()
+        };
+
+        // Present on the 1st of April and May, gone in June; the newcomer is
+        // not a contributor of the payment
+        assert_eq!(
+            splits(&payment),
+            [None, None, Some(vec![(1, Money::from_major(100))])]
+        );
+
+        // Pro-rated: half of May
+        payment.prorate_presence = true;
+        let splits = splits(&payment);
+        assert_eq!(splits[0], None);
+        // Weights 1 and 15/31
+        assert_eq!(
+            splits[1],
+            Some(vec![
+                (1, Money::from_minor(6739, 2)),
+                (2, Money::from_minor(3261, 2))
+            ])
+        );
+        assert_eq!(splits[2], Some(vec![(1, Money::from_major(100))]));
+
+        // Nobody present: the contributors still share it
+        let nobody = PresenceCalendar::from_periods([
+            (1, None, parse_date("2025-01-31")),
+            (2, None, parse_date("2025-01-31")),
+        ]);
+        let occurrence = &occurrences_of(&payment, parse_date("2025-04-30").unwrap())[0];
+        assert_eq!(
+            occurrence_split(
+                occurrence,
+                &payment,
+                &contributions,
+                &ProfileWeights::default(),
+                &nobody
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_rrule_respects_end_date() {
+        let mut payment = make_recurring_payment("2025-01-10", "monthly", 1, Some("2025-03-01"));
+        payment.recurrence_rule = Some("FREQ=MONTHLY;COUNT=12".to_string());
+        let occurrences = occurrences_of(&payment, parse_date("2025-12-31").unwrap());
+        assert_eq!(occurrences.len(), 2);
+    }
+
+    #[test]
+    fn test_legacy_weekly_cycle_converted_exactly() {
+        // Two-week cycle: Monday in the first week, Wednesday and Friday in the second
+        let mut payment = make_recurring_payment("2025-01-08", "weekly", 2, None);
+        payment.recurrence_weekdays = Some("[[1],[3,5]]".to_string());
+        let dates: Vec<String> = occurrences_of(&payment, parse_date("2025-02-02").unwrap())
+            .into_iter()
+            .map(|o| o.occurrence_date)
+            .collect();
+        // The cycle starts in the week of Sunday Jan 5th; Monday Jan 6th is before the start
+        assert_eq!(
+            dates,
+            [
+                "2025-01-15",
+                "2025-01-17",
+                "2025-01-20",
+                "2025-01-29",
+                "2025-01-31"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_foreign_occurrence_converted_at_its_date_rate() {
+        let rates = ExchangeRateTable::from_rates(vec![
+            (
+                "EUR".to_string(),
+                "USD".to_string(),
+                NaiveDate::from_ymd_opt(2025, 1, 1).unwrap(),
+                1.25,
+            ),
+            (
+                "EUR".to_string(),
+                "USD".to_string(),
+                NaiveDate::from_ymd_opt(2025, 2, 1).unwrap(),
+                1.00,
+            ),
+        ]);
+        let mut payment = make_recurring_payment("2025-01-15", "monthly", 1, None);
+        payment.currency = Some("USD".to_string());
+        let target = NaiveDate::from_ymd_opt(2025, 2, 28).unwrap();
+
+        let occurrences: Vec<PaymentOccurrence> = occurrences_of(&payment, target)
+            .into_iter()
+            .map(|occurrence| occurrence.convert_to("EUR", "USD", &rates).unwrap())
+            .collect();
+
+        // 100 USD at 1.25 in January, at par in February
+        assert_eq!(occurrences[0].amount, Money::from_major(80));
+        assert_eq!(occurrences[1].amount, Money::from_major(100));
+        let original = occurrences[0].original.as_ref().unwrap();
+        assert_eq!(original.currency, "USD");
+        assert_eq!(original.amount, Money::from_major(100));
+
+        // Shares are re-split from the original amounts and sum to the converted total
+        let mut contribution_map = HashMap::new();
+        contribution_map.insert(
+            payment.id,
+            vec![
+                (1, Money::from_minor(3334, 2)),
+                (2, Money::from_minor(3333, 2)),
+                (3, Money::from_minor(3333, 2)),
+            ],
+        );
+        let shares = occurrence_shares(&occurrences[0], &contribution_map);
+        let total: Money = shares.iter().map(|s| s.amount).sum();
+        assert_eq!(total, occurrences[0].amount);
+        assert_eq!(shares[0].amount, Money::from_minor(2667, 2));
+        let breakdown = shares[0].breakdown(&occurrences[0]);
+        assert_eq!(
+            breakdown.original.unwrap().amount,
+            Money::from_minor(3334, 2)
+        );
+    }
+
+    #[test]
+    fn test_foreign_occurrence_without_rate_is_set_aside() {
+        let mut payment = make_recurring_payment("2025-01-15", "monthly", 1, None);
+        payment.is_recurring = false;
+        let target = NaiveDate::from_ymd_opt(2025, 2, 28).unwrap();
+        let occurrence = occurrences_of(&payment, target).remove(0);
+
+        let unconverted = occurrence
+            .convert_to("EUR", "JPY", &ExchangeRateTable::default())
+            .unwrap_err();
+        assert_eq!(unconverted.currency, "JPY");
+        assert_eq!(unconverted.amount, payment.amount);
+        assert_eq!(unconverted.occurrence_date, "2025-01-15");
+    }
+
+    #[test]
+    fn test_series_dates_monthly_from_month_end() {
+        let from = NaiveDate::from_ymd_opt(2025, 1, 31).unwrap();
+        let to = NaiveDate::from_ymd_opt(2025, 4, 30).unwrap();
+        let dates: Vec<String> = series_dates(from, to, SeriesStep::Month)
+            .unwrap()
+            .iter()
+            .map(|d| d.format("%Y-%m-%d").to_string())
+            .collect();
+        assert_eq!(
+            dates,
+            vec!["2025-01-31", "2025-02-28", "2025-03-31", "2025-04-30"]
+        );
+    }
+
+    #[test]
+    fn test_series_dates_weekly_and_limits() {
+        let from = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        let to = NaiveDate::from_ymd_opt(2025, 1, 20).unwrap();
+        assert_eq!(series_dates(from, to, SeriesStep::Week).unwrap().len(), 3);
+        assert_eq!(series_dates(from, to, SeriesStep::Day).unwrap().len(), 20);
+
+        // Reversed range and too many points
+        assert!(series_dates(to, from, SeriesStep::Day).is_none());
+        let far = NaiveDate::from_ymd_opt(2030, 1, 1).unwrap();
+        assert!(series_dates(from, far, SeriesStep::Day).is_none());
+        assert!(series_dates(from, far, SeriesStep::Week).is_some());
     }
 }
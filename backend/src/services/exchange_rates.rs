@@ -0,0 +1,296 @@
+use chrono::NaiveDate;
+use sqlx::SqlitePool;
+use std::collections::{BTreeSet, HashMap};
+
+use crate::error::{AppError, AppResult, ErrorCode};
+use crate::models::{currency_scale, is_valid_currency_code, Money, DEFAULT_SCALE};
+
+/// In-memory view of a project's exchange rates, used to convert payment
+/// amounts into the project's base currency.
+///
+/// Rates are stored per (base, quote) pair. A lookup uses the latest rate on or
+/// before the requested date; there is no rate for a date before all known
+/// rates of a pair.
+#[derive(Debug, Default)]
+pub struct ExchangeRateTable {
+    // (base, quote) -> [(date, rate)] sorted by date
+    pairs: HashMap<(String, String), Vec<(NaiveDate, f64)>>,
+    currencies: BTreeSet<String>,
+}
+
+impl ExchangeRateTable {
+    /// Load all rates for a project
+    pub async fn load(pool: &SqlitePool, project_id: i64) -> AppResult<Self> {
+        let rows: Vec<(String, String, String, f64)> = sqlx::query_as(
+            "SELECT base_currency, quote_currency, rate_date, rate
+             FROM exchange_rates WHERE project_id = ?",
+        )
+        .bind(project_id)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(Self::from_rates(rows.into_iter().filter_map(
+            |(base, quote, date, rate)| {
+                let date = NaiveDate::parse_from_str(&date, "%Y-%m-%d").ok()?;
+                Some((base, quote, date, rate))
+            },
+        )))
+    }
+
+    /// Build a table from (base, quote, date, rate) tuples
+    pub fn from_rates(rates: impl IntoIterator<Item = (String, String, NaiveDate, f64)>) -> Self {
+        let mut table = Self::default();
+        for (base, quote, date, rate) in rates {
+            if !(rate.is_finite() && rate > 0.0) {
+                continue;
+            }
+            table.currencies.insert(base.clone());
+            table.currencies.insert(quote.clone());
+            table
+                .pairs
+                .entry((base, quote))
+                .or_default()
+                .push((date, rate));
+        }
+        for series in table.pairs.values_mut() {
+            series.sort_by_key(|(date, _)| *date);
+        }
+        table
+    }
+
+    /// Units of `to` for one unit of `from` on `date`.
+    /// Tries the direct pair, its inverse, then a cross rate through a common currency.
+    pub fn rate(&self, from: &str, to: &str, date: NaiveDate) -> Option<f64> {
+        if from == to {
+            return Some(1.0);
+        }
+        if let Some(rate) = self.pair_rate(from, to, date) {
+            return Some(rate);
+        }
+        self.currencies
+            .iter()
+            .filter(|pivot| pivot.as_str() != from && pivot.as_str() != to)
+            .find_map(|pivot| {
+                let first = self.pair_rate(from, pivot, date)?;
+                let second = self.pair_rate(pivot, to, date)?;
+                Some(first * second)
+            })
+    }
+
+    /// Convert `amount` from one currency to another at the rate on `date`.
+    /// Returns the converted amount (rounded to the minor unit of `to`) and the rate used.
+    pub fn convert(
+        &self,
+        amount: Money,
+        from: &str,
+        to: &str,
+        date: NaiveDate,
+    ) -> Option<(Money, f64)> {
+        let rate = self.rate(from, to, date)?;
+        Some((amount.mul_to_scale(rate, currency_scale(to)), rate))
+    }
+
+    fn pair_rate(&self, base: &str, quote: &str, date: NaiveDate) -> Option<f64> {
+        if let Some(series) = self.pairs.get(&(base.to_string(), quote.to_string())) {
+            return Self::rate_on(series, date);
+        }
+        self.pairs
+            .get(&(quote.to_string(), base.to_string()))
+            .and_then(|series| Self::rate_on(series, date))
+            .map(|rate| 1.0 / rate)
+    }
+
+    fn rate_on(series: &[(NaiveDate, f64)], date: NaiveDate) -> Option<f64> {
+        // Index of the first entry after `date`
+        let idx = series.partition_point(|(d, _)| *d <= date);
+        idx.checked_sub(1).map(|idx| series[idx].1)
+    }
+}
+
+/// An amount in `currency` (the project's base currency when None) at the
+/// decimals of that currency. Fails with `InvalidAmountPrecision` when it has
+/// more decimals than the currency.
+pub async fn amount_in_currency(
+    pool: &SqlitePool,
+    project_id: i64,
+    amount: Money,
+    currency: Option<&str>,
+) -> AppResult<Money> {
+    let scale = match currency {
+        Some(currency) => currency_scale(currency),
+        None => {
+            let base_currency: Option<String> =
+                sqlx::query_scalar("SELECT base_currency FROM projects WHERE id = ?")
+                    .bind(project_id)
+                    .fetch_optional(pool)
+                    .await?;
+            base_currency.map_or(DEFAULT_SCALE, |c| currency_scale(&c))
+        }
+    };
+    if !amount.fits_scale(scale) {
+        return Err(AppError::bad_request(ErrorCode::InvalidAmountPrecision));
+    }
+    Ok(amount.rescale(scale))
+}
+
+/// Rates read from an ECB-style CSV file
+#[derive(Debug, Default)]
+pub struct ParsedRates {
+    /// (date, quote currency, rate against the file's base currency)
+    pub rates: Vec<(NaiveDate, String, f64)>,
+    /// Cells that were empty, "N/A" or not a positive number
+    pub skipped: usize,
+}
+
+/// Parse an ECB-style exchange rate CSV.
+///
+/// The first column is the date (`2024-01-05` as in eurofxref-hist.csv, or
+/// `05 January 2024` as in the daily eurofxref.csv) and every other column is a
+/// currency code with the number of units per one unit of the base currency.
+/// Trailing empty columns are ignored. Returns an error message for a malformed
+/// header or date.
+pub fn parse_ecb_csv(content: &str) -> Result<ParsedRates, String> {
+    let mut lines = content
+        .lines()
+        .map(|line| line.trim_start_matches('\u{feff}').trim())
+        .filter(|line| !line.is_empty());
+
+    let header = lines.next().ok_or("empty file")?;
+    let mut columns = header.split(',').map(str::trim);
+    match columns.next() {
+        Some(first) if first.eq_ignore_ascii_case("date") => {}
+        _ => return Err("first column must be Date".to_string()),
+    }
+    let currencies: Vec<Option<String>> = columns
+        .map(|code| {
+            if code.is_empty() {
+                Ok(None)
+            } else if is_valid_currency_code(code) {
+                Ok(Some(code.to_string()))
+            } else {
+                Err(format!("invalid currency column '{}'", code))
+            }
+        })
+        .collect::<Result<_, _>>()?;
+
+    let mut parsed = ParsedRates::default();
+    for line in lines {
+        let mut cells = line.split(',').map(str::trim);
+        let date_cell = cells.next().unwrap_or_default();
+        let date = NaiveDate::parse_from_str(date_cell, "%Y-%m-%d")
+            .or_else(|_| NaiveDate::parse_from_str(date_cell, "%d %B %Y"))
+            .map_err(|_| format!("invalid date '{}'", date_cell))?;
+
+        for (currency, cell) in currencies.iter().zip(cells) {
+            let Some(currency) = currency else {
+                continue;
+            };
+            match cell.parse::<f64>() {
+                Ok(rate) if rate.is_finite() && rate > 0.0 => {
+                    parsed.rates.push((date, currency.clone(), rate));
+                }
+                _ => parsed.skipped += 1,
+            }
+        }
+    }
+
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn table() -> ExchangeRateTable {
+        ExchangeRateTable::from_rates(vec![
+            (
+                "EUR".to_string(),
+                "USD".to_string(),
+                date("2024-01-02"),
+                1.10,
+            ),
+            (
+                "EUR".to_string(),
+                "USD".to_string(),
+                date("2024-01-05"),
+                1.20,
+            ),
+            (
+                "EUR".to_string(),
+                "CAD".to_string(),
+                date("2024-01-02"),
+                1.50,
+            ),
+        ])
+    }
+
+    #[test]
+    fn test_rate_uses_latest_on_or_before_date() {
+        let t = table();
+        assert_eq!(t.rate("EUR", "USD", date("2024-01-04")), Some(1.10));
+        assert_eq!(t.rate("EUR", "USD", date("2024-01-05")), Some(1.20));
+        assert_eq!(t.rate("EUR", "USD", date("2024-02-01")), Some(1.20));
+        // Before the first known rate: none
+        assert_eq!(t.rate("EUR", "USD", date("2023-12-25")), None);
+    }
+
+    #[test]
+    fn test_rate_inverse_and_cross() {
+        let t = table();
+        let inverse = t.rate("USD", "EUR", date("2024-01-03")).unwrap();
+        assert!((inverse - 1.0 / 1.10).abs() < 1e-12);
+
+        // USD -> CAD through EUR
+        let cross = t.rate("USD", "CAD", date("2024-01-03")).unwrap();
+        assert!((cross - 1.50 / 1.10).abs() < 1e-12);
+
+        assert_eq!(t.rate("EUR", "JPY", date("2024-01-03")), None);
+        assert_eq!(t.rate("JPY", "JPY", date("2024-01-03")), Some(1.0));
+    }
+
+    #[test]
+    fn test_convert_rounds_to_minor_unit() {
+        let t = table();
+        // 100.00 USD at 1 EUR = 1.10 USD -> 90.909.. EUR
+        let (eur, rate) = t
+            .convert(Money::from_major(100), "USD", "EUR", date("2024-01-03"))
+            .unwrap();
+        assert_eq!(eur, Money::from_minor(9091, 2));
+        assert!((rate - 1.0 / 1.10).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_parse_ecb_hist_csv() {
+        let csv = "Date,USD,JPY,BGN,\n2024-01-05,1.0921,158.63,N/A,\n2024-01-04,1.0953,,1.9558,\n";
+        let parsed = parse_ecb_csv(csv).unwrap();
+        assert_eq!(parsed.rates.len(), 4);
+        assert_eq!(parsed.skipped, 2);
+        assert_eq!(
+            parsed.rates[0],
+            (date("2024-01-05"), "USD".to_string(), 1.0921)
+        );
+    }
+
+    #[test]
+    fn test_parse_ecb_daily_csv() {
+        let csv = "Date, USD, JPY, \n05 January 2024, 1.0921, 158.63, \n";
+        let parsed = parse_ecb_csv(csv).unwrap();
+        assert_eq!(parsed.rates.len(), 2);
+        assert_eq!(
+            parsed.rates[1],
+            (date("2024-01-05"), "JPY".to_string(), 158.63)
+        );
+    }
+
+    #[test]
+    fn test_parse_ecb_csv_rejects_bad_input() {
+        assert!(parse_ecb_csv("").is_err());
+        assert!(parse_ecb_csv("Day,USD\n2024-01-05,1.1").is_err());
+        assert!(parse_ecb_csv("Date,usd\n2024-01-05,1.1").is_err());
+        assert!(parse_ecb_csv("Date,USD\nyesterday,1.1").is_err());
+    }
+}
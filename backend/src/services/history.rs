@@ -4,7 +4,9 @@ use crate::models::{
 };
 use serde::Serialize;
 use sha2::{Digest, Sha256};
-use sqlx::SqlitePool;
+use sqlx::{Acquire, Sqlite, SqliteConnection, SqlitePool};
+use std::future::Future;
+use std::pin::Pin;
 use uuid::Uuid;
 
 /// Parameters for logging a history event
@@ -42,10 +44,10 @@ impl HistoryService {
     }
 
     /// Get the hash of the most recent history entry (for chaining)
-    async fn get_previous_hash(pool: &SqlitePool) -> AppResult<Option<String>> {
+    async fn get_previous_hash(conn: &mut SqliteConnection) -> AppResult<Option<String>> {
         let result: Option<(String,)> =
             sqlx::query_as("SELECT entry_hash FROM history_log ORDER BY id DESC LIMIT 1")
-                .fetch_optional(pool)
+                .fetch_optional(conn)
                 .await?;
 
         Ok(result.map(|(hash,)| hash))
@@ -85,10 +87,27 @@ impl HistoryService {
         hex::encode(hasher.finalize())
     }
 
-    /// Log a single event to the history log
-    pub async fn log_event(pool: &SqlitePool, params: LogEventParams<'_>) -> AppResult<i64> {
+    /// Log a single event to the history log, on the pool or inside a
+    /// transaction so the entry commits with the change it records.
+    ///
+    /// The future is boxed so handlers holding it stay `Send` whatever
+    /// connection type they pass.
+    pub fn log_event<'a, 'c: 'a>(
+        conn: impl Acquire<'c, Database = Sqlite> + Send + 'a,
+        params: LogEventParams<'a>,
+    ) -> Pin<Box<dyn Future<Output = AppResult<i64>> + Send + 'a>> {
+        Box::pin(async move {
+            let mut conn = conn.acquire().await?;
+            Self::insert_entry(&mut conn, params).await
+        })
+    }
+
+    async fn insert_entry(
+        conn: &mut SqliteConnection,
+        params: LogEventParams<'_>,
+    ) -> AppResult<i64> {
         // Get previous hash for chaining
-        let previous_hash = Self::get_previous_hash(pool).await?;
+        let previous_hash = Self::get_previous_hash(conn).await?;
 
         // Generate timestamp
         let created_at = chrono::Utc::now()
@@ -128,15 +147,15 @@ impl HistoryService {
         .bind(params.undoes_history_id)
         .bind(previous_hash)
         .bind(&entry_hash)
-        .execute(pool)
+        .execute(conn)
         .await?;
 
         Ok(result.last_insert_rowid())
     }
 
     /// Log a CREATE action
-    pub async fn log_create<T: Serialize>(
-        pool: &SqlitePool,
+    pub async fn log_create<'c, T: Serialize>(
+        conn: impl Acquire<'c, Database = Sqlite> + Send,
         correlation_id: &str,
         actor_user_id: i64,
         project_id: i64,
@@ -148,7 +167,7 @@ impl HistoryService {
             .map_err(|e| AppError::Internal(format!("Failed to serialize entity: {}", e)))?;
 
         Self::log_event(
-            pool,
+            conn,
             LogEventParams {
                 correlation_id,
                 actor_user_id: Some(actor_user_id),
@@ -166,8 +185,8 @@ impl HistoryService {
     }
 
     /// Log an UPDATE action
-    pub async fn log_update<T: Serialize>(
-        pool: &SqlitePool,
+    pub async fn log_update<'c, T: Serialize>(
+        conn: impl Acquire<'c, Database = Sqlite> + Send,
         params: LogUpdateParams<'_, T>,
     ) -> AppResult<i64> {
         let payload_before = serde_json::to_string(params.before)
@@ -176,7 +195,7 @@ impl HistoryService {
             .map_err(|e| AppError::Internal(format!("Failed to serialize after state: {}", e)))?;
 
         Self::log_event(
-            pool,
+            conn,
             LogEventParams {
                 correlation_id: params.correlation_id,
                 actor_user_id: Some(params.actor_user_id),
@@ -194,8 +213,8 @@ impl HistoryService {
     }
 
     /// Log a DELETE action
-    pub async fn log_delete<T: Serialize>(
-        pool: &SqlitePool,
+    pub async fn log_delete<'c, T: Serialize>(
+        conn: impl Acquire<'c, Database = Sqlite> + Send,
         correlation_id: &str,
         actor_user_id: i64,
         project_id: i64,
@@ -207,7 +226,7 @@ impl HistoryService {
             .map_err(|e| AppError::Internal(format!("Failed to serialize entity: {}", e)))?;
 
         Self::log_event(
-            pool,
+            conn,
             LogEventParams {
                 correlation_id,
                 actor_user_id: Some(actor_user_id),
@@ -299,6 +318,21 @@ impl HistoryService {
         Ok(entry)
     }
 
+    /// All entries logged under one correlation id, oldest first
+    pub async fn get_correlated_entries(
+        pool: &SqlitePool,
+        correlation_id: &str,
+    ) -> AppResult<Vec<HistoryEntry>> {
+        let entries = sqlx::query_as::<_, HistoryEntry>(
+            "SELECT * FROM history_log WHERE correlation_id = ? ORDER BY id",
+        )
+        .bind(correlation_id)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(entries)
+    }
+
     /// Check if an entry has been undone
     pub async fn is_entry_undone(pool: &SqlitePool, history_id: i64) -> AppResult<bool> {
         let result: Option<(i64,)> = sqlx::query_as(
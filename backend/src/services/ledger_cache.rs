@@ -0,0 +1,178 @@
+use chrono::NaiveDate;
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::error::AppResult;
+
+/// Projects kept in memory at once; the least recently used one is dropped first
+const MAX_CACHED_PROJECTS: usize = 64;
+
+/// Current ledger stamp of a project.
+///
+/// Database triggers replace the stamp with a new random value whenever
+/// anything the ledger is expanded from changes: the project's base currency,
+/// participants and their presence, payments with their contributions, payers,
+/// occurrence exceptions and refunds, holidays, exchange rates, weight profile
+/// versions, pool valuations, pool rules and contribution rules. A cached
+/// ledger is therefore valid exactly as long as the stamp it was built with is
+/// still current. None if the project is gone.
+pub async fn ledger_stamp(pool: &SqlitePool, project_id: i64) -> AppResult<Option<String>> {
+    let stamp: Option<Option<String>> =
+        sqlx::query_scalar("SELECT ledger_stamp FROM projects WHERE id = ?")
+            .bind(project_id)
+            .fetch_optional(pool)
+            .await?;
+    Ok(stamp.flatten())
+}
+
+struct Entry<T> {
+    stamp: String,
+    horizon: NaiveDate,
+    value: Arc<T>,
+    last_used: u64,
+}
+
+/// In-memory cache of per-project ledgers, keyed by project and draft inclusion.
+///
+/// Each entry covers occurrences up to a horizon date, so it serves any request
+/// for that date or an earlier one.
+pub struct LedgerCache<T> {
+    entries: Mutex<Entries<T>>,
+}
+
+struct Entries<T> {
+    map: HashMap<(i64, bool), Entry<T>>,
+    // Access counter used to find the least recently used entry
+    clock: u64,
+}
+
+impl<T> Default for LedgerCache<T> {
+    fn default() -> Self {
+        Self {
+            entries: Mutex::new(Entries {
+                map: HashMap::new(),
+                clock: 0,
+            }),
+        }
+    }
+}
+
+impl<T> LedgerCache<T> {
+    /// Cached value built with `stamp` and covering `target`, if any
+    pub fn get(
+        &self,
+        project_id: i64,
+        include_drafts: bool,
+        stamp: &str,
+        target: NaiveDate,
+    ) -> Option<Arc<T>> {
+        let mut guard = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let Entries { map, clock } = &mut *guard;
+        let entry = map.get_mut(&(project_id, include_drafts))?;
+        if entry.stamp != stamp || entry.horizon < target {
+            return None;
+        }
+        *clock += 1;
+        entry.last_used = *clock;
+        Some(entry.value.clone())
+    }
+
+    /// Horizon of the value cached for a project, even if its stamp is stale
+    pub fn horizon(&self, project_id: i64, include_drafts: bool) -> Option<NaiveDate> {
+        let guard = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        guard
+            .map
+            .get(&(project_id, include_drafts))
+            .map(|entry| entry.horizon)
+    }
+
+    pub fn insert(
+        &self,
+        project_id: i64,
+        include_drafts: bool,
+        stamp: String,
+        horizon: NaiveDate,
+        value: Arc<T>,
+    ) {
+        let mut guard = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let Entries { map, clock } = &mut *guard;
+        let key = (project_id, include_drafts);
+        *clock += 1;
+        if !map.contains_key(&key) && map.len() >= MAX_CACHED_PROJECTS {
+            if let Some(oldest) = map
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| *key)
+            {
+                map.remove(&oldest);
+            }
+        }
+        map.insert(
+            key,
+            Entry {
+                stamp,
+                horizon,
+                value,
+                last_used: *clock,
+            },
+        );
+    }
+
+    /// Drop every cached value
+    pub fn clear(&self) {
+        self.entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .map
+            .clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_get_requires_current_stamp_and_horizon() {
+        let cache = LedgerCache::default();
+        cache.insert(1, false, "a".to_string(), date("2025-06-30"), Arc::new(42));
+
+        assert_eq!(
+            cache.get(1, false, "a", date("2025-01-01")).as_deref(),
+            Some(&42)
+        );
+        assert_eq!(
+            cache.get(1, false, "a", date("2025-06-30")).as_deref(),
+            Some(&42)
+        );
+        // Beyond the horizon, stale stamp, other key
+        assert!(cache.get(1, false, "a", date("2025-07-01")).is_none());
+        assert!(cache.get(1, false, "b", date("2025-01-01")).is_none());
+        assert!(cache.get(1, true, "a", date("2025-01-01")).is_none());
+        assert_eq!(cache.horizon(1, false), Some(date("2025-06-30")));
+
+        cache.clear();
+        assert!(cache.get(1, false, "a", date("2025-01-01")).is_none());
+    }
+
+    #[test]
+    fn test_insert_evicts_least_recently_used() {
+        let cache = LedgerCache::default();
+        let horizon = date("2025-01-01");
+        for id in 0..MAX_CACHED_PROJECTS as i64 {
+            cache.insert(id, false, "s".to_string(), horizon, Arc::new(id));
+        }
+        // Touch project 0 so project 1 becomes the oldest
+        assert!(cache.get(0, false, "s", horizon).is_some());
+        cache.insert(1000, false, "s".to_string(), horizon, Arc::new(1000));
+
+        assert!(cache.get(0, false, "s", horizon).is_some());
+        assert!(cache.get(1, false, "s", horizon).is_none());
+        assert!(cache.get(1000, false, "s", horizon).is_some());
+    }
+}
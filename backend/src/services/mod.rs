@@ -1,9 +1,22 @@
 pub mod approval_service;
+pub mod business_days;
 pub mod debt_calculator;
+pub mod exchange_rates;
 pub mod history;
 pub mod image_validator;
+pub mod ledger_cache;
+pub mod presence;
+pub mod settlement;
+pub mod warnings;
+pub mod weight_profiles;
 
 pub use approval_service::*;
+pub use business_days::BusinessCalendar;
 pub use debt_calculator::*;
+pub use exchange_rates::{amount_in_currency, parse_ecb_csv, ExchangeRateTable};
 pub use history::HistoryService;
 pub use image_validator::validate_image_base64;
+pub use presence::PresenceCalendar;
+pub use settlement::{SettlementOptions, SettlementStrategy};
+pub use warnings::evaluate_warnings;
+pub use weight_profiles::ProfileWeights;
@@ -0,0 +1,125 @@
+use chrono::{Datelike, Months, NaiveDate};
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+
+use crate::error::AppResult;
+
+/// A presence period, open-ended on a side without a date
+type Period = (Option<NaiveDate>, Option<NaiveDate>);
+
+/// When participants of a project are present (e.g. living in the house).
+/// A participant without presence periods is always present; with periods,
+/// only on the days one of them covers.
+#[derive(Debug, Default)]
+pub struct PresenceCalendar {
+    // participant_id -> periods
+    periods: HashMap<i64, Vec<Period>>,
+}
+
+impl PresenceCalendar {
+    /// Load the presence periods of a project
+    pub async fn load(pool: &SqlitePool, project_id: i64) -> AppResult<Self> {
+        let rows: Vec<(i64, Option<String>, Option<String>)> = sqlx::query_as(
+            "SELECT participant_id, from_date, to_date
+             FROM participant_presence WHERE project_id = ?",
+        )
+        .bind(project_id)
+        .fetch_all(pool)
+        .await?;
+
+        let parse = |date: Option<String>| {
+            date.and_then(|d| NaiveDate::parse_from_str(&d, "%Y-%m-%d").ok())
+        };
+        Ok(Self::from_periods(rows.into_iter().map(
+            |(participant_id, from, to)| (participant_id, parse(from), parse(to)),
+        )))
+    }
+
+    pub fn from_periods(
+        periods: impl IntoIterator<Item = (i64, Option<NaiveDate>, Option<NaiveDate>)>,
+    ) -> Self {
+        let mut calendar = Self::default();
+        for (participant_id, from, to) in periods {
+            calendar
+                .periods
+                .entry(participant_id)
+                .or_default()
+                .push((from, to));
+        }
+        calendar
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.periods.is_empty()
+    }
+
+    pub fn is_present(&self, participant_id: i64, date: NaiveDate) -> bool {
+        self.periods.get(&participant_id).is_none_or(|periods| {
+            periods.iter().any(|(from, to)| {
+                from.is_none_or(|from| from <= date) && to.is_none_or(|to| date <= to)
+            })
+        })
+    }
+
+    /// Share of an occurrence on `date` a participant takes part in: 1 when
+    /// present that day, 0 otherwise, or with `prorate` the fraction of the
+    /// days of its month they are present
+    pub fn share(&self, participant_id: i64, date: NaiveDate, prorate: bool) -> f64 {
+        if !self.periods.contains_key(&participant_id) {
+            return 1.0;
+        }
+        if !prorate {
+            return if self.is_present(participant_id, date) {
+                1.0
+            } else {
+                0.0
+            };
+        }
+        let Some(first) = date.with_day(1) else {
+            return 0.0;
+        };
+        let days: Vec<NaiveDate> = first
+            .iter_days()
+            .take_while(|d| *d < first + Months::new(1))
+            .collect();
+        let present = days
+            .iter()
+            .filter(|d| self.is_present(participant_id, **d))
+            .count();
+        present as f64 / days.len() as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_presence_periods() {
+        // Participant 1 moved out on April 15th, participant 2 lives there
+        // from March 1st to the end of May and again from September
+        let calendar = PresenceCalendar::from_periods([
+            (1, None, Some(date("2025-04-15"))),
+            (2, Some(date("2025-03-01")), Some(date("2025-05-31"))),
+            (2, Some(date("2025-09-01")), None),
+        ]);
+
+        assert!(calendar.is_present(1, date("2025-04-15")));
+        assert!(!calendar.is_present(1, date("2025-04-16")));
+        assert!(!calendar.is_present(2, date("2025-02-28")));
+        assert!(calendar.is_present(2, date("2025-05-31")));
+        assert!(!calendar.is_present(2, date("2025-07-01")));
+        assert!(calendar.is_present(2, date("2025-12-25")));
+        // No periods: always present
+        assert!(calendar.is_present(3, date("1999-01-01")));
+
+        assert_eq!(calendar.share(1, date("2025-04-01"), false), 1.0);
+        assert_eq!(calendar.share(1, date("2025-05-01"), false), 0.0);
+        assert_eq!(calendar.share(1, date("2025-04-01"), true), 0.5);
+        assert_eq!(calendar.share(3, date("2025-04-01"), true), 1.0);
+    }
+}
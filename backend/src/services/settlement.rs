@@ -0,0 +1,478 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+use crate::models::Money;
+
+/// Largest number of participants with a non-zero balance handled by the exact
+/// solver. The search is exponential; beyond this the greedy strategy is used.
+pub const EXACT_SOLVER_MAX_PARTICIPANTS: usize = 12;
+
+/// Step budget when looking for a settlement of one group that avoids excluded pairs
+const PAIR_SEARCH_BUDGET: usize = 20_000;
+
+/// Stand-in for money owed to or by accounts outside the settlement (pools),
+/// so balances that don't sum to zero can still be solved exactly
+const SLACK_ID: i64 = i64::MIN;
+
+/// How net balances are turned into transfers
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SettlementStrategy {
+    /// Exact solver for small groups, greedy for large ones
+    #[default]
+    Auto,
+    /// Largest debtor pays the largest creditor, repeatedly
+    Greedy,
+    /// Fewest possible transfers; falls back to greedy for large groups
+    Optimal,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SettlementOptions {
+    pub strategy: SettlementStrategy,
+    /// (from, to) participant pairs that must never appear as a transfer
+    pub excluded_pairs: HashSet<(i64, i64)>,
+    /// Transfers smaller than this are dropped
+    pub min_transfer: Money,
+    /// Transfer amounts are rounded to a multiple of this (zero = exact amounts)
+    pub rounding_unit: Money,
+}
+
+/// (from, to, amount in minor units) used inside the exact solver
+type RawTransfer = (i64, i64, i64);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Transfer {
+    pub from: i64,
+    pub to: i64,
+    pub amount: Money,
+}
+
+/// Compute transfers settling `balances` (participant id, net balance; positive
+/// means the participant is owed money).
+///
+/// Returns the strategy actually used (`Optimal` or `Greedy`) and the transfers,
+/// largest first. Amounts that cannot be settled because of excluded pairs, the
+/// threshold or rounding are simply left out.
+pub fn plan_settlements(
+    balances: &[(i64, Money)],
+    options: &SettlementOptions,
+) -> (SettlementStrategy, Vec<Transfer>) {
+    let mut balances: Vec<(i64, Money)> = balances
+        .iter()
+        .filter(|(_, balance)| !balance.is_zero())
+        .copied()
+        .collect();
+    balances.sort_by_key(|(id, _)| *id);
+
+    let try_exact = match options.strategy {
+        SettlementStrategy::Greedy => false,
+        SettlementStrategy::Auto | SettlementStrategy::Optimal => {
+            balances.len() <= EXACT_SOLVER_MAX_PARTICIPANTS
+        }
+    };
+
+    let exact = if try_exact {
+        exact_settlements(&balances, &options.excluded_pairs)
+    } else {
+        None
+    };
+    let (strategy, transfers) = match exact {
+        Some(transfers) => (SettlementStrategy::Optimal, transfers),
+        None => (
+            SettlementStrategy::Greedy,
+            greedy_settlements(&balances, &options.excluded_pairs),
+        ),
+    };
+
+    let mut transfers: Vec<Transfer> = transfers
+        .into_iter()
+        .map(|t| Transfer {
+            amount: t.amount.round_to(options.rounding_unit),
+            ..t
+        })
+        .filter(|t| t.amount.is_positive() && t.amount >= options.min_transfer)
+        .collect();
+    transfers.sort_by_key(|t| (std::cmp::Reverse(t.amount), t.from, t.to));
+
+    (strategy, transfers)
+}
+
+/// Largest debtor pays the largest creditor they are allowed to pay, until no
+/// allowed pair is left
+fn greedy_settlements(balances: &[(i64, Money)], excluded: &HashSet<(i64, i64)>) -> Vec<Transfer> {
+    let mut debtors: Vec<(i64, Money)> = balances
+        .iter()
+        .filter(|(_, b)| b.is_negative())
+        .map(|(id, b)| (*id, -*b))
+        .collect();
+    let mut creditors: Vec<(i64, Money)> = balances
+        .iter()
+        .filter(|(_, b)| b.is_positive())
+        .copied()
+        .collect();
+
+    let mut transfers = Vec::new();
+    loop {
+        debtors.sort_by_key(|(id, amount)| (std::cmp::Reverse(*amount), *id));
+        creditors.sort_by_key(|(id, amount)| (std::cmp::Reverse(*amount), *id));
+
+        let next = debtors
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, amount))| amount.is_positive())
+            .find_map(|(d_idx, (debtor_id, _))| {
+                creditors
+                    .iter()
+                    .position(|(creditor_id, amount)| {
+                        amount.is_positive() && !excluded.contains(&(*debtor_id, *creditor_id))
+                    })
+                    .map(|c_idx| (d_idx, c_idx))
+            });
+        let Some((d_idx, c_idx)) = next else {
+            break;
+        };
+
+        let amount = debtors[d_idx].1.min(creditors[c_idx].1);
+        debtors[d_idx].1 -= amount;
+        creditors[c_idx].1 -= amount;
+        transfers.push(Transfer {
+            from: debtors[d_idx].0,
+            to: creditors[c_idx].0,
+            amount,
+        });
+    }
+
+    transfers
+}
+
+/// Fewest transfers settling every balance exactly.
+///
+/// A group of k participants whose balances sum to zero can always be settled in
+/// k - 1 transfers, so the minimum is reached by splitting everyone into as many
+/// zero-sum groups as possible (dynamic programming over subsets). With excluded
+/// pairs a group only counts if it can still be settled in k - 1 allowed
+/// transfers. Returns `None` when no such split exists.
+fn exact_settlements(
+    balances: &[(i64, Money)],
+    excluded: &HashSet<(i64, i64)>,
+) -> Option<Vec<Transfer>> {
+    // Exact sums need a common scale: the finest among the balances
+    let scale = balances.iter().map(|(_, b)| b.scale()).max().unwrap_or(2);
+    let mut members: Vec<(i64, i64)> = balances
+        .iter()
+        .map(|(id, b)| (*id, b.rescale(scale).minor()))
+        .collect();
+    let total: i64 = members.iter().map(|(_, b)| b).sum();
+    if total != 0 {
+        members.push((SLACK_ID, -total));
+    }
+
+    let n = members.len();
+    if n == 0 {
+        return Some(Vec::new());
+    }
+    let full = (1usize << n) - 1;
+
+    let mut sums = vec![0i64; full + 1];
+    for mask in 1..=full {
+        let low = mask.trailing_zeros() as usize;
+        sums[mask] = sums[mask & (mask - 1)] + members[low].1;
+    }
+
+    // Settlement of each zero-sum group, computed on demand
+    let mut group_cache: HashMap<usize, Option<Vec<RawTransfer>>> = HashMap::new();
+    let mut group_settlement = |mask: usize| -> Option<Vec<RawTransfer>> {
+        group_cache
+            .entry(mask)
+            .or_insert_with(|| {
+                let group: Vec<(i64, i64)> = (0..n)
+                    .filter(|i| mask & (1 << i) != 0)
+                    .map(|i| members[i])
+                    .collect();
+                settle_group(&group, excluded)
+            })
+            .clone()
+    };
+
+    // best[mask] = most zero-sum groups `mask` splits into, with the first group
+    let mut best: Vec<Option<(usize, usize)>> = vec![None; full + 1];
+    best[0] = Some((0, 0));
+    for mask in 1..=full {
+        if sums[mask] != 0 {
+            continue;
+        }
+        // Every split of `mask` has a group containing its lowest member
+        let low = mask & mask.wrapping_neg();
+        let rest = mask ^ low;
+        let mut sub = rest;
+        loop {
+            let group = sub | low;
+            let remainder = mask ^ group;
+            if sums[group] == 0 {
+                if let Some((count, _)) = best[remainder] {
+                    let better = best[mask].is_none_or(|(current, _)| count + 1 > current);
+                    if better && group_settlement(group).is_some() {
+                        best[mask] = Some((count + 1, group));
+                    }
+                }
+            }
+            if sub == 0 {
+                break;
+            }
+            sub = (sub - 1) & rest;
+        }
+    }
+
+    best[full]?;
+    let mut transfers = Vec::new();
+    let mut mask = full;
+    while mask != 0 {
+        let (_, group) = best[mask]?;
+        transfers.extend(group_settlement(group)?);
+        mask ^= group;
+    }
+
+    Some(
+        transfers
+            .into_iter()
+            .filter(|(from, to, _)| *from != SLACK_ID && *to != SLACK_ID)
+            .map(|(from, to, amount)| Transfer {
+                from,
+                to,
+                amount: Money::from_minor(amount, scale),
+            })
+            .collect(),
+    )
+}
+
+/// Settle one zero-sum group (balances in minor units) in at most k - 1
+/// transfers that avoid excluded pairs. Every transfer fully settles one side,
+/// and any tree-shaped settlement can be built that way, so a bounded
+/// depth-first search over (debtor, creditor) choices finds one if it exists.
+fn settle_group(group: &[(i64, i64)], excluded: &HashSet<(i64, i64)>) -> Option<Vec<RawTransfer>> {
+    fn search(
+        remaining: &mut [(i64, i64)],
+        excluded: &HashSet<(i64, i64)>,
+        transfers: &mut Vec<RawTransfer>,
+        budget: &mut usize,
+    ) -> bool {
+        if remaining.iter().all(|(_, b)| *b == 0) {
+            return true;
+        }
+        if *budget == 0 {
+            return false;
+        }
+        *budget -= 1;
+
+        for d in 0..remaining.len() {
+            if remaining[d].1 >= 0 {
+                continue;
+            }
+            for c in 0..remaining.len() {
+                let (debtor, creditor) = (remaining[d].0, remaining[c].0);
+                if remaining[c].1 <= 0
+                    || (debtor != SLACK_ID
+                        && creditor != SLACK_ID
+                        && excluded.contains(&(debtor, creditor)))
+                {
+                    continue;
+                }
+                let amount = (-remaining[d].1).min(remaining[c].1);
+                remaining[d].1 += amount;
+                remaining[c].1 -= amount;
+                transfers.push((debtor, creditor, amount));
+                if search(remaining, excluded, transfers, budget) {
+                    return true;
+                }
+                transfers.pop();
+                remaining[d].1 -= amount;
+                remaining[c].1 += amount;
+            }
+        }
+        false
+    }
+
+    // Largest amounts first so the unconstrained case succeeds on the first path
+    let mut remaining = group.to_vec();
+    remaining.sort_by_key(|(id, b)| (std::cmp::Reverse(b.abs()), *id));
+    let mut transfers = Vec::new();
+    let mut budget = PAIR_SEARCH_BUDGET;
+    search(&mut remaining, excluded, &mut transfers, &mut budget).then_some(transfers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn balances(values: &[(i64, i64)]) -> Vec<(i64, Money)> {
+        values
+            .iter()
+            .map(|(id, major)| (*id, Money::from_major(*major)))
+            .collect()
+    }
+
+    fn options(strategy: SettlementStrategy) -> SettlementOptions {
+        SettlementOptions {
+            strategy,
+            ..Default::default()
+        }
+    }
+
+    /// Net effect of the transfers on each participant
+    fn net(transfers: &[Transfer]) -> HashMap<i64, Money> {
+        let mut net = HashMap::new();
+        for t in transfers {
+            *net.entry(t.from).or_insert(Money::ZERO) += t.amount;
+            *net.entry(t.to).or_insert(Money::ZERO) -= t.amount;
+        }
+        net
+    }
+
+    fn assert_settles(balances: &[(i64, Money)], transfers: &[Transfer]) {
+        let net = net(transfers);
+        for (id, balance) in balances {
+            assert_eq!(
+                *balance + net.get(id).copied().unwrap_or(Money::ZERO),
+                Money::ZERO,
+                "participant {} not settled",
+                id
+            );
+        }
+    }
+
+    #[test]
+    fn test_optimal_uses_fewer_transfers_than_greedy() {
+        // {1, 3} and {2, 4, 5} each sum to zero: 3 transfers suffice,
+        // while largest-to-largest matching needs 4
+        let b = balances(&[(1, 3), (2, 4), (3, -3), (4, -2), (5, -2)]);
+
+        let (strategy, greedy) = plan_settlements(&b, &options(SettlementStrategy::Greedy));
+        assert_eq!(strategy, SettlementStrategy::Greedy);
+        assert_eq!(greedy.len(), 4);
+        assert_settles(&b, &greedy);
+
+        let (strategy, optimal) = plan_settlements(&b, &options(SettlementStrategy::Optimal));
+        assert_eq!(strategy, SettlementStrategy::Optimal);
+        assert_eq!(optimal.len(), 3);
+        assert_settles(&b, &optimal);
+        assert!(optimal.contains(&Transfer {
+            from: 3,
+            to: 1,
+            amount: Money::from_major(3)
+        }));
+    }
+
+    #[test]
+    fn test_excluded_pair_is_never_used() {
+        let b = balances(&[(1, 10), (2, 10), (3, -10), (4, -10)]);
+        let mut opts = options(SettlementStrategy::Optimal);
+        opts.excluded_pairs.insert((3, 1));
+
+        let (strategy, transfers) = plan_settlements(&b, &opts);
+        assert_eq!(strategy, SettlementStrategy::Optimal);
+        assert_eq!(transfers.len(), 2);
+        assert!(transfers.iter().all(|t| (t.from, t.to) != (3, 1)));
+        assert_settles(&b, &transfers);
+    }
+
+    #[test]
+    fn test_exclusions_respected_by_greedy() {
+        let b = balances(&[(1, 10), (2, 5), (3, -15)]);
+        let mut opts = options(SettlementStrategy::Greedy);
+        opts.excluded_pairs.insert((3, 1));
+
+        let (_, transfers) = plan_settlements(&b, &opts);
+        // Only 3 -> 2 is allowed; the rest stays unsettled
+        assert_eq!(
+            transfers,
+            vec![Transfer {
+                from: 3,
+                to: 2,
+                amount: Money::from_major(5)
+            }]
+        );
+    }
+
+    #[test]
+    fn test_unsatisfiable_exclusions_fall_back_to_greedy() {
+        let b = balances(&[(1, 10), (2, -10)]);
+        let mut opts = options(SettlementStrategy::Optimal);
+        opts.excluded_pairs.insert((2, 1));
+
+        let (strategy, transfers) = plan_settlements(&b, &opts);
+        assert_eq!(strategy, SettlementStrategy::Greedy);
+        assert!(transfers.is_empty());
+    }
+
+    #[test]
+    fn test_balances_not_summing_to_zero() {
+        // Participant 2 also owes 5 to a pool, which is not part of the settlement
+        let b = balances(&[(1, 10), (2, -15)]);
+        let (strategy, transfers) = plan_settlements(&b, &options(SettlementStrategy::Auto));
+        assert_eq!(strategy, SettlementStrategy::Optimal);
+        assert_eq!(
+            transfers,
+            vec![Transfer {
+                from: 2,
+                to: 1,
+                amount: Money::from_major(10)
+            }]
+        );
+    }
+
+    #[test]
+    fn test_threshold_and_rounding() {
+        let b = vec![
+            (1, Money::from_minor(2049, 2)),
+            (2, Money::from_minor(-2000, 2)),
+            (3, Money::from_minor(-49, 2)),
+        ];
+        let opts = SettlementOptions {
+            strategy: SettlementStrategy::Optimal,
+            min_transfer: Money::from_major(1),
+            rounding_unit: Money::from_minor(50, 2),
+            ..Default::default()
+        };
+
+        let (_, transfers) = plan_settlements(&b, &opts);
+        assert_eq!(
+            transfers,
+            vec![Transfer {
+                from: 2,
+                to: 1,
+                amount: Money::from_major(20)
+            }]
+        );
+    }
+
+    #[test]
+    fn test_large_group_uses_greedy() {
+        let values: Vec<(i64, i64)> = (1..=EXACT_SOLVER_MAX_PARTICIPANTS as i64 + 1)
+            .map(|id| (id, if id % 2 == 0 { 7 } else { -7 }))
+            .chain(std::iter::once((100, 7)))
+            .collect();
+        let b = balances(&values);
+
+        let (strategy, transfers) = plan_settlements(&b, &options(SettlementStrategy::Optimal));
+        assert_eq!(strategy, SettlementStrategy::Greedy);
+        assert_settles(&b, &transfers);
+    }
+
+    #[test]
+    fn test_optimal_never_worse_than_greedy() {
+        let cases: [&[(i64, i64)]; 4] = [
+            &[(1, 50), (2, -20), (3, -30)],
+            &[(1, 7), (2, 5), (3, -4), (4, -4), (5, -4)],
+            &[(1, 12), (2, -4), (3, -4), (4, 6), (5, -10)],
+            &[(1, 1), (2, 2), (3, 3), (4, -1), (5, -2), (6, -3)],
+        ];
+        for case in cases {
+            let b = balances(case);
+            let (_, greedy) = plan_settlements(&b, &options(SettlementStrategy::Greedy));
+            let (_, optimal) = plan_settlements(&b, &options(SettlementStrategy::Optimal));
+            assert_settles(&b, &optimal);
+            assert!(optimal.len() <= greedy.len(), "{:?}", case);
+        }
+    }
+}
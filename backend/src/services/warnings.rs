@@ -0,0 +1,219 @@
+use chrono::{Datelike, Months, NaiveDate};
+use sqlx::SqlitePool;
+
+use crate::error::AppResult;
+use crate::models::{Money, PoolWarning, PoolWarningKind};
+use crate::services::debt_calculator::{forecast_pools, ForecastCurve, ForecastPoint};
+
+/// Horizons accepted for `warning_horizon_account` and `warning_horizon_users`
+pub const WARNING_HORIZONS: [&str; 4] = [
+    "end_of_current_month",
+    "end_of_next_month",
+    "3_months",
+    "6_months",
+];
+
+/// Last date covered by a warning horizon, counted from `today`.
+/// Returns None for an unknown horizon.
+pub fn horizon_end_date(horizon: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let first_of_month = today.with_day(1)?;
+    match horizon {
+        "end_of_current_month" => first_of_month
+            .checked_add_months(Months::new(1))?
+            .pred_opt(),
+        "end_of_next_month" => first_of_month
+            .checked_add_months(Months::new(2))?
+            .pred_opt(),
+        "3_months" => today.checked_add_months(Months::new(3)),
+        "6_months" => today.checked_add_months(Months::new(6)),
+        _ => None,
+    }
+}
+
+/// Horizon setting and the date it resolves to
+type ResolvedHorizon = (String, NaiveDate);
+
+#[derive(sqlx::FromRow)]
+struct PoolWarningSettings {
+    id: i64,
+    warning_horizon_account: Option<String>,
+    warning_horizon_users: Option<String>,
+}
+
+/// Evaluate the warning horizons of every pool in a project, as of `today`.
+///
+/// Drafts are excluded. Warnings are sorted by date, pool warnings first.
+pub async fn evaluate_warnings(
+    pool: &SqlitePool,
+    project_id: i64,
+    today: NaiveDate,
+) -> AppResult<Vec<PoolWarning>> {
+    let settings: Vec<PoolWarningSettings> = sqlx::query_as(
+        "SELECT id, warning_horizon_account, warning_horizon_users
+         FROM participants WHERE project_id = ? AND account_type = 'pool'",
+    )
+    .bind(project_id)
+    .fetch_all(pool)
+    .await?;
+
+    let resolve = |horizon: &Option<String>| {
+        let horizon = horizon.as_deref()?;
+        Some((horizon.to_string(), horizon_end_date(horizon, today)?))
+    };
+    let horizons: Vec<(i64, Option<ResolvedHorizon>, Option<ResolvedHorizon>)> = settings
+        .iter()
+        .map(|s| {
+            (
+                s.id,
+                resolve(&s.warning_horizon_account),
+                resolve(&s.warning_horizon_users),
+            )
+        })
+        .filter(|(_, account, users)| account.is_some() || users.is_some())
+        .collect();
+
+    // Forecast once up to the furthest horizon
+    let Some(furthest) = horizons
+        .iter()
+        .flat_map(|(_, account, users)| [account, users])
+        .filter_map(|h| h.as_ref().map(|(_, date)| *date))
+        .max()
+    else {
+        return Ok(Vec::new());
+    };
+    let forecasts = forecast_pools(pool, project_id, today, furthest, false).await?;
+
+    let mut warnings = Vec::new();
+    for forecast in &forecasts {
+        let Some((_, account, users)) = horizons.iter().find(|(id, _, _)| *id == forecast.pool_id)
+        else {
+            continue;
+        };
+        let warning =
+            |kind, (date, shortfall): (String, Money), (horizon, end): &(String, NaiveDate)| {
+                PoolWarning {
+                    kind,
+                    pool_id: forecast.pool_id,
+                    pool_name: forecast.pool_name.clone(),
+                    participant_id: None,
+                    participant_name: None,
+                    date,
+                    shortfall,
+                    horizon: horizon.clone(),
+                    horizon_date: end.format("%Y-%m-%d").to_string(),
+                }
+            };
+
+        if let Some(account) = account {
+            let points = points_until(&forecast.curve, account.1);
+            if let Some(found) = first_shortfall(points, |p| p.expected_minimum - p.balance) {
+                warnings.push(warning(PoolWarningKind::PoolBelowExpected, found, account));
+            }
+            if let Some(found) = first_shortfall(points, |p| -p.expected_minimum) {
+                warnings.push(warning(
+                    PoolWarningKind::NegativeExpectedMinimum,
+                    found,
+                    account,
+                ));
+            }
+        }
+
+        if let Some(users) = users {
+            for user in &forecast.users {
+                let points = points_until(&user.curve, users.1);
+                if let Some(found) = first_shortfall(points, |p| p.expected_minimum - p.balance) {
+                    warnings.push(PoolWarning {
+                        participant_id: Some(user.participant_id),
+                        participant_name: Some(user.participant_name.clone()),
+                        ..warning(PoolWarningKind::UserBelowExpected, found, users)
+                    });
+                }
+            }
+        }
+    }
+
+    warnings.sort_by(|a, b| {
+        a.date
+            .cmp(&b.date)
+            .then(a.participant_id.is_some().cmp(&b.participant_id.is_some()))
+    });
+    Ok(warnings)
+}
+
+/// Points of a curve dated on or before `end`
+fn points_until(curve: &ForecastCurve, end: NaiveDate) -> &[ForecastPoint] {
+    let end = end.format("%Y-%m-%d").to_string();
+    let count = curve.points.partition_point(|p| p.date <= end);
+    &curve.points[..count]
+}
+
+/// First date where `gap` is positive, with the largest gap over all points
+fn first_shortfall(
+    points: &[ForecastPoint],
+    gap: impl Fn(&ForecastPoint) -> Money,
+) -> Option<(String, Money)> {
+    let first = points.iter().find(|p| gap(p).is_positive())?;
+    let largest = points.iter().map(&gap).max()?;
+    Some((first.date.clone(), largest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn point(d: &str, balance: i64, expected_minimum: i64) -> ForecastPoint {
+        ForecastPoint {
+            date: d.to_string(),
+            balance: Money::from_major(balance),
+            expected_minimum: Money::from_major(expected_minimum),
+        }
+    }
+
+    #[test]
+    fn test_horizon_end_date() {
+        let today = date("2025-01-31");
+        assert_eq!(
+            horizon_end_date("end_of_current_month", today),
+            Some(date("2025-01-31"))
+        );
+        assert_eq!(
+            horizon_end_date("end_of_next_month", today),
+            Some(date("2025-02-28"))
+        );
+        assert_eq!(
+            horizon_end_date("3_months", today),
+            Some(date("2025-04-30"))
+        );
+        assert_eq!(
+            horizon_end_date("6_months", today),
+            Some(date("2025-07-31"))
+        );
+        assert_eq!(horizon_end_date("next_year", today), None);
+        for horizon in WARNING_HORIZONS {
+            assert!(horizon_end_date(horizon, today).is_some());
+        }
+    }
+
+    #[test]
+    fn test_first_shortfall_reports_largest_gap() {
+        let points = [
+            point("2025-01-01", 100, 50),
+            point("2025-02-01", 40, 50),
+            point("2025-03-01", 20, 60),
+            point("2025-04-01", 80, 60),
+        ];
+        let found = first_shortfall(&points, |p| p.expected_minimum - p.balance);
+        assert_eq!(
+            found,
+            Some(("2025-02-01".to_string(), Money::from_major(40)))
+        );
+        assert_eq!(
+            first_shortfall(&points[..1], |p| p.expected_minimum - p.balance),
+            None
+        );
+    }
+}
@@ -0,0 +1,101 @@
+use chrono::NaiveDate;
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+
+use crate::error::AppResult;
+use crate::models::WeightProfileVersion;
+
+/// Weights of a profile from a date on (None = the first version)
+type Version = (Option<NaiveDate>, Vec<(i64, f64)>);
+
+/// Versions of the weight profiles of a project, for splitting the
+/// occurrences of payments that reference one
+#[derive(Debug, Default)]
+pub struct ProfileWeights {
+    // profile_id -> versions, oldest first
+    versions: HashMap<i64, Vec<Version>>,
+}
+
+impl ProfileWeights {
+    /// Load the profile versions of a project
+    pub async fn load(pool: &SqlitePool, project_id: i64) -> AppResult<Self> {
+        let versions: Vec<WeightProfileVersion> = sqlx::query_as(
+            "SELECT v.* FROM weight_profile_versions v
+             JOIN weight_profiles p ON v.profile_id = p.id
+             WHERE p.project_id = ?",
+        )
+        .bind(project_id)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(Self::from_versions(versions.iter().map(|version| {
+            let from = version
+                .effective_from
+                .as_deref()
+                .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok());
+            let weights = version
+                .contributions()
+                .into_iter()
+                .map(|c| (c.participant_id, c.weight))
+                .collect();
+            (version.profile_id, from, weights)
+        })))
+    }
+
+    pub fn from_versions(
+        versions: impl IntoIterator<Item = (i64, Option<NaiveDate>, Vec<(i64, f64)>)>,
+    ) -> Self {
+        let mut profiles = Self::default();
+        for (profile_id, from, weights) in versions {
+            profiles
+                .versions
+                .entry(profile_id)
+                .or_default()
+                .push((from, weights));
+        }
+        for versions in profiles.versions.values_mut() {
+            versions.sort_by_key(|(from, _)| *from);
+        }
+        profiles
+    }
+
+    /// Weights of a profile in effect on `date`
+    pub fn weights_on(&self, profile_id: i64, date: NaiveDate) -> Option<&[(i64, f64)]> {
+        self.versions
+            .get(&profile_id)?
+            .iter()
+            .rev()
+            .find(|(from, _)| from.is_none_or(|from| from <= date))
+            .map(|(_, weights)| weights.as_slice())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_profile_version_in_effect() {
+        let profiles = ProfileWeights::from_versions([
+            (1, Some(date("2025-07-01")), vec![(1, 1.0), (2, 2.0)]),
+            (1, None, vec![(1, 1.0), (2, 1.0)]),
+            (2, Some(date("2025-03-01")), vec![(3, 1.0)]),
+        ]);
+
+        assert_eq!(
+            profiles.weights_on(1, date("2025-06-30")),
+            Some(&[(1, 1.0), (2, 1.0)][..])
+        );
+        assert_eq!(
+            profiles.weights_on(1, date("2025-07-01")),
+            Some(&[(1, 1.0), (2, 2.0)][..])
+        );
+        // Before its first version a profile has no weights
+        assert_eq!(profiles.weights_on(2, date("2025-02-28")), None);
+        assert_eq!(profiles.weights_on(3, date("2025-07-01")), None);
+    }
+}
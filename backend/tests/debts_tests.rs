@@ -0,0 +1,3648 @@
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+    middleware, Router,
+};
+use bonscompte_backend::{auth::middleware::JwtSecret, config::Config, db, routes, AppState};
+use serde_json::{json, Value};
+use sqlx::SqlitePool;
+use tower::ServiceExt;
+
+/// Same extension injection as the real server (pool and JWT secret for extractors)
+async fn inject_extensions(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    mut request: Request<Body>,
+    next: middleware::Next,
+) -> axum::response::Response {
+    request
+        .extensions_mut()
+        .insert(JwtSecret(state.jwt_secret.clone()));
+    request.extensions_mut().insert(state.pool.clone());
+    next.run(request).await
+}
+
+/// Helper to create a test app with the project routes and an in-memory database
+async fn create_test_app() -> (Router, SqlitePool) {
+    let pool = db::init_pool("sqlite::memory:")
+        .await
+        .expect("Failed to create test database pool");
+
+    db::run_migrations(&pool)
+        .await
+        .expect("Failed to run migrations");
+
+    let jwt_secret = "test-secret-key-for-testing".to_string();
+
+    let config = Config {
+        database_url: "sqlite::memory:".to_string(),
+        jwt_secret: jwt_secret.clone(),
+        host: "127.0.0.1".to_string(),
+        port: 8000,
+        max_projects_per_user: None,
+    };
+
+    let state = AppState {
+        pool: pool.clone(),
+        jwt_secret,
+        config,
+    };
+
+    let project_routes = Router::new()
+        .nest("/participants", routes::participants::router())
+        .nest("/payments", routes::payments::router())
+        .nest("/debts", routes::debts::router())
+        .nest("/exchange-rates", routes::exchange_rates::router())
+        .nest("/holidays", routes::holidays::router())
+        .nest("/presence", routes::presence::router())
+        .nest("/pool-valuations", routes::pool_valuations::router())
+        .nest("/pool-rules", routes::pool_rules::router())
+        .nest("/pool-statements", routes::pool_statements::router())
+        .nest("/contribution-rules", routes::contribution_rules::router())
+        .nest("/weight-profiles", routes::weight_profiles::router())
+        .nest("/history", routes::history::router())
+        .nest("/warnings", routes::warnings::router());
+
+    let app = Router::new()
+        .nest("/auth", routes::auth::router())
+        .nest("/projects", routes::projects::router())
+        .nest("/projects/{id}", project_routes)
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            inject_extensions,
+        ))
+        .with_state(state);
+
+    (app, pool)
+}
+
+/// Send a JSON request and return the status and parsed body
+async fn send(
+    app: &Router,
+    method: &str,
+    uri: &str,
+    token: Option<&str>,
+    body: Option<Value>,
+) -> (StatusCode, Value) {
+    let mut builder = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("Content-Type", "application/json");
+    if let Some(token) = token {
+        builder = builder.header("Authorization", format!("Bearer {}", token));
+    }
+    let body = body.map(|b| b.to_string()).unwrap_or_default();
+
+    let response = app
+        .clone()
+        .oneshot(builder.body(Body::from(body)).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let value = serde_json::from_slice(&bytes).unwrap_or(Value::Null);
+    (status, value)
+}
+
+/// Register and activate a user, returning a token
+async fn login(app: &Router, pool: &SqlitePool, username: &str) -> String {
+    let credentials = json!({ "username": username, "password": "password123" });
+    let (status, _) = send(
+        app,
+        "POST",
+        "/auth/register",
+        None,
+        Some(credentials.clone()),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    sqlx::query("UPDATE users SET user_state = 'active' WHERE username = ?")
+        .bind(username)
+        .execute(pool)
+        .await
+        .expect("Failed to activate user");
+
+    let (status, body) = send(app, "POST", "/auth/login", None, Some(credentials)).await;
+    assert_eq!(status, StatusCode::OK);
+    body["token"].as_str().unwrap().to_string()
+}
+
+/// Project where Alice paid 90 shared by Alice, Bob and Carol, and Bob paid 30 for Carol.
+/// Returns (project id, [alice, bob, carol]).
+async fn setup_project(app: &Router, token: &str) -> (i64, [i64; 3]) {
+    let (status, project) = send(
+        app,
+        "POST",
+        "/projects",
+        Some(token),
+        Some(json!({ "name": "Trip" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let project_id = project["id"].as_i64().unwrap();
+
+    let mut ids = [0; 3];
+    for (i, name) in ["Alice", "Bob", "Carol"].iter().enumerate() {
+        let (status, participant) = send(
+            app,
+            "POST",
+            &format!("/projects/{}/participants", project_id),
+            Some(token),
+            Some(json!({ "name": name })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        ids[i] = participant["id"].as_i64().unwrap();
+    }
+    let [alice, bob, carol] = ids;
+
+    for (payer, amount, contributors) in [
+        (alice, 90.0, vec![alice, bob, carol]),
+        (bob, 30.0, vec![carol]),
+    ] {
+        let contributions: Vec<Value> = contributors
+            .iter()
+            .map(|id| json!({ "participant_id": id, "weight": 1.0 }))
+            .collect();
+        let (status, _) = send(
+            app,
+            "POST",
+            &format!("/projects/{}/payments", project_id),
+            Some(token),
+            Some(json!({
+                "payer_id": payer,
+                "amount": amount,
+                "description": "Expense",
+                "payment_date": "2025-01-10",
+                "contributions": contributions,
+            })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    (project_id, ids)
+}
+
+fn expected_balances(summary: &Value) -> Value {
+    Value::Array(
+        summary["balances"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|b| json!({ "participant_id": b["participant_id"], "net_balance": b["net_balance"] }))
+            .collect(),
+    )
+}
+
+#[tokio::test]
+async fn test_settle_all_debts_zeroes_balances() {
+    let (app, pool) = create_test_app().await;
+    let token = login(&app, &pool, "alice").await;
+    let (project_id, _) = setup_project(&app, &token).await;
+
+    let uri = format!("/projects/{}/debts?date=2025-01-31", project_id);
+    let (status, summary) = send(&app, "GET", &uri, Some(&token), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(!summary["settlements"].as_array().unwrap().is_empty());
+
+    let (status, result) = send(
+        &app,
+        "POST",
+        &format!("/projects/{}/debts/settle?date=2025-01-31", project_id),
+        Some(&token),
+        Some(json!({ "expected_balances": expected_balances(&summary) })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let payments = result["payments"].as_array().unwrap();
+    assert_eq!(
+        payments.len(),
+        summary["settlements"].as_array().unwrap().len()
+    );
+    assert!(payments.iter().all(|p| p["receiver_account_id"].is_i64()));
+    assert!(result["summary"]["settlements"]
+        .as_array()
+        .unwrap()
+        .is_empty());
+    assert!(result["summary"]["balances"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .all(|b| b["net_balance"].as_f64() == Some(0.0)));
+
+    // All transfers share one correlation id in history
+    let correlation_id = result["correlation_id"].as_str().unwrap();
+    let count: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM history_log WHERE correlation_id = ? AND entity_type = 'payment'",
+    )
+    .bind(correlation_id)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(count as usize, payments.len());
+}
+
+#[tokio::test]
+async fn test_settle_selected_debt_only() {
+    let (app, pool) = create_test_app().await;
+    let token = login(&app, &pool, "alice").await;
+    let (project_id, _) = setup_project(&app, &token).await;
+
+    let uri = format!("/projects/{}/debts?date=2025-01-31", project_id);
+    let (_, summary) = send(&app, "GET", &uri, Some(&token), None).await;
+    let first = summary["settlements"][0].clone();
+
+    let (status, result) = send(
+        &app,
+        "POST",
+        &format!("/projects/{}/debts/settle?date=2025-01-31", project_id),
+        Some(&token),
+        Some(json!({
+            "debts": [first],
+            "expected_balances": expected_balances(&summary),
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(result["payments"].as_array().unwrap().len(), 1);
+    assert_eq!(
+        result["summary"]["settlements"].as_array().unwrap().len(),
+        summary["settlements"].as_array().unwrap().len() - 1
+    );
+
+    // A debt that was never suggested is rejected
+    let (status, body) = send(
+        &app,
+        "POST",
+        &format!("/projects/{}/debts/settle?date=2025-01-31", project_id),
+        Some(&token),
+        Some(json!({
+            "debts": [first],
+            "expected_balances": expected_balances(&result["summary"]),
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "SETTLEMENT_NOT_SUGGESTED");
+}
+
+#[tokio::test]
+async fn test_settle_rejects_partial_settlement_options() {
+    let (app, pool) = create_test_app().await;
+    let token = login(&app, &pool, "alice").await;
+    let (project_id, _) = setup_project(&app, &token).await;
+
+    let uri = format!("/projects/{}/debts?date=2025-01-31", project_id);
+    let (_, summary) = send(&app, "GET", &uri, Some(&token), None).await;
+
+    // Rounding or a threshold would leave part of the balances unsettled
+    for options in ["rounding=7", "min_transfer=50"] {
+        let (status, body) = send(
+            &app,
+            "POST",
+            &format!(
+                "/projects/{}/debts/settle?date=2025-01-31&{}",
+                project_id, options
+            ),
+            Some(&token),
+            Some(json!({
+                "source": "settlement_plan",
+                "expected_balances": expected_balances(&summary),
+            })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "INVALID_SETTLEMENT_OPTIONS");
+    }
+
+    let transfers: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM payments WHERE project_id = ? AND receiver_account_id IS NOT NULL",
+    )
+    .bind(project_id)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(transfers, 0);
+
+    // The plan without them settles everything
+    let (status, result) = send(
+        &app,
+        "POST",
+        &format!("/projects/{}/debts/settle?date=2025-01-31", project_id),
+        Some(&token),
+        Some(json!({
+            "source": "settlement_plan",
+            "expected_balances": expected_balances(&summary),
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(result["summary"]["balances"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .all(|b| b["net_balance"].as_f64() == Some(0.0)));
+}
+
+#[tokio::test]
+async fn test_settle_rejects_stale_balances() {
+    let (app, pool) = create_test_app().await;
+    let token = login(&app, &pool, "alice").await;
+    let (project_id, [alice, bob, _]) = setup_project(&app, &token).await;
+
+    let uri = format!("/projects/{}/debts?date=2025-01-31", project_id);
+    let (_, summary) = send(&app, "GET", &uri, Some(&token), None).await;
+
+    // Someone records another expense in the meantime
+    let (status, _) = send(
+        &app,
+        "POST",
+        &format!("/projects/{}/payments", project_id),
+        Some(&token),
+        Some(json!({
+            "payer_id": bob,
+            "amount": 10.0,
+            "description": "Coffee",
+            "payment_date": "2025-01-15",
+            "contributions": [{ "participant_id": alice, "weight": 1.0 }],
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, body) = send(
+        &app,
+        "POST",
+        &format!("/projects/{}/debts/settle?date=2025-01-31", project_id),
+        Some(&token),
+        Some(json!({ "expected_balances": expected_balances(&summary) })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(body["code"], "BALANCES_CHANGED");
+
+    let transfers: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM payments WHERE project_id = ? AND receiver_account_id IS NOT NULL",
+    )
+    .bind(project_id)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(transfers, 0);
+}
+
+#[tokio::test]
+async fn test_foreign_payment_counts_once_a_rate_covers_it() {
+    let (app, pool) = create_test_app().await;
+    let token = login(&app, &pool, "alice").await;
+    let (project_id, [alice, bob, _]) = setup_project(&app, &token).await;
+
+    let debts = format!("/projects/{}/debts?date=2025-01-31", project_id);
+    let (_, summary) = send(&app, "GET", &debts, Some(&token), None).await;
+    let baseline = expected_balances(&summary);
+
+    create_payment(
+        &app,
+        &token,
+        project_id,
+        json!({
+            "payer_id": alice,
+            "amount": 100.0,
+            "currency": "USD",
+            "description": "Hotel",
+            "payment_date": "2025-01-10",
+            "contributions": [{ "participant_id": bob, "weight": 1.0 }],
+        }),
+    )
+    .await;
+
+    // No rate on or before the payment date: it is left out and reported
+    let rates = format!("/projects/{}/exchange-rates", project_id);
+    let (status, _) = send(
+        &app,
+        "POST",
+        &rates,
+        Some(&token),
+        Some(json!({ "quote_currency": "USD", "rate_date": "2025-01-20", "rate": 1.0 })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, summary) = send(&app, "GET", &debts, Some(&token), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(expected_balances(&summary), baseline);
+    let unconverted = summary["unconverted"].as_array().unwrap();
+    assert_eq!(unconverted.len(), 1);
+    assert_eq!(unconverted[0]["currency"], "USD");
+    assert_eq!(unconverted[0]["amount"], 100.0);
+    assert_eq!(unconverted[0]["occurrence_date"], "2025-01-10");
+
+    // 1 EUR = 1.25 USD on the day: Alice paid 80 for Bob
+    let (status, _) = send(
+        &app,
+        "POST",
+        &rates,
+        Some(&token),
+        Some(json!({ "quote_currency": "USD", "rate_date": "2025-01-10", "rate": 1.25 })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, summary) = send(&app, "GET", &debts, Some(&token), None).await;
+    assert!(summary["unconverted"].as_array().unwrap().is_empty());
+    let net = |id: i64| {
+        summary["balances"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|b| b["participant_id"] == id)
+            .unwrap()["net_balance"]
+            .as_f64()
+            .unwrap()
+    };
+    let before = |id: i64| {
+        baseline
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|b| b["participant_id"] == id)
+            .unwrap()["net_balance"]
+            .as_f64()
+            .unwrap()
+    };
+    assert_eq!(net(alice), before(alice) + 80.0);
+    assert_eq!(net(bob), before(bob) - 80.0);
+
+    // Recorded amounts fix the base currency
+    let settings = format!("/projects/{}/settings", project_id);
+    let (status, body) = send(
+        &app,
+        "PUT",
+        &settings,
+        Some(&token),
+        Some(json!({ "base_currency": "GBP" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(body["code"], "BASE_CURRENCY_IN_USE");
+    let (status, _) = send(
+        &app,
+        "PUT",
+        &settings,
+        Some(&token),
+        Some(json!({ "base_currency": "EUR" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (_, empty) = send(
+        &app,
+        "POST",
+        "/projects",
+        Some(&token),
+        Some(json!({ "name": "Empty" })),
+    )
+    .await;
+    let (status, project) = send(
+        &app,
+        "PUT",
+        &format!("/projects/{}/settings", empty["id"]),
+        Some(&token),
+        Some(json!({ "base_currency": "GBP" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(project["base_currency"], "GBP");
+}
+
+#[tokio::test]
+async fn test_amount_precision_follows_currency() {
+    let (app, pool) = create_test_app().await;
+    let token = login(&app, &pool, "alice").await;
+    let (project_id, [alice, bob, _]) = setup_project(&app, &token).await;
+
+    let payments = format!("/projects/{}/payments", project_id);
+    let payment = |amount: f64, currency: &str| {
+        json!({
+            "payer_id": alice,
+            "amount": amount,
+            "currency": currency,
+            "description": "Souvenir",
+            "payment_date": "2025-01-10",
+            "contributions": [{ "participant_id": bob, "weight": 1.0 }],
+        })
+    };
+
+    // Yen have no minor unit, dinars have three decimals
+    let (status, body) = send(
+        &app,
+        "POST",
+        &payments,
+        Some(&token),
+        Some(payment(1000.5, "JPY")),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "INVALID_AMOUNT_PRECISION");
+    let (status, body) = send(
+        &app,
+        "POST",
+        &payments,
+        Some(&token),
+        Some(payment(12.5, "EUR")),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let (status, body) = send(
+        &app,
+        "POST",
+        &payments,
+        Some(&token),
+        Some(payment(12.345, "EUR")),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "INVALID_AMOUNT_PRECISION");
+    let (status, body) = send(
+        &app,
+        "POST",
+        &payments,
+        Some(&token),
+        Some(payment(1.234, "KWD")),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let (_, body) = send(
+        &app,
+        "GET",
+        &format!("{}/{}", payments, body["id"]),
+        Some(&token),
+        None,
+    )
+    .await;
+    assert_eq!(body["amount"], 1.234);
+
+    let (status, body) = send(
+        &app,
+        "POST",
+        &payments,
+        Some(&token),
+        Some(payment(2e9, "EUR")),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY, "{}", body);
+}
+
+#[tokio::test]
+async fn test_balance_series_matches_point_queries() {
+    let (app, pool) = create_test_app().await;
+    let token = login(&app, &pool, "alice").await;
+    let (project_id, [alice, bob, carol]) = setup_project(&app, &token).await;
+
+    // Monthly rent paid by Carol for everyone, starting mid-January
+    let (status, _) = send(
+        &app,
+        "POST",
+        &format!("/projects/{}/payments", project_id),
+        Some(&token),
+        Some(json!({
+            "payer_id": carol,
+            "amount": 100.0,
+            "description": "Rent",
+            "payment_date": "2025-01-15",
+            "is_recurring": true,
+            "recurrence_type": "monthly",
+            "recurrence_interval": 1,
+            "contributions": [
+                { "participant_id": alice, "weight": 1.0 },
+                { "participant_id": bob, "weight": 1.0 },
+                { "participant_id": carol, "weight": 2.0 },
+            ],
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, series) = send(
+        &app,
+        "GET",
+        &format!(
+            "/projects/{}/debts/series?from=2025-01-01&to=2025-04-30&step=week",
+            project_id
+        ),
+        Some(&token),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let points = series["points"].as_array().unwrap();
+    assert_eq!(points.len(), 18);
+
+    for point in points {
+        let date = point["date"].as_str().unwrap();
+        let (_, summary) = send(
+            &app,
+            "GET",
+            &format!("/projects/{}/debts?date={}", project_id, date),
+            Some(&token),
+            None,
+        )
+        .await;
+
+        for balance in summary["balances"].as_array().unwrap() {
+            let in_series = point["balances"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|b| b["participant_id"] == balance["participant_id"])
+                .unwrap();
+            for field in ["total_paid", "total_owed", "net_balance"] {
+                assert_eq!(in_series[field], balance[field], "{} on {}", field, date);
+            }
+        }
+    }
+
+    // Reversed range is rejected
+    let (status, body) = send(
+        &app,
+        "GET",
+        &format!(
+            "/projects/{}/debts/series?from=2025-05-01&to=2025-01-01",
+            project_id
+        ),
+        Some(&token),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "INVALID_SERIES_RANGE");
+}
+
+/// Create a payment and assert it was accepted
+async fn create_payment(app: &Router, token: &str, project_id: i64, payment: Value) {
+    let (status, body) = send(
+        app,
+        "POST",
+        &format!("/projects/{}/payments", project_id),
+        Some(token),
+        Some(payment),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+}
+
+async fn create_pool(app: &Router, token: &str, project_id: i64, name: &str) -> i64 {
+    let (status, body) = send(
+        app,
+        "POST",
+        &format!("/projects/{}/participants", project_id),
+        Some(token),
+        Some(json!({ "name": name, "account_type": "pool" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    body["id"].as_i64().unwrap()
+}
+
+/// A payment moving `amount` from `payer` into a pool
+fn deposit(pool_id: i64, payer: i64, amount: f64, date: &str) -> Value {
+    json!({
+        "payer_id": payer,
+        "amount": amount,
+        "description": "Deposit",
+        "payment_date": date,
+        "receiver_account_id": pool_id,
+        "contributions": [{ "participant_id": pool_id, "weight": 1.0 }],
+    })
+}
+
+#[tokio::test]
+async fn test_pool_forecast_curves() {
+    let (app, pool) = create_test_app().await;
+    let token = login(&app, &pool, "alice").await;
+    let (project_id, [alice, bob, _]) = setup_project(&app, &token).await;
+
+    let house = create_pool(&app, &token, project_id, "House").await;
+
+    // Alice and Bob each deposit 300
+    for payer in [alice, bob] {
+        create_payment(
+            &app,
+            &token,
+            project_id,
+            deposit(house, payer, 300.0, "2025-01-01"),
+        )
+        .await;
+    }
+    // Rule: Alice is expected to keep 100 more in the pool every month
+    let (status, _) = send(
+        &app,
+        "POST",
+        &format!("/projects/{}/contribution-rules", project_id),
+        Some(&token),
+        Some(json!({
+            "pool_id": house,
+            "recurrence_rule": "FREQ=MONTHLY",
+            "start_date": "2025-01-01",
+            "description": "Reserve rule",
+            "amounts": [{ "participant_id": alice, "amount": 100.0 }],
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    // The pool pays a 500 repair shared by Alice and Bob
+    create_payment(
+        &app,
+        &token,
+        project_id,
+        json!({
+            "payer_id": house,
+            "amount": 500.0,
+            "description": "Repair",
+            "payment_date": "2025-02-15",
+            "contributions": [
+                { "participant_id": alice, "weight": 1.0 },
+                { "participant_id": bob, "weight": 1.0 },
+            ],
+        }),
+    )
+    .await;
+
+    let (status, forecasts) = send(
+        &app,
+        "GET",
+        &format!(
+            "/projects/{}/debts/forecast?from=2025-01-15&to=2025-03-31",
+            project_id
+        ),
+        Some(&token),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let forecast = &forecasts[0];
+    assert_eq!(forecast["pool_id"], house);
+
+    let curve: Vec<(String, f64, f64)> = forecast["points"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|p| {
+            (
+                p["date"].as_str().unwrap().to_string(),
+                p["balance"].as_f64().unwrap(),
+                p["expected_minimum"].as_f64().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        curve,
+        vec![
+            ("2025-01-15".to_string(), 600.0, 100.0),
+            ("2025-02-01".to_string(), 600.0, 200.0),
+            ("2025-02-15".to_string(), 100.0, 200.0),
+            ("2025-03-01".to_string(), 100.0, 300.0),
+        ]
+    );
+    assert_eq!(forecast["first_below_expected_date"], "2025-02-15");
+    assert_eq!(forecast["lowest"]["date"], "2025-02-15");
+    assert_eq!(forecast["lowest"]["balance"], 100.0);
+
+    let users = forecast["users"].as_array().unwrap();
+    let share = |id: i64| users.iter().find(|u| u["participant_id"] == id).unwrap();
+    assert_eq!(share(alice)["first_below_expected_date"], "2025-02-15");
+    assert_eq!(share(alice)["points"][3]["expected_minimum"], 300.0);
+    assert_eq!(share(bob)["first_below_expected_date"], Value::Null);
+    assert_eq!(share(bob)["lowest"]["balance"], 50.0);
+}
+
+#[tokio::test]
+async fn test_pool_warnings_within_horizon() {
+    let (app, pool) = create_test_app().await;
+    let token = login(&app, &pool, "alice").await;
+    let (project_id, [alice, bob, _]) = setup_project(&app, &token).await;
+
+    let house = create_pool(&app, &token, project_id, "House").await;
+    let (status, _) = send(
+        &app,
+        "PATCH",
+        &format!(
+            "/projects/{}/participants/{}/warning-settings",
+            project_id, house
+        ),
+        Some(&token),
+        Some(json!({ "warning_horizon_account": "3_months", "warning_horizon_users": "3_months" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let today = chrono::Utc::now().date_naive();
+    let in_ten_days = (today + chrono::Duration::days(10))
+        .format("%Y-%m-%d")
+        .to_string();
+    let today = today.format("%Y-%m-%d").to_string();
+
+    for payer in [alice, bob] {
+        create_payment(
+            &app,
+            &token,
+            project_id,
+            deposit(house, payer, 100.0, &today),
+        )
+        .await;
+    }
+    // Alice is expected to keep 80 in the pool
+    let (status, _) = send(
+        &app,
+        "POST",
+        &format!("/projects/{}/contribution-rules", project_id),
+        Some(&token),
+        Some(json!({
+            "pool_id": house,
+            "start_date": today,
+            "description": "Reserve rule",
+            "amounts": [{ "participant_id": alice, "amount": 80.0 }],
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    // An upcoming 150 expense paid by the pool
+    create_payment(
+        &app,
+        &token,
+        project_id,
+        json!({
+            "payer_id": house,
+            "amount": 150.0,
+            "description": "Repair",
+            "payment_date": in_ten_days,
+            "contributions": [
+                { "participant_id": alice, "weight": 1.0 },
+                { "participant_id": bob, "weight": 1.0 },
+            ],
+        }),
+    )
+    .await;
+
+    let (status, warnings) = send(
+        &app,
+        "GET",
+        &format!("/projects/{}/warnings", project_id),
+        Some(&token),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let warnings = warnings.as_array().unwrap();
+    assert_eq!(warnings.len(), 2, "{:?}", warnings);
+
+    // Pool: 200 - 150 = 50 against an expected 80
+    assert_eq!(warnings[0]["kind"], "pool_below_expected");
+    assert_eq!(warnings[0]["pool_id"], house);
+    assert_eq!(warnings[0]["participant_id"], Value::Null);
+    assert_eq!(warnings[0]["date"], in_ten_days);
+    assert_eq!(warnings[0]["shortfall"], 30.0);
+    assert_eq!(warnings[0]["horizon"], "3_months");
+
+    // Alice: 100 - 75 = 25 against an expected 80; Bob has no expectation
+    assert_eq!(warnings[1]["kind"], "user_below_expected");
+    assert_eq!(warnings[1]["participant_id"], alice);
+    assert_eq!(warnings[1]["shortfall"], 55.0);
+
+    // The project list carries the same warnings
+    let (status, projects) = send(&app, "GET", "/projects", Some(&token), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(projects[0]["warnings"].as_array().unwrap().len(), 2);
+
+    // Disabling the user horizon drops the user warning
+    send(
+        &app,
+        "PATCH",
+        &format!(
+            "/projects/{}/participants/{}/warning-settings",
+            project_id, house
+        ),
+        Some(&token),
+        Some(json!({ "warning_horizon_users": "" })),
+    )
+    .await;
+    let (_, warnings) = send(
+        &app,
+        "GET",
+        &format!("/projects/{}/warnings", project_id),
+        Some(&token),
+        None,
+    )
+    .await;
+    assert_eq!(warnings.as_array().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_cached_totals_match_full_recomputation() {
+    use bonscompte_backend::models::Money;
+    use bonscompte_backend::services::debt_calculator::{
+        calculate_debts_at_date, calculate_totals_at_date, clear_ledger_cache, LedgerTotals,
+        PoolOwnershipEntry,
+    };
+    use std::time::Instant;
+
+    let (app, pool) = create_test_app().await;
+    let token = login(&app, &pool, "alice").await;
+    let (project_id, [alice, bob, carol]) = setup_project(&app, &token).await;
+
+    let house = create_pool(&app, &token, project_id, "House").await;
+
+    // Five years of household life: rent, groceries and pool deposits and spending
+    for (payer, amount, description, recurrence_type, receiver, contributors) in [
+        (
+            carol,
+            1234.56,
+            "Rent",
+            "monthly",
+            None,
+            vec![alice, bob, carol],
+        ),
+        (
+            alice,
+            87.13,
+            "Groceries",
+            "weekly",
+            None,
+            vec![alice, bob, carol],
+        ),
+        (bob, 150.0, "Deposit", "monthly", Some(house), vec![house]),
+        (house, 45.99, "Utilities", "monthly", None, vec![alice, bob]),
+    ] {
+        create_payment(
+            &app,
+            &token,
+            project_id,
+            json!({
+                "payer_id": payer,
+                "amount": amount,
+                "description": description,
+                "payment_date": "2021-01-05",
+                "is_recurring": true,
+                "recurrence_type": recurrence_type,
+                "recurrence_interval": 1,
+                "recurrence_end_date": "2025-12-31",
+                "receiver_account_id": receiver,
+                "contributions": contributors
+                    .iter()
+                    .map(|id| json!({ "participant_id": id, "weight": 1.0 }))
+                    .collect::<Vec<_>>(),
+            }),
+        )
+        .await;
+    }
+
+    let dates: Vec<String> = (2021..=2025)
+        .flat_map(|year| {
+            (1..=12).flat_map(move |month| {
+                [
+                    format!("{year}-{month:02}-04"),
+                    format!("{year}-{month:02}-20"),
+                ]
+            })
+        })
+        .collect();
+
+    // Full recomputation: every request reloads and re-expands the whole project
+    let started = Instant::now();
+    let mut full = Vec::with_capacity(dates.len());
+    for date in &dates {
+        clear_ledger_cache();
+        full.push(
+            calculate_debts_at_date(&pool, project_id, date, false)
+                .await
+                .unwrap(),
+        );
+    }
+    let full_time = started.elapsed();
+
+    // Cached: one expansion, then each date replays from its nearest checkpoint
+    let started = Instant::now();
+    let mut cached = Vec::with_capacity(dates.len());
+    for date in dates.iter().rev() {
+        cached.push(
+            calculate_totals_at_date(&pool, project_id, date, false)
+                .await
+                .unwrap(),
+        );
+    }
+    cached.reverse();
+    let cached_time = started.elapsed();
+    eprintln!(
+        "{} dates: full recomputation {:?}, cached ledger {:?}",
+        dates.len(),
+        full_time,
+        cached_time
+    );
+
+    for ((date, full), totals) in dates.iter().zip(&full).zip(&cached) {
+        assert_eq!(&totals.target_date, date);
+
+        let mut expected = full.balances.iter().collect::<Vec<_>>();
+        let mut actual = totals.balances.iter().collect::<Vec<_>>();
+        expected.sort_by_key(|b| b.participant_id);
+        actual.sort_by_key(|b| b.participant_id);
+        assert_eq!(actual.len(), expected.len());
+        for (a, e) in actual.iter().zip(&expected) {
+            assert_eq!(a.participant_id, e.participant_id);
+            assert_eq!(a.total_paid, e.total_paid, "paid on {}", date);
+            assert_eq!(a.total_owed, e.total_owed, "owed on {}", date);
+            assert_eq!(a.net_balance, e.net_balance, "net on {}", date);
+        }
+
+        assert_eq!(totals.pool_ownerships.len(), full.pool_ownerships.len());
+        for (a, e) in totals.pool_ownerships.iter().zip(&full.pool_ownerships) {
+            assert_eq!(a.pool_id, e.pool_id);
+            assert_eq!(a.total_balance, e.total_balance, "pool on {}", date);
+            assert_eq!(
+                a.expected_minimum, e.expected_minimum,
+                "minimum on {}",
+                date
+            );
+            let owners = |entries: &[_]| -> Vec<(i64, Money)> {
+                entries
+                    .iter()
+                    .map(|x: &PoolOwnershipEntry| (x.participant_id, x.ownership))
+                    .collect()
+            };
+            assert_eq!(
+                owners(&a.entries),
+                owners(&e.entries),
+                "ownership on {}",
+                date
+            );
+        }
+    }
+
+    // Any payment change invalidates the cached ledger
+    let before = calculate_totals_at_date(&pool, project_id, "2025-12-31", false)
+        .await
+        .unwrap();
+    create_payment(
+        &app,
+        &token,
+        project_id,
+        json!({
+            "payer_id": bob,
+            "amount": 30.0,
+            "description": "Late expense",
+            "payment_date": "2025-06-01",
+            "contributions": [{ "participant_id": alice, "weight": 1.0 }],
+        }),
+    )
+    .await;
+    let after = calculate_totals_at_date(&pool, project_id, "2025-12-31", false)
+        .await
+        .unwrap();
+    let paid = |totals: &LedgerTotals| {
+        totals
+            .balances
+            .iter()
+            .find(|b| b.participant_id == bob)
+            .unwrap()
+            .total_paid
+    };
+    assert_eq!(paid(&after) - paid(&before), Money::from_major(30));
+}
+
+#[tokio::test]
+async fn test_cached_totals_match_hand_computed_totals() {
+    use bonscompte_backend::models::Money;
+    use bonscompte_backend::services::debt_calculator::calculate_totals_at_date;
+    use chrono::{Datelike, NaiveDate};
+
+    let (app, pool) = create_test_app().await;
+    let token = login(&app, &pool, "alice").await;
+    let (project_id, [alice, bob, carol]) = setup_project(&app, &token).await;
+
+    let house = create_pool(&app, &token, project_id, "House").await;
+
+    // Five years of household life: rent, groceries and pool deposits and spending
+    for (payer, amount, description, recurrence_type, receiver, contributors) in [
+        (
+            carol,
+            1234.56,
+            "Rent",
+            "monthly",
+            None,
+            vec![alice, bob, carol],
+        ),
+        (
+            alice,
+            87.15,
+            "Groceries",
+            "weekly",
+            None,
+            vec![alice, bob, carol],
+        ),
+        (bob, 150.0, "Deposit", "monthly", Some(house), vec![house]),
+        (house, 45.98, "Utilities", "monthly", None, vec![alice, bob]),
+    ] {
+        create_payment(
+            &app,
+            &token,
+            project_id,
+            json!({
+                "payer_id": payer,
+                "amount": amount,
+                "description": description,
+                "payment_date": "2021-01-05",
+                "is_recurring": true,
+                "recurrence_type": recurrence_type,
+                "recurrence_interval": 1,
+                "recurrence_end_date": "2025-12-31",
+                "receiver_account_id": receiver,
+                "contributions": contributors
+                    .iter()
+                    .map(|id| json!({ "participant_id": id, "weight": 1.0 }))
+                    .collect::<Vec<_>>(),
+            }),
+        )
+        .await;
+    }
+
+    let dates: Vec<String> = (2021..=2025)
+        .flat_map(|year| {
+            (1..=12).flat_map(move |month| {
+                [
+                    format!("{year}-{month:02}-04"),
+                    format!("{year}-{month:02}-20"),
+                ]
+            })
+        })
+        .collect();
+
+    // Independent expansion: the number of occurrences of each schedule on or
+    // before a date, and the totals they add up to (all shares split evenly)
+    let first = NaiveDate::from_ymd_opt(2021, 1, 5).unwrap();
+    let last = NaiveDate::from_ymd_opt(2025, 12, 31).unwrap();
+    let occurrences = |date: NaiveDate, weekly: bool| -> i64 {
+        let date = date.min(last);
+        if date < first {
+            0
+        } else if weekly {
+            (date - first).num_days() / 7 + 1
+        } else {
+            let months = (date.year() - first.year()) * 12 + date.month0() as i32 + 1;
+            i64::from(months) - i64::from(date.day() < first.day())
+        }
+    };
+    let cents = |cents: i64, times: i64| Money::from_minor(cents * times, 2);
+    // Balances leave pool deposits and pool spending to the pool's ownership
+    let expected = |date: NaiveDate, participant_id: i64| -> (Money, Money) {
+        let monthly = occurrences(date, false);
+        let groceries = occurrences(date, true);
+        // The two expenses of `setup_project`
+        let setup = i64::from(date >= NaiveDate::from_ymd_opt(2025, 1, 10).unwrap());
+        let mut paid = Money::ZERO;
+        let mut owed = Money::ZERO;
+        if participant_id == carol {
+            paid += cents(123456, monthly);
+            owed += cents(6000, setup);
+        }
+        if participant_id == alice {
+            paid += cents(8715, groceries) + cents(9000, setup);
+            owed += cents(3000, setup);
+        }
+        if participant_id == bob {
+            paid += cents(3000, setup);
+            owed += cents(3000, setup);
+        }
+        if participant_id == house {
+            paid += cents(4598, monthly);
+        }
+        if [alice, bob, carol].contains(&participant_id) {
+            owed += cents(41152, monthly) + cents(2905, groceries);
+        }
+        (paid, owed)
+    };
+    let ownership = |date: NaiveDate, participant_id: i64| -> Money {
+        let monthly = occurrences(date, false);
+        let deposits = if participant_id == bob { 15000 } else { 0 };
+        cents(deposits - 2299, monthly)
+    };
+
+    // Cached: one expansion, then each date replays from its nearest checkpoint
+    for date in dates.iter().rev() {
+        let totals = calculate_totals_at_date(&pool, project_id, date, false)
+            .await
+            .unwrap();
+        assert_eq!(&totals.target_date, date);
+        let day = NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap();
+
+        for balance in &totals.balances {
+            let (paid, owed) = expected(day, balance.participant_id);
+            assert_eq!(balance.total_paid, paid, "paid on {}", date);
+            assert_eq!(balance.total_owed, owed, "owed on {}", date);
+            assert_eq!(balance.net_balance, paid - owed, "net on {}", date);
+        }
+
+        let [pool_ownership] = totals.pool_ownerships.as_slice() else {
+            panic!("one pool expected on {}", date);
+        };
+        let mut owners: Vec<(i64, Money)> = pool_ownership
+            .entries
+            .iter()
+            .map(|e| (e.participant_id, e.ownership))
+            .filter(|(_, ownership)| !ownership.is_zero())
+            .collect();
+        owners.sort();
+        let expected_owners: Vec<(i64, Money)> = [alice, bob]
+            .into_iter()
+            .map(|id| (id, ownership(day, id)))
+            .filter(|(_, ownership)| !ownership.is_zero())
+            .collect();
+        assert_eq!(owners, expected_owners, "ownership on {}", date);
+        let total: Money = expected_owners
+            .iter()
+            .map(|(_, ownership)| *ownership)
+            .sum();
+        assert_eq!(pool_ownership.total_balance, total, "pool on {}", date);
+    }
+}
+
+#[tokio::test]
+async fn test_payments_accept_and_expose_rrule() {
+    let (app, pool) = create_test_app().await;
+    let token = login(&app, &pool, "alice").await;
+    let (project_id, [alice, bob, _]) = setup_project(&app, &token).await;
+
+    let payment = |rule: Value| {
+        json!({
+            "payer_id": alice,
+            "amount": 50.0,
+            "description": "Cleaning",
+            "payment_date": "2025-01-01",
+            "is_recurring": true,
+            "recurrence_rule": rule,
+            "recurrence_type": "weekly",
+            "recurrence_interval": 1,
+            "recurrence_weekdays": "[[2]]",
+            "contributions": [{ "participant_id": bob, "weight": 1.0 }],
+        })
+    };
+
+    // The rule wins over the legacy fields and is stored normalized
+    let (status, created) = send(
+        &app,
+        "POST",
+        &format!("/projects/{}/payments", project_id),
+        Some(&token),
+        Some(payment(json!("rrule:freq=monthly;byday=-1fr;count=3"))),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        created["recurrence_rule"],
+        "FREQ=MONTHLY;BYDAY=-1FR;COUNT=3"
+    );
+
+    let (_, summary) = send(
+        &app,
+        "GET",
+        &format!("/projects/{}/debts?date=2025-12-31", project_id),
+        Some(&token),
+        None,
+    )
+    .await;
+    let occurrences: Vec<&str> = summary["occurrences"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|o| o["description"] == "Cleaning")
+        .map(|o| o["occurrence_date"].as_str().unwrap())
+        .collect();
+    assert_eq!(occurrences, ["2025-01-31", "2025-02-28", "2025-03-28"]);
+
+    // Legacy fields alone are converted
+    let (status, created) = send(
+        &app,
+        "POST",
+        &format!("/projects/{}/payments", project_id),
+        Some(&token),
+        Some(payment(Value::Null)),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(created["recurrence_rule"], "FREQ=WEEKLY;BYDAY=TU;WKST=SU");
+
+    let (status, body) = send(
+        &app,
+        "POST",
+        &format!("/projects/{}/payments", project_id),
+        Some(&token),
+        Some(payment(json!("FREQ=WEEKLY;BYDAY=2MO"))),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "INVALID_RECURRENCE_RULE");
+}
+
+#[tokio::test]
+async fn test_occurrence_exceptions_skip_move_and_override() {
+    let (app, pool) = create_test_app().await;
+    let token = login(&app, &pool, "alice").await;
+    let (project_id, [alice, bob, carol]) = setup_project(&app, &token).await;
+
+    let (status, rent) = send(
+        &app,
+        "POST",
+        &format!("/projects/{}/payments", project_id),
+        Some(&token),
+        Some(json!({
+            "payer_id": carol,
+            "amount": 100.0,
+            "description": "Rent",
+            "payment_date": "2025-01-01",
+            "is_recurring": true,
+            "recurrence_rule": "FREQ=MONTHLY;COUNT=4",
+            "contributions": [
+                { "participant_id": alice, "weight": 1.0 },
+                { "participant_id": bob, "weight": 1.0 },
+            ],
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let exceptions = format!(
+        "/projects/{}/payments/{}/exceptions",
+        project_id,
+        rent["id"].as_i64().unwrap()
+    );
+
+    for (date, change) in [
+        ("2025-02-01", json!({ "skip": true })),
+        (
+            "2025-03-01",
+            json!({ "moved_to": "2025-03-05", "amount": 60.0 }),
+        ),
+        (
+            "2025-04-01",
+            json!({ "contributions": [{ "participant_id": bob, "weight": 1.0 }] }),
+        ),
+    ] {
+        let (status, body) = send(
+            &app,
+            "PUT",
+            &format!("{}/{}", exceptions, date),
+            Some(&token),
+            Some(change),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+    }
+
+    let rent_occurrences = |summary: &Value| -> Vec<(String, f64, Value)> {
+        summary["occurrences"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|o| o["description"] == "Rent")
+            .map(|o| {
+                (
+                    o["occurrence_date"].as_str().unwrap().to_string(),
+                    o["amount"].as_f64().unwrap(),
+                    o["original_date"].clone(),
+                )
+            })
+            .collect()
+    };
+    let balance = |summary: &Value, id: i64| -> f64 {
+        summary["balances"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|b| b["participant_id"] == id)
+            .unwrap()["total_owed"]
+            .as_f64()
+            .unwrap()
+    };
+
+    let (_, summary) = send(
+        &app,
+        "GET",
+        &format!("/projects/{}/debts?date=2025-12-31", project_id),
+        Some(&token),
+        None,
+    )
+    .await;
+    assert_eq!(
+        rent_occurrences(&summary),
+        [
+            ("2025-01-01".to_string(), 100.0, Value::Null),
+            ("2025-03-05".to_string(), 60.0, json!("2025-03-01")),
+            ("2025-04-01".to_string(), 100.0, json!("2025-04-01")),
+        ]
+    );
+    // Alice and Bob each owe 30 from setup, then 50 + 30 for Alice and 50 + 30 + 100 for Bob
+    assert_eq!(balance(&summary, alice), 110.0);
+    assert_eq!(balance(&summary, bob), 210.0);
+
+    // The moved occurrence is not there yet on March 1st
+    let (_, summary) = send(
+        &app,
+        "GET",
+        &format!("/projects/{}/debts?date=2025-03-01", project_id),
+        Some(&token),
+        None,
+    )
+    .await;
+    assert_eq!(rent_occurrences(&summary).len(), 1);
+
+    // Only actual occurrences can have exceptions
+    let (status, body) = send(
+        &app,
+        "PUT",
+        &format!("{}/2025-02-15", exceptions),
+        Some(&token),
+        Some(json!({ "skip": true })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["code"], "OCCURRENCE_NOT_FOUND");
+
+    let (_, list) = send(&app, "GET", &exceptions, Some(&token), None).await;
+    assert_eq!(list.as_array().unwrap().len(), 3);
+
+    // Removing the skip brings February back
+    let (status, _) = send(
+        &app,
+        "DELETE",
+        &format!("{}/2025-02-01", exceptions),
+        Some(&token),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, summary) = send(
+        &app,
+        "GET",
+        &format!("/projects/{}/debts?date=2025-12-31", project_id),
+        Some(&token),
+        None,
+    )
+    .await;
+    assert_eq!(rent_occurrences(&summary).len(), 4);
+
+    let (_, history) = send(
+        &app,
+        "GET",
+        &format!("/projects/{}/history", project_id),
+        Some(&token),
+        None,
+    )
+    .await;
+    let logged = history
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|e| e["entity_type"] == "occurrence_exception")
+        .collect::<Vec<_>>();
+    assert_eq!(logged.len(), 4);
+
+    // Undoing the removal skips February again
+    let removal = logged.iter().find(|e| e["action"] == "DELETE").unwrap();
+    let (status, body) = send(
+        &app,
+        "POST",
+        &format!(
+            "/projects/{}/history/{}/undo",
+            project_id,
+            removal["id"].as_i64().unwrap()
+        ),
+        Some(&token),
+        Some(json!({})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let (_, summary) = send(
+        &app,
+        "GET",
+        &format!("/projects/{}/debts?date=2025-12-31", project_id),
+        Some(&token),
+        None,
+    )
+    .await;
+    assert_eq!(rent_occurrences(&summary).len(), 3);
+}
+
+#[tokio::test]
+async fn test_split_series_keeps_past_and_undoes_as_pair() {
+    let (app, pool) = create_test_app().await;
+    let token = login(&app, &pool, "alice").await;
+    let (project_id, [alice, bob, carol]) = setup_project(&app, &token).await;
+
+    let (status, rent) = send(
+        &app,
+        "POST",
+        &format!("/projects/{}/payments", project_id),
+        Some(&token),
+        Some(json!({
+            "payer_id": carol,
+            "amount": 100.0,
+            "description": "Rent",
+            "payment_date": "2025-01-31",
+            "is_recurring": true,
+            "recurrence_rule": "FREQ=MONTHLY;COUNT=6",
+            "contributions": [
+                { "participant_id": alice, "weight": 1.0 },
+                { "participant_id": bob, "weight": 1.0 },
+            ],
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let rent_id = rent["id"].as_i64().unwrap();
+
+    // May is skipped before the split and stays skipped after it
+    let (status, _) = send(
+        &app,
+        "PUT",
+        &format!(
+            "/projects/{}/payments/{}/exceptions/2025-05-31",
+            project_id, rent_id
+        ),
+        Some(&token),
+        Some(json!({ "skip": true })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let split = format!("/projects/{}/payments/{}/split", project_id, rent_id);
+    for (from_date, code) in [
+        ("2025-01-31", "CANNOT_SPLIT_FIRST_OCCURRENCE"),
+        ("2025-04-15", "OCCURRENCE_NOT_FOUND"),
+    ] {
+        let (_, body) = send(
+            &app,
+            "POST",
+            &split,
+            Some(&token),
+            Some(json!({ "from_date": from_date, "amount": 120.0 })),
+        )
+        .await;
+        assert_eq!(body["code"], code);
+    }
+
+    let (status, body) = send(
+        &app,
+        "POST",
+        &split,
+        Some(&token),
+        Some(json!({
+            "from_date": "2025-04-30",
+            "amount": 120.0,
+            "contributions": [{ "participant_id": bob, "weight": 1.0 }],
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["ended"]["recurrence_end_date"], "2025-04-29");
+    assert_eq!(body["continuation"]["split_from_id"], rent_id);
+    assert_eq!(body["continuation"]["payment_date"], "2025-04-30");
+
+    let rent_occurrences = |summary: &Value| -> Vec<(String, f64)> {
+        summary["occurrences"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|o| o["description"] == "Rent")
+            .map(|o| {
+                (
+                    o["occurrence_date"].as_str().unwrap().to_string(),
+                    o["amount"].as_f64().unwrap(),
+                )
+            })
+            .collect()
+    };
+    let debts = format!("/projects/{}/debts?date=2025-12-31", project_id);
+
+    let (_, summary) = send(&app, "GET", &debts, Some(&token), None).await;
+    let dates = |occurrences: &[(&str, f64)]| -> Vec<(String, f64)> {
+        occurrences
+            .iter()
+            .map(|(date, amount)| (date.to_string(), *amount))
+            .collect()
+    };
+    assert_eq!(
+        rent_occurrences(&summary),
+        dates(&[
+            ("2025-01-31", 100.0),
+            ("2025-02-28", 100.0),
+            ("2025-03-31", 100.0),
+            ("2025-04-30", 120.0),
+            ("2025-06-30", 120.0),
+        ])
+    );
+
+    // Undoing the continuation's creation restores the whole series
+    let (_, history) = send(
+        &app,
+        "GET",
+        &format!("/projects/{}/history", project_id),
+        Some(&token),
+        None,
+    )
+    .await;
+    let creation = history
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|e| e["entity_id"] == body["continuation"]["id"] && e["action"] == "CREATE")
+        .unwrap();
+    let (status, undo) = send(
+        &app,
+        "POST",
+        &format!(
+            "/projects/{}/history/{}/undo",
+            project_id,
+            creation["id"].as_i64().unwrap()
+        ),
+        Some(&token),
+        Some(json!({})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", undo);
+
+    let (_, summary) = send(&app, "GET", &debts, Some(&token), None).await;
+    assert_eq!(
+        rent_occurrences(&summary),
+        dates(&[
+            ("2025-01-31", 100.0),
+            ("2025-02-28", 100.0),
+            ("2025-03-31", 100.0),
+            ("2025-04-30", 100.0),
+            ("2025-06-30", 100.0),
+        ])
+    );
+    let (_, payments) = send(
+        &app,
+        "GET",
+        &format!("/projects/{}/payments", project_id),
+        Some(&token),
+        None,
+    )
+    .await;
+    let rents: Vec<&Value> = payments
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|p| p["description"] == "Rent")
+        .collect();
+    assert_eq!(rents.len(), 1);
+    assert_eq!(rents[0]["recurrence_end_date"], Value::Null);
+}
+
+#[tokio::test]
+async fn test_amount_schedule_indexes_occurrences_and_shares() {
+    let (app, pool) = create_test_app().await;
+    let token = login(&app, &pool, "alice").await;
+    let (project_id, [alice, bob, carol]) = setup_project(&app, &token).await;
+    let payments = format!("/projects/{}/payments", project_id);
+
+    let rent = |schedule: &str| {
+        json!({
+            "payer_id": carol,
+            "amount": 1000.0,
+            "description": "Rent",
+            "payment_date": "2024-07-01",
+            "is_recurring": true,
+            "recurrence_rule": "FREQ=MONTHLY",
+            "amount_schedule": schedule,
+            "contributions": [
+                { "participant_id": alice, "weight": 1.0 },
+                { "participant_id": bob, "weight": 3.0 },
+            ],
+        })
+    };
+
+    let (status, body) = send(
+        &app,
+        "POST",
+        &payments,
+        Some(&token),
+        Some(rent(r#"{"type": "yearly", "percent": "3"}"#)),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "INVALID_AMOUNT_SCHEDULE");
+
+    let (status, created) = send(
+        &app,
+        "POST",
+        &payments,
+        Some(&token),
+        Some(rent(r#"{"type": "yearly", "percent": 3, "round_to": 1}"#)),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", created);
+
+    let summary_at = |date: &'static str| {
+        let app = app.clone();
+        let token = token.clone();
+        async move {
+            send(
+                &app,
+                "GET",
+                &format!("/projects/{}/debts?date={}", project_id, date),
+                Some(&token),
+                None,
+            )
+            .await
+            .1
+        }
+    };
+    let rent_amounts = |summary: &Value| -> Vec<f64> {
+        summary["occurrences"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|o| o["description"] == "Rent")
+            .map(|o| o["amount"].as_f64().unwrap())
+            .collect()
+    };
+    let owed = |summary: &Value, id: i64| -> f64 {
+        summary["balances"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|b| b["participant_id"] == id)
+            .unwrap()["total_owed"]
+            .as_f64()
+            .unwrap()
+    };
+
+    let summary = summary_at("2025-08-31").await;
+    let amounts = rent_amounts(&summary);
+    assert_eq!(amounts.len(), 14);
+    assert_eq!(amounts[11], 1000.0);
+    assert_eq!(amounts[12..], [1030.0, 1030.0]);
+    // 30 from setup, then a quarter (three quarters for Bob) of each rent
+    assert_eq!(owed(&summary, alice), 30.0 + 12.0 * 250.0 + 2.0 * 257.5);
+    assert_eq!(owed(&summary, bob), 30.0 + 12.0 * 750.0 + 2.0 * 772.5);
+
+    // A split continues from the indexed amount and keeps the increase day
+    let (status, split) = send(
+        &app,
+        "POST",
+        &format!("{}/{}/split", payments, created["id"]),
+        Some(&token),
+        Some(json!({ "from_date": "2025-09-01", "payer_id": alice })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", split);
+    assert_eq!(split["continuation"]["amount"], 1030.0);
+
+    let summary = summary_at("2026-07-31").await;
+    let amounts = rent_amounts(&summary);
+    assert_eq!(amounts.len(), 25);
+    assert_eq!(amounts[23..], [1030.0, 1061.0]);
+}
+
+#[tokio::test]
+async fn test_business_day_shift_follows_project_holidays() {
+    let (app, pool) = create_test_app().await;
+    let token = login(&app, &pool, "alice").await;
+    let (project_id, [alice, bob, carol]) = setup_project(&app, &token).await;
+
+    let cleaning = |shift: &str| {
+        json!({
+            "payer_id": carol,
+            "amount": 50.0,
+            "description": "Cleaning",
+            "payment_date": "2025-05-01",
+            "is_recurring": true,
+            "recurrence_rule": "FREQ=MONTHLY;BYMONTHDAY=-1",
+            "business_day_shift": shift,
+            "contributions": [
+                { "participant_id": alice, "weight": 1.0 },
+                { "participant_id": bob, "weight": 1.0 },
+            ],
+        })
+    };
+    let payments = format!("/projects/{}/payments", project_id);
+
+    let (status, body) = send(
+        &app,
+        "POST",
+        &payments,
+        Some(&token),
+        Some(cleaning("later")),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "INVALID_BUSINESS_DAY_SHIFT");
+
+    let (status, body) = send(
+        &app,
+        "POST",
+        &payments,
+        Some(&token),
+        Some(cleaning("previous")),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["business_day_shift"], "previous");
+
+    let cleaning_dates = || async {
+        let (_, summary) = send(
+            &app,
+            "GET",
+            &format!("/projects/{}/debts?date=2025-10-31", project_id),
+            Some(&token),
+            None,
+        )
+        .await;
+        summary["occurrences"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|o| o["description"] == "Cleaning")
+            .map(|o| o["occurrence_date"].as_str().unwrap().to_string())
+            .collect::<Vec<_>>()
+    };
+
+    // May 31st and August 31st are weekends
+    assert_eq!(
+        cleaning_dates().await,
+        [
+            "2025-05-30",
+            "2025-06-30",
+            "2025-07-31",
+            "2025-08-29",
+            "2025-09-30",
+            "2025-10-31"
+        ]
+    );
+
+    let holidays = format!("/projects/{}/holidays", project_id);
+    let (status, holiday) = send(
+        &app,
+        "POST",
+        &holidays,
+        Some(&token),
+        Some(json!({ "holiday_date": "2025-10-31", "name": "Founders' day" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", holiday);
+    assert_eq!(cleaning_dates().await.last().unwrap(), "2025-10-30");
+
+    let (_, list) = send(&app, "GET", &holidays, Some(&token), None).await;
+    assert_eq!(list.as_array().unwrap().len(), 1);
+
+    let (status, _) = send(
+        &app,
+        "DELETE",
+        &format!("{}/{}", holidays, holiday["id"]),
+        Some(&token),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(cleaning_dates().await.last().unwrap(), "2025-10-31");
+}
+
+#[tokio::test]
+async fn test_presence_periods_drive_recurring_splits() {
+    let (app, pool) = create_test_app().await;
+    let token = login(&app, &pool, "alice").await;
+    let (project_id, [alice, bob, carol]) = setup_project(&app, &token).await;
+
+    let rent = |prorate: bool| {
+        json!({
+            "payer_id": carol,
+            "amount": 300.0,
+            "description": "Rent",
+            "payment_date": "2025-01-01",
+            "is_recurring": true,
+            "recurrence_rule": "FREQ=MONTHLY",
+            "prorate_presence": prorate,
+            "contributions": [
+                { "participant_id": alice, "weight": 1.0 },
+                { "participant_id": bob, "weight": 1.0 },
+                { "participant_id": carol, "weight": 1.0 },
+            ],
+        })
+    };
+    let payments = format!("/projects/{}/payments", project_id);
+    let (status, created) = send(&app, "POST", &payments, Some(&token), Some(rent(false))).await;
+    assert_eq!(status, StatusCode::OK, "{}", created);
+    assert_eq!(created["prorate_presence"], false);
+
+    let owed = || async {
+        let (_, summary) = send(
+            &app,
+            "GET",
+            &format!("/projects/{}/debts?date=2025-05-31", project_id),
+            Some(&token),
+            None,
+        )
+        .await;
+        let owed_by = |id: i64| {
+            summary["balances"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|b| b["participant_id"] == id)
+                .unwrap()["total_owed"]
+                .as_f64()
+                .unwrap()
+        };
+        (owed_by(alice), owed_by(bob))
+    };
+    // 30 each from setup, then 100 of each rent
+    assert_eq!(owed().await, (30.0 + 500.0, 30.0 + 500.0));
+
+    let presence = format!("/projects/{}/presence", project_id);
+    let (status, body) = send(
+        &app,
+        "POST",
+        &presence,
+        Some(&token),
+        Some(json!({ "participant_id": bob, "from_date": "2025-05-01", "to_date": "2025-04-15" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "INVALID_PRESENCE_PERIOD");
+
+    // Bob moves out on April 15th: May's rent is shared by Alice and Carol
+    let (status, period) = send(
+        &app,
+        "POST",
+        &presence,
+        Some(&token),
+        Some(json!({ "participant_id": bob, "to_date": "2025-04-15" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", period);
+    assert_eq!(owed().await, (30.0 + 400.0 + 150.0, 30.0 + 400.0));
+
+    // Pro-rated, Bob pays for half of April
+    let (status, body) = send(
+        &app,
+        "PUT",
+        &format!("{}/{}", payments, created["id"]),
+        Some(&token),
+        Some(rent(true)),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(
+        owed().await,
+        (30.0 + 300.0 + 120.0 + 150.0, 30.0 + 300.0 + 60.0)
+    );
+
+    // Without any period Bob is always present again
+    let (status, _) = send(
+        &app,
+        "DELETE",
+        &format!("{}/{}", presence, period["id"]),
+        Some(&token),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(owed().await, (30.0 + 500.0, 30.0 + 500.0));
+}
+
+#[tokio::test]
+async fn test_weight_profile_versions_split_payments() {
+    let (app, pool) = create_test_app().await;
+    let token = login(&app, &pool, "alice").await;
+    let (project_id, [alice, bob, carol]) = setup_project(&app, &token).await;
+
+    let profiles = format!("/projects/{}/weight-profiles", project_id);
+    let (status, profile) = send(
+        &app,
+        "POST",
+        &profiles,
+        Some(&token),
+        Some(json!({
+            "name": "Rent by room size",
+            "contributions": [
+                { "participant_id": alice, "weight": 1.0 },
+                { "participant_id": bob, "weight": 2.0 },
+            ],
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", profile);
+    assert_eq!(profile["versions"].as_array().unwrap().len(), 1);
+    let profile_url = format!("{}/{}", profiles, profile["id"]);
+
+    let (status, body) = send(
+        &app,
+        "POST",
+        &profiles,
+        Some(&token),
+        Some(json!({
+            "name": "Rent by room size",
+            "contributions": [{ "participant_id": alice, "weight": 1.0 }],
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "WEIGHT_PROFILE_NAME_EXISTS");
+
+    // A monthly rent and a one-off payment split by the profile
+    let payments = format!("/projects/{}/payments", project_id);
+    let (status, rent) = send(
+        &app,
+        "POST",
+        &payments,
+        Some(&token),
+        Some(json!({
+            "payer_id": carol,
+            "amount": 300.0,
+            "description": "Rent",
+            "payment_date": "2025-01-01",
+            "is_recurring": true,
+            "recurrence_rule": "FREQ=MONTHLY",
+            "weight_profile_id": profile["id"],
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", rent);
+    assert_eq!(rent["weight_profile_id"], profile["id"]);
+    assert_eq!(rent["contributions"][1]["amount"], 200.0);
+
+    let (status, repairs) = send(
+        &app,
+        "POST",
+        &payments,
+        Some(&token),
+        Some(json!({
+            "payer_id": carol,
+            "amount": 30.0,
+            "description": "Repairs",
+            "payment_date": "2025-03-15",
+            "weight_profile_id": profile["id"],
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", repairs);
+
+    let owed = || async {
+        let (_, summary) = send(
+            &app,
+            "GET",
+            &format!("/projects/{}/debts?date=2025-04-30", project_id),
+            Some(&token),
+            None,
+        )
+        .await;
+        let owed_by = |id: i64| {
+            summary["balances"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|b| b["participant_id"] == id)
+                .unwrap()["total_owed"]
+                .as_f64()
+                .unwrap()
+        };
+        (owed_by(alice), owed_by(bob))
+    };
+    // 30 each from setup, then a third (two thirds for Bob) of each
+    assert_eq!(owed().await, (30.0 + 400.0 + 10.0, 30.0 + 800.0 + 20.0));
+
+    // Even split from March on: earlier occurrences keep their weights
+    let (status, updated) = send(
+        &app,
+        "PUT",
+        &profile_url,
+        Some(&token),
+        Some(json!({
+            "effective_from": "2025-03-01",
+            "contributions": [
+                { "participant_id": alice, "weight": 1.0 },
+                { "participant_id": bob, "weight": 1.0 },
+            ],
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", updated);
+    assert_eq!(updated["versions"].as_array().unwrap().len(), 2);
+    assert_eq!(
+        owed().await,
+        (30.0 + 200.0 + 300.0 + 15.0, 30.0 + 400.0 + 300.0 + 15.0)
+    );
+
+    // The one-off payment after the change is re-split, the rent keeps the
+    // weights of its first occurrence
+    let (_, repairs) = send(
+        &app,
+        "GET",
+        &format!("{}/{}", payments, repairs["id"]),
+        Some(&token),
+        None,
+    )
+    .await;
+    assert_eq!(repairs["contributions"][0]["amount"], 15.0);
+    let (_, rent) = send(
+        &app,
+        "GET",
+        &format!("{}/{}", payments, rent["id"]),
+        Some(&token),
+        None,
+    )
+    .await;
+    assert_eq!(rent["contributions"][1]["amount"], 200.0);
+
+    // History logs the versions before and after
+    let (_, history) = send(
+        &app,
+        "GET",
+        &format!(
+            "/projects/{}/history/weight_profile/{}",
+            project_id, profile["id"]
+        ),
+        Some(&token),
+        None,
+    )
+    .await;
+    let update = history
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|e| e["action"] == "UPDATE")
+        .unwrap();
+    assert_eq!(
+        update["payload_before"]["versions"]
+            .as_array()
+            .unwrap()
+            .len(),
+        1
+    );
+    assert_eq!(
+        update["payload_after"]["versions"][1]["effective_from"],
+        "2025-03-01"
+    );
+
+    // and each payment it re-split under the same correlation id
+    let resplit = |payment: &Value| {
+        let url = format!("/projects/{}/history/payment/{}", project_id, payment["id"]);
+        let correlation_id = update["correlation_id"].clone();
+        let (app, token) = (&app, &token);
+        async move {
+            let (_, history) = send(app, "GET", &url, Some(token), None).await;
+            history
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|e| e["correlation_id"] == correlation_id)
+                .cloned()
+        }
+    };
+    let entry = resplit(&repairs).await.unwrap();
+    assert_eq!(entry["action"], "UPDATE");
+    assert_eq!(entry["payload_before"]["contributions"][0]["amount"], 10.0);
+    assert_eq!(entry["payload_after"]["contributions"][0]["amount"], 15.0);
+    assert!(resplit(&rent).await.is_none());
+
+    let (status, body) = send(&app, "DELETE", &profile_url, Some(&token), None).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "WEIGHT_PROFILE_IN_USE");
+}
+
+#[tokio::test]
+async fn test_split_modes_resolve_and_round_trip() {
+    let (app, pool) = create_test_app().await;
+    let token = login(&app, &pool, "alice").await;
+    let (project_id, [alice, bob, carol]) = setup_project(&app, &token).await;
+    let payments = format!("/projects/{}/payments", project_id);
+
+    let dinner = |mode: &str, contributions: Value| {
+        json!({
+            "payer_id": alice,
+            "amount": 90.0,
+            "description": "Dinner",
+            "payment_date": "2025-03-01",
+            "split_mode": mode,
+            "contributions": contributions,
+        })
+    };
+    let amounts = |payment: &Value| -> Vec<f64> {
+        payment["contributions"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|c| c["amount"].as_f64().unwrap())
+            .collect()
+    };
+
+    for (mode, contributions, code) in [
+        (
+            "exact",
+            json!([
+                { "participant_id": alice, "amount": 50.0 },
+                { "participant_id": bob, "amount": 30.0 },
+            ]),
+            "SPLIT_AMOUNTS_MISMATCH",
+        ),
+        (
+            "percentages",
+            json!([
+                { "participant_id": alice, "weight": 50.0 },
+                { "participant_id": bob, "weight": 40.0 },
+            ]),
+            "PERCENTAGES_MUST_TOTAL_100",
+        ),
+        (
+            "shares",
+            json!([{ "participant_id": alice, "weight": 1.5 }]),
+            "SHARES_MUST_BE_WHOLE",
+        ),
+        (
+            "weights",
+            json!([
+                { "participant_id": alice, "weight": 1.0, "cap": 10.0 },
+                { "participant_id": bob, "weight": 1.0, "cap": 10.0 },
+            ]),
+            "INVALID_SPLIT_LIMITS",
+        ),
+        (
+            "halves",
+            json!([{ "participant_id": alice, "weight": 1.0 }]),
+            "INVALID_SPLIT_MODE",
+        ),
+    ] {
+        let (status, body) = send(
+            &app,
+            "POST",
+            &payments,
+            Some(&token),
+            Some(dinner(mode, contributions)),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", mode);
+        assert_eq!(body["code"], code, "{}", mode);
+    }
+
+    let (status, exact) = send(
+        &app,
+        "POST",
+        &payments,
+        Some(&token),
+        Some(dinner(
+            "exact",
+            json!([
+                { "participant_id": alice, "amount": 55.5 },
+                { "participant_id": bob, "amount": 34.5 },
+            ]),
+        )),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", exact);
+    assert_eq!(exact["split_mode"], "exact");
+    assert_eq!(amounts(&exact), [55.5, 34.5]);
+
+    let (_, percentages) = send(
+        &app,
+        "POST",
+        &payments,
+        Some(&token),
+        Some(dinner(
+            "percentages",
+            json!([
+                { "participant_id": alice, "weight": 20.0 },
+                { "participant_id": bob, "weight": 80.0 },
+            ]),
+        )),
+    )
+    .await;
+    assert_eq!(amounts(&percentages), [18.0, 72.0]);
+
+    // Carol's third is capped at 20, her excess goes to the others
+    let (status, capped) = send(
+        &app,
+        "POST",
+        &payments,
+        Some(&token),
+        Some(dinner(
+            "weights",
+            json!([
+                { "participant_id": alice, "weight": 1.0 },
+                { "participant_id": bob, "weight": 1.0, "floor": 36.0 },
+                { "participant_id": carol, "weight": 1.0, "cap": 20.0 },
+            ]),
+        )),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", capped);
+    // Then Bob's 35 is raised to his floor of 36, taken from Alice
+    assert_eq!(amounts(&capped), [34.0, 36.0, 20.0]);
+    assert_eq!(capped["split_mode"], "weights");
+    assert_eq!(capped["contributions"][2]["cap"], 20.0);
+
+    // Undoing an edit brings the capped split back as it was
+    let capped_url = format!("{}/{}", payments, capped["id"]);
+    let (status, _) = send(
+        &app,
+        "PUT",
+        &capped_url,
+        Some(&token),
+        Some(dinner(
+            "shares",
+            json!([
+                { "participant_id": alice, "weight": 1.0 },
+                { "participant_id": bob, "weight": 2.0 },
+            ]),
+        )),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, history) = send(
+        &app,
+        "GET",
+        &format!("/projects/{}/history/payment/{}", project_id, capped["id"]),
+        Some(&token),
+        None,
+    )
+    .await;
+    let update = history
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|e| e["action"] == "UPDATE")
+        .unwrap();
+    let (status, body) = send(
+        &app,
+        "POST",
+        &format!("/projects/{}/history/{}/undo", project_id, update["id"]),
+        Some(&token),
+        Some(json!({})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let (_, restored) = send(&app, "GET", &capped_url, Some(&token), None).await;
+    assert_eq!(restored["split_mode"], "weights");
+    assert_eq!(amounts(&restored), [34.0, 36.0, 20.0]);
+    assert_eq!(restored["contributions"][1]["floor"], 36.0);
+}
+
+#[tokio::test]
+async fn test_itemized_receipt_derives_contributions() {
+    let (app, pool) = create_test_app().await;
+    let token = login(&app, &pool, "alice").await;
+    let (project_id, [alice, bob, carol]) = setup_project(&app, &token).await;
+    let payments = format!("/projects/{}/payments", project_id);
+
+    let receipt = |amount: f64, items: Value| {
+        json!({
+            "payer_id": carol,
+            "amount": amount,
+            "description": "Restaurant",
+            "payment_date": "2025-03-01",
+            "items": items,
+        })
+    };
+    let lines = json!([
+        { "description": "Steak", "amount": 30.0, "contributions": [{ "participant_id": alice, "weight": 1.0 }] },
+        { "description": "Salad", "amount": 10.0, "contributions": [{ "participant_id": bob, "weight": 1.0 }] },
+        { "description": "Wine", "amount": 20.0, "contributions": [
+            { "participant_id": alice, "weight": 1.0 },
+            { "participant_id": bob, "weight": 1.0 },
+        ] },
+        { "kind": "tax", "description": "Tax", "amount": 6.0 },
+        { "kind": "tip", "description": "Tip", "amount": 12.0 },
+        { "kind": "discount", "description": "Coupon", "amount": 3.0 },
+    ]);
+
+    // The lines must add up to the amount
+    let (status, body) = send(
+        &app,
+        "POST",
+        &payments,
+        Some(&token),
+        Some(receipt(80.0, lines.clone())),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "ITEMS_TOTAL_MISMATCH");
+
+    // Subtotals 40 and 20: Alice takes 2/3 of tax, tip and discount
+    let (status, payment) = send(
+        &app,
+        "POST",
+        &payments,
+        Some(&token),
+        Some(receipt(75.0, lines)),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", payment);
+    assert_eq!(payment["split_mode"], "exact");
+    assert_eq!(payment["items"].as_array().unwrap().len(), 6);
+    assert_eq!(payment["items"][5]["kind"], "discount");
+    let split: Vec<(i64, f64)> = payment["contributions"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|c| {
+            (
+                c["participant_id"].as_i64().unwrap(),
+                c["amount"].as_f64().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(split, [(alice, 50.0), (bob, 25.0)]);
+
+    // Carol paid the receipt: on top of setup_project's balances (Alice
+    // +60, Bob 0, Carol -60), Alice and Bob owe her their totals
+    let (_, summary) = send(
+        &app,
+        "GET",
+        &format!("/projects/{}/debts?date=2025-12-31", project_id),
+        Some(&token),
+        None,
+    )
+    .await;
+    let balance = |id: i64| {
+        summary["balances"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|b| b["participant_id"] == id)
+            .unwrap()["net_balance"]
+            .as_f64()
+            .unwrap()
+    };
+    assert_eq!(
+        [balance(alice), balance(bob), balance(carol)],
+        [10.0, -25.0, 15.0]
+    );
+
+    // Deleting and undoing brings the receipt back line by line
+    let url = format!("{}/{}", payments, payment["id"]);
+    let (status, _) = send(&app, "DELETE", &url, Some(&token), None).await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, history) = send(
+        &app,
+        "GET",
+        &format!("/projects/{}/history/payment/{}", project_id, payment["id"]),
+        Some(&token),
+        None,
+    )
+    .await;
+    let deletion = history
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|e| e["action"] == "DELETE")
+        .unwrap();
+    let (status, body) = send(
+        &app,
+        "POST",
+        &format!("/projects/{}/history/{}/undo", project_id, deletion["id"]),
+        Some(&token),
+        Some(json!({})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let (_, restored) = send(&app, "GET", &url, Some(&token), None).await;
+    let lines = |payment: &Value| -> Vec<Value> {
+        payment["items"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|i| json!([i["kind"], i["description"], i["amount"], i["weights"]]))
+            .collect()
+    };
+    assert_eq!(lines(&restored), lines(&payment));
+    assert_eq!(restored["contributions"][0]["amount"], 50.0);
+    assert_eq!(restored["contributions"][1]["amount"], 25.0);
+}
+
+#[tokio::test]
+async fn test_several_payers_are_credited_their_parts() {
+    let (app, pool) = create_test_app().await;
+    let token = login(&app, &pool, "alice").await;
+    let (project_id, [alice, bob, carol]) = setup_project(&app, &token).await;
+    let payments = format!("/projects/{}/payments", project_id);
+
+    let groceries = |payers: Value| {
+        json!({
+            "payer_id": null,
+            "payers": payers,
+            "amount": 90.0,
+            "description": "Groceries",
+            "payment_date": "2025-03-01",
+            "contributions": [
+                { "participant_id": alice, "weight": 1.0 },
+                { "participant_id": bob, "weight": 1.0 },
+                { "participant_id": carol, "weight": 1.0 },
+            ],
+        })
+    };
+
+    for (payers, code) in [
+        (
+            json!([
+                { "participant_id": alice, "amount": 50.0 },
+                { "participant_id": bob, "amount": 30.0 },
+            ]),
+            "PAYER_AMOUNTS_MISMATCH",
+        ),
+        (
+            json!([
+                { "participant_id": alice, "amount": 45.0 },
+                { "participant_id": alice, "amount": 45.0 },
+            ]),
+            "INVALID_PAYERS",
+        ),
+    ] {
+        let (status, body) = send(
+            &app,
+            "POST",
+            &payments,
+            Some(&token),
+            Some(groceries(payers)),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], code);
+    }
+
+    let (status, payment) = send(
+        &app,
+        "POST",
+        &payments,
+        Some(&token),
+        Some(groceries(json!([
+            { "participant_id": alice, "amount": 50.0 },
+            { "participant_id": bob, "amount": 40.0 },
+        ]))),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", payment);
+    assert_eq!(payment["payer_id"], alice);
+    assert_eq!(payment["payers"][1]["participant_name"], "Bob");
+    assert_eq!(payment["payers"][1]["amount"], 40.0);
+
+    let balances = || async {
+        let (_, summary) = send(
+            &app,
+            "GET",
+            &format!("/projects/{}/debts?date=2025-12-31", project_id),
+            Some(&token),
+            None,
+        )
+        .await;
+        [alice, bob, carol].map(|id| {
+            summary["balances"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|b| b["participant_id"] == id)
+                .unwrap()["net_balance"]
+                .as_f64()
+                .unwrap()
+        })
+    };
+    // On top of setup_project's +60, 0 and -60, each payer is credited
+    // their part less their 30 share
+    assert_eq!(balances().await, [80.0, 10.0, -90.0]);
+
+    // Back to a single payer: the payers are dropped
+    let url = format!("{}/{}", payments, payment["id"]);
+    let mut single = groceries(json!([]));
+    single["payer_id"] = json!(alice);
+    let (status, payment) = send(&app, "PUT", &url, Some(&token), Some(single)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(payment["payers"], json!([]));
+    assert_eq!(balances().await, [120.0, -30.0, -90.0]);
+}
+
+#[tokio::test]
+async fn test_refund_reverses_part_of_a_payment() {
+    let (app, pool) = create_test_app().await;
+    let token = login(&app, &pool, "alice").await;
+    let (project_id, [alice, bob, carol]) = setup_project(&app, &token).await;
+    let payments = format!("/projects/{}/payments", project_id);
+
+    let (status, payment) = send(
+        &app,
+        "POST",
+        &payments,
+        Some(&token),
+        Some(json!({
+            "payer_id": alice,
+            "amount": 90.0,
+            "description": "Concert tickets",
+            "payment_date": "2025-03-01",
+            "contributions": [
+                { "participant_id": alice, "weight": 1.0 },
+                { "participant_id": bob, "weight": 1.0 },
+                { "participant_id": carol, "weight": 1.0 },
+            ],
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", payment);
+    let url = format!("{}/{}", payments, payment["id"]);
+    let refunds = format!("{}/refunds", url);
+
+    let (status, refund) = send(
+        &app,
+        "POST",
+        &refunds,
+        Some(&token),
+        Some(
+            json!({ "amount": 30.0, "refund_date": "2025-03-10", "description": "Cancelled seat" }),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", refund);
+
+    for (amount, code) in [
+        (-5.0, "AMOUNT_MUST_BE_POSITIVE"),
+        (70.0, "REFUND_EXCEEDS_PAYMENT"),
+    ] {
+        let (status, body) = send(
+            &app,
+            "POST",
+            &refunds,
+            Some(&token),
+            Some(json!({ "amount": amount, "refund_date": "2025-03-10" })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], code);
+    }
+
+    // The payment shows what it comes to after the refund
+    let (_, payment) = send(&app, "GET", &url, Some(&token), None).await;
+    assert_eq!(payment["refunds"][0]["amount"], 30.0);
+    assert_eq!(payment["net_cost"]["amount"], 60.0);
+    let net: Vec<f64> = payment["net_cost"]["contributions"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|c| c["amount"].as_f64().unwrap())
+        .collect();
+    assert_eq!(net, [20.0, 20.0, 20.0]);
+
+    let summary = |date: &'static str| {
+        let app = &app;
+        let token = &token;
+        async move {
+            send(
+                app,
+                "GET",
+                &format!("/projects/{}/debts?date={}", project_id, date),
+                Some(token),
+                None,
+            )
+            .await
+            .1
+        }
+    };
+    let balances = |summary: &Value| {
+        [alice, bob, carol].map(|id| {
+            summary["balances"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|b| b["participant_id"] == id)
+                .unwrap()["net_balance"]
+                .as_f64()
+                .unwrap()
+        })
+    };
+    // On top of setup_project's +60, 0 and -60: the whole payment until the
+    // refund, then 30 back to Alice taken off everyone's share
+    assert_eq!(
+        balances(&summary("2025-03-05").await),
+        [120.0, -30.0, -90.0]
+    );
+    let after = summary("2025-12-31").await;
+    assert_eq!(balances(&after), [100.0, -20.0, -80.0]);
+
+    // The refund shows as a negative line of what Alice paid for Bob
+    let alice_for_bob = after["pairwise_balances"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|p| p["participant_id"] == alice && p["other_participant_id"] == bob)
+        .unwrap();
+    let lines: Vec<f64> = alice_for_bob["paid_for_breakdown"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|line| line["payment_id"] == payment["id"])
+        .map(|line| line["amount"].as_f64().unwrap())
+        .collect();
+    assert_eq!(lines, [30.0, -10.0]);
+
+    // Deleting the refund, then undoing the deletion
+    let (status, _) = send(
+        &app,
+        "DELETE",
+        &format!("{}/{}", refunds, refund["id"]),
+        Some(&token),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        balances(&summary("2025-12-31").await),
+        [120.0, -30.0, -90.0]
+    );
+
+    let (_, history) = send(
+        &app,
+        "GET",
+        &format!("/projects/{}/history/refund/{}", project_id, refund["id"]),
+        Some(&token),
+        None,
+    )
+    .await;
+    let deletion = history
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|e| e["action"] == "DELETE")
+        .unwrap();
+    let (status, body) = send(
+        &app,
+        "POST",
+        &format!("/projects/{}/history/{}/undo", project_id, deletion["id"]),
+        Some(&token),
+        Some(json!({})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(
+        balances(&summary("2025-12-31").await),
+        [100.0, -20.0, -80.0]
+    );
+}
+
+#[tokio::test]
+async fn test_pool_to_pool_transfer_carries_ownership() {
+    let (app, pool) = create_test_app().await;
+    let token = login(&app, &pool, "alice").await;
+    let (project_id, [alice, bob, _]) = setup_project(&app, &token).await;
+
+    let checking = create_pool(&app, &token, project_id, "Checking").await;
+    let savings = create_pool(&app, &token, project_id, "Savings").await;
+
+    let transfer = |payer: i64, receiver: i64, amount: f64, date: &str| {
+        json!({
+            "payer_id": payer,
+            "amount": amount,
+            "description": "Transfer",
+            "payment_date": date,
+            "receiver_account_id": receiver,
+            "contributions": [{ "participant_id": receiver, "weight": 1.0 }],
+        })
+    };
+    create_payment(
+        &app,
+        &token,
+        project_id,
+        transfer(alice, checking, 300.0, "2025-01-01"),
+    )
+    .await;
+    create_payment(
+        &app,
+        &token,
+        project_id,
+        transfer(bob, checking, 100.0, "2025-01-01"),
+    )
+    .await;
+    create_payment(
+        &app,
+        &token,
+        project_id,
+        transfer(checking, savings, 200.0, "2025-02-01"),
+    )
+    .await;
+
+    let (_, summary) = send(
+        &app,
+        "GET",
+        &format!("/projects/{}/debts?date=2025-12-31", project_id),
+        Some(&token),
+        None,
+    )
+    .await;
+    let ownership = |pool_id: i64| -> Vec<(i64, f64)> {
+        summary["pool_ownerships"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|p| p["pool_id"] == pool_id)
+            .unwrap()["entries"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| {
+                (
+                    e["participant_id"].as_i64().unwrap(),
+                    e["ownership"].as_f64().unwrap(),
+                )
+            })
+            .collect()
+    };
+    // Half of checking moves to savings, each owner keeping their share
+    assert_eq!(ownership(checking), [(alice, 150.0), (bob, 50.0)]);
+    assert_eq!(ownership(savings), [(alice, 150.0), (bob, 50.0)]);
+
+    let savings_entries = summary["pool_ownerships"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|p| p["pool_id"] == savings)
+        .unwrap();
+    assert_eq!(savings_entries["total_balance"], 200.0);
+    assert_eq!(
+        savings_entries["entries"][1]["contributed_breakdown"][0]["amount"],
+        50.0
+    );
+}
+
+#[tokio::test]
+async fn test_unitized_pool_reports_units_and_gains() {
+    let (app, pool) = create_test_app().await;
+    let token = login(&app, &pool, "alice").await;
+    let (project_id, [alice, bob, carol]) = setup_project(&app, &token).await;
+    let participants = format!("/projects/{}/participants", project_id);
+
+    let savings = create_pool(&app, &token, project_id, "Savings").await;
+
+    // Only pools can be unitized
+    let (status, _) = send(
+        &app,
+        "PATCH",
+        &format!("{}/{}/pool-mode", participants, carol),
+        Some(&token),
+        Some(json!({ "unitized": true })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, updated) = send(
+        &app,
+        "PATCH",
+        &format!("{}/{}/pool-mode", participants, savings),
+        Some(&token),
+        Some(json!({ "unitized": true })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(updated["unitized"], true);
+
+    let deposit = |payer: i64, amount: f64, date: &str| deposit(savings, payer, amount, date);
+    create_payment(
+        &app,
+        &token,
+        project_id,
+        deposit(alice, 100.0, "2025-01-01"),
+    )
+    .await;
+    let valuations = format!("/projects/{}/pool-valuations", project_id);
+    let (status, body) = send(
+        &app,
+        "POST",
+        &valuations,
+        Some(&token),
+        Some(json!({ "pool_id": savings, "valuation_date": "2025-01-31", "amount": 120.0 })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["kind"], "value");
+    create_payment(&app, &token, project_id, deposit(bob, 60.0, "2025-02-01")).await;
+
+    for (valuation, code) in [
+        (
+            json!({ "pool_id": savings, "valuation_date": "2025-03-01", "kind": "fee", "amount": 5.0 }),
+            "INVALID_POOL_VALUATION",
+        ),
+        (
+            json!({ "pool_id": savings, "valuation_date": "2025-03-01", "amount": -5.0 }),
+            "INVALID_POOL_VALUATION",
+        ),
+        (
+            json!({ "pool_id": carol, "valuation_date": "2025-03-01", "amount": 5.0 }),
+            "INVALID_PARTICIPANT",
+        ),
+    ] {
+        let (status, body) = send(&app, "POST", &valuations, Some(&token), Some(valuation)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], code);
+    }
+
+    let (status, _) = send(
+        &app,
+        "POST",
+        &valuations,
+        Some(&token),
+        Some(json!({
+            "pool_id": savings,
+            "valuation_date": "2025-03-01",
+            "kind": "interest",
+            "amount": 30.0,
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (_, summary) = send(
+        &app,
+        "GET",
+        &format!("/projects/{}/debts?date=2025-12-31", project_id),
+        Some(&token),
+        None,
+    )
+    .await;
+    let ownership = summary["pool_ownerships"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|p| p["pool_id"] == savings)
+        .unwrap();
+    // Alice's 100 units and Bob's 50 (bought at 1.2) share 210
+    assert_eq!(ownership["unit_value"], 1.4);
+    assert_eq!(ownership["total_balance"], 210.0);
+    let entry = |id: i64| {
+        ownership["entries"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|e| e["participant_id"] == id)
+            .unwrap()
+    };
+    assert_eq!(entry(alice)["ownership"], 140.0);
+    assert_eq!(entry(alice)["contributed"], 100.0);
+    assert_eq!(entry(bob)["ownership"], 70.0);
+    assert_eq!(entry(bob)["units"], 50.0);
+
+    // Before the interest, the unit was worth 1.2
+    let (_, summary) = send(
+        &app,
+        "GET",
+        &format!("/projects/{}/debts?date=2025-02-15", project_id),
+        Some(&token),
+        None,
+    )
+    .await;
+    let ownership = summary["pool_ownerships"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|p| p["pool_id"] == savings)
+        .unwrap();
+    assert_eq!(ownership["total_balance"], 180.0);
+}
+
+#[tokio::test]
+async fn test_pool_rules_credit_interest_and_charge_fees() {
+    let (app, pool) = create_test_app().await;
+    let token = login(&app, &pool, "alice").await;
+    let (project_id, [alice, bob, carol]) = setup_project(&app, &token).await;
+
+    let savings = create_pool(&app, &token, project_id, "Savings").await;
+    for (payer, amount) in [(alice, 600.0), (bob, 400.0)] {
+        create_payment(
+            &app,
+            &token,
+            project_id,
+            deposit(savings, payer, amount, "2025-01-01"),
+        )
+        .await;
+    }
+
+    let rules = format!("/projects/{}/pool-rules", project_id);
+    for (rule, code) in [
+        (
+            json!({ "pool_id": savings, "kind": "fee", "recurrence_rule": "FREQ=MONTHLY", "start_date": "2025-01-15" }),
+            "INVALID_POOL_RULE",
+        ),
+        (
+            json!({ "pool_id": savings, "kind": "interest", "rate": 2.0, "recurrence_rule": "FREQ=SOMETIMES", "start_date": "2025-01-01" }),
+            "INVALID_RECURRENCE_RULE",
+        ),
+        (
+            json!({ "pool_id": carol, "kind": "fee", "amount": 4.0, "recurrence_rule": "FREQ=MONTHLY", "start_date": "2025-01-15" }),
+            "INVALID_PARTICIPANT",
+        ),
+    ] {
+        let (status, body) = send(&app, "POST", &rules, Some(&token), Some(rule)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], code);
+    }
+
+    // A 4.00 fee mid-month and 3.65% a year on the daily balance, credited
+    // on the last day of the month
+    let (status, fee) = send(
+        &app,
+        "POST",
+        &rules,
+        Some(&token),
+        Some(json!({
+            "pool_id": savings,
+            "kind": "fee",
+            "amount": 4.0,
+            "recurrence_rule": "FREQ=MONTHLY",
+            "start_date": "2025-01-15",
+            "description": "Bank fee",
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", fee);
+    let (status, interest) = send(
+        &app,
+        "POST",
+        &rules,
+        Some(&token),
+        Some(json!({
+            "pool_id": savings,
+            "kind": "interest",
+            "rate": 3.65,
+            "recurrence_rule": "FREQ=MONTHLY;BYMONTHDAY=-1",
+            "start_date": "2025-01-01",
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", interest);
+
+    let ownership_on = |date: &'static str| {
+        let app = app.clone();
+        let token = token.clone();
+        async move {
+            let (_, summary) = send(
+                &app,
+                "GET",
+                &format!("/projects/{}/debts?date={}", project_id, date),
+                Some(&token),
+                None,
+            )
+            .await;
+            summary["pool_ownerships"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|p| p["pool_id"] == savings)
+                .unwrap()
+                .clone()
+        }
+    };
+    let entry = |ownership: &Value, id: i64| {
+        ownership["entries"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|e| e["participant_id"] == id)
+            .unwrap()
+            .clone()
+    };
+
+    // The fee is split 60/40; interest accrues on 1000 for 14 days, then on
+    // 996 for 17 days: 3.09, shared as each owner earned it
+    let ownership = ownership_on("2025-01-31").await;
+    assert_eq!(ownership["total_balance"], 999.09);
+    let alice_entry = entry(&ownership, alice);
+    assert_eq!(alice_entry["ownership"], 599.45);
+    assert_eq!(
+        alice_entry["consumed_breakdown"][0]["description"],
+        "Bank fee"
+    );
+    assert_eq!(alice_entry["consumed_breakdown"][0]["amount"], 2.4);
+    assert_eq!(
+        alice_entry["consumed_breakdown"][0]["pool_rule_id"],
+        fee["id"]
+    );
+    assert_eq!(alice_entry["contributed_breakdown"][1]["amount"], 1.85);
+    assert_eq!(
+        alice_entry["contributed_breakdown"][1]["pool_rule_id"],
+        interest["id"]
+    );
+    assert_eq!(entry(&ownership, bob)["ownership"], 399.64);
+
+    // Nothing is credited before the end of the month
+    let ownership = ownership_on("2025-01-30").await;
+    assert_eq!(ownership["total_balance"], 996.0);
+
+    // Deleting the fee, then undoing the deletion
+    let (status, _) = send(
+        &app,
+        "DELETE",
+        &format!("{}/{}", rules, fee["id"]),
+        Some(&token),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let ownership = ownership_on("2025-01-31").await;
+    assert_eq!(ownership["total_balance"], 1003.1);
+
+    let (_, history) = send(
+        &app,
+        "GET",
+        &format!("/projects/{}/history/pool_rule/{}", project_id, fee["id"]),
+        Some(&token),
+        None,
+    )
+    .await;
+    let deletion = history
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|e| e["action"] == "DELETE")
+        .unwrap();
+    let (status, body) = send(
+        &app,
+        "POST",
+        &format!("/projects/{}/history/{}/undo", project_id, deletion["id"]),
+        Some(&token),
+        Some(json!({})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let ownership = ownership_on("2025-01-31").await;
+    assert_eq!(ownership["total_balance"], 999.09);
+}
+
+#[tokio::test]
+async fn test_pool_statements_reconcile_and_adjust() {
+    let (app, pool) = create_test_app().await;
+    let token = login(&app, &pool, "alice").await;
+    let (project_id, [alice, bob, carol]) = setup_project(&app, &token).await;
+
+    let savings = create_pool(&app, &token, project_id, "Savings").await;
+    for (payer, amount, date) in [
+        (alice, 600.0, "2025-01-01"),
+        (bob, 400.0, "2025-01-01"),
+        (carol, 50.0, "2025-02-10"),
+    ] {
+        create_payment(
+            &app,
+            &token,
+            project_id,
+            deposit(savings, payer, amount, date),
+        )
+        .await;
+    }
+
+    let statements = format!("/projects/{}/pool-statements", project_id);
+    let (status, body) = send(
+        &app,
+        "POST",
+        &statements,
+        Some(&token),
+        Some(json!({ "pool_id": carol, "statement_date": "2025-01-31", "balance": 1000.0 })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "INVALID_PARTICIPANT");
+
+    let mut ids = Vec::new();
+    for (date, balance) in [
+        ("2025-01-31", 1000.0),
+        ("2025-02-28", 1040.0),
+        ("2025-03-31", 1035.0),
+    ] {
+        let (status, statement) = send(
+            &app,
+            "POST",
+            &statements,
+            Some(&token),
+            Some(json!({ "pool_id": savings, "statement_date": date, "balance": balance })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{}", statement);
+        ids.push(statement["id"].as_i64().unwrap());
+    }
+
+    let reconciliation = || {
+        let app = app.clone();
+        let token = token.clone();
+        async move {
+            let (status, body) = send(
+                &app,
+                "GET",
+                &format!(
+                    "/projects/{}/pool-statements/reconciliation?pool_id={}",
+                    project_id, savings
+                ),
+                Some(&token),
+                None,
+            )
+            .await;
+            assert_eq!(status, StatusCode::OK, "{}", body);
+            body["statements"].as_array().unwrap().clone()
+        }
+    };
+    let figures = |statements: &[Value]| -> Vec<(f64, f64, f64)> {
+        statements
+            .iter()
+            .map(|s| {
+                (
+                    s["total_balance"].as_f64().unwrap(),
+                    s["drift"].as_f64().unwrap(),
+                    s["unexplained"].as_f64().unwrap(),
+                )
+            })
+            .collect()
+    };
+
+    // 10 went missing in February and 5 more in March
+    let report = reconciliation().await;
+    assert_eq!(
+        figures(&report),
+        [
+            (1000.0, 0.0, 0.0),
+            (1050.0, -10.0, -10.0),
+            (1050.0, -15.0, -5.0)
+        ]
+    );
+    assert_eq!(report[0]["occurrences"].as_array().unwrap().len(), 2);
+    assert_eq!(report[1]["occurrences"][0]["payer_id"], carol);
+    assert!(report[2]["occurrences"].as_array().unwrap().is_empty());
+    let adjustment: Vec<f64> = report[1]["adjustment"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|s| s["amount"].as_f64().unwrap())
+        .collect();
+    assert_eq!(adjustment, [-5.71, -3.81, -0.48]);
+
+    // Posting February's gap charges it to the owners by ownership
+    let adjust = format!("{}/{}/adjust", statements, ids[1]);
+    let (status, payment) = send(&app, "POST", &adjust, Some(&token), None).await;
+    assert_eq!(status, StatusCode::OK, "{}", payment);
+    assert_eq!(payment["payer_id"], savings);
+    assert_eq!(payment["amount"], 10.0);
+    assert_eq!(payment["payment_date"], "2025-02-28");
+    assert_eq!(payment["contributions"].as_array().unwrap().len(), 3);
+
+    let report = reconciliation().await;
+    assert_eq!(
+        figures(&report),
+        [(1000.0, 0.0, 0.0), (1040.0, 0.0, 0.0), (1040.0, -5.0, -5.0)]
+    );
+    let (status, body) = send(&app, "POST", &adjust, Some(&token), None).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "NOTHING_TO_ADJUST");
+}
+
+#[tokio::test]
+async fn test_contribution_rules_set_expected_deposits() {
+    let (app, pool) = create_test_app().await;
+    let token = login(&app, &pool, "alice").await;
+    let (project_id, [alice, bob, carol]) = setup_project(&app, &token).await;
+
+    let house = create_pool(&app, &token, project_id, "House").await;
+    for (payer, amount) in [(alice, 300.0), (bob, 100.0)] {
+        create_payment(
+            &app,
+            &token,
+            project_id,
+            deposit(house, payer, amount, "2025-01-01"),
+        )
+        .await;
+    }
+
+    // Rule payments are no longer accepted
+    let (status, body) = send(
+        &app,
+        "POST",
+        &format!("/projects/{}/payments", project_id),
+        Some(&token),
+        Some(json!({
+            "payer_id": bob,
+            "amount": 50.0,
+            "description": "Bob's share",
+            "payment_date": "2025-01-01",
+            "receiver_account_id": house,
+            "affects_balance": false,
+            "affects_receiver_expectation": true,
+            "contributions": [{ "participant_id": house, "weight": 1.0 }],
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "USE_CONTRIBUTION_RULE");
+
+    // Rule payments saved before contribution rules existed: Bob's own monthly
+    // share, and a one-off deposit split between Alice and Carol
+    let insert_rule_payment =
+        |payer: Option<i64>, description: &'static str, date: &'static str| {
+            let pool = pool.clone();
+            async move {
+                let recurrence_rule = payer.map(|_| "FREQ=MONTHLY");
+                sqlx::query(
+                    "INSERT INTO payments (project_id, payer_id, amount, description, payment_date,
+                 is_recurring, recurrence_rule, receiver_account_id, affects_balance,
+                 affects_receiver_expectation)
+                 VALUES (?, ?, 50000, ?, ?, ?, ?, ?, 0, 1)",
+                )
+                .bind(project_id)
+                .bind(payer)
+                .bind(description)
+                .bind(date)
+                .bind(recurrence_rule.is_some())
+                .bind(recurrence_rule)
+                .bind(house)
+                .execute(&pool)
+                .await
+                .unwrap()
+                .last_insert_rowid()
+            }
+        };
+    let add_contribution = |payment_id: i64, participant_id: i64, amount: i64| {
+        let pool = pool.clone();
+        async move {
+            sqlx::query(
+                "INSERT INTO contributions (participant_id, payment_id, amount) VALUES (?, ?, ?)",
+            )
+            .bind(participant_id)
+            .bind(payment_id)
+            .bind(amount)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+    };
+    let bobs = insert_rule_payment(Some(bob), "Bob's share", "2025-01-01").await;
+    add_contribution(bobs, house, 50000).await;
+    let split = insert_rule_payment(None, "Top-up", "2025-06-01").await;
+    add_contribution(split, alice, 30000).await;
+    add_contribution(split, carol, 20000).await;
+
+    // Upgrading a database that has no contribution rules yet converts them
+    for table in ["contribution_rule_amounts", "contribution_rules"] {
+        sqlx::query(sqlx::AssertSqlSafe(format!("DROP TABLE {}", table)))
+            .execute(&pool)
+            .await
+            .unwrap();
+    }
+    db::run_migrations(&pool).await.unwrap();
+    for payment_id in [bobs, split] {
+        let (status, _) = send(
+            &app,
+            "GET",
+            &format!("/projects/{}/payments/{}", project_id, payment_id),
+            Some(&token),
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    let rules = format!("/projects/{}/contribution-rules", project_id);
+    let (_, migrated) = send(&app, "GET", &rules, Some(&token), None).await;
+    let migrated = migrated.as_array().unwrap();
+    assert_eq!(migrated.len(), 2);
+    assert_eq!(migrated[0]["pool_id"], house);
+    assert_eq!(migrated[0]["recurrence_rule"], "FREQ=MONTHLY");
+    assert_eq!(migrated[0]["description"], "Bob's share");
+    assert_eq!(migrated[0]["amounts"][0]["participant_id"], bob);
+    assert_eq!(migrated[0]["amounts"][0]["amount"], 50.0);
+    assert_eq!(migrated[1]["recurrence_rule"], Value::Null);
+    assert_eq!(migrated[1]["start_date"], "2025-06-01");
+    let split_amounts: Vec<(i64, f64)> = migrated[1]["amounts"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|a| {
+            (
+                a["participant_id"].as_i64().unwrap(),
+                a["amount"].as_f64().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(split_amounts, [(alice, 30.0), (carol, 20.0)]);
+
+    // Later startups leave payments alone
+    let kept = insert_rule_payment(Some(bob), "Kept", "2025-01-01").await;
+    db::run_migrations(&pool).await.unwrap();
+    let (status, _) = send(
+        &app,
+        "GET",
+        &format!("/projects/{}/payments/{}", project_id, kept),
+        Some(&token),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    sqlx::query("DELETE FROM payments WHERE id = ?")
+        .bind(kept)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    for (rule, code) in [
+        (
+            json!({ "pool_id": house, "start_date": "2025-01-01", "amounts": [] }),
+            "INVALID_CONTRIBUTION_RULE",
+        ),
+        (
+            json!({ "pool_id": house, "start_date": "2025-01-01", "amounts": [{ "participant_id": house, "amount": 10.0 }] }),
+            "INVALID_PARTICIPANT",
+        ),
+        (
+            json!({ "pool_id": carol, "start_date": "2025-01-01", "amounts": [{ "participant_id": alice, "amount": 10.0 }] }),
+            "INVALID_PARTICIPANT",
+        ),
+    ] {
+        let (status, body) = send(&app, "POST", &rules, Some(&token), Some(rule)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], code);
+    }
+
+    // Alice is expected to put in 100 a month and Carol 20, until March
+    let (status, rule) = send(
+        &app,
+        "POST",
+        &rules,
+        Some(&token),
+        Some(json!({
+            "pool_id": house,
+            "recurrence_rule": "FREQ=MONTHLY",
+            "start_date": "2025-01-01",
+            "end_date": "2025-03-31",
+            "description": "Reserve",
+            "amounts": [
+                { "participant_id": alice, "amount": 100.0 },
+                { "participant_id": carol, "amount": 20.0 },
+            ],
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", rule);
+    assert_eq!(rule["amounts"].as_array().unwrap().len(), 2);
+
+    let ownership_on = |date: &'static str| {
+        let app = app.clone();
+        let token = token.clone();
+        async move {
+            let (_, summary) = send(
+                &app,
+                "GET",
+                &format!("/projects/{}/debts?date={}", project_id, date),
+                Some(&token),
+                None,
+            )
+            .await;
+            summary["pool_ownerships"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|p| p["pool_id"] == house)
+                .unwrap()
+                .clone()
+        }
+    };
+    let expected = |ownership: &Value| -> Vec<(i64, f64, Option<f64>)> {
+        let mut entries: Vec<(i64, f64, Option<f64>)> = ownership["entries"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| {
+                (
+                    e["participant_id"].as_i64().unwrap(),
+                    e["expected_minimum"].as_f64().unwrap(),
+                    e["shortfall"].as_f64(),
+                )
+            })
+            .collect();
+        entries.sort_by_key(|(id, _, _)| *id);
+        entries
+    };
+
+    // Three months in: Alice is on track, Bob and Carol are behind
+    let ownership = ownership_on("2025-03-15").await;
+    assert_eq!(ownership["expected_minimum"], 510.0);
+    assert_eq!(
+        expected(&ownership),
+        [
+            (alice, 300.0, None),
+            (bob, 150.0, Some(50.0)),
+            (carol, 60.0, Some(60.0))
+        ]
+    );
+    // The rule ends with March; Bob's goes on
+    let ownership = ownership_on("2025-05-15").await;
+    assert_eq!(ownership["expected_minimum"], 610.0);
+
+    // Deleting the rule, then undoing the deletion
+    let (status, _) = send(
+        &app,
+        "DELETE",
+        &format!("{}/{}", rules, rule["id"]),
+        Some(&token),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(ownership_on("2025-03-15").await["expected_minimum"], 150.0);
+
+    let (_, history) = send(
+        &app,
+        "GET",
+        &format!(
+            "/projects/{}/history/contribution_rule/{}",
+            project_id, rule["id"]
+        ),
+        Some(&token),
+        None,
+    )
+    .await;
+    let deletion = history
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|e| e["action"] == "DELETE")
+        .unwrap();
+    let (status, body) = send(
+        &app,
+        "POST",
+        &format!("/projects/{}/history/{}/undo", project_id, deletion["id"]),
+        Some(&token),
+        Some(json!({})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(ownership_on("2025-03-15").await["expected_minimum"], 510.0);
+}
+
+#[tokio::test]
+async fn test_undo_of_converted_rule_payment_is_not_found() {
+    let (app, pool) = create_test_app().await;
+    let token = login(&app, &pool, "alice").await;
+    let (project_id, [alice, _, _]) = setup_project(&app, &token).await;
+
+    let house = create_pool(&app, &token, project_id, "House").await;
+    let payments = format!("/projects/{}/payments", project_id);
+    let (status, payment) = send(
+        &app,
+        "POST",
+        &payments,
+        Some(&token),
+        Some(deposit(house, alice, 50.0, "2025-01-01")),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let payment_id = payment["id"].as_i64().unwrap();
+    let (status, _) = send(
+        &app,
+        "PUT",
+        &format!("{}/{}", payments, payment_id),
+        Some(&token),
+        Some(deposit(house, alice, 60.0, "2025-01-01")),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    // The payment was a rule payment, converted to a contribution rule on upgrade
+    sqlx::query(
+        "UPDATE payments SET affects_balance = 0, affects_receiver_expectation = 1 WHERE id = ?",
+    )
+    .bind(payment_id)
+    .execute(&pool)
+    .await
+    .unwrap();
+    for table in ["contribution_rule_amounts", "contribution_rules"] {
+        sqlx::query(sqlx::AssertSqlSafe(format!("DROP TABLE {}", table)))
+            .execute(&pool)
+            .await
+            .unwrap();
+    }
+    db::run_migrations(&pool).await.unwrap();
+    let rules: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM contribution_rules")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(rules, 1);
+
+    // Its history can no longer be undone
+    let (_, history) = send(
+        &app,
+        "GET",
+        &format!("/projects/{}/history/payment/{}", project_id, payment_id),
+        Some(&token),
+        None,
+    )
+    .await;
+    let history = history.as_array().unwrap();
+    assert_eq!(history.len(), 2);
+    for entry in history {
+        let (status, body) = send(
+            &app,
+            "POST",
+            &format!("/projects/{}/history/{}/undo", project_id, entry["id"]),
+            Some(&token),
+            Some(json!({})),
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND, "{}", body);
+        assert_eq!(body["code"], "PAYMENT_NOT_FOUND");
+    }
+}
@@ -296,12 +296,25 @@ export interface PoolSummary {
   ownership: number;
 }
 
+export interface PoolWarning {
+  kind: 'pool_below_expected' | 'user_below_expected' | 'negative_expected_minimum';
+  pool_id: number;
+  pool_name: string;
+  participant_id: number | null;
+  participant_name: string | null;
+  date: string;
+  shortfall: number;
+  horizon: string;
+  horizon_date: string;
+}
+
 export interface ProjectWithRole extends Project {
   role: string;
   owner_name: string;
   user_balance: number | null;
   user_pools: PoolSummary[];
   member_status: 'active' | 'pending' | 'recovered';
+  warnings: PoolWarning[];
 }
 
 export interface Participant {
@@ -314,6 +327,7 @@ export interface Participant {
   account_type: 'user' | 'pool';
   warning_horizon_account: string | null; // Pool warning horizon for account total (null = disabled)
   warning_horizon_users: string | null; // Pool warning horizon for user ownership (null = disabled)
+  unitized: boolean; // Pool ownership tracked in units priced by valuations
 }
 
 export interface ProjectMember {
@@ -375,6 +389,10 @@ export interface Payment {
   recurrence_weekdays: string | null; // e.g., "[[1,3],[0,5]]" - weekdays per week in cycle
   recurrence_monthdays: string | null; // e.g., "[1, 15, 28]" - days of month
   recurrence_months: string | null; // e.g., "[1, 6, 12]" - months (1=Jan, 12=Dec)
+  // RFC 5545 RRULE, e.g. "FREQ=MONTHLY;BYDAY=-1FR" (converted from the fields above if not sent).
+  // May span lines: "RRULE:" rules (each optionally after a "DTSTART:YYYYMMDD" line), then
+  // "RDATE;VALUE=DATE:" and "EXDATE;VALUE=DATE:" lines of comma-separated YYYYMMDD dates
+  recurrence_rule: string | null;
   // Internal transfer support
   // null = external expense (money leaves system, affects settlements)
   // number = internal transfer to this account (only affects pool ownership)
@@ -388,8 +406,29 @@ export interface Payment {
   affects_payer_expectation: boolean;
   // affects_receiver_expectation: When receiver is a pool and true, increases receiver's expected minimum
   affects_receiver_expectation: boolean;
+  // Series this payment continues after an "edit this and following" split
+  split_from_id: number | null;
+  // JSON AmountSchedule: how the amount of a recurring payment changes
+  amount_schedule: string | null;
+  // Move occurrences off weekends and project holidays
+  business_day_shift: BusinessDayShift | null;
+  // Charge contributors present part of a month for that part only
+  prorate_presence: boolean;
+  // Weight profile splitting the payment instead of its own weights
+  weight_profile_id: number | null;
+  // How contributions split the amount (null = weights)
+  split_mode: SplitMode | null;
 }
 
+export type SplitMode = 'weights' | 'exact' | 'percentages' | 'shares';
+
+export type BusinessDayShift = 'next' | 'previous';
+
+// Scheduled amount changes, stored as JSON in amount_schedule
+export type AmountSchedule =
+  | { type: 'steps'; steps: { effective_from: string; amount: number }[] }
+  | { type: 'yearly'; percent: number; round_to?: number; first_increase?: string };
+
 export interface Contribution {
   id: number;
   participant_id: number;
@@ -397,11 +436,63 @@ export interface Contribution {
   payment_id: number;
   amount: number;
   weight: number;
+  // Limits on the share, weights mode only
+  cap?: number | null;
+  floor?: number | null;
+}
+
+export interface ContributionInput {
+  participant_id: number;
+  // Weight, percentage or number of shares depending on the split mode
+  weight?: number;
+  // Exact mode only
+  amount?: number;
+  cap?: number;
+  floor?: number;
+}
+
+export type PaymentItemKind = 'item' | 'tax' | 'tip' | 'discount';
+
+// Receipt line of an itemized payment
+export interface PaymentItem {
+  id: number;
+  payment_id: number;
+  position: number;
+  kind: PaymentItemKind;
+  description: string;
+  // Positive; discounts are subtracted
+  amount: number;
+  // JSON: [{"participant_id": 1, "weight": 2.0}], items only
+  weights: string | null;
+}
+
+export interface PaymentItemInput {
+  // Default: item
+  kind?: PaymentItemKind;
+  description?: string;
+  amount: number;
+  // Items only
+  contributions?: Array<{ participant_id: number; weight: number }>;
+}
+
+// One of several payers of a payment and the part they paid
+export interface PaymentPayer {
+  id: number;
+  payment_id: number;
+  participant_id: number;
+  participant_name: string;
+  amount: number;
 }
 
 export interface PaymentWithContributions extends Payment {
   payer_name: string | null;
+  // Empty when a single participant paid (payer_id)
+  payers: PaymentPayer[];
   contributions: Contribution[];
+  items: PaymentItem[];
+  refunds: Refund[];
+  // Amount and contributions less the refunds, null without refunds
+  net_cost: NetCost | null;
 }
 
 export interface ParticipantBalance {
@@ -425,6 +516,14 @@ export interface PaymentOccurrence {
   description: string;
   amount: number;
   occurrence_date: string;
+  // Date the series scheduled this occurrence on, when an exception changed it
+  original_date: string | null;
+  // Set on the negative occurrence of a refund of the payment
+  refund_id: number | null;
+  // Set on a fee or interest of a pool rule (payment_id is then 0)
+  pool_rule_id: number | null;
+  // Set on a deposit expected by a contribution rule (payment_id is then 0)
+  contribution_rule_id: number | null;
   payer_id: number | null;
   is_recurring: boolean;
   // Internal transfer support
@@ -442,6 +541,7 @@ export interface PairwisePaymentBreakdown {
   description: string;
   occurrence_date: string;
   amount: number;
+  pool_rule_id: number | null; // Set for a fee or interest of a pool rule
 }
 
 export interface PairwiseBalance {
@@ -461,7 +561,10 @@ export interface PoolOwnershipEntry {
   participant_name: string;
   contributed: number;
   consumed: number;
-  ownership: number;
+  ownership: number; // contributed - consumed, or units × unit value
+  units: number | null; // Units held in a unitized pool
+  expected_minimum: number; // Deposits expected from this participant so far
+  shortfall: number | null; // expected_minimum - ownership (if positive)
   contributed_breakdown: PairwisePaymentBreakdown[];
   consumed_breakdown: PairwisePaymentBreakdown[];
 }
@@ -471,6 +574,7 @@ export interface PoolOwnership {
   pool_name: string;
   entries: PoolOwnershipEntry[];
   total_balance: number;
+  unit_value: number | null; // Value of one unit of a unitized pool
   // Dual ledger: expected minimum from affects_expectation=true transactions
   expected_minimum: number;
   is_below_expected: boolean; // total_balance < expected_minimum
@@ -585,6 +689,16 @@ export const updatePoolWarningSettings = (
     body: JSON.stringify(data)
   });
 
+export const updatePoolMode = (
+  projectId: number,
+  participantId: number,
+  data: { unitized: boolean }
+): Promise<Participant> =>
+  authFetch(`/projects/${projectId}/participants/${participantId}/pool-mode`, {
+    method: 'PATCH',
+    body: JSON.stringify(data)
+  });
+
 // Participant Invites
 export const createParticipantInvite = (
   projectId: number,
@@ -647,10 +761,19 @@ export const rejectMember = (projectId: number, userId: number): Promise<{ rejec
 // Payments
 export interface CreatePaymentInput {
   payer_id: number | null;
+  // Several payers, their amounts adding up to the amount; the first becomes
+  // payer_id (external expenses only)
+  payers?: Array<{ participant_id: number; amount: number }>;
   amount: number;
   description: string;
   payment_date?: string;
-  contributions: Array<{ participant_id: number; weight: number }>;
+  // Ignored when weight_profile_id or items are set
+  contributions: ContributionInput[];
+  // Itemized receipt: contributions are derived from the lines, which must
+  // add up to the amount
+  items?: PaymentItemInput[];
+  // Default: weights
+  split_mode?: SplitMode;
   // Receipt image (Base64 encoded)
   receipt_image?: string;
   // Recurrence fields
@@ -663,6 +786,15 @@ export interface CreatePaymentInput {
   recurrence_weekdays?: string;
   recurrence_monthdays?: string;
   recurrence_months?: string;
+  // RFC 5545 RRULE; takes precedence over the fields above
+  recurrence_rule?: string;
+  // JSON AmountSchedule (recurring payments only)
+  amount_schedule?: string;
+  // Recurring payments only
+  business_day_shift?: BusinessDayShift;
+  // Recurring payments only (default: false)
+  prorate_presence?: boolean;
+  weight_profile_id?: number | null;
   // Internal transfer: recipient account (null = external expense)
   receiver_account_id?: number | null;
   // Payment finalization status: true (default) = final, false = draft
@@ -706,6 +838,471 @@ export const updatePayment = (
 export const deletePayment = (projectId: number, paymentId: number) =>
   authFetch(`/projects/${projectId}/payments/${paymentId}`, { method: 'DELETE' });
 
+// "Edit this and following": end the series before from_date and continue it with changes
+export interface SplitPaymentInput {
+  from_date: string;
+  amount?: number;
+  payer_id?: number;
+  contributions?: { participant_id: number; weight: number }[];
+}
+
+export interface SplitPaymentResult {
+  ended: PaymentWithContributions;
+  continuation: PaymentWithContributions;
+}
+
+export const splitPayment = (
+  projectId: number,
+  paymentId: number,
+  payload: SplitPaymentInput
+): Promise<SplitPaymentResult> =>
+  authFetch(`/projects/${projectId}/payments/${paymentId}/split`, {
+    method: 'POST',
+    body: JSON.stringify(payload)
+  });
+
+// Occurrence exceptions of recurring payments
+export interface OccurrenceException {
+  id: number;
+  payment_id: number;
+  original_date: string;
+  is_skipped: boolean;
+  moved_to: string | null;
+  amount: number | null;
+  // JSON array of { participant_id, weight }
+  weights: string | null;
+  created_at: string;
+}
+
+export interface SetOccurrenceExceptionInput {
+  skip?: boolean;
+  moved_to?: string;
+  amount?: number;
+  contributions?: { participant_id: number; weight: number }[];
+}
+
+export const getOccurrenceExceptions = (
+  projectId: number,
+  paymentId: number
+): Promise<OccurrenceException[]> =>
+  authFetch(`/projects/${projectId}/payments/${paymentId}/exceptions`);
+
+export const setOccurrenceException = (
+  projectId: number,
+  paymentId: number,
+  originalDate: string,
+  payload: SetOccurrenceExceptionInput
+): Promise<OccurrenceException> =>
+  authFetch(`/projects/${projectId}/payments/${paymentId}/exceptions/${originalDate}`, {
+    method: 'PUT',
+    body: JSON.stringify(payload)
+  });
+
+export const deleteOccurrenceException = (
+  projectId: number,
+  paymentId: number,
+  originalDate: string
+) =>
+  authFetch(`/projects/${projectId}/payments/${paymentId}/exceptions/${originalDate}`, {
+    method: 'DELETE'
+  });
+
+// Refunds of external expenses
+export interface Refund {
+  id: number;
+  payment_id: number;
+  amount: number;
+  refund_date: string;
+  description: string;
+  // JSON array of { participant_id, weight }, null = the payment's split
+  weights: string | null;
+  created_at: string;
+}
+
+export interface NetCost {
+  amount: number;
+  contributions: Array<{ participant_id: number; amount: number }>;
+}
+
+export interface CreateRefundInput {
+  amount: number;
+  refund_date?: string;
+  description?: string;
+  contributions?: { participant_id: number; weight: number }[];
+}
+
+export const getRefunds = (projectId: number, paymentId: number): Promise<Refund[]> =>
+  authFetch(`/projects/${projectId}/payments/${paymentId}/refunds`);
+
+export const createRefund = (
+  projectId: number,
+  paymentId: number,
+  payload: CreateRefundInput
+): Promise<Refund> =>
+  authFetch(`/projects/${projectId}/payments/${paymentId}/refunds`, {
+    method: 'POST',
+    body: JSON.stringify(payload)
+  });
+
+export const updateRefund = (
+  projectId: number,
+  paymentId: number,
+  refundId: number,
+  payload: CreateRefundInput
+): Promise<Refund> =>
+  authFetch(`/projects/${projectId}/payments/${paymentId}/refunds/${refundId}`, {
+    method: 'PUT',
+    body: JSON.stringify(payload)
+  });
+
+export const deleteRefund = (projectId: number, paymentId: number, refundId: number) =>
+  authFetch(`/projects/${projectId}/payments/${paymentId}/refunds/${refundId}`, {
+    method: 'DELETE'
+  });
+
+// Project holidays (not business days for shifted recurring payments)
+export interface Holiday {
+  id: number;
+  project_id: number;
+  holiday_date: string;
+  name: string | null;
+  created_at: string;
+}
+
+export const getHolidays = (projectId: number): Promise<Holiday[]> =>
+  authFetch(`/projects/${projectId}/holidays`);
+
+export const createHoliday = (
+  projectId: number,
+  payload: { holiday_date: string; name?: string }
+): Promise<Holiday> =>
+  authFetch(`/projects/${projectId}/holidays`, {
+    method: 'POST',
+    body: JSON.stringify(payload)
+  });
+
+export const deleteHoliday = (projectId: number, holidayId: number) =>
+  authFetch(`/projects/${projectId}/holidays/${holidayId}`, { method: 'DELETE' });
+
+// Participant presence periods (who shares recurring payments when)
+export interface PresencePeriod {
+  id: number;
+  project_id: number;
+  participant_id: number;
+  from_date: string | null;
+  to_date: string | null;
+  created_at: string;
+}
+
+export interface PresencePeriodInput {
+  participant_id: number;
+  from_date?: string | null;
+  to_date?: string | null;
+}
+
+export const getPresence = (projectId: number): Promise<PresencePeriod[]> =>
+  authFetch(`/projects/${projectId}/presence`);
+
+export const createPresence = (
+  projectId: number,
+  payload: PresencePeriodInput
+): Promise<PresencePeriod> =>
+  authFetch(`/projects/${projectId}/presence`, {
+    method: 'POST',
+    body: JSON.stringify(payload)
+  });
+
+export const updatePresence = (
+  projectId: number,
+  presenceId: number,
+  payload: PresencePeriodInput
+): Promise<PresencePeriod> =>
+  authFetch(`/projects/${projectId}/presence/${presenceId}`, {
+    method: 'PUT',
+    body: JSON.stringify(payload)
+  });
+
+export const deletePresence = (projectId: number, presenceId: number) =>
+  authFetch(`/projects/${projectId}/presence/${presenceId}`, { method: 'DELETE' });
+
+// Pool valuations: what a unitized pool is worth, or gained, on a date
+export interface PoolValuation {
+  id: number;
+  project_id: number;
+  pool_id: number;
+  valuation_date: string;
+  kind: 'value' | 'interest';
+  amount: number;
+  description: string;
+  created_at: string;
+}
+
+export interface PoolValuationInput {
+  pool_id: number;
+  valuation_date: string;
+  kind?: 'value' | 'interest';
+  amount: number;
+  description?: string;
+}
+
+export const getPoolValuations = (projectId: number): Promise<PoolValuation[]> =>
+  authFetch(`/projects/${projectId}/pool-valuations`);
+
+export const createPoolValuation = (
+  projectId: number,
+  payload: PoolValuationInput
+): Promise<PoolValuation> =>
+  authFetch(`/projects/${projectId}/pool-valuations`, {
+    method: 'POST',
+    body: JSON.stringify(payload)
+  });
+
+export const updatePoolValuation = (
+  projectId: number,
+  valuationId: number,
+  payload: PoolValuationInput
+): Promise<PoolValuation> =>
+  authFetch(`/projects/${projectId}/pool-valuations/${valuationId}`, {
+    method: 'PUT',
+    body: JSON.stringify(payload)
+  });
+
+export const deletePoolValuation = (projectId: number, valuationId: number) =>
+  authFetch(`/projects/${projectId}/pool-valuations/${valuationId}`, { method: 'DELETE' });
+
+// Pool rules: recurring fees and interest shared by a pool's owners
+export interface PoolRule {
+  id: number;
+  project_id: number;
+  pool_id: number;
+  kind: 'fee' | 'interest';
+  amount: number | null; // Fee charged on each date
+  rate: number | null; // Annual interest rate, in percent
+  recurrence_rule: string; // Dates fees are charged or interest is credited
+  start_date: string;
+  end_date: string | null;
+  description: string;
+  created_at: string;
+}
+
+export interface PoolRuleInput {
+  pool_id: number;
+  kind: 'fee' | 'interest';
+  amount?: number;
+  rate?: number;
+  recurrence_rule: string;
+  start_date: string;
+  end_date?: string;
+  description?: string;
+}
+
+export const getPoolRules = (projectId: number): Promise<PoolRule[]> =>
+  authFetch(`/projects/${projectId}/pool-rules`);
+
+export const createPoolRule = (projectId: number, payload: PoolRuleInput): Promise<PoolRule> =>
+  authFetch(`/projects/${projectId}/pool-rules`, {
+    method: 'POST',
+    body: JSON.stringify(payload)
+  });
+
+export const updatePoolRule = (
+  projectId: number,
+  ruleId: number,
+  payload: PoolRuleInput
+): Promise<PoolRule> =>
+  authFetch(`/projects/${projectId}/pool-rules/${ruleId}`, {
+    method: 'PUT',
+    body: JSON.stringify(payload)
+  });
+
+export const deletePoolRule = (projectId: number, ruleId: number) =>
+  authFetch(`/projects/${projectId}/pool-rules/${ruleId}`, { method: 'DELETE' });
+
+// Pool statements: bank balances of a pool, reconciled against the ledger
+export interface PoolStatement {
+  id: number;
+  project_id: number;
+  pool_id: number;
+  statement_date: string;
+  balance: number;
+  description: string;
+  created_at: string;
+}
+
+export interface PoolStatementInput {
+  pool_id: number;
+  statement_date: string;
+  balance: number;
+  description?: string;
+}
+
+export interface AdjustmentShare {
+  participant_id: number;
+  participant_name: string;
+  amount: number; // Positive when the bank holds more than recorded
+}
+
+export interface StatementReconciliation {
+  statement_id: number;
+  statement_date: string;
+  statement_balance: number;
+  total_balance: number; // On record at the end of the statement date
+  drift: number; // statement_balance - total_balance
+  unexplained: number; // Drift added since the previous statement
+  occurrences: PaymentOccurrence[]; // What moved the pool since the previous statement
+  adjustment: AdjustmentShare[];
+}
+
+export interface PoolReconciliation {
+  pool_id: number;
+  pool_name: string;
+  statements: StatementReconciliation[];
+}
+
+export const getPoolStatements = (projectId: number, poolId?: number): Promise<PoolStatement[]> =>
+  authFetch(
+    `/projects/${projectId}/pool-statements${poolId !== undefined ? `?pool_id=${poolId}` : ''}`
+  );
+
+export const createPoolStatement = (
+  projectId: number,
+  payload: PoolStatementInput
+): Promise<PoolStatement> =>
+  authFetch(`/projects/${projectId}/pool-statements`, {
+    method: 'POST',
+    body: JSON.stringify(payload)
+  });
+
+export const updatePoolStatement = (
+  projectId: number,
+  statementId: number,
+  payload: PoolStatementInput
+): Promise<PoolStatement> =>
+  authFetch(`/projects/${projectId}/pool-statements/${statementId}`, {
+    method: 'PUT',
+    body: JSON.stringify(payload)
+  });
+
+export const deletePoolStatement = (projectId: number, statementId: number) =>
+  authFetch(`/projects/${projectId}/pool-statements/${statementId}`, { method: 'DELETE' });
+
+export const getPoolReconciliation = (
+  projectId: number,
+  poolId: number
+): Promise<PoolReconciliation> =>
+  authFetch(`/projects/${projectId}/pool-statements/reconciliation?pool_id=${poolId}`);
+
+// Post the drift at a statement as a payment shared by the pool's owners
+export const adjustToPoolStatement = (
+  projectId: number,
+  statementId: number
+): Promise<PaymentWithContributions> =>
+  authFetch(`/projects/${projectId}/pool-statements/${statementId}/adjust`, { method: 'POST' });
+
+// Contribution rules: deposits participants are expected to make to a pool
+export interface ContributionRuleAmount {
+  id: number;
+  rule_id: number;
+  participant_id: number;
+  participant_name: string;
+  amount: number;
+}
+
+export interface ContributionRule {
+  id: number;
+  project_id: number;
+  pool_id: number;
+  recurrence_rule: string | null; // null = once, on the start date
+  start_date: string;
+  end_date: string | null;
+  description: string;
+  created_at: string;
+  amounts: ContributionRuleAmount[];
+}
+
+export interface ContributionRuleInput {
+  pool_id: number;
+  recurrence_rule?: string;
+  start_date: string;
+  end_date?: string;
+  description?: string;
+  amounts: { participant_id: number; amount: number }[];
+}
+
+export const getContributionRules = (projectId: number): Promise<ContributionRule[]> =>
+  authFetch(`/projects/${projectId}/contribution-rules`);
+
+export const createContributionRule = (
+  projectId: number,
+  payload: ContributionRuleInput
+): Promise<ContributionRule> =>
+  authFetch(`/projects/${projectId}/contribution-rules`, {
+    method: 'POST',
+    body: JSON.stringify(payload)
+  });
+
+export const updateContributionRule = (
+  projectId: number,
+  ruleId: number,
+  payload: ContributionRuleInput
+): Promise<ContributionRule> =>
+  authFetch(`/projects/${projectId}/contribution-rules/${ruleId}`, {
+    method: 'PUT',
+    body: JSON.stringify(payload)
+  });
+
+export const deleteContributionRule = (projectId: number, ruleId: number) =>
+  authFetch(`/projects/${projectId}/contribution-rules/${ruleId}`, { method: 'DELETE' });
+
+// Weight profiles: named weights payments can reference
+export interface WeightProfileVersion {
+  id: number;
+  profile_id: number;
+  // null = the first version
+  effective_from: string | null;
+  // JSON: [{"participant_id": 1, "weight": 2.0}]
+  weights: string;
+  created_at: string;
+}
+
+export interface WeightProfile {
+  id: number;
+  project_id: number;
+  name: string;
+  created_at: string;
+  versions: WeightProfileVersion[];
+}
+
+export const getWeightProfiles = (projectId: number): Promise<WeightProfile[]> =>
+  authFetch(`/projects/${projectId}/weight-profiles`);
+
+export const createWeightProfile = (
+  projectId: number,
+  payload: { name: string; contributions: Array<{ participant_id: number; weight: number }> }
+): Promise<WeightProfile> =>
+  authFetch(`/projects/${projectId}/weight-profiles`, {
+    method: 'POST',
+    body: JSON.stringify(payload)
+  });
+
+// New weights apply from effective_from on (without it, the first version changes)
+export const updateWeightProfile = (
+  projectId: number,
+  profileId: number,
+  payload: {
+    name?: string;
+    contributions?: Array<{ participant_id: number; weight: number }>;
+    effective_from?: string;
+  }
+): Promise<WeightProfile> =>
+  authFetch(`/projects/${projectId}/weight-profiles/${profileId}`, {
+    method: 'PUT',
+    body: JSON.stringify(payload)
+  });
+
+export const deleteWeightProfile = (projectId: number, profileId: number) =>
+  authFetch(`/projects/${projectId}/weight-profiles/${profileId}`, { method: 'DELETE' });
+
 // Debts
 export const getDebts = (
   projectId: number,
@@ -719,6 +1316,10 @@ export const getDebts = (
   return authFetch(`/projects/${projectId}/debts${queryString ? '?' + queryString : ''}`);
 };
 
+// Warnings
+export const getWarnings = (projectId: number): Promise<PoolWarning[]> =>
+  authFetch(`/projects/${projectId}/warnings`);
+
 // History
 export interface HistoryEntry {
   id: number;
@@ -494,6 +494,129 @@ export function getFirstPatternOccurrenceFrom(
   return new Date(fromDate);
 }
 
+// --- RRULE conversion ---
+
+/** A recurrence as the payment form edits it */
+export interface RecurrencePattern {
+  type: 'daily' | 'weekly' | 'monthly' | 'yearly';
+  interval: number;
+  weekdays?: number[][];
+  monthdays?: number[];
+  months?: number[];
+}
+
+// RRULE weekday codes, indexed like Date.getDay() (0 = Sunday)
+const RRULE_WEEKDAYS = ['SU', 'MO', 'TU', 'WE', 'TH', 'FR', 'SA'];
+
+/**
+ * Write a form pattern as an RRULE, the way the server converts the recurrence
+ * fields of a payment. A weekly cycle with different days in some of its weeks
+ * becomes one rule per week, each starting on the Sunday of its week.
+ */
+export function toRecurrenceRule(startDateStr: string, pattern: RecurrencePattern): string {
+  const interval = Math.max(1, pattern.interval);
+  const byDay = (days: number[]) => days.map((d) => RRULE_WEEKDAYS[d]).join(',');
+
+  if (pattern.type === 'weekly' && pattern.weekdays && pattern.weekdays.length > 0) {
+    const weeks = pattern.weekdays;
+    const cycle = Array.from({ length: interval }, (_, k) =>
+      [...new Set(weeks[k % weeks.length].filter((d) => d >= 0 && d < 7))].sort((a, b) => a - b)
+    );
+    if (cycle.every((days) => byDay(days) === byDay(cycle[0]))) {
+      if (cycle[0].length > 0) return `FREQ=WEEKLY;BYDAY=${byDay(cycle[0])};WKST=SU`;
+    } else {
+      const start = parseDate(startDateStr);
+      const firstSunday = addDays(start, -start.getDay());
+      const lines: string[] = [];
+      cycle.forEach((days, k) => {
+        if (days.length === 0) return;
+        if (k > 0) {
+          lines.push(`DTSTART:${formatDateStr(addDays(firstSunday, 7 * k)).replace(/-/g, '')}`);
+        }
+        lines.push(`RRULE:FREQ=WEEKLY;INTERVAL=${interval};BYDAY=${byDay(days)};WKST=SU`);
+      });
+      return lines.join('\n');
+    }
+  }
+
+  if (pattern.type === 'monthly' && interval === 1 && pattern.monthdays?.length) {
+    return `FREQ=MONTHLY;BYMONTHDAY=${pattern.monthdays.join(',')}`;
+  }
+  if (pattern.type === 'yearly' && interval === 1 && pattern.months?.length) {
+    const monthdays = pattern.monthdays?.length
+      ? pattern.monthdays
+      : [parseDate(startDateStr).getDate()];
+    return `FREQ=YEARLY;BYMONTH=${pattern.months.join(',')};BYMONTHDAY=${monthdays.join(',')}`;
+  }
+  return `FREQ=${pattern.type.toUpperCase()};INTERVAL=${interval}`;
+}
+
+/**
+ * Read an RRULE back into a form pattern. Returns null for rules the form
+ * cannot show, such as nth weekdays, BYSETPOS or several unrelated rules.
+ */
+export function fromRecurrenceRule(startDateStr: string, rule: string): RecurrencePattern | null {
+  const rules: { start: Date | null; parts: Map<string, string> }[] = [];
+  let ruleStart: Date | null = null;
+  const lines = rule
+    .split('\n')
+    .map((l) => l.trim().toUpperCase())
+    .filter(Boolean);
+  for (const line of lines) {
+    if (line.startsWith('DTSTART:')) {
+      const d = line.slice(8, 16);
+      ruleStart = parseDate(`${d.slice(0, 4)}-${d.slice(4, 6)}-${d.slice(6, 8)}`);
+      continue;
+    }
+    const parts = new Map(
+      line
+        .replace(/^RRULE:/, '')
+        .split(';')
+        .map((part) => part.split('=') as [string, string])
+    );
+    rules.push({ start: ruleStart, parts });
+    ruleStart = null;
+  }
+
+  const supported = ['FREQ', 'INTERVAL', 'BYDAY', 'BYMONTHDAY', 'BYMONTH', 'WKST'];
+  const unsupported = rules.some((r) => [...r.parts.keys()].some((k) => !supported.includes(k)));
+  if (rules.length === 0 || unsupported) return null;
+
+  const type = rules[0].parts.get('FREQ')?.toLowerCase();
+  if (type !== 'daily' && type !== 'weekly' && type !== 'monthly' && type !== 'yearly') return null;
+  const interval = parseInt(rules[0].parts.get('INTERVAL') ?? '1') || 1;
+  const numbers = (value: string | undefined) =>
+    value ? value.split(',').map((v) => parseInt(v)) : undefined;
+  const weekdays = (value: string | undefined) =>
+    value ? value.split(',').map((v) => RRULE_WEEKDAYS.indexOf(v)) : [];
+
+  if (type === 'weekly') {
+    const start = parseDate(startDateStr);
+    const firstSunday = addDays(start, -start.getDay());
+    const cycle: number[][] = Array.from({ length: interval }, () => []);
+    for (const r of rules) {
+      const days = weekdays(r.parts.get('BYDAY'));
+      const week = r.start ? Math.round(daysBetween(firstSunday, r.start) / 7) : 0;
+      if (
+        r.parts.get('FREQ') !== 'WEEKLY' ||
+        (parseInt(r.parts.get('INTERVAL') ?? '1') || 1) !== interval ||
+        days.some((d) => d < 0) ||
+        week < 0 ||
+        week >= interval
+      )
+        return null;
+      cycle[week] = days.length > 0 ? days : [start.getDay()];
+    }
+    return interval <= 4 ? { type, interval, weekdays: cycle } : { type, interval };
+  }
+
+  if (rules.length > 1 || rules[0].parts.has('BYDAY')) return null;
+  const monthdays = numbers(rules[0].parts.get('BYMONTHDAY'));
+  const months = numbers(rules[0].parts.get('BYMONTH'));
+  if ([...(monthdays ?? []), ...(months ?? [])].some((n) => isNaN(n) || n < 1)) return null;
+  return { type, interval, monthdays, months };
+}
+
 // --- Internal helpers ---
 
 function parseJson<T>(raw: string | null): T | null {
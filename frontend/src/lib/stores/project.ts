@@ -43,7 +43,8 @@ export async function loadProject(projectId: number) {
       owner_name: '',
       user_balance: null,
       user_pools: [],
-      member_status: 'active'
+      member_status: 'active',
+      warnings: []
     }
   );
   participants.set(participantsList);
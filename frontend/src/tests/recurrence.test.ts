@@ -3,7 +3,9 @@ import {
   computeLastOccurrenceDate,
   computeNthOccurrenceDate,
   getLastPatternOccurrenceBefore,
-  getFirstPatternOccurrenceFrom
+  getFirstPatternOccurrenceFrom,
+  toRecurrenceRule,
+  fromRecurrenceRule
 } from '$lib/recurrence-utils';
 
 /**
@@ -394,3 +396,50 @@ describe('computeNthOccurrenceDate', () => {
     });
   });
 });
+
+describe('toRecurrenceRule / fromRecurrenceRule', () => {
+  it('writes a plain interval', () => {
+    expect(toRecurrenceRule('2025-01-15', { type: 'monthly', interval: 3 })).toBe(
+      'FREQ=MONTHLY;INTERVAL=3'
+    );
+  });
+
+  it('writes month days and months', () => {
+    expect(
+      toRecurrenceRule('2025-01-15', { type: 'monthly', interval: 1, monthdays: [1, 15] })
+    ).toBe('FREQ=MONTHLY;BYMONTHDAY=1,15');
+    expect(toRecurrenceRule('2025-01-15', { type: 'yearly', interval: 1, months: [1, 7] })).toBe(
+      'FREQ=YEARLY;BYMONTH=1,7;BYMONTHDAY=15'
+    );
+  });
+
+  it('writes a weekly cycle as one rule per week', () => {
+    // Wednesday Jan 15, 2025: Monday in week one, Friday in week two
+    expect(
+      toRecurrenceRule('2025-01-15', { type: 'weekly', interval: 2, weekdays: [[1], [5]] })
+    ).toBe(
+      'RRULE:FREQ=WEEKLY;INTERVAL=2;BYDAY=MO;WKST=SU\n' +
+        'DTSTART:20250119\nRRULE:FREQ=WEEKLY;INTERVAL=2;BYDAY=FR;WKST=SU'
+    );
+  });
+
+  it('reads back what it writes', () => {
+    const patterns = [
+      { type: 'daily' as const, interval: 3 },
+      { type: 'weekly' as const, interval: 1, weekdays: [[1, 3]] },
+      { type: 'weekly' as const, interval: 2, weekdays: [[1], [5]] },
+      { type: 'monthly' as const, interval: 1, monthdays: [1, 15] },
+      { type: 'yearly' as const, interval: 1, months: [1, 7], monthdays: [15] }
+    ];
+    for (const pattern of patterns) {
+      const rule = toRecurrenceRule('2025-01-15', pattern);
+      expect(fromRecurrenceRule('2025-01-15', rule)).toMatchObject(pattern);
+    }
+  });
+
+  it('returns null for rules the form cannot show', () => {
+    expect(fromRecurrenceRule('2025-01-15', 'FREQ=MONTHLY;BYDAY=MO;BYSETPOS=-1')).toBeNull();
+    expect(fromRecurrenceRule('2025-01-15', 'FREQ=MONTHLY;BYDAY=2TU')).toBeNull();
+    expect(fromRecurrenceRule('2025-01-15', 'FREQ=HOURLY')).toBeNull();
+  });
+});